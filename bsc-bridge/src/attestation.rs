@@ -0,0 +1,564 @@
+//! # Validator Attestations
+//!
+//! The alternative to the [`light_client`](crate::light_client): the bridge
+//! validators attest to each transfer, and it is released once validators
+//! holding at least the threshold of the active weight have, for example
+//! two thirds of it.
+//!
+//! A validator attests to a digest, see [`transfer_digest`], in one of two
+//! ways:
+//!
+//! - [`Attestation::Secp256k1`]: a signature of the digest by the key of its
+//!   `eth_address`, which anyone may submit
+//! - [`Attestation::Ed25519`]: its `pubkey` signs the transaction submitting
+//!   the attestation, and so the digest the program computes from it
+//!
+//! Attestations to a digest accumulate in an account at the address
+//! [`attestations_address`] derives from it and the bridge account, which
+//! also keeps a transfer from being released twice. The bridge must be
+//! configured for attestations, see [`Verification`](crate::Verification),
+//! and a released transfer counts towards the bridge's daily limit like one
+//! proven against the light client.
+//!
+//! The bridge authority sets up the first validator set, see
+//! [`process_initialize_validators`]; from then on only the validators
+//! change it.
+//!
+//! Changes to the validator set, see [`ValidatorChange`], need the same
+//! threshold of attestations, see [`change_digest`]. Each change starts a new
+//! epoch of the set, and attestations gathered in an earlier epoch, which
+//! refer to validators by their place in the set, no longer count.
+
+use {
+    crate::{
+        create_account, header::recover_signer, BridgeState, TokenType, TransferRequest, Validator,
+        Verification,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    bsc_evm_rlp::keccak256,
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::{clock::Clock, Sysvar},
+    },
+};
+
+/// Largest set of bridge validators, one for each bit of [`Attestations::attested`]
+pub const MAX_BRIDGE_VALIDATORS: usize = 64;
+
+/// Seed of the [`BridgeValidators`] account
+pub const VALIDATORS_SEED: &[u8] = b"validators";
+
+/// Seed prefix of [`Attestations`] accounts
+pub const ATTESTATIONS_SEED: &[u8] = b"attestations";
+
+/// Space the borsh encoded [`BridgeValidators`] can take
+pub const VALIDATORS_LEN: usize = 1 + 8 + 2 + 4 + MAX_BRIDGE_VALIDATORS * Validator::LEN;
+
+/// Space the borsh encoded [`Attestations`] takes
+pub const ATTESTATIONS_LEN: usize = 1 + 32 + 8 + 8 + 1;
+
+/// Prefix of the transfer digest, which keeps it from being any other message
+const TRANSFER_DOMAIN: &[u8] = b"bsc-bridge transfer";
+
+/// Prefix of the validator change digest
+const CHANGE_DOMAIN: &[u8] = b"bsc-bridge validator change";
+
+/// How a validator attests to a digest
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Attestation {
+    /// Signature of the digest, `r`, `s` and the recovery id, which may be
+    /// given as 27 or 28 the way Ethereum wallets do
+    Secp256k1 { signature: [u8; 65] },
+    /// The validator's `pubkey` signs the transaction
+    Ed25519,
+}
+
+/// A change to the validator set, which validators refer to by their place in it
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ValidatorChange {
+    Add {
+        validator: Validator,
+    },
+    Remove {
+        index: u8,
+    },
+    Update {
+        index: u8,
+        weight: u64,
+        active: bool,
+    },
+    SetThreshold {
+        threshold_bps: u16,
+    },
+}
+
+/// Validators attesting to transfers, the account at [`validators_address`]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BridgeValidators {
+    pub is_initialized: bool,
+    /// Number of changes made to the set
+    pub epoch: u64,
+    /// Share of the active weight that must attest, in basis points
+    pub threshold_bps: u16,
+    pub validators: Vec<Validator>,
+}
+
+impl BridgeValidators {
+    /// A set in its first epoch
+    pub fn new(threshold_bps: u16, validators: Vec<Validator>) -> Result<Self, ProgramError> {
+        let set = Self {
+            is_initialized: true,
+            epoch: 0,
+            threshold_bps,
+            validators,
+        };
+        set.check()?;
+        Ok(set)
+    }
+
+    /// State held by the account data `data`
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let set =
+            Self::deserialize(&mut &data[..]).map_err(|_| ProgramError::InvalidAccountData)?;
+        if !set.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(set)
+    }
+
+    fn pack(&self, data: &mut [u8]) {
+        let packed = borsh::to_vec(self).unwrap();
+        data[..packed.len()].copy_from_slice(&packed);
+    }
+
+    /// Weight of the active validators
+    pub fn active_weight(&self) -> u64 {
+        self.validators
+            .iter()
+            .filter(|validator| validator.active)
+            .map(|v| v.weight)
+            .sum()
+    }
+
+    /// Whether the validators whose bits are set in `attested` hold the threshold
+    pub fn has_quorum(&self, attested: u64) -> bool {
+        let weight: u64 = self
+            .validators
+            .iter()
+            .enumerate()
+            .filter(|(index, validator)| validator.active && attested & (1 << index) != 0)
+            .map(|(_, validator)| validator.weight)
+            .sum();
+        u128::from(weight) * 10_000
+            >= u128::from(self.active_weight()) * u128::from(self.threshold_bps)
+    }
+
+    /// Index of the active validator `attestation` comes from
+    ///
+    /// `signer` is the key signing the transaction for an ed25519 attestation.
+    pub fn attester(
+        &self,
+        digest: &[u8; 32],
+        attestation: &Attestation,
+        signer: Option<&Pubkey>,
+    ) -> Result<usize, ProgramError> {
+        let index = match attestation {
+            Attestation::Secp256k1 { signature } => {
+                let mut signature = *signature;
+                if signature[64] >= 27 {
+                    signature[64] -= 27;
+                }
+                let address = recover_signer(digest, &signature)?;
+                self.validators.iter().position(|validator| {
+                    validator.active && validator.eth_address == address && address != [0u8; 20]
+                })
+            }
+            Attestation::Ed25519 => {
+                let signer = signer.ok_or_else(|| {
+                    msg!("An ed25519 attestation needs the validator to sign");
+                    ProgramError::MissingRequiredSignature
+                })?;
+                self.validators.iter().position(|validator| {
+                    validator.active && validator.pubkey == *signer && *signer != Pubkey::default()
+                })
+            }
+        };
+        index.ok_or_else(|| {
+            msg!("The attestation is not from an active bridge validator");
+            ProgramError::MissingRequiredSignature
+        })
+    }
+
+    /// Make `change`, starting a new epoch, unless it leaves a set that cannot attest
+    pub fn apply(&mut self, change: &ValidatorChange) -> ProgramResult {
+        let mut next = self.clone();
+        match change {
+            ValidatorChange::Add { validator } => next.validators.push(validator.clone()),
+            ValidatorChange::Remove { index } => {
+                next.validator(*index)?;
+                next.validators.remove(usize::from(*index));
+            }
+            ValidatorChange::Update {
+                index,
+                weight,
+                active,
+            } => {
+                let validator = next.validator(*index)?;
+                validator.weight = *weight;
+                validator.active = *active;
+            }
+            ValidatorChange::SetThreshold { threshold_bps } => next.threshold_bps = *threshold_bps,
+        }
+        next.check()?;
+        next.epoch += 1;
+        *self = next;
+        Ok(())
+    }
+
+    fn validator(&mut self, index: u8) -> Result<&mut Validator, ProgramError> {
+        self.validators.get_mut(usize::from(index)).ok_or_else(|| {
+            msg!("There is no bridge validator {}", index);
+            ProgramError::InvalidArgument
+        })
+    }
+
+    /// Check the set can attest, and no validator can attest twice
+    fn check(&self) -> ProgramResult {
+        if self.threshold_bps <= 5_000 || self.threshold_bps > 10_000 {
+            msg!("The threshold must be more than half the weight, and at most all of it");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.validators.len() > MAX_BRIDGE_VALIDATORS {
+            msg!(
+                "Bridge validator sets are limited to {} validators",
+                MAX_BRIDGE_VALIDATORS
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        let mut weight = 0u64;
+        for (index, validator) in self.validators.iter().enumerate() {
+            if validator.weight == 0
+                || (validator.eth_address == [0u8; 20] && validator.pubkey == Pubkey::default())
+            {
+                msg!("Bridge validator {} has no weight or no key", index);
+                return Err(ProgramError::InvalidArgument);
+            }
+            let shares_key = self.validators[..index].iter().any(|other| {
+                (validator.eth_address != [0u8; 20] && other.eth_address == validator.eth_address)
+                    || (validator.pubkey != Pubkey::default() && other.pubkey == validator.pubkey)
+            });
+            if shares_key {
+                msg!("Bridge validator {} shares a key with another", index);
+                return Err(ProgramError::InvalidArgument);
+            }
+            if validator.active {
+                weight = weight
+                    .checked_add(validator.weight)
+                    .ok_or(ProgramError::InvalidArgument)?;
+            }
+        }
+        if weight == 0 {
+            msg!("The bridge needs an active validator");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+}
+
+/// Attestations to one digest, the account at [`attestations_address`]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Attestations {
+    pub is_initialized: bool,
+    pub digest: [u8; 32],
+    /// Epoch of the validator set the attestations were made in
+    pub epoch: u64,
+    /// Bit `i` is set once validator `i` has attested
+    pub attested: u64,
+    /// Whether the threshold was reached and the transfer or change made
+    pub executed: bool,
+}
+
+impl Attestations {
+    /// Add the attestation of validator `index` of `validators` to `digest`
+    ///
+    /// Returns whether it brings the attestations to the threshold, at which
+    /// point the transfer or change must be made. Fails once it was.
+    pub fn record(
+        &mut self,
+        validators: &BridgeValidators,
+        digest: [u8; 32],
+        index: usize,
+    ) -> Result<bool, ProgramError> {
+        if !self.is_initialized {
+            *self = Self {
+                is_initialized: true,
+                digest,
+                epoch: validators.epoch,
+                ..Self::default()
+            };
+        }
+        if self.digest != digest {
+            msg!("The attestations account holds another digest");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.executed {
+            msg!("The attested transfer or change was already made");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.epoch != validators.epoch {
+            self.epoch = validators.epoch;
+            self.attested = 0;
+        }
+        let bit = 1u64 << index;
+        if self.attested & bit != 0 {
+            msg!("Bridge validator {} already attested", index);
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.attested |= bit;
+        self.executed = validators.has_quorum(self.attested);
+        Ok(self.executed)
+    }
+}
+
+/// Digest validators attest to for the transfer `request` through the bridge
+/// account `bridge` of the program `program_id`
+///
+/// The Keccak-256 of the domain, the program id, the bridge account, and the request's source
+/// transaction hash, source block, recipient, amount, token (the zero
+/// address for BNB) and nonce, numbers in big endian. The receipt proof is
+/// not part of it.
+pub fn transfer_digest(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    request: &TransferRequest,
+) -> [u8; 32] {
+    let token = match request.token_type {
+        TokenType::Bnb => [0u8; 20],
+        TokenType::Bep20(token) => token,
+    };
+    keccak256(
+        &[
+            TRANSFER_DOMAIN,
+            program_id.as_ref(),
+            bridge.as_ref(),
+            &request.source_tx_hash,
+            &request.source_block.to_be_bytes(),
+            request.recipient.as_ref(),
+            &request.amount.to_be_bytes(),
+            &token,
+            &request.nonce.to_be_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+/// Digest validators attest to for `change` to the set in epoch `epoch`
+///
+/// The Keccak-256 of the domain, the program id, the bridge account, the
+/// epoch in big endian and the borsh encoded change.
+pub fn change_digest(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    epoch: u64,
+    change: &ValidatorChange,
+) -> [u8; 32] {
+    keccak256(
+        &[
+            CHANGE_DOMAIN,
+            program_id.as_ref(),
+            bridge.as_ref(),
+            &epoch.to_be_bytes(),
+            &borsh::to_vec(change).unwrap(),
+        ]
+        .concat(),
+    )
+}
+
+/// Address of the [`BridgeValidators`] account of the bridge account `bridge`
+pub fn validators_address(program_id: &Pubkey, bridge: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VALIDATORS_SEED, bridge.as_ref()], program_id)
+}
+
+/// Address of the [`Attestations`] account for `digest` to the bridge account `bridge`
+pub fn attestations_address(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    digest: &[u8; 32],
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ATTESTATIONS_SEED, bridge.as_ref(), digest], program_id)
+}
+
+/// Set up the validators of the bridge account `bridge`
+///
+/// Accounts: the validators account, then the payer and the system program.
+/// The processor checks the bridge authority signed.
+pub fn process_initialize_validators<'a>(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    accounts_iter: &mut std::slice::Iter<'_, AccountInfo<'a>>,
+    threshold_bps: u16,
+    validators: Vec<Validator>,
+) -> ProgramResult {
+    let validators_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let (address, bump_seed) = validators_address(program_id, bridge);
+    if *validators_account.key != address {
+        msg!("Error: Invalid validators account");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !validators_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let set = BridgeValidators::new(threshold_bps, validators)?;
+    create_account(
+        program_id,
+        validators_account,
+        payer,
+        system_program,
+        VALIDATORS_LEN,
+        &[VALIDATORS_SEED, bridge.as_ref(), &[bump_seed]],
+    )?;
+    set.pack(&mut validators_account.try_borrow_mut_data()?);
+
+    msg!(
+        "Bridge validators: {}, threshold {} bps",
+        set.validators.len(),
+        set.threshold_bps
+    );
+    Ok(())
+}
+
+/// Attest to the transfer `request`, releasing it once the threshold is reached
+///
+/// Accounts: the validators account, the transfer's attestations account, the
+/// payer and the system program, then for an ed25519 attestation the
+/// validator. The processor stores `bridge_state` back in the bridge account.
+pub fn process_attest_transfer<'a>(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    accounts_iter: &mut std::slice::Iter<'_, AccountInfo<'a>>,
+    bridge_state: &mut BridgeState,
+    request: &TransferRequest,
+    attestation: &Attestation,
+) -> ProgramResult {
+    if bridge_state.config.verification != Verification::Attestations {
+        msg!("The bridge releases transfers proven against its light client");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let validators_account = next_validators(program_id, bridge, accounts_iter)?;
+    let validators = BridgeValidators::unpack(&validators_account.try_borrow_data()?)?;
+    let digest = transfer_digest(program_id, bridge, request);
+
+    if attest(
+        program_id,
+        bridge,
+        accounts_iter,
+        &validators,
+        digest,
+        attestation,
+    )? {
+        bridge_state.reset_daily_volume(Clock::get()?.unix_timestamp as u64);
+        bridge_state.process_attested_transfer(request, digest)?;
+        msg!("Transfer released");
+    }
+    Ok(())
+}
+
+/// Attest to `change` to the validator set, making it once the threshold is reached
+///
+/// Accounts: as for [`process_attest_transfer`], with the validators account
+/// writable.
+pub fn process_attest_validator_change<'a>(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    accounts_iter: &mut std::slice::Iter<'_, AccountInfo<'a>>,
+    change: &ValidatorChange,
+    attestation: &Attestation,
+) -> ProgramResult {
+    let validators_account = next_validators(program_id, bridge, accounts_iter)?;
+    if !validators_account.is_writable {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut validators = BridgeValidators::unpack(&validators_account.try_borrow_data()?)?;
+    let digest = change_digest(program_id, bridge, validators.epoch, change);
+
+    if attest(
+        program_id,
+        bridge,
+        accounts_iter,
+        &validators,
+        digest,
+        attestation,
+    )? {
+        validators.apply(change)?;
+        validators.pack(&mut validators_account.try_borrow_mut_data()?);
+        msg!("Bridge validator set changed, epoch {}", validators.epoch);
+    }
+    Ok(())
+}
+
+/// Next account, which must be the validators account of `bridge`
+fn next_validators<'a, 'b>(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    accounts_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    let validators_account = next_account_info(accounts_iter)?;
+    if validators_account.owner != program_id
+        || *validators_account.key != validators_address(program_id, bridge).0
+    {
+        msg!("Error: Invalid validators account");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(validators_account)
+}
+
+/// Record an attestation to `digest`, returning whether it reached the threshold
+fn attest<'a>(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    accounts_iter: &mut std::slice::Iter<'_, AccountInfo<'a>>,
+    validators: &BridgeValidators,
+    digest: [u8; 32],
+    attestation: &Attestation,
+) -> Result<bool, ProgramError> {
+    let attestations_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let signer = accounts_iter
+        .next()
+        .filter(|account| account.is_signer)
+        .map(|account| account.key);
+
+    let index = validators.attester(&digest, attestation, signer)?;
+
+    let (address, bump_seed) = attestations_address(program_id, bridge, &digest);
+    if *attestations_account.key != address {
+        msg!("Error: Invalid attestations account");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if attestations_account.data_is_empty() {
+        create_account(
+            program_id,
+            attestations_account,
+            payer,
+            system_program,
+            ATTESTATIONS_LEN,
+            &[ATTESTATIONS_SEED, bridge.as_ref(), &digest, &[bump_seed]],
+        )?;
+    }
+
+    let mut attestations = Attestations::try_from_slice(&attestations_account.try_borrow_data()?)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let reached = attestations.record(validators, digest, index)?;
+    attestations.serialize(&mut &mut attestations_account.try_borrow_mut_data()?[..])?;
+
+    msg!("Attestation from bridge validator {} recorded", index);
+    Ok(reached)
+}
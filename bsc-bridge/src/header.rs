@@ -0,0 +1,200 @@
+//! # BSC Headers
+//!
+//! Block headers of BNB Smart Chain and what Parlia consensus puts in them:
+//!
+//! - The hash is the Keccak-256 of the header's RLP encoding
+//! - The validator sealing a block signs the seal hash, see
+//!   [`BscHeader::seal_hash`], and appends the 65-byte signature to the extra
+//!   data. The signer is also the block's coinbase
+//! - The difficulty is 2 if the signer was in turn, 1 otherwise
+//! - An epoch header, one whose number is a multiple of the epoch length,
+//!   lists the next validator set in its extra data, see
+//!   [`BscHeader::epoch_validators`]
+//!
+//! Extra data is laid out as 32 vanity bytes, then in epoch headers the
+//! validator count and each validator's address and BLS key, then since Bohr
+//! the turn length, then an optional RLP encoded vote attestation, and last
+//! the seal. Headers from before Luban, whose epoch headers list validators
+//! without a count, are not supported.
+
+use {
+    bsc_evm_rlp::{
+        keccak256,
+        rlp::{decode_bytes, decode_list, decode_u64, encode_bytes, encode_list, encode_uint},
+    },
+    solana_program::{
+        keccak, msg, program_error::ProgramError, secp256k1_recover::secp256k1_recover,
+    },
+};
+
+/// Vanity bytes at the start of the extra data
+pub const EXTRA_VANITY_LEN: usize = 32;
+
+/// Seal signature at the end of the extra data
+pub const EXTRA_SEAL_LEN: usize = 65;
+
+/// Address and BLS public key of each validator listed in an epoch header
+pub const VALIDATOR_BYTES_LEN: usize = 20 + 48;
+
+/// Difficulty of a block sealed by the validator in turn
+pub const DIFF_IN_TURN: u64 = 2;
+
+/// Difficulty of a block sealed by any other validator
+pub const DIFF_NO_TURN: u64 = 1;
+
+/// Half the secp256k1 curve order, the largest `s` allowed in a signature
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// The fields of a header Parlia and the bridge use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BscHeader {
+    pub parent_hash: [u8; 32],
+    pub coinbase: [u8; 20],
+    /// Root of the block's receipt trie
+    pub receipts_root: [u8; 32],
+    pub difficulty: u64,
+    pub number: u64,
+    pub timestamp: u64,
+    pub extra: Vec<u8>,
+    /// Hash of the header
+    pub hash: [u8; 32],
+    /// Hash the sealing validator signed
+    pub seal_hash: [u8; 32],
+}
+
+/// Validator set listed by an epoch header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochValidators {
+    /// Validator addresses, in the order listed
+    pub validators: Vec<[u8; 20]>,
+    /// Consecutive blocks each validator seals in turn, listed since Bohr
+    pub turn_length: Option<u8>,
+}
+
+impl BscHeader {
+    /// Decode the RLP encoded header `raw` of the chain `chain_id`
+    pub fn decode(raw: &[u8], chain_id: u64) -> Result<Self, ProgramError> {
+        let items = decode_list(raw).map_err(invalid_header)?;
+        if items.len() < 15 {
+            msg!("A header has at least 15 fields");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let extra = decode_bytes(items[12]).map_err(invalid_header)?.to_vec();
+        if extra.len() < EXTRA_VANITY_LEN + EXTRA_SEAL_LEN {
+            msg!("The header's extra data is too short to hold a seal");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            parent_hash: decode_fixed(items[0])?,
+            coinbase: decode_fixed(items[2])?,
+            receipts_root: decode_fixed(items[5])?,
+            difficulty: decode_u64(items[7]).map_err(invalid_header)?,
+            number: decode_u64(items[8]).map_err(invalid_header)?,
+            timestamp: decode_u64(items[11]).map_err(invalid_header)?,
+            hash: keccak256(raw),
+            seal_hash: seal_hash(&items, &extra, chain_id)?,
+            extra,
+        })
+    }
+
+    /// Hash the sealing validator signed
+    ///
+    /// The Keccak-256 of the RLP list of the chain id, the header's fields up
+    /// to the nonce with the seal cut off the extra data, and, for headers
+    /// with a zero parent beacon root, every field from the base fee on.
+    pub fn seal_hash(&self) -> [u8; 32] {
+        self.seal_hash
+    }
+
+    /// Address of the validator that sealed the header
+    pub fn signer(&self) -> Result<[u8; 20], ProgramError> {
+        let seal = &self.extra[self.extra.len() - EXTRA_SEAL_LEN..];
+        recover_signer(&self.seal_hash, seal.try_into().unwrap())
+    }
+
+    /// Validator set listed in the extra data of an epoch header
+    pub fn epoch_validators(&self) -> Result<EpochValidators, ProgramError> {
+        let body = &self.extra[EXTRA_VANITY_LEN..self.extra.len() - EXTRA_SEAL_LEN];
+        let (count, listed) = body.split_first().ok_or_else(|| {
+            msg!("The epoch header lists no validators");
+            ProgramError::InvalidInstructionData
+        })?;
+        let listed_len = *count as usize * VALIDATOR_BYTES_LEN;
+        if *count == 0 || listed.len() < listed_len {
+            msg!("The epoch header's validator list is malformed");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let validators = listed[..listed_len]
+            .chunks(VALIDATOR_BYTES_LEN)
+            .map(|validator| validator[..20].try_into().unwrap())
+            .collect();
+        // A vote attestation is an RLP list, whose prefix is at least 0xc0,
+        // and the turn length is far below that
+        let turn_length = match listed.get(listed_len) {
+            Some(&byte) if byte < 0xc0 => Some(byte),
+            _ => None,
+        };
+        Ok(EpochValidators { validators, turn_length })
+    }
+}
+
+/// Address whose key made `signature`, the `r`, `s` and recovery id, over `hash`
+///
+/// Only the low `s` form of a signature and a recovery id of 0 or 1 are
+/// accepted, so each signature has one encoding.
+pub fn recover_signer(hash: &[u8; 32], signature: &[u8; 65]) -> Result<[u8; 20], ProgramError> {
+    let (signature, recovery_id) = signature.split_at(64);
+    if signature[32..] > SECP256K1_HALF_ORDER[..] || recovery_id[0] > 1 {
+        msg!("The signature is malleable or malformed");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let public_key = secp256k1_recover(hash, recovery_id[0], signature)
+        .map_err(|_| ProgramError::MissingRequiredSignature)?;
+
+    let hash = keccak::hash(&public_key.to_bytes()).to_bytes();
+    let mut signer = [0u8; 20];
+    signer.copy_from_slice(&hash[12..]);
+    Ok(signer)
+}
+
+fn seal_hash(items: &[&[u8]], extra: &[u8], chain_id: u64) -> Result<[u8; 32], ProgramError> {
+    let mut payload = Vec::new();
+    encode_uint(&chain_id.to_be_bytes(), &mut payload);
+    for item in &items[..12] {
+        payload.extend_from_slice(item);
+    }
+    encode_bytes(&extra[..extra.len() - EXTRA_SEAL_LEN], &mut payload);
+    payload.extend_from_slice(items[13]);
+    payload.extend_from_slice(items[14]);
+    // Parlia only signs the fields added since London once Cancun set a
+    // parent beacon root, which it always leaves zero
+    if let Some(parent_beacon_root) = items.get(19) {
+        if decode_fixed::<32>(parent_beacon_root)? == [0u8; 32] {
+            for item in &items[15..] {
+                payload.extend_from_slice(item);
+            }
+        }
+    }
+
+    let mut encoded = Vec::with_capacity(payload.len() + 9);
+    encode_list(&payload, &mut encoded);
+    Ok(keccak256(&encoded))
+}
+
+/// Byte string field of exactly `N` bytes
+fn decode_fixed<const N: usize>(encoded: &[u8]) -> Result<[u8; N], ProgramError> {
+    decode_bytes(encoded).map_err(invalid_header)?.try_into().map_err(|_| {
+        msg!("A header field has the wrong length");
+        ProgramError::InvalidInstructionData
+    })
+}
+
+fn invalid_header(err: bsc_evm_rlp::DecoderError) -> ProgramError {
+    msg!("Invalid header: {}", err);
+    ProgramError::InvalidInstructionData
+}
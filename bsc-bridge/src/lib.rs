@@ -6,18 +6,49 @@
 //! Features:
 //! - Cross-chain BNB transfers
 //! - BSC token bridging
-//! - Merkle proof verification
+//! - Light-client verification of BSC headers and lock receipts
+//! - Weighted validator attestations, as an alternative to the light client
 //! - Validator set management
 //! - Emergency pause functionality
+//!
+//! A transfer from BSC is released once the bridge's [`light_client`] holds
+//! the block the lock was made in, under at least `min_confirmations` more
+//! blocks, and a receipt proof shows the bridge contract emitted the matching
+//! [`LockEvent`](receipt::LockEvent) in it. An account at the address
+//! [`processed_address`] derives from the lock event's id records the release.
+//!
+//! The bridge account holds the borsh encoded [`BridgeState`], including the
+//! authority that signs the instructions changing the bridge's configuration
+//! and the one light client account it proves locks against.
+//!
+//! Alternatively, a transfer is released once bridge validators holding the
+//! configured share of the weight attested to it, see [`attestation`]. The
+//! bridge's [`Verification`] says which of the two it accepts.
+//!
+//! Releasing a transfer is still a stub: it records the release, counts the
+//! amount towards the daily limit and logs it, but pays out nothing. Moving
+//! BNB or minting the bridged token to the recipient is not implemented.
+
+pub mod attestation;
+pub mod header;
+pub mod light_client;
+pub mod mpt;
+pub mod receipt;
 
 use {
+    attestation::{Attestation, ValidatorChange},
+    borsh::{BorshDeserialize, BorshSerialize},
+    receipt::{LockEvent, ReceiptProof},
     solana_program::{
         account_info::{next_account_info, AccountInfo},
         entrypoint::{ProgramResult, HEAP_LENGTH, HEAP_START_ADDRESS},
+        msg,
+        program::invoke_signed,
         program_error::ProgramError,
         pubkey::Pubkey,
         rent::Rent,
         system_instruction,
+        sysvar::{clock::Clock, Sysvar},
     },
     std::collections::HashMap,
 };
@@ -25,19 +56,38 @@ use {
 /// BSC Bridge Program ID
 solana_program::declare_id!("11111111111111111111111111111113");
 
+/// Space a bridge account is created with, room for the state and 64 validators
+pub const BRIDGE_ACCOUNT_LEN: usize = 512 + 64 * (20 + Validator::LEN);
+
+/// Seed prefix of the accounts recording released lock events
+pub const PROCESSED_SEED: &[u8] = b"processed";
+
 /// Bridge Configuration
-#[derive(Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct BridgeConfig {
     /// BSC chain ID
     pub bsc_chain_id: u64,
     /// Minimum confirmation blocks on BSC
     pub min_confirmations: u64,
+    /// Bridge contract on BSC whose lock events are accepted
+    pub bsc_bridge_contract: [u8; 20],
     /// Maximum daily transfer limit
     pub daily_limit: u128,
     /// Bridge fee percentage (in basis points)
     pub bridge_fee_bps: u16,
     /// Emergency pause flag
     pub paused: bool,
+    /// How transfers are verified
+    pub verification: Verification,
+}
+
+/// How the bridge verifies a transfer from BSC
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    /// A receipt proof against the bridge's light client
+    LightClient,
+    /// Attestations of the bridge validators, see [`attestation`]
+    Attestations,
 }
 
 impl Default for BridgeConfig {
@@ -45,15 +95,17 @@ impl Default for BridgeConfig {
         Self {
             bsc_chain_id: 97, // BSC Testnet
             min_confirmations: 15,
+            bsc_bridge_contract: [0u8; 20],
             daily_limit: 1000 * 10_u128.pow(18), // 1000 BNB
             bridge_fee_bps: 10, // 0.1%
             paused: false,
+            verification: Verification::LightClient,
         }
     }
 }
 
 /// Validator Information
-#[derive(Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Validator {
     /// Validator Ethereum address
     pub eth_address: [u8; 20],
@@ -65,12 +117,17 @@ pub struct Validator {
     pub active: bool,
 }
 
+impl Validator {
+    /// Space the borsh encoded validator takes
+    pub const LEN: usize = 20 + 32 + 8 + 1;
+}
+
 /// Transfer Request
-#[derive(Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct TransferRequest {
     /// Source transaction hash on BSC
     pub source_tx_hash: [u8; 32],
-    /// Source block number
+    /// Source block number, which the lock receipt is proven in
     pub source_block: u64,
     /// Recipient address on Solana
     pub recipient: Pubkey,
@@ -80,14 +137,14 @@ pub struct TransferRequest {
     pub token_type: TokenType,
     /// Token contract address (for BEP20)
     pub token_contract: Option<[u8; 20]>,
-    /// Merkle proof
-    pub merkle_proof: Vec<[u8; 32]>,
-    /// Nonce to prevent replay attacks
+    /// Proof of the lock event's receipt
+    pub receipt_proof: ReceiptProof,
+    /// Nonce of the lock event
     pub nonce: u64,
 }
 
 /// Token Types
-#[derive(Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub enum TokenType {
     /// Native BNB
     Bnb,
@@ -95,13 +152,25 @@ pub enum TokenType {
     Bep20([u8; 20]),
 }
 
-/// Bridge State
+/// Bridge State, held by the bridge account
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
 pub struct BridgeState {
+    pub is_initialized: bool,
+    /// Signs the instructions changing the bridge's configuration
+    pub authority: Pubkey,
     /// Bridge configuration
     pub config: BridgeConfig,
+    /// Light client account locks are proven against
+    pub light_client: Option<Pubkey>,
     /// Validator set
     pub validators: HashMap<[u8; 20], Validator>,
-    /// Processed transfers (to prevent replay)
+    /// Processed transfers by lock event id, see [`ReceiptProof::event_id`],
+    /// or by digest when attested, see [`attestation::transfer_digest`] (to prevent replay)
+    ///
+    /// Not stored in the bridge account: across instructions, the accounts at
+    /// [`processed_address`] and the [`attestation::Attestations`] accounts
+    /// record the releases.
+    #[borsh(skip)]
     pub processed_transfers: HashMap<[u8; 32], bool>,
     /// Daily transfer volume
     pub daily_volume: u128,
@@ -113,14 +182,28 @@ pub struct BridgeState {
 
 impl BridgeState {
     pub fn new() -> Self {
-        Self {
-            config: BridgeConfig::default(),
-            validators: HashMap::new(),
-            processed_transfers: HashMap::new(),
-            daily_volume: 0,
-            last_reset: 0,
-            total_weight: 0,
+        Self::default()
+    }
+
+    /// State held by the bridge account data `data`
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let state =
+            Self::deserialize(&mut &data[..]).map_err(|_| ProgramError::InvalidAccountData)?;
+        if !state.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
         }
+        Ok(state)
+    }
+
+    /// Store the state in the bridge account data `data`
+    pub fn pack(&self, data: &mut [u8]) -> ProgramResult {
+        let packed = borsh::to_vec(self)?;
+        if packed.len() > data.len() {
+            msg!("The bridge account is too small for its state");
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        data[..packed.len()].copy_from_slice(&packed);
+        Ok(())
     }
 
     /// Add validator
@@ -144,51 +227,120 @@ impl BridgeState {
         }
     }
 
-    /// Verify transfer request
-    pub fn verify_transfer(&self, request: &TransferRequest) -> Result<(), ProgramError> {
-        // Check if bridge is paused
-        if self.config.paused {
-            return Err(ProgramError::InvalidAccountData);
-        }
-
-        // Check if transfer already processed
-        if self.processed_transfers.contains_key(&request.source_tx_hash) {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    /// Verify transfer request against the light client account data `light_client`
+    ///
+    /// Returns the id of the lock event the transfer releases.
+    pub fn verify_transfer(
+        &self,
+        request: &TransferRequest,
+        light_client: &[u8],
+    ) -> Result<[u8; 32], ProgramError> {
+        self.check_limits(request)?;
 
-        // Check daily limit
-        if self.daily_volume + request.amount > self.config.daily_limit {
-            return Err(ProgramError::InsufficientFunds);
-        }
+        // Verify the lock was made on BSC
+        let event_id = self.verify_lock_event(request, light_client)?;
 
-        // Verify merkle proof (simplified)
-        if !self.verify_merkle_proof(request) {
+        // Check if transfer already processed
+        if self.processed_transfers.contains_key(&event_id) {
             return Err(ProgramError::InvalidAccountData);
         }
 
-        Ok(())
+        Ok(event_id)
     }
 
-    /// Process transfer
-    pub fn process_transfer(&mut self, request: TransferRequest) -> Result<(), ProgramError> {
+    /// Process transfer, returning the id of the lock event it releases
+    ///
+    /// Only records the release, see the [crate] documentation.
+    pub fn process_transfer(
+        &mut self,
+        request: TransferRequest,
+        light_client: &[u8],
+    ) -> Result<[u8; 32], ProgramError> {
         // Verify transfer
-        self.verify_transfer(&request)?;
+        let event_id = self.verify_transfer(&request, light_client)?;
 
         // Mark as processed
-        self.processed_transfers.insert(request.source_tx_hash, true);
+        self.processed_transfers.insert(event_id, true);
 
         // Update daily volume
         self.daily_volume += request.amount;
 
+        Ok(event_id)
+    }
+
+    /// Release a transfer the bridge validators attested to, identified by its digest
+    ///
+    /// The attestations are checked by [`attestation::process_attest_transfer`].
+    /// Only records the release, see the [crate] documentation.
+    pub fn process_attested_transfer(
+        &mut self,
+        request: &TransferRequest,
+        digest: [u8; 32],
+    ) -> Result<(), ProgramError> {
+        self.check_limits(request)?;
+
+        // Check if transfer already processed
+        if self.processed_transfers.contains_key(&digest) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        self.processed_transfers.insert(digest, true);
+        self.daily_volume += request.amount;
+
+        Ok(())
+    }
+
+    /// Check the bridge is running and `request` stays within the daily limit
+    fn check_limits(&self, request: &TransferRequest) -> Result<(), ProgramError> {
+        // Check if bridge is paused
+        if self.config.paused {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Check daily limit
+        if self.daily_volume + request.amount > self.config.daily_limit {
+            return Err(ProgramError::InsufficientFunds);
+        }
+
         Ok(())
     }
 
-    /// Verify merkle proof (simplified implementation)
-    fn verify_merkle_proof(&self, request: &TransferRequest) -> bool {
-        // In a real implementation, this would verify the cryptographic proof
-        // that the transfer occurred on BSC
-        // For now, we'll implement a simple verification
-        request.merkle_proof.len() >= 2 && request.amount > 0
+    /// Verify the receipt proof shows the lock `request` releases, returning the lock event id
+    fn verify_lock_event(
+        &self,
+        request: &TransferRequest,
+        light_client: &[u8],
+    ) -> Result<[u8; 32], ProgramError> {
+        let proof = &request.receipt_proof;
+        let (header, receipt) = light_client::verify_receipt(
+            light_client,
+            request.source_block,
+            self.config.min_confirmations,
+            proof,
+        )?;
+        let event = usize::try_from(proof.log_index)
+            .ok()
+            .filter(|_| receipt.success)
+            .and_then(|log_index| receipt.logs.get(log_index))
+            .and_then(|log| LockEvent::decode(log, &self.config.bsc_bridge_contract))
+            .ok_or_else(|| {
+                msg!("The proven log is not a lock event of the bridge contract");
+                ProgramError::InvalidArgument
+            })?;
+
+        let token = match request.token_type {
+            TokenType::Bnb => [0u8; 20],
+            TokenType::Bep20(token) => token,
+        };
+        if event.token != token
+            || event.recipient != request.recipient.to_bytes()
+            || event.amount != request.amount
+            || event.nonce != request.nonce
+        {
+            msg!("The lock event does not match the transfer");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(proof.event_id(&header.hash))
     }
 
     /// Get bridge fee
@@ -236,9 +388,47 @@ pub enum BridgeInstruction {
     EmergencyWithdraw {
         amount: u128,
     },
+    /// Start the light client from an epoch header, see [`light_client::initialize`]
+    InitializeLightClient {
+        chain_id: u64,
+        epoch_length: u64,
+        checkpoint: Vec<u8>,
+    },
+    /// Add the next BSC header to the light client, see [`light_client::submit_header`]
+    SubmitHeader {
+        header: Vec<u8>,
+    },
+    /// Set up the attesting validators, see [`attestation::process_initialize_validators`]
+    InitializeValidators {
+        threshold_bps: u16,
+        validators: Vec<Validator>,
+    },
+    /// Attest to a transfer, see [`attestation::process_attest_transfer`]
+    AttestTransfer {
+        request: TransferRequest,
+        attestation: Attestation,
+    },
+    /// Attest to a validator set change, see [`attestation::process_attest_validator_change`]
+    AttestValidatorChange {
+        change: ValidatorChange,
+        attestation: Attestation,
+    },
 }
 
 /// Process bridge instruction
+///
+/// Every instruction takes the bridge account first, writable unless the
+/// instruction only reads its state. The instructions changing the
+/// configuration take the authority second, as a signer: `AddValidator`,
+/// `RemoveValidator`, `Pause`, `Unpause`, `UpdateConfig`,
+/// `EmergencyWithdraw`, `InitializeLightClient` and `InitializeValidators`.
+/// `Initialize` takes the authority it stores the same way, with the bridge
+/// account signing too.
+///
+/// `InitializeLightClient` takes the light client account third, and
+/// `SubmitHeader` second. `ProcessTransfer` takes the light client account,
+/// the account at [`processed_address`], the payer and the system program.
+/// The attestation instructions take the accounts their processors list.
 pub fn process_bridge_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -254,49 +444,254 @@ pub fn process_bridge_instruction(
 
     // Parse instruction
     let instruction = parse_bridge_instruction(instruction_data)?;
-    
-    // Initialize bridge state (in a real implementation, this would be loaded from account data)
-    let mut bridge_state = BridgeState::new();
+
+    let mut bridge_state = match instruction {
+        BridgeInstruction::Initialize { .. } => BridgeState::new(),
+        _ => BridgeState::unpack(&bridge_account.try_borrow_data()?)?,
+    };
 
     // Execute instruction
     match instruction {
         BridgeInstruction::Initialize { config } => {
+            if !bridge_account.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if BridgeState::unpack(&bridge_account.try_borrow_data()?).is_ok() {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            let authority = next_account_info(accounts_iter)?;
+            if !authority.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            bridge_state.is_initialized = true;
+            bridge_state.authority = *authority.key;
             bridge_state.config = config;
             solana_program::log::sol_log("Bridge initialized successfully");
         }
         BridgeInstruction::AddValidator { validator } => {
+            check_authority(&bridge_state, accounts_iter)?;
             bridge_state.add_validator(validator)?;
             solana_program::log::sol_log("Validator added successfully");
         }
         BridgeInstruction::RemoveValidator { eth_address } => {
+            check_authority(&bridge_state, accounts_iter)?;
             bridge_state.remove_validator(&eth_address)?;
             solana_program::log::sol_log("Validator removed successfully");
         }
         BridgeInstruction::ProcessTransfer { request } => {
-            bridge_state.process_transfer(request)?;
+            let light_client = next_light_client(&bridge_state, accounts_iter)?;
+            let processed_account = next_account_info(accounts_iter)?;
+            let payer = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+            if bridge_state.config.verification != Verification::LightClient {
+                msg!("The bridge releases transfers its validators attest to");
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            bridge_state.reset_daily_volume(Clock::get()?.unix_timestamp as u64);
+            let event_id =
+                bridge_state.process_transfer(request, &light_client.try_borrow_data()?)?;
+            mark_processed(
+                program_id,
+                bridge_account.key,
+                processed_account,
+                payer,
+                system_program,
+                &event_id,
+            )?;
             solana_program::log::sol_log("Transfer processed successfully");
         }
         BridgeInstruction::Pause => {
+            check_authority(&bridge_state, accounts_iter)?;
             bridge_state.config.paused = true;
             solana_program::log::sol_log("Bridge paused");
         }
         BridgeInstruction::Unpause => {
+            check_authority(&bridge_state, accounts_iter)?;
             bridge_state.config.paused = false;
             solana_program::log::sol_log("Bridge unpaused");
         }
         BridgeInstruction::UpdateConfig { new_config } => {
+            check_authority(&bridge_state, accounts_iter)?;
             bridge_state.config = new_config;
             solana_program::log::sol_log("Configuration updated");
         }
         BridgeInstruction::EmergencyWithdraw { amount } => {
+            check_authority(&bridge_state, accounts_iter)?;
             // In a real implementation, this would transfer funds to emergency account
             solana_program::log::sol_log(&format!("Emergency withdrawal: {}", amount));
+            return Ok(());
+        }
+        BridgeInstruction::InitializeLightClient { chain_id, epoch_length, checkpoint } => {
+            check_authority(&bridge_state, accounts_iter)?;
+            let light_client = next_account_info(accounts_iter)?;
+            if light_client.owner != program_id || !light_client.is_writable {
+                msg!("Error: Invalid light client account");
+                return Err(ProgramError::InvalidArgument);
+            }
+            light_client::initialize(
+                &mut light_client.try_borrow_mut_data()?,
+                chain_id,
+                epoch_length,
+                bridge_state.config.min_confirmations,
+                &checkpoint,
+            )?;
+            bridge_state.light_client = Some(*light_client.key);
+        }
+        BridgeInstruction::SubmitHeader { header } => {
+            let light_client = next_light_client(&bridge_state, accounts_iter)?;
+            return light_client::submit_header(&mut light_client.try_borrow_mut_data()?, &header);
+        }
+        BridgeInstruction::InitializeValidators { threshold_bps, validators } => {
+            check_authority(&bridge_state, accounts_iter)?;
+            attestation::process_initialize_validators(
+                program_id,
+                bridge_account.key,
+                accounts_iter,
+                threshold_bps,
+                validators,
+            )?;
+            return Ok(());
+        }
+        BridgeInstruction::AttestTransfer { request, attestation } => {
+            attestation::process_attest_transfer(
+                program_id,
+                bridge_account.key,
+                accounts_iter,
+                &mut bridge_state,
+                &request,
+                &attestation,
+            )?;
+        }
+        BridgeInstruction::AttestValidatorChange { change, attestation } => {
+            return attestation::process_attest_validator_change(
+                program_id,
+                bridge_account.key,
+                accounts_iter,
+                &change,
+                &attestation,
+            );
         }
     }
 
+    if !bridge_account.is_writable {
+        msg!("Error: The bridge account must be writable");
+        return Err(ProgramError::InvalidArgument);
+    }
+    bridge_state.pack(&mut bridge_account.try_borrow_mut_data()?)
+}
+
+/// Next account, which must be the bridge's authority, signing
+fn check_authority(
+    bridge_state: &BridgeState,
+    accounts_iter: &mut std::slice::Iter<AccountInfo>,
+) -> ProgramResult {
+    let authority = next_account_info(accounts_iter)?;
+    if *authority.key != bridge_state.authority || !authority.is_signer {
+        msg!("Error: The bridge authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
     Ok(())
 }
 
+/// Next account, which must be the bridge's light client account
+fn next_light_client<'a, 'b>(
+    bridge_state: &BridgeState,
+    accounts_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    let light_client = next_account_info(accounts_iter)?;
+    if bridge_state.light_client != Some(*light_client.key) {
+        msg!("Error: Not the bridge's light client account");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(light_client)
+}
+
+/// Address of the account recording the release of the lock event `event_id`
+/// through the bridge account `bridge`
+pub fn processed_address(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    event_id: &[u8; 32],
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROCESSED_SEED, bridge.as_ref(), event_id], program_id)
+}
+
+/// Create the account recording the release of `event_id`, failing if it exists
+fn mark_processed<'a>(
+    program_id: &Pubkey,
+    bridge: &Pubkey,
+    processed_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    event_id: &[u8; 32],
+) -> ProgramResult {
+    let (address, bump_seed) = processed_address(program_id, bridge, event_id);
+    if *processed_account.key != address {
+        msg!("Error: Invalid processed transfer account");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if processed_account.owner == program_id {
+        msg!("The transfer was already processed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    create_account(
+        program_id,
+        processed_account,
+        payer,
+        system_program,
+        0,
+        &[PROCESSED_SEED, bridge.as_ref(), event_id, &[bump_seed]],
+    )
+}
+
+/// Create the program account `account` at the address `seeds` derive
+///
+/// An address already holding lamports is topped up to rent exemption and
+/// assigned, since creating it would fail.
+pub(crate) fn create_account<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent_lamports = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                rent_lamports,
+                space as u64,
+                program_id,
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    let shortfall = rent_lamports.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke_signed(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, program_id),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
 /// Parse bridge instruction
 fn parse_bridge_instruction(data: &[u8]) -> Result<BridgeInstruction, ProgramError> {
     if data.is_empty() {
@@ -305,8 +700,9 @@ fn parse_bridge_instruction(data: &[u8]) -> Result<BridgeInstruction, ProgramErr
 
     match data[0] {
         0 => {
-            // Initialize
-            let config = BridgeConfig::default(); // Simplified
+            // Initialize: the borsh encoded config
+            let config = BridgeConfig::try_from_slice(&data[1..])
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
             Ok(BridgeInstruction::Initialize { config })
         }
         1 => {
@@ -334,24 +730,17 @@ fn parse_bridge_instruction(data: &[u8]) -> Result<BridgeInstruction, ProgramErr
             Ok(BridgeInstruction::RemoveValidator { eth_address })
         }
         3 => {
-            // ProcessTransfer
-            let request = TransferRequest {
-                source_tx_hash: [0u8; 32],
-                source_block: 0,
-                recipient: Pubkey::default(),
-                amount: 0,
-                token_type: TokenType::Bnb,
-                token_contract: None,
-                merkle_proof: vec![],
-                nonce: 0,
-            };
+            // ProcessTransfer: the borsh encoded request
+            let request = TransferRequest::try_from_slice(&data[1..])
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
             Ok(BridgeInstruction::ProcessTransfer { request })
         }
         4 => Ok(BridgeInstruction::Pause),
         5 => Ok(BridgeInstruction::Unpause),
         6 => {
-            // UpdateConfig
-            let new_config = BridgeConfig::default();
+            // UpdateConfig: the borsh encoded config
+            let new_config = BridgeConfig::try_from_slice(&data[1..])
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
             Ok(BridgeInstruction::UpdateConfig { new_config })
         }
         7 => {
@@ -362,6 +751,40 @@ fn parse_bridge_instruction(data: &[u8]) -> Result<BridgeInstruction, ProgramErr
             let amount = u128::from_le_bytes(data[1..17].try_into().unwrap());
             Ok(BridgeInstruction::EmergencyWithdraw { amount })
         }
+        8 => {
+            // InitializeLightClient: chain id and epoch length, then the checkpoint header
+            if data.len() < 17 {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            let chain_id = u64::from_le_bytes(data[1..9].try_into().unwrap());
+            let epoch_length = u64::from_le_bytes(data[9..17].try_into().unwrap());
+            let checkpoint = data[17..].to_vec();
+            Ok(BridgeInstruction::InitializeLightClient { chain_id, epoch_length, checkpoint })
+        }
+        9 => {
+            // SubmitHeader: the RLP encoded header
+            Ok(BridgeInstruction::SubmitHeader { header: data[1..].to_vec() })
+        }
+        10 => {
+            // InitializeValidators: the borsh encoded threshold and validators
+            let (threshold_bps, validators) = <(u16, Vec<Validator>)>::try_from_slice(&data[1..])
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
+            Ok(BridgeInstruction::InitializeValidators { threshold_bps, validators })
+        }
+        11 => {
+            // AttestTransfer: the borsh encoded request and attestation
+            let (request, attestation) =
+                <(TransferRequest, Attestation)>::try_from_slice(&data[1..])
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+            Ok(BridgeInstruction::AttestTransfer { request, attestation })
+        }
+        12 => {
+            // AttestValidatorChange: the borsh encoded change and attestation
+            let (change, attestation) =
+                <(ValidatorChange, Attestation)>::try_from_slice(&data[1..])
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+            Ok(BridgeInstruction::AttestValidatorChange { change, attestation })
+        }
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -0,0 +1,514 @@
+//! # BSC Light Client
+//!
+//! Follows BNB Smart Chain from a trusted checkpoint, one header at a time,
+//! checking each the way Parlia does:
+//!
+//! - it extends a header the client holds, with a timestamp no earlier than
+//!   its parent's
+//! - it is sealed by a validator of the set sealing its height, who is its
+//!   coinbase and has not sealed a full turn within the last
+//!   [`ValidatorSet::miner_history_check_len`] blocks of its branch
+//! - its difficulty says whether the validator was in turn
+//!
+//! An epoch header lists the next validator set, which takes over once the
+//! current set has sealed `miner_history_check_len` more blocks.
+//!
+//! ## Forks
+//!
+//! Two validators can seal competing headers at one height, one in turn and
+//! one out of turn, and a reorg can replace the tip. The client keeps up to
+//! [`FORKS`] headers per height and follows the branch with the highest
+//! total difficulty, the first seen on a tie, as Parlia does. A header may
+//! fork off any header with fewer than `finality_depth` confirmations; deeper
+//! blocks are final, and only those on the followed branch can prove
+//! receipts, see [`verify_receipt`].
+//!
+//! The light client lives in one account: the state below, followed by a
+//! ring of the headers of the last [`MAX_HEADERS`] heights, see
+//! [`HeaderRecord`].
+//!
+//! The checkpoint must be an epoch header whose validator set already seals
+//! its successors, one that did not change the set. If it did, the next
+//! headers fail to verify rather than verify against the wrong set.
+
+use {
+    crate::{
+        header::{BscHeader, EpochValidators, DIFF_IN_TURN, DIFF_NO_TURN},
+        mpt,
+        receipt::{Receipt, ReceiptProof},
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{entrypoint::ProgramResult, msg, program_error::ProgramError},
+};
+
+/// Largest validator set followed
+pub const MAX_VALIDATORS: usize = 64;
+
+/// Heights kept, and so the oldest block a receipt can be proven in
+pub const MAX_HEADERS: u64 = 1024;
+
+/// Competing headers kept per height
+pub const FORKS: usize = 2;
+
+/// Space the borsh encoded [`ValidatorSet`] can take
+const VALIDATOR_SET_LEN: usize = 4 + 20 * MAX_VALIDATORS + 1;
+
+/// Space the borsh encoded [`LightClient`] can take, ahead of the header ring
+pub const STATE_LEN: usize = 1
+    + 8 * 4
+    + VALIDATOR_SET_LEN
+    + 8
+    + (1 + VALIDATOR_SET_LEN)
+    + (1 + 8 + VALIDATOR_SET_LEN)
+    + 8
+    + 32;
+
+/// Space a light client account needs
+pub const LIGHT_CLIENT_LEN: usize = STATE_LEN + MAX_HEADERS as usize * FORKS * HeaderRecord::LEN;
+
+/// Validators sealing blocks, and how many consecutive blocks each seals in turn
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorSet {
+    /// Addresses in ascending order, the order turns go in
+    pub validators: Vec<[u8; 20]>,
+    pub turn_length: u8,
+}
+
+impl ValidatorSet {
+    /// Set listed by an epoch header
+    ///
+    /// Headers from before Bohr list no turn length, and each validator seals
+    /// one block in turn.
+    pub fn from_epoch(epoch: EpochValidators) -> Result<Self, ProgramError> {
+        let mut validators = epoch.validators;
+        validators.sort_unstable();
+        validators.dedup();
+        if validators.len() > MAX_VALIDATORS {
+            msg!(
+                "Validator sets are limited to {} validators",
+                MAX_VALIDATORS
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        let set = Self {
+            validators,
+            turn_length: epoch.turn_length.unwrap_or(1),
+        };
+        if set.turn_length == 0 || set.miner_history_check_len() >= MAX_HEADERS / 2 {
+            msg!("The turn length is out of range");
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(set)
+    }
+
+    /// Blocks back that a validator must not have sealed a full turn in
+    pub fn miner_history_check_len(&self) -> u64 {
+        (self.validators.len() as u64 / 2 + 1) * u64::from(self.turn_length) - 1
+    }
+
+    /// Validator whose turn it is to seal block `number`
+    pub fn in_turn(&self, number: u64) -> &[u8; 20] {
+        let turn = number / u64::from(self.turn_length);
+        &self.validators[(turn % self.validators.len() as u64) as usize]
+    }
+}
+
+/// What the light client keeps of a header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderRecord {
+    pub number: u64,
+    pub hash: [u8; 32],
+    pub parent_hash: [u8; 32],
+    pub receipts_root: [u8; 32],
+    /// Validator that sealed the header
+    pub signer: [u8; 20],
+    pub timestamp: u64,
+    /// Difficulty of the branch from the checkpoint up to the header
+    pub total_difficulty: u64,
+}
+
+impl HeaderRecord {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 20 + 8 + 8;
+
+    fn new(header: &BscHeader, signer: [u8; 20], total_difficulty: u64) -> Self {
+        Self {
+            number: header.number,
+            hash: header.hash,
+            parent_hash: header.parent_hash,
+            receipts_root: header.receipts_root,
+            signer,
+            timestamp: header.timestamp,
+            total_difficulty,
+        }
+    }
+
+    fn pack(&self, dst: &mut [u8]) {
+        dst[..8].copy_from_slice(&self.number.to_le_bytes());
+        dst[8..40].copy_from_slice(&self.hash);
+        dst[40..72].copy_from_slice(&self.parent_hash);
+        dst[72..104].copy_from_slice(&self.receipts_root);
+        dst[104..124].copy_from_slice(&self.signer);
+        dst[124..132].copy_from_slice(&self.timestamp.to_le_bytes());
+        dst[132..].copy_from_slice(&self.total_difficulty.to_le_bytes());
+    }
+
+    fn unpack(src: &[u8]) -> Self {
+        Self {
+            number: u64::from_le_bytes(src[..8].try_into().unwrap()),
+            hash: src[8..40].try_into().unwrap(),
+            parent_hash: src[40..72].try_into().unwrap(),
+            receipts_root: src[72..104].try_into().unwrap(),
+            signer: src[104..124].try_into().unwrap(),
+            timestamp: u64::from_le_bytes(src[124..132].try_into().unwrap()),
+            total_difficulty: u64::from_le_bytes(src[132..].try_into().unwrap()),
+        }
+    }
+}
+
+/// State of a light client account
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LightClient {
+    pub is_initialized: bool,
+    /// Chain id the seals sign over
+    pub chain_id: u64,
+    /// Blocks between epoch headers
+    pub epoch_length: u64,
+    /// Confirmations after which a block is final
+    pub finality_depth: u64,
+    /// Number of the checkpoint, the first header held
+    pub checkpoint_number: u64,
+    /// Validators sealing the blocks from `validators_since` on
+    pub validators: ValidatorSet,
+    pub validators_since: u64,
+    /// Validators sealing the blocks before `validators_since`, for forks
+    /// across the last switch
+    pub previous_validators: Option<ValidatorSet>,
+    /// Set listed by the last epoch header, and the first block it seals
+    pub next_validators: Option<(u64, ValidatorSet)>,
+    /// Tip of the followed branch
+    pub latest_number: u64,
+    pub latest_hash: [u8; 32],
+}
+
+impl LightClient {
+    /// State held by the account data `data`
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < LIGHT_CLIENT_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let client = Self::deserialize(&mut &data[..STATE_LEN])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if !client.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(client)
+    }
+
+    fn pack(&self, data: &mut [u8]) {
+        let packed = borsh::to_vec(self).unwrap();
+        data[..packed.len()].copy_from_slice(&packed);
+    }
+
+    /// Header `hash` of block `number`, `None` if the ring does not hold it
+    pub fn find(data: &[u8], number: u64, hash: &[u8; 32]) -> Option<HeaderRecord> {
+        (0..FORKS)
+            .map(|slot| HeaderRecord::unpack(&data[record_range(number, slot)]))
+            .find(|record| record.number == number && record.hash == *hash)
+    }
+
+    /// Header of block `number` on the followed branch, `None` if the ring
+    /// does not hold it
+    pub fn header(&self, data: &[u8], number: u64) -> Option<HeaderRecord> {
+        if number > self.latest_number {
+            return None;
+        }
+        let mut record = Self::find(data, self.latest_number, &self.latest_hash)?;
+        while record.number > number {
+            record = Self::find(data, record.number - 1, &record.parent_hash)?;
+        }
+        Some(record)
+    }
+
+    /// Validators sealing block `number`
+    fn validator_set(&self, number: u64) -> Result<&ValidatorSet, ProgramError> {
+        if let Some((activation, next)) = &self.next_validators {
+            if number >= *activation {
+                return Ok(next);
+            }
+        }
+        if number >= self.validators_since {
+            return Ok(&self.validators);
+        }
+        self.previous_validators.as_ref().ok_or_else(|| {
+            msg!("No validator set is known for block {}", number);
+            ProgramError::InvalidArgument
+        })
+    }
+
+    /// Store `record` next to the headers competing with it at its height,
+    /// replacing the weakest off the followed branch if all slots are taken
+    fn store_header(&self, data: &mut [u8], record: &HeaderRecord) -> ProgramResult {
+        let held: Vec<_> = (0..FORKS)
+            .map(|slot| HeaderRecord::unpack(&data[record_range(record.number, slot)]))
+            .collect();
+        let free = held
+            .iter()
+            .position(|held| held.number != record.number || held.hash == [0; 32]);
+        let slot = match free {
+            Some(slot) => slot,
+            None => {
+                let followed = self.header(data, record.number).map(|header| header.hash);
+                held.iter()
+                    .enumerate()
+                    .filter(|(_, held)| {
+                        Some(held.hash) != followed
+                            && held.total_difficulty < record.total_difficulty
+                    })
+                    .min_by_key(|(_, held)| held.total_difficulty)
+                    .map(|(slot, _)| slot)
+                    .ok_or_else(|| {
+                        msg!(
+                            "The light client holds stronger headers at {}",
+                            record.number
+                        );
+                        ProgramError::InvalidArgument
+                    })?
+            }
+        };
+        record.pack(&mut data[record_range(record.number, slot)]);
+        Ok(())
+    }
+
+    /// Check `header` extends `parent` under Parlia, returning its signer
+    fn verify_header(
+        &self,
+        header: &BscHeader,
+        parent: &HeaderRecord,
+        data: &[u8],
+    ) -> Result<[u8; 20], ProgramError> {
+        if header.timestamp < parent.timestamp {
+            msg!("The header is older than its parent");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let signer = header.signer()?;
+        if signer != header.coinbase {
+            msg!("The header is not sealed by its coinbase");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let set = self.validator_set(header.number)?;
+        if set.validators.binary_search(&signer).is_err() {
+            msg!("The header is not sealed by a validator");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Seals on the header's own branch, which a header below the
+        // checkpoint ends
+        let window = set.miner_history_check_len().min(header.number - 1);
+        let mut recent_seals = 0;
+        let mut ancestor = Some(*parent);
+        for number in (header.number - window..header.number).rev() {
+            let Some(record) = ancestor else {
+                if number >= self.checkpoint_number {
+                    msg!(
+                        "The light client no longer holds block {} of the branch",
+                        number
+                    );
+                    return Err(ProgramError::InvalidArgument);
+                }
+                break;
+            };
+            if record.signer == signer {
+                recent_seals += 1;
+            }
+            ancestor = Self::find(data, number - 1, &record.parent_hash);
+        }
+        if recent_seals >= usize::from(set.turn_length) {
+            msg!("The validator sealed a full turn recently");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let difficulty = if *set.in_turn(header.number) == signer {
+            DIFF_IN_TURN
+        } else {
+            DIFF_NO_TURN
+        };
+        if header.difficulty != difficulty {
+            msg!("The header's difficulty should be {}", difficulty);
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(signer)
+    }
+}
+
+/// Range of account data holding slot `slot` of block `number`
+fn record_range(number: u64, slot: usize) -> std::ops::Range<usize> {
+    let index = (number % MAX_HEADERS) as usize * FORKS + slot;
+    let start = STATE_LEN + index * HeaderRecord::LEN;
+    start..start + HeaderRecord::LEN
+}
+
+/// Start following the chain `chain_id` from the epoch header `checkpoint`,
+/// treating blocks with `finality_depth` confirmations as final
+pub fn initialize(
+    data: &mut [u8],
+    chain_id: u64,
+    epoch_length: u64,
+    finality_depth: u64,
+    checkpoint: &[u8],
+) -> ProgramResult {
+    if data.len() < LIGHT_CLIENT_LEN {
+        msg!(
+            "A light client account needs {} bytes of space",
+            LIGHT_CLIENT_LEN
+        );
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    if data[..STATE_LEN].iter().any(|byte| *byte != 0) {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let header = BscHeader::decode(checkpoint, chain_id)?;
+    if epoch_length == 0 || header.number % epoch_length != 0 {
+        msg!("The checkpoint must be an epoch header");
+        return Err(ProgramError::InvalidArgument);
+    }
+    // Forks never span two validator set switches
+    if finality_depth == 0 || finality_depth >= epoch_length || finality_depth > MAX_HEADERS / 2 {
+        msg!(
+            "The finality depth must be below the epoch length and {}",
+            MAX_HEADERS / 2
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    let validators = ValidatorSet::from_epoch(header.epoch_validators()?)?;
+
+    let client = LightClient {
+        is_initialized: true,
+        chain_id,
+        epoch_length,
+        finality_depth,
+        checkpoint_number: header.number,
+        validators,
+        validators_since: header.number,
+        previous_validators: None,
+        next_validators: None,
+        latest_number: header.number,
+        latest_hash: header.hash,
+    };
+    client.store_header(data, &HeaderRecord::new(&header, header.signer()?, 0))?;
+    client.pack(data);
+
+    msg!("BSC light client checkpoint: {}", header.number);
+    Ok(())
+}
+
+/// Verify the RLP encoded header `raw`, which must extend a header with fewer
+/// than `finality_depth` confirmations, and store it
+///
+/// The header becomes the tip if its branch has a higher total difficulty
+/// than the followed one.
+pub fn submit_header(data: &mut [u8], raw: &[u8]) -> ProgramResult {
+    let mut client = LightClient::unpack(data)?;
+    let header = BscHeader::decode(raw, client.chain_id)?;
+    let parent = header
+        .number
+        .checked_sub(1)
+        .and_then(|number| LightClient::find(data, number, &header.parent_hash))
+        .ok_or_else(|| {
+            msg!(
+                "Header {} does not extend a header the light client holds",
+                header.number
+            );
+            ProgramError::InvalidArgument
+        })?;
+    if client.latest_number.saturating_sub(parent.number) >= client.finality_depth {
+        msg!(
+            "Block {} is final, headers can no longer fork off it",
+            parent.number
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    if LightClient::find(data, header.number, &header.hash).is_some() {
+        msg!("The light client already holds header {}", header.number);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let signer = client.verify_header(&header, &parent, data)?;
+
+    if header.number % client.epoch_length == 0 {
+        let next = ValidatorSet::from_epoch(header.epoch_validators()?)?;
+        let activation = header.number
+            + client
+                .validator_set(header.number)?
+                .miner_history_check_len()
+            + 1;
+        // Competing epoch headers must agree on the set
+        let known = match &client.next_validators {
+            Some((pending, set)) if *pending == activation => Some(set),
+            _ if client.validators_since == activation => Some(&client.validators),
+            _ => None,
+        };
+        match known {
+            Some(set) if *set != next => {
+                msg!("Epoch header {} lists another validator set", header.number);
+                return Err(ProgramError::InvalidArgument);
+            }
+            Some(_) => {}
+            None => client.next_validators = Some((activation, next)),
+        }
+    }
+
+    let record = HeaderRecord::new(&header, signer, parent.total_difficulty + header.difficulty);
+    client.store_header(data, &record)?;
+
+    if let Some((activation, _)) = &client.next_validators {
+        if header.number + 1 >= *activation {
+            let (activation, next) = client.next_validators.take().unwrap();
+            client.previous_validators = Some(std::mem::replace(&mut client.validators, next));
+            client.validators_since = activation;
+            msg!("BSC validator set switched after block {}", activation - 1);
+        }
+    }
+
+    let tip = LightClient::find(data, client.latest_number, &client.latest_hash);
+    if record.total_difficulty > tip.map_or(0, |tip| tip.total_difficulty) {
+        if record.parent_hash != client.latest_hash {
+            msg!("BSC reorg to block {}", record.number);
+        }
+        client.latest_number = record.number;
+        client.latest_hash = record.hash;
+    }
+    client.pack(data);
+    Ok(())
+}
+
+/// Header of block `block_number` on the followed branch and the receipt
+/// `proof` proves in it
+///
+/// Fails unless at least `min_confirmations` headers, and no fewer than the
+/// finality depth, were followed on top of the block, and the block is
+/// still in the ring.
+pub fn verify_receipt(
+    data: &[u8],
+    block_number: u64,
+    min_confirmations: u64,
+    proof: &ReceiptProof,
+) -> Result<(HeaderRecord, Receipt), ProgramError> {
+    let client = LightClient::unpack(data)?;
+    let min_confirmations = min_confirmations.max(client.finality_depth);
+    let confirmations = client.latest_number.checked_sub(block_number);
+    if !matches!(confirmations, Some(confirmations) if confirmations >= min_confirmations) {
+        msg!(
+            "Block {} needs {} confirmations",
+            block_number,
+            min_confirmations
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    let header = client.header(data, block_number).ok_or_else(|| {
+        msg!("The light client does not hold block {}", block_number);
+        ProgramError::InvalidArgument
+    })?;
+
+    let encoded = mpt::verify_proof(&header.receipts_root, &proof.key(), &proof.nodes)?;
+    Ok((header, Receipt::decode(&encoded)?))
+}
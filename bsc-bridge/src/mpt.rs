@@ -0,0 +1,119 @@
+//! # Merkle-Patricia Proofs
+//!
+//! A proof is the list of trie nodes on the path from the root to a key, each
+//! RLP encoded, the way `eth_getProof` and receipt proof tools return them.
+//! A node is referenced by its Keccak-256 hash, or embedded in its parent if
+//! its encoding is shorter than 32 bytes.
+
+use {
+    bsc_evm_rlp::{
+        keccak256,
+        rlp::{decode_bytes, decode_item, decode_list, Item},
+    },
+    solana_program::{msg, program_error::ProgramError},
+};
+
+/// Value stored at `key` in the trie with root `root`, proven by `proof`
+///
+/// Fails unless the proof is exactly the path to `key` and `key` holds a
+/// value.
+pub fn verify_proof(
+    root: &[u8; 32],
+    key: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<Vec<u8>, ProgramError> {
+    let key: Vec<u8> = key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect();
+    let mut proof = proof.iter();
+    let mut next = NodeRef::Hash(*root);
+    let mut position = 0;
+
+    loop {
+        let node = match next {
+            NodeRef::Hash(hash) => {
+                let node = proof.next().ok_or_else(|| invalid_proof("the proof is incomplete"))?;
+                if keccak256(node) != hash {
+                    return Err(invalid_proof("a node does not match its hash"));
+                }
+                node.as_slice()
+            }
+            NodeRef::Embedded(node) => node,
+        };
+        let items = decode_list(node).map_err(|_| invalid_proof("a node is malformed"))?;
+
+        match items.len() {
+            // Branch: a child for each nibble, then the value ending here
+            17 => match key.get(position) {
+                Some(&nibble) => {
+                    next = node_ref(items[nibble as usize])?;
+                    position += 1;
+                }
+                None => return proven_value(items[16], proof.len()),
+            },
+            // Extension or leaf: the compact encoded path, then the child or value
+            2 => {
+                let (is_leaf, path) = decode_path(items[0])?;
+                if !key[position..].starts_with(&path) {
+                    return Err(invalid_proof("the key is not in the trie"));
+                }
+                position += path.len();
+                if is_leaf {
+                    if position != key.len() {
+                        return Err(invalid_proof("the key is not in the trie"));
+                    }
+                    return proven_value(items[1], proof.len());
+                }
+                next = node_ref(items[1])?;
+            }
+            _ => return Err(invalid_proof("a node is malformed")),
+        }
+    }
+}
+
+/// How a node refers to a child
+enum NodeRef<'a> {
+    Hash([u8; 32]),
+    /// Encoding of a child shorter than a hash
+    Embedded(&'a [u8]),
+}
+
+fn node_ref(encoded: &[u8]) -> Result<NodeRef<'_>, ProgramError> {
+    match decode_item(encoded).map_err(|_| invalid_proof("a node is malformed"))?.0 {
+        Item::Bytes(hash) if hash.len() == 32 => Ok(NodeRef::Hash(hash.try_into().unwrap())),
+        Item::Bytes([]) => Err(invalid_proof("the key is not in the trie")),
+        Item::Bytes(_) => Err(invalid_proof("a node is malformed")),
+        Item::List(_) => Ok(NodeRef::Embedded(encoded)),
+    }
+}
+
+/// Whether a compact encoded path ends in a leaf, and its nibbles
+fn decode_path(encoded: &[u8]) -> Result<(bool, Vec<u8>), ProgramError> {
+    let bytes = decode_bytes(encoded).map_err(|_| invalid_proof("a node is malformed"))?;
+    let (&flags, rest) = bytes.split_first().ok_or_else(|| invalid_proof("a path is empty"))?;
+    let is_leaf = match flags >> 4 {
+        0 | 1 => false,
+        2 | 3 => true,
+        _ => return Err(invalid_proof("a path is malformed")),
+    };
+    let mut path = Vec::with_capacity(rest.len() * 2 + 1);
+    if flags & 0x10 != 0 {
+        path.push(flags & 0x0f);
+    }
+    path.extend(rest.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]));
+    Ok((is_leaf, path))
+}
+
+fn proven_value(encoded: &[u8], unused_nodes: usize) -> Result<Vec<u8>, ProgramError> {
+    if unused_nodes > 0 {
+        return Err(invalid_proof("the proof has nodes past the key"));
+    }
+    match decode_bytes(encoded) {
+        Ok([]) => Err(invalid_proof("the key is not in the trie")),
+        Ok(value) => Ok(value.to_vec()),
+        Err(_) => Err(invalid_proof("a value is malformed")),
+    }
+}
+
+fn invalid_proof(reason: &str) -> ProgramError {
+    msg!("Invalid Merkle-Patricia proof: {}", reason);
+    ProgramError::InvalidArgument
+}
@@ -0,0 +1,156 @@
+//! # Receipts and Lock Events
+//!
+//! A BSC transfer starts with the bridge contract emitting a [`LockEvent`].
+//! The bridge proves it with a [`ReceiptProof`]: the receipt of the
+//! transaction that emitted it, proven against the receipts root of a header
+//! the light client stored, see [`light_client`](crate::light_client).
+//!
+//! Receipts are keyed in the receipt trie by the RLP encoding of the
+//! transaction's index in the block. A typed receipt is its type byte followed
+//! by the RLP list of its fields, a legacy receipt the list alone.
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    bsc_evm_rlp::{
+        keccak256,
+        rlp::{decode_bytes, decode_item, decode_list, decode_u64, encode_uint, split_items, Item},
+    },
+    solana_program::{msg, program_error::ProgramError},
+};
+
+/// Signature of the event the BSC bridge contract emits for each lock
+///
+/// `Locked(address indexed token, address indexed sender, bytes32 indexed
+/// recipient, uint256 amount, uint256 nonce)`, where the token is the zero
+/// address for BNB and the recipient a Solana address.
+pub const LOCK_EVENT_SIGNATURE: &str = "Locked(address,address,bytes32,uint256,uint256)";
+
+/// Proof that a transaction's receipt is in a BSC block
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptProof {
+    /// Index of the transaction in the block
+    pub tx_index: u64,
+    /// Index of the lock event among the receipt's logs
+    pub log_index: u64,
+    /// Receipt trie nodes from the root to the receipt
+    pub nodes: Vec<Vec<u8>>,
+}
+
+impl ReceiptProof {
+    /// Key of the receipt in the receipt trie
+    pub fn key(&self) -> Vec<u8> {
+        let mut key = Vec::new();
+        encode_uint(&self.tx_index.to_be_bytes(), &mut key);
+        key
+    }
+
+    /// Identifier of the proven event in the block `block_hash`, unique across
+    /// all of BSC
+    pub fn event_id(&self, block_hash: &[u8; 32]) -> [u8; 32] {
+        keccak256(
+            &[&block_hash[..], &self.tx_index.to_be_bytes(), &self.log_index.to_be_bytes()]
+                .concat(),
+        )
+    }
+}
+
+/// A log emitted by a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    pub address: [u8; 20],
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+/// The fields of a receipt the bridge uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    /// Whether the transaction succeeded
+    pub success: bool,
+    pub logs: Vec<Log>,
+}
+
+impl Receipt {
+    /// Decode a receipt as it is stored in the receipt trie
+    pub fn decode(encoded: &[u8]) -> Result<Self, ProgramError> {
+        let list = match encoded.first() {
+            Some(tx_type) if *tx_type < 0x80 => &encoded[1..],
+            _ => encoded,
+        };
+        let fields = decode_list(list).map_err(|_| invalid_receipt())?;
+        let [status, _cumulative_gas_used, _bloom, logs] = fields[..] else {
+            return Err(invalid_receipt());
+        };
+
+        let success = decode_u64(status).map_err(|_| invalid_receipt())? == 1;
+        let logs = decode_list(logs)
+            .map_err(|_| invalid_receipt())?
+            .into_iter()
+            .map(decode_log)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { success, logs })
+    }
+}
+
+fn decode_log(encoded: &[u8]) -> Result<Log, ProgramError> {
+    let fields = decode_list(encoded).map_err(|_| invalid_receipt())?;
+    let [address, topics, data] = fields[..] else {
+        return Err(invalid_receipt());
+    };
+    let topics = match decode_item(topics).map_err(|_| invalid_receipt())?.0 {
+        Item::List(payload) => split_items(payload).map_err(|_| invalid_receipt())?,
+        Item::Bytes(_) => return Err(invalid_receipt()),
+    };
+
+    Ok(Log {
+        address: fixed(address)?,
+        topics: topics.into_iter().map(fixed).collect::<Result<_, _>>()?,
+        data: decode_bytes(data).map_err(|_| invalid_receipt())?.to_vec(),
+    })
+}
+
+/// A lock recorded by the BSC bridge contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockEvent {
+    /// BEP-20 token locked, the zero address for BNB
+    pub token: [u8; 20],
+    pub sender: [u8; 20],
+    /// Solana address to release the tokens to
+    pub recipient: [u8; 32],
+    pub amount: u128,
+    pub nonce: u64,
+}
+
+impl LockEvent {
+    /// The lock `log` records, `None` if it is not a lock event of `bridge_contract`
+    pub fn decode(log: &Log, bridge_contract: &[u8; 20]) -> Option<Self> {
+        if log.address != *bridge_contract
+            || log.topics.len() != 4
+            || log.topics[0] != keccak256(LOCK_EVENT_SIGNATURE.as_bytes())
+            || log.data.len() != 64
+        {
+            return None;
+        }
+        // The amount must fit in a `u128` and the nonce in a `u64`
+        if log.data[..16].iter().chain(&log.data[32..56]).any(|byte| *byte != 0) {
+            return None;
+        }
+        Some(Self {
+            token: log.topics[1][12..].try_into().unwrap(),
+            sender: log.topics[2][12..].try_into().unwrap(),
+            recipient: log.topics[3],
+            amount: u128::from_be_bytes(log.data[16..32].try_into().unwrap()),
+            nonce: u64::from_be_bytes(log.data[56..].try_into().unwrap()),
+        })
+    }
+}
+
+/// Byte string field of exactly `N` bytes
+fn fixed<const N: usize>(encoded: &[u8]) -> Result<[u8; N], ProgramError> {
+    decode_bytes(encoded).map_err(|_| invalid_receipt())?.try_into().map_err(|_| invalid_receipt())
+}
+
+fn invalid_receipt() -> ProgramError {
+    msg!("The proven receipt is malformed");
+    ProgramError::InvalidArgument
+}
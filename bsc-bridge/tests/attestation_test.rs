@@ -0,0 +1,312 @@
+//! Validator attestations: weighted thresholds, both signature schemes and
+//! validator set changes.
+
+use bsc_bridge::{
+    attestation::{
+        change_digest, transfer_digest, Attestation, Attestations, BridgeValidators,
+        ValidatorChange,
+    },
+    receipt::ReceiptProof,
+    BridgeState, TokenType, TransferRequest, Validator,
+};
+use bsc_evm_rlp::keccak256;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+const PROGRAM_ID: Pubkey = Pubkey::new_from_array([0x42; 32]);
+const BRIDGE: Pubkey = Pubkey::new_from_array([0xb0; 32]);
+
+/// A validator's secp256k1 key
+fn secret(seed: u8) -> libsecp256k1::SecretKey {
+    libsecp256k1::SecretKey::parse(&[seed; 32]).unwrap()
+}
+
+fn eth_address(seed: u8) -> [u8; 20] {
+    let public_key = libsecp256k1::PublicKey::from_secret_key(&secret(seed)).serialize();
+    keccak256(&public_key[1..])[12..].try_into().unwrap()
+}
+
+/// A validator attesting with the secp256k1 key `seed` or the ed25519 key
+/// `Pubkey::new_from_array([seed; 32])`
+fn validator(seed: u8, weight: u64) -> Validator {
+    Validator {
+        eth_address: eth_address(seed),
+        pubkey: Pubkey::new_from_array([seed; 32]),
+        weight,
+        active: true,
+    }
+}
+
+/// Attestation signed with the secp256k1 key `seed`, with an Ethereum style recovery id
+fn sign(seed: u8, digest: &[u8; 32]) -> Attestation {
+    let message = libsecp256k1::Message::parse(digest);
+    let (signature, recovery_id) = libsecp256k1::sign(&message, &secret(seed));
+    let mut bytes = [0u8; 65];
+    bytes[..64].copy_from_slice(&signature.serialize());
+    bytes[64] = recovery_id.serialize() + 27;
+    Attestation::Secp256k1 { signature: bytes }
+}
+
+fn request(nonce: u64) -> TransferRequest {
+    TransferRequest {
+        source_tx_hash: [0x7a; 32],
+        source_block: 1_000,
+        recipient: Pubkey::new_from_array([0x99; 32]),
+        amount: 10_u128.pow(18),
+        token_type: TokenType::Bnb,
+        token_contract: None,
+        receipt_proof: ReceiptProof {
+            tx_index: 0,
+            log_index: 0,
+            nodes: vec![],
+        },
+        nonce,
+    }
+}
+
+/// Record the secp256k1 attestation of `seed`, returning whether it reached the threshold
+fn attest(
+    validators: &BridgeValidators,
+    attestations: &mut Attestations,
+    digest: &[u8; 32],
+    seed: u8,
+) -> Result<bool, ProgramError> {
+    let index = validators.attester(digest, &sign(seed, digest), None)?;
+    attestations.record(validators, *digest, index)
+}
+
+#[test]
+fn test_threshold_releases_transfer() {
+    // Two thirds, rounded down to a basis point
+    let validators =
+        BridgeValidators::new(6_666, (1..=4).map(|seed| validator(seed, 1)).collect()).unwrap();
+    let transfer = request(1);
+    let digest = transfer_digest(&PROGRAM_ID, &BRIDGE, &transfer);
+    let mut attestations = Attestations::default();
+
+    // Two of four is below two thirds, three is above
+    assert_eq!(
+        attest(&validators, &mut attestations, &digest, 1),
+        Ok(false)
+    );
+    let ed25519_signer = Pubkey::new_from_array([2; 32]);
+    let index = validators
+        .attester(&digest, &Attestation::Ed25519, Some(&ed25519_signer))
+        .unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(attestations.record(&validators, digest, index), Ok(false));
+    assert_eq!(attest(&validators, &mut attestations, &digest, 4), Ok(true));
+    assert!(attestations.executed);
+    assert_eq!(attestations.attested, 0b1011);
+
+    // Once executed, no attestation counts again
+    assert!(attest(&validators, &mut attestations, &digest, 3).is_err());
+
+    let mut state = BridgeState::new();
+    state.process_attested_transfer(&transfer, digest).unwrap();
+    assert_eq!(state.daily_volume, transfer.amount);
+    assert_eq!(
+        state.process_attested_transfer(&transfer, digest),
+        Err(ProgramError::InvalidAccountData)
+    );
+
+    state.config.paused = true;
+    let transfer = request(2);
+    let digest = transfer_digest(&PROGRAM_ID, &BRIDGE, &transfer);
+    assert!(state.process_attested_transfer(&transfer, digest).is_err());
+}
+
+#[test]
+fn test_weighted_threshold() {
+    let validators = BridgeValidators::new(
+        6_666,
+        vec![
+            validator(1, 5),
+            validator(2, 1),
+            validator(3, 1),
+            validator(4, 1),
+        ],
+    )
+    .unwrap();
+    let digest = transfer_digest(&PROGRAM_ID, &BRIDGE, &request(1));
+
+    // Five of eight is below two thirds, six is above
+    let mut attestations = Attestations::default();
+    assert_eq!(
+        attest(&validators, &mut attestations, &digest, 1),
+        Ok(false)
+    );
+    assert_eq!(attest(&validators, &mut attestations, &digest, 3), Ok(true));
+
+    // The three light validators together are not enough
+    let mut attestations = Attestations::default();
+    for seed in 2..=4 {
+        assert_eq!(
+            attest(&validators, &mut attestations, &digest, seed),
+            Ok(false)
+        );
+    }
+
+    // Inactive weight does not count towards the total
+    let mut validators = validators;
+    validators.validators[0].active = false;
+    let mut attestations = Attestations::default();
+    assert!(attest(&validators, &mut attestations, &digest, 1).is_err());
+    assert_eq!(
+        attest(&validators, &mut attestations, &digest, 2),
+        Ok(false)
+    );
+    assert_eq!(attest(&validators, &mut attestations, &digest, 3), Ok(true));
+}
+
+#[test]
+fn test_rejects_invalid_attestations() {
+    let validators =
+        BridgeValidators::new(6_666, (1..=3).map(|seed| validator(seed, 1)).collect()).unwrap();
+    let digest = transfer_digest(&PROGRAM_ID, &BRIDGE, &request(1));
+    let mut attestations = Attestations::default();
+
+    // A key outside the set, or a signature of another transfer
+    assert_eq!(
+        validators.attester(&digest, &sign(9, &digest), None),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+    let other = transfer_digest(&PROGRAM_ID, &BRIDGE, &request(2));
+    assert_eq!(
+        validators.attester(&digest, &sign(1, &other), None),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+    let other_program = Pubkey::new_from_array([0x43; 32]);
+    assert_ne!(
+        transfer_digest(&other_program, &BRIDGE, &request(1)),
+        digest
+    );
+    assert_ne!(
+        transfer_digest(&PROGRAM_ID, &other_program, &request(1)),
+        digest
+    );
+
+    // An ed25519 attestation without the validator signing
+    assert!(validators
+        .attester(&digest, &Attestation::Ed25519, None)
+        .is_err());
+    let stranger = Pubkey::new_from_array([9; 32]);
+    assert!(validators
+        .attester(&digest, &Attestation::Ed25519, Some(&stranger))
+        .is_err());
+
+    // The same validator twice, with either scheme
+    assert_eq!(
+        attest(&validators, &mut attestations, &digest, 1),
+        Ok(false)
+    );
+    assert!(attest(&validators, &mut attestations, &digest, 1).is_err());
+    let signer = Pubkey::new_from_array([1; 32]);
+    let index = validators
+        .attester(&digest, &Attestation::Ed25519, Some(&signer))
+        .unwrap();
+    assert!(attestations.record(&validators, digest, index).is_err());
+
+    // Another digest in the same account
+    assert!(attestations.record(&validators, other, 1).is_err());
+}
+
+#[test]
+fn test_validator_sets() {
+    let set = |threshold_bps, validators| BridgeValidators::new(threshold_bps, validators);
+    assert!(set(6_666, vec![]).is_err());
+    assert!(set(5_000, vec![validator(1, 1)]).is_err());
+    assert!(set(10_001, vec![validator(1, 1)]).is_err());
+    assert!(set(6_666, vec![validator(1, 0)]).is_err());
+    assert!(set(6_666, vec![validator(1, 1), validator(1, 2)]).is_err());
+    assert!(set(6_666, (0..=64).map(|seed| validator(seed + 1, 1)).collect()).is_err());
+
+    let mut inactive = validator(1, 1);
+    inactive.active = false;
+    assert!(set(6_666, vec![inactive]).is_err());
+
+    // A validator with only an ed25519 key
+    let mut ed25519_only = validator(2, 1);
+    ed25519_only.eth_address = [0u8; 20];
+    let validators = set(10_000, vec![validator(1, 1), ed25519_only]).unwrap();
+    let digest = [0x11; 32];
+    let signer = Pubkey::new_from_array([2; 32]);
+    assert_eq!(
+        validators.attester(&digest, &Attestation::Ed25519, Some(&signer)),
+        Ok(1)
+    );
+}
+
+#[test]
+fn test_validator_change() {
+    let mut validators =
+        BridgeValidators::new(6_666, (1..=3).map(|seed| validator(seed, 1)).collect()).unwrap();
+    let transfer = transfer_digest(&PROGRAM_ID, &BRIDGE, &request(1));
+    let mut pending = Attestations::default();
+    assert_eq!(attest(&validators, &mut pending, &transfer, 1), Ok(false));
+
+    // Adding a validator takes two of the three
+    let change = ValidatorChange::Add {
+        validator: validator(4, 1),
+    };
+    let digest = change_digest(&PROGRAM_ID, &BRIDGE, validators.epoch, &change);
+    let mut attestations = Attestations::default();
+    assert_eq!(
+        attest(&validators, &mut attestations, &digest, 4),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+    assert_eq!(
+        attest(&validators, &mut attestations, &digest, 2),
+        Ok(false)
+    );
+    assert_eq!(attest(&validators, &mut attestations, &digest, 3), Ok(true));
+    validators.apply(&change).unwrap();
+    assert_eq!(validators.epoch, 1);
+    assert_eq!(validators.validators.len(), 4);
+
+    // The same change is a new digest in the new epoch
+    assert_ne!(
+        change_digest(&PROGRAM_ID, &BRIDGE, validators.epoch, &change),
+        digest
+    );
+    assert!(validators.apply(&change).is_err());
+    assert_eq!(validators.epoch, 1);
+
+    // Attestations from the earlier epoch were dropped: validator 1 must
+    // attest again, and now three of four are needed
+    assert_eq!(attest(&validators, &mut pending, &transfer, 2), Ok(false));
+    assert_eq!(pending.attested, 0b10);
+    assert_eq!(attest(&validators, &mut pending, &transfer, 1), Ok(false));
+    assert_eq!(attest(&validators, &mut pending, &transfer, 4), Ok(true));
+
+    // Changes leaving a set that cannot attest are refused
+    assert!(validators
+        .apply(&ValidatorChange::SetThreshold {
+            threshold_bps: 4_000
+        })
+        .is_err());
+    assert!(validators
+        .apply(&ValidatorChange::Remove { index: 4 })
+        .is_err());
+    for index in 0..4 {
+        let change = ValidatorChange::Update {
+            index,
+            weight: 1,
+            active: false,
+        };
+        let result = validators.apply(&change);
+        assert_eq!(result.is_err(), index == 3);
+    }
+    assert_eq!(validators.epoch, 4);
+
+    validators
+        .apply(&ValidatorChange::Remove { index: 0 })
+        .unwrap();
+    validators
+        .apply(&ValidatorChange::SetThreshold {
+            threshold_bps: 10_000,
+        })
+        .unwrap();
+    assert_eq!(validators.validators.len(), 3);
+    assert_eq!(validators.threshold_bps, 10_000);
+    assert_eq!(validators.epoch, 6);
+}
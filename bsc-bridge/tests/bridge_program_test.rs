@@ -0,0 +1,350 @@
+//! The bridge program: its state across instructions, the authority, and
+//! transfers proven against its light client or attested by its validators.
+
+mod common;
+
+use bsc_bridge::{
+    attestation::{attestations_address, transfer_digest, validators_address, Attestation},
+    light_client::LIGHT_CLIENT_LEN,
+    process_bridge_instruction, processed_address, BridgeConfig, BridgeState, TransferRequest,
+    Validator as BridgeValidator, Verification, BRIDGE_ACCOUNT_LEN,
+};
+use bsc_evm_rlp::keccak256;
+use common::*;
+use solana_program::{pubkey::Pubkey, rent::Rent};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+/// The bridge program with one bridge account, and its authority
+struct TestBridge {
+    context: ProgramTestContext,
+    program_id: Pubkey,
+    bridge: Keypair,
+    authority: Keypair,
+}
+
+impl TestBridge {
+    /// Start the program with a bridge account initialized with `config`,
+    /// and the zeroed program accounts `accounts` of the given sizes
+    async fn start(config: &BridgeConfig, accounts: &[(Pubkey, usize)]) -> Self {
+        let program_id = Pubkey::new_unique();
+        let bridge = Keypair::new();
+        let mut program_test = ProgramTest::new(
+            "bsc_bridge",
+            program_id,
+            processor!(process_bridge_instruction),
+        );
+        for (address, space) in [(bridge.pubkey(), BRIDGE_ACCOUNT_LEN)]
+            .iter()
+            .chain(accounts)
+        {
+            let lamports = Rent::default().minimum_balance(*space);
+            program_test.add_account(*address, Account::new(lamports, *space, &program_id));
+        }
+        let mut test = Self {
+            context: program_test.start_with_context().await,
+            program_id,
+            bridge,
+            authority: Keypair::new(),
+        };
+
+        let accounts = vec![
+            AccountMeta::new(test.bridge.pubkey(), true),
+            AccountMeta::new_readonly(test.authority.pubkey(), true),
+        ];
+        let initialize = test.instruction(0, &borsh::to_vec(config).unwrap(), accounts);
+        let signers = [&test.bridge, &test.authority];
+        send(&mut test.context, initialize.clone(), &signers)
+            .await
+            .unwrap();
+        assert_eq!(
+            send(&mut test.context, initialize, &signers).await,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::AccountAlreadyInitialized
+            ))
+        );
+        test
+    }
+
+    fn instruction(&self, tag: u8, data: &[u8], accounts: Vec<AccountMeta>) -> Instruction {
+        Instruction::new_with_bytes(self.program_id, &[&[tag], data].concat(), accounts)
+    }
+
+    /// Instruction `tag` with `data`, signed by `authority`
+    fn admin_instruction(&self, tag: u8, data: &[u8], authority: &Pubkey) -> Instruction {
+        let accounts = vec![
+            AccountMeta::new(self.bridge.pubkey(), false),
+            AccountMeta::new_readonly(*authority, true),
+        ];
+        self.instruction(tag, data, accounts)
+    }
+
+    async fn send(
+        &mut self,
+        instruction: Instruction,
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        send(&mut self.context, instruction, signers).await
+    }
+
+    async fn send_as_authority(
+        &mut self,
+        instruction: Instruction,
+    ) -> Result<(), TransactionError> {
+        send(&mut self.context, instruction, &[&self.authority]).await
+    }
+
+    async fn state(&mut self) -> BridgeState {
+        let account = self
+            .context
+            .banks_client
+            .get_account(self.bridge.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        BridgeState::unpack(&account.data).unwrap()
+    }
+}
+
+/// Process `instruction`, signed by the payer and `signers`, in a new block
+async fn send(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut keys = vec![&context.payer];
+    keys.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &keys,
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+fn instruction_error(error: InstructionError) -> Result<(), TransactionError> {
+    Err(TransactionError::InstructionError(0, error))
+}
+
+#[tokio::test]
+async fn test_process_transfer_once() {
+    // Each header sealed in turn, from the epoch header at 10
+    let set = validators(&[1, 2, 3]);
+    let mut header = Header::new([0x99; 32], 10);
+    header.epoch = Some((
+        set.iter().map(|validator| validator.address).collect(),
+        None,
+    ));
+    let checkpoint = header.seal(&set[0]);
+    let recipient = Pubkey::new_unique();
+    let amount = 5 * 10_u128.pow(18);
+    let (receipts_root, request) = lock_in_block_11(recipient, amount);
+    let mut headers: Vec<Vec<u8>> = vec![];
+    for number in 11..=11 + FINALITY_DEPTH {
+        let parent_hash = keccak256(headers.last().unwrap_or(&checkpoint));
+        let mut header = Header::new(parent_hash, number);
+        if number == 11 {
+            header.receipts_root = receipts_root;
+        }
+        headers.push(header.seal(&set[(number % 3) as usize]));
+    }
+
+    let (light_client, other_light_client) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let config = BridgeConfig {
+        bsc_chain_id: CHAIN_ID,
+        min_confirmations: FINALITY_DEPTH,
+        bsc_bridge_contract: BRIDGE_CONTRACT,
+        ..BridgeConfig::default()
+    };
+    let accounts = [
+        (light_client, LIGHT_CLIENT_LEN),
+        (other_light_client, LIGHT_CLIENT_LEN),
+    ];
+    let mut test = TestBridge::start(&config, &accounts).await;
+
+    // Only the authority sets the light client up
+    let data = [
+        &CHAIN_ID.to_le_bytes()[..],
+        &EPOCH_LENGTH.to_le_bytes(),
+        &checkpoint,
+    ]
+    .concat();
+    let initialize_light_client = |authority: &Pubkey| {
+        let mut instruction = test.admin_instruction(8, &data, authority);
+        instruction
+            .accounts
+            .push(AccountMeta::new(light_client, false));
+        instruction
+    };
+    let attacker = Keypair::new();
+    let (by_attacker, by_authority) = (
+        initialize_light_client(&attacker.pubkey()),
+        initialize_light_client(&test.authority.pubkey()),
+    );
+    assert_eq!(
+        test.send(by_attacker, &[&attacker]).await,
+        instruction_error(InstructionError::MissingRequiredSignature)
+    );
+    test.send_as_authority(by_authority).await.unwrap();
+
+    // Block 11 holds the lock, under as many blocks as the bridge needs
+    for raw in &headers {
+        let accounts = vec![
+            AccountMeta::new_readonly(test.bridge.pubkey(), false),
+            AccountMeta::new(light_client, false),
+        ];
+        let submit_header = test.instruction(9, raw, accounts);
+        test.send(submit_header, &[]).await.unwrap();
+    }
+
+    let event_id = request.receipt_proof.event_id(&keccak256(&headers[0]));
+    let (processed, _) = processed_address(&test.program_id, &test.bridge.pubkey(), &event_id);
+    let process_transfer = |light_client: Pubkey| {
+        let accounts = vec![
+            AccountMeta::new(test.bridge.pubkey(), false),
+            AccountMeta::new_readonly(light_client, false),
+            AccountMeta::new(processed, false),
+            AccountMeta::new(test.context.payer.pubkey(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        test.instruction(3, &borsh::to_vec(&request).unwrap(), accounts)
+    };
+    let (with_other, with_bridge) = (
+        process_transfer(other_light_client),
+        process_transfer(light_client),
+    );
+
+    // Proven against another light client account, the lock is refused
+    assert_eq!(
+        test.send(with_other, &[]).await,
+        instruction_error(InstructionError::InvalidArgument)
+    );
+
+    // The same proof releases the transfer once
+    test.send(with_bridge.clone(), &[]).await.unwrap();
+    assert_eq!(
+        test.send(with_bridge, &[]).await,
+        instruction_error(InstructionError::InvalidAccountData)
+    );
+
+    let state = test.state().await;
+    assert_eq!(state.authority, test.authority.pubkey());
+    assert_eq!(state.light_client, Some(light_client));
+    assert_eq!(state.config.bsc_bridge_contract, BRIDGE_CONTRACT);
+    assert_eq!(state.daily_volume, amount);
+}
+
+/// Attestation signed with the secp256k1 key of `validator`
+fn attest(validator: &Validator, digest: &[u8; 32]) -> Attestation {
+    let message = libsecp256k1::Message::parse(digest);
+    let (signature, recovery_id) = libsecp256k1::sign(&message, &validator.secret);
+    let mut bytes = [0u8; 65];
+    bytes[..64].copy_from_slice(&signature.serialize());
+    bytes[64] = recovery_id.serialize();
+    Attestation::Secp256k1 { signature: bytes }
+}
+
+#[tokio::test]
+async fn test_attested_transfer_limits() {
+    let amount = 5 * 10_u128.pow(18);
+    let config = BridgeConfig {
+        daily_limit: amount * 3 / 2,
+        verification: Verification::Attestations,
+        ..BridgeConfig::default()
+    };
+    let mut test = TestBridge::start(&config, &[]).await;
+    let bridge = test.bridge.pubkey();
+
+    // Only the authority sets the validators up
+    let signers = validators(&[1, 2]);
+    let set: Vec<_> = signers
+        .iter()
+        .map(|validator| BridgeValidator {
+            eth_address: validator.address,
+            pubkey: Pubkey::default(),
+            weight: 1,
+            active: true,
+        })
+        .collect();
+    let (validators_account, _) = validators_address(&test.program_id, &bridge);
+    let data = borsh::to_vec(&(6_666_u16, set)).unwrap();
+    let initialize_validators = |authority: &Pubkey| {
+        let mut instruction = test.admin_instruction(10, &data, authority);
+        instruction.accounts.extend([
+            AccountMeta::new(validators_account, false),
+            AccountMeta::new(test.context.payer.pubkey(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ]);
+        instruction
+    };
+    let attacker = Keypair::new();
+    let (by_attacker, by_authority) = (
+        initialize_validators(&attacker.pubkey()),
+        initialize_validators(&test.authority.pubkey()),
+    );
+    assert_eq!(
+        test.send(by_attacker, &[&attacker]).await,
+        instruction_error(InstructionError::MissingRequiredSignature)
+    );
+    test.send_as_authority(by_authority).await.unwrap();
+
+    let attest_transfer = |request: &TransferRequest, signer: &Validator| {
+        let digest = transfer_digest(&test.program_id, &bridge, request);
+        let (attestations, _) = attestations_address(&test.program_id, &bridge, &digest);
+        let accounts = vec![
+            AccountMeta::new(bridge, false),
+            AccountMeta::new_readonly(validators_account, false),
+            AccountMeta::new(attestations, false),
+            AccountMeta::new(test.context.payer.pubkey(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        let data = borsh::to_vec(&(request, attest(signer, &digest))).unwrap();
+        test.instruction(11, &data, accounts)
+    };
+    let (_, first) = lock_in_block_11(Pubkey::new_unique(), amount);
+    let (_, second) = lock_in_block_11(Pubkey::new_unique(), amount);
+    let first_attestations = [
+        attest_transfer(&first, &signers[0]),
+        attest_transfer(&first, &signers[1]),
+    ];
+    let second_attestations = [
+        attest_transfer(&second, &signers[0]),
+        attest_transfer(&second, &signers[1]),
+    ];
+    let pause = test.admin_instruction(4, &[], &test.authority.pubkey());
+    let unpause = test.admin_instruction(5, &[], &test.authority.pubkey());
+    let [first_0, first_1] = first_attestations;
+    let [second_0, second_1] = second_attestations;
+
+    // Paused, the bridge records attestations but releases nothing
+    test.send_as_authority(pause).await.unwrap();
+    test.send(first_0, &[]).await.unwrap();
+    assert_eq!(
+        test.send(first_1.clone(), &[]).await,
+        instruction_error(InstructionError::InvalidAccountData)
+    );
+    test.send_as_authority(unpause).await.unwrap();
+    test.send(first_1, &[]).await.unwrap();
+    assert_eq!(test.state().await.daily_volume, amount);
+
+    // A second transfer would go over the daily limit
+    test.send(second_0, &[]).await.unwrap();
+    assert_eq!(
+        test.send(second_1, &[]).await,
+        instruction_error(InstructionError::InsufficientFunds)
+    );
+    assert_eq!(test.state().await.daily_volume, amount);
+}
@@ -0,0 +1,328 @@
+//! BSC headers sealed with test keys, the way Parlia validators seal them,
+//! and lock receipts to prove in them.
+//!
+//! Headers are built in the BSC format since Cancun.
+#![allow(dead_code)]
+
+use bsc_bridge::{
+    light_client::{self, LightClient, LIGHT_CLIENT_LEN},
+    receipt::{ReceiptProof, LOCK_EVENT_SIGNATURE},
+    TokenType, TransferRequest,
+};
+use bsc_evm_rlp::{
+    keccak256,
+    rlp::{encode_bytes, encode_list, encode_uint},
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+pub const CHAIN_ID: u64 = 56;
+pub const EPOCH_LENGTH: u64 = 10;
+pub const FINALITY_DEPTH: u64 = 3;
+pub const BRIDGE_CONTRACT: [u8; 20] = [0xb1; 20];
+
+/// A validator's sealing key
+#[derive(Clone)]
+pub struct Validator {
+    pub secret: libsecp256k1::SecretKey,
+    pub address: [u8; 20],
+}
+
+impl Validator {
+    pub fn new(seed: u8) -> Self {
+        let secret = libsecp256k1::SecretKey::parse(&[seed; 32]).unwrap();
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret).serialize();
+        let address = keccak256(&public_key[1..])[12..].try_into().unwrap();
+        Self { secret, address }
+    }
+}
+
+/// Validators sorted by address, the order turns go in
+pub fn validators(seeds: &[u8]) -> Vec<Validator> {
+    let mut validators: Vec<_> = seeds.iter().map(|seed| Validator::new(*seed)).collect();
+    validators.sort_by_key(|validator| validator.address);
+    validators
+}
+
+/// A header to seal
+#[derive(Clone)]
+pub struct Header {
+    pub parent_hash: [u8; 32],
+    pub number: u64,
+    pub difficulty: u64,
+    pub timestamp: u64,
+    pub receipts_root: [u8; 32],
+    /// Validators listed by an epoch header, and the turn length
+    pub epoch: Option<(Vec<[u8; 20]>, Option<u8>)>,
+    /// Fields since London, ending in a zero parent beacon root
+    pub cancun: bool,
+}
+
+impl Header {
+    pub fn new(parent_hash: [u8; 32], number: u64) -> Self {
+        Self {
+            parent_hash,
+            number,
+            difficulty: 2,
+            timestamp: 1_700_000_000 + number * 3,
+            receipts_root: [0x56; 32],
+            epoch: None,
+            cancun: true,
+        }
+    }
+
+    /// RLP encoding of the fields, with `extra` as the extra data
+    pub fn fields(&self, coinbase: &[u8; 20], extra: &[u8]) -> Vec<Vec<u8>> {
+        let bytes = |value: &[u8]| {
+            let mut out = Vec::new();
+            encode_bytes(value, &mut out);
+            out
+        };
+        let uint = |value: u64| {
+            let mut out = Vec::new();
+            encode_uint(&value.to_be_bytes(), &mut out);
+            out
+        };
+        let mut fields = vec![
+            bytes(&self.parent_hash),
+            bytes(&[0x1d; 32]),
+            bytes(coinbase),
+            bytes(&[0x33; 32]),
+            bytes(&[0x44; 32]),
+            bytes(&self.receipts_root),
+            bytes(&[0u8; 256]),
+            uint(self.difficulty),
+            uint(self.number),
+            uint(140_000_000),
+            uint(21_000),
+            uint(self.timestamp),
+            bytes(extra),
+            bytes(&[0u8; 32]),
+            bytes(&[0u8; 8]),
+        ];
+        if self.cancun {
+            fields.extend([
+                uint(0),
+                bytes(&[0x77; 32]),
+                uint(0),
+                uint(0),
+                bytes(&[0u8; 32]),
+            ]);
+        }
+        fields
+    }
+
+    /// Extra data without the seal
+    pub fn unsealed_extra(&self) -> Vec<u8> {
+        let mut extra = vec![0xee; 32];
+        if let Some((validators, turn_length)) = &self.epoch {
+            extra.push(validators.len() as u8);
+            for validator in validators {
+                extra.extend_from_slice(validator);
+                extra.extend_from_slice(&[0xb5; 48]);
+            }
+            extra.extend(turn_length);
+        }
+        extra
+    }
+
+    /// Header sealed by `signer` for `chain_id`, with `coinbase`
+    pub fn seal_as(&self, signer: &Validator, coinbase: &[u8; 20], chain_id: u64) -> Vec<u8> {
+        let unsealed = self.unsealed_extra();
+        let mut fields = self.fields(coinbase, &[]);
+        let mut payload = Vec::new();
+        encode_uint(&chain_id.to_be_bytes(), &mut payload);
+        for (i, field) in fields.iter().enumerate() {
+            if i == 12 {
+                encode_bytes(&unsealed, &mut payload);
+            } else {
+                payload.extend_from_slice(field);
+            }
+        }
+        let mut signing_data = Vec::new();
+        encode_list(&payload, &mut signing_data);
+
+        let message = libsecp256k1::Message::parse(&keccak256(&signing_data));
+        let (signature, recovery_id) = libsecp256k1::sign(&message, &signer.secret);
+        let mut extra = unsealed;
+        extra.extend_from_slice(&signature.serialize());
+        extra.push(recovery_id.serialize());
+
+        fields[12].clear();
+        encode_bytes(&extra, &mut fields[12]);
+        let mut header = Vec::new();
+        encode_list(&fields.concat(), &mut header);
+        header
+    }
+
+    pub fn seal(&self, signer: &Validator) -> Vec<u8> {
+        self.seal_as(signer, &signer.address, CHAIN_ID)
+    }
+}
+
+/// A light client account following `set` from an epoch header at `number`
+pub struct Chain {
+    pub data: Vec<u8>,
+    pub set: Vec<Validator>,
+    pub latest_hash: [u8; 32],
+    pub latest_number: u64,
+}
+
+impl Chain {
+    pub fn new(set: Vec<Validator>, number: u64) -> Self {
+        let mut checkpoint = Header::new([0x99; 32], number);
+        checkpoint.epoch = Some((
+            set.iter().map(|validator| validator.address).collect(),
+            None,
+        ));
+        let raw = checkpoint.seal(&set[0]);
+        let mut data = vec![0u8; LIGHT_CLIENT_LEN];
+        light_client::initialize(&mut data, CHAIN_ID, EPOCH_LENGTH, FINALITY_DEPTH, &raw).unwrap();
+        Self {
+            data,
+            set,
+            latest_hash: keccak256(&raw),
+            latest_number: number,
+        }
+    }
+
+    /// Next header, listing the same set if it is an epoch header
+    pub fn next(&self) -> Header {
+        let mut header = Header::new(self.latest_hash, self.latest_number + 1);
+        if header.number % EPOCH_LENGTH == 0 {
+            let addresses = self.set.iter().map(|validator| validator.address).collect();
+            header.epoch = Some((addresses, None));
+        }
+        header
+    }
+
+    pub fn in_turn(&self, number: u64) -> &Validator {
+        &self.set[(number % self.set.len() as u64) as usize]
+    }
+
+    /// Submit `raw`, following the tip the light client chooses
+    pub fn submit(&mut self, raw: &[u8]) -> Result<(), ProgramError> {
+        light_client::submit_header(&mut self.data, raw)?;
+        let client = LightClient::unpack(&self.data).unwrap();
+        self.latest_hash = client.latest_hash;
+        self.latest_number = client.latest_number;
+        Ok(())
+    }
+
+    /// Submit `header` sealed by the validator in turn, or out of turn by
+    /// another if that one sealed too recently, returning the sealed header
+    pub fn extend(&mut self, mut header: Header) -> Vec<u8> {
+        let client = LightClient::unpack(&self.data).unwrap();
+        let window = client.validators.miner_history_check_len();
+        let recent: Vec<_> = (header.number - window..header.number)
+            .filter_map(|number| client.header(&self.data, number))
+            .map(|record| record.signer)
+            .collect();
+        let mut signer = self.in_turn(header.number).clone();
+        if recent.contains(&signer.address) {
+            header.difficulty = 1;
+            signer = self
+                .set
+                .iter()
+                .find(|v| !recent.contains(&v.address))
+                .unwrap()
+                .clone();
+        }
+        let raw = header.seal(&signer);
+        self.submit(&raw).unwrap();
+        raw
+    }
+}
+
+/// RLP encoding of a list of encoded items
+pub fn list(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_list(&items.concat(), &mut out);
+    out
+}
+
+pub fn bytes(value: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_bytes(value, &mut out);
+    out
+}
+
+/// How a parent refers to `node`
+pub fn reference(node: &[u8]) -> Vec<u8> {
+    if node.len() < 32 {
+        node.to_vec()
+    } else {
+        bytes(&keccak256(node))
+    }
+}
+
+pub fn branch(children: &[(usize, Vec<u8>)]) -> Vec<u8> {
+    let mut items = vec![bytes(&[]); 17];
+    for (nibble, child) in children {
+        items[*nibble] = reference(child);
+    }
+    list(&items)
+}
+
+/// RLP encoding of a successful dynamic fee transaction's receipt with `logs`
+pub fn receipt(logs: &[Vec<u8>]) -> Vec<u8> {
+    let fields = [
+        bytes(&[1]),
+        bytes(&[0x52, 0x08]),
+        bytes(&[0u8; 256]),
+        list(logs),
+    ];
+    [vec![2], list(&fields)].concat()
+}
+
+pub fn lock_log(token: [u8; 20], recipient: &Pubkey, amount: u128, nonce: u64) -> Vec<u8> {
+    let word = |address: &[u8; 20]| {
+        let mut word = [0u8; 32];
+        word[12..].copy_from_slice(address);
+        word
+    };
+    let topics = [
+        keccak256(LOCK_EVENT_SIGNATURE.as_bytes()),
+        word(&token),
+        word(&[0xaa; 20]),
+        recipient.to_bytes(),
+    ];
+    let mut data = [0u8; 64];
+    data[16..32].copy_from_slice(&amount.to_be_bytes());
+    data[56..].copy_from_slice(&nonce.to_be_bytes());
+    list(&[
+        bytes(&BRIDGE_CONTRACT),
+        list(&topics.iter().map(|topic| bytes(topic)).collect::<Vec<_>>()),
+        bytes(&data),
+    ])
+}
+
+/// Receipts root of block 11, whose second transaction locked `amount` of
+/// BNB for `recipient` in its second log, and the transfer it requests
+pub fn lock_in_block_11(recipient: Pubkey, amount: u128) -> ([u8; 32], TransferRequest) {
+    let other_log = list(&[bytes(&[0x01; 20]), list(&[]), bytes(b"")]);
+
+    // Receipts of transactions 0 and 1, keyed by 0x80 and 0x01
+    let receipts = [
+        receipt(&[]),
+        receipt(&[other_log, lock_log([0u8; 20], &recipient, amount, 7)]),
+    ];
+    let leaf_0 = list(&[bytes(&[0x30]), bytes(&receipts[0])]);
+    let leaf_1 = list(&[bytes(&[0x31]), bytes(&receipts[1])]);
+    let root_node = branch(&[(0, leaf_1.clone()), (8, leaf_0)]);
+
+    let request = TransferRequest {
+        source_tx_hash: [0x5a; 32],
+        source_block: 11,
+        recipient,
+        amount,
+        token_type: TokenType::Bnb,
+        token_contract: None,
+        receipt_proof: ReceiptProof {
+            tx_index: 1,
+            log_index: 1,
+            nodes: vec![root_node.clone(), leaf_1],
+        },
+        nonce: 7,
+    };
+    (keccak256(&root_node), request)
+}
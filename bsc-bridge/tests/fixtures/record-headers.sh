@@ -0,0 +1,48 @@
+#!/usr/bin/env bash
+#
+# Records an epoch header of BSC testnet and the header after it, as
+# returned by eth_getBlockByNumber, for test_recorded_headers in
+# light_client_test.rs.
+#
+# Pick an epoch that did not change the validator set: the header after it
+# is sealed by the set the epoch header lists only if that set was already
+# sealing. Pass the epoch number, or the script takes the last full epoch.
+#
+#   BSC_RPC=https://... EPOCH_LENGTH=1000 ./record-headers.sh [number]
+#
+set -euo pipefail
+
+here=$(dirname "$0")
+rpc=${BSC_RPC:-https://data-seed-prebsc-1-s1.bnbchain.org:8545}
+chain_id=${CHAIN_ID:-97}
+epoch_length=${EPOCH_LENGTH:-1000}
+
+call() {
+  curl -sf -X POST -H 'Content-Type: application/json' \
+    --data "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"$1\",\"params\":$2}" "$rpc" |
+    jq -e .result
+}
+
+if [ $# -gt 0 ]; then
+  number=$1
+else
+  latest=$(($(call eth_blockNumber '[]' | jq -r .)))
+  number=$(((latest / epoch_length - 1) * epoch_length))
+fi
+if [ $((number % epoch_length)) -ne 0 ]; then
+  echo "$number is not an epoch header" >&2
+  exit 1
+fi
+
+block() {
+  call eth_getBlockByNumber "[\"$(printf '0x%x' "$1")\", false]" | jq 'del(.transactions, .uncles, .size, .totalDifficulty)'
+}
+
+jq -n \
+  --argjson chainId "$chain_id" \
+  --argjson epochLength "$epoch_length" \
+  --argjson epoch "$(block "$number")" \
+  --argjson next "$(block $((number + 1)))" \
+  '{chainId: $chainId, epochLength: $epochLength, headers: [$epoch, $next]}' \
+  >"$here/bsc-$chain_id-$number.json"
+echo "Recorded $here/bsc-$chain_id-$number.json"
@@ -0,0 +1,66 @@
+//! # RLP and Ethereum Transactions for BSC EVM
+//!
+//! Recursive Length Prefix encoding and the Ethereum transaction envelopes
+//! built on it, shared by the on-chain `bsc-evm` program and the
+//! `rpc-bsc-evm` JSON-RPC server so both agree byte for byte on what a
+//! transaction, its signing hash and its hash are.
+//!
+//! Supported envelopes:
+//! - Legacy transactions, with or without EIP-155 replay protection
+//! - EIP-2930 access list transactions (type 1)
+//! - EIP-1559 dynamic fee transactions (type 2)
+
+pub mod rlp;
+pub mod transaction;
+
+pub use transaction::{AccessList, SignedTransaction, TransactionType};
+
+/// Errors returned when decoding RLP or a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecoderError {
+    /// The input ended in the middle of an item
+    InputTooShort,
+    /// Bytes were left over after the item
+    TrailingBytes,
+    /// An item or length was not encoded in its shortest form
+    NonCanonical,
+    /// A list was found where a byte string was expected, or the reverse
+    UnexpectedType,
+    /// An integer does not fit the field it is decoded into
+    Overflow,
+    /// A transaction list has the wrong number of fields
+    WrongFieldCount,
+    /// The first byte is not a supported transaction type
+    UnsupportedType,
+    /// An address was not 20 bytes long
+    InvalidAddress,
+    /// A storage key was not 32 bytes long
+    InvalidStorageKey,
+    /// The signature's `v` or y parity is out of range
+    InvalidSignature,
+}
+
+impl std::fmt::Display for DecoderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecoderError::InputTooShort => write!(f, "Input too short"),
+            DecoderError::TrailingBytes => write!(f, "Trailing bytes"),
+            DecoderError::NonCanonical => write!(f, "Non-canonical encoding"),
+            DecoderError::UnexpectedType => write!(f, "Unexpected item type"),
+            DecoderError::Overflow => write!(f, "Integer overflow"),
+            DecoderError::WrongFieldCount => write!(f, "Wrong number of fields"),
+            DecoderError::UnsupportedType => write!(f, "Unsupported transaction type"),
+            DecoderError::InvalidAddress => write!(f, "Invalid address"),
+            DecoderError::InvalidStorageKey => write!(f, "Invalid storage key"),
+            DecoderError::InvalidSignature => write!(f, "Invalid signature"),
+        }
+    }
+}
+
+impl std::error::Error for DecoderError {}
+
+/// Keccak-256 hash of `data`
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    use sha3::{Digest, Keccak256};
+    Keccak256::digest(data).into()
+}
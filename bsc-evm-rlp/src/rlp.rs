@@ -0,0 +1,220 @@
+//! # RLP
+//!
+//! Decoding borrows from the input and rejects every non-canonical encoding,
+//! so a decoded value re-encodes to exactly the bytes it came from.
+
+use crate::DecoderError;
+
+/// A decoded RLP item borrowing from the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Item<'a> {
+    /// Byte string payload
+    Bytes(&'a [u8]),
+    /// Concatenated encodings of the list's items
+    List(&'a [u8]),
+}
+
+/// Split the first item off `input`, returning the item, its full encoding and the rest
+pub fn decode_item(input: &[u8]) -> Result<(Item<'_>, &[u8], &[u8]), DecoderError> {
+    let prefix = *input.first().ok_or(DecoderError::InputTooShort)?;
+
+    let (is_list, header_len, payload_len) = match prefix {
+        0x00..=0x7f => return Ok((Item::Bytes(&input[..1]), &input[..1], &input[1..])),
+        0x80..=0xb7 => (false, 1, (prefix - 0x80) as usize),
+        0xb8..=0xbf => {
+            let len_of_len = (prefix - 0xb7) as usize;
+            (false, 1 + len_of_len, read_length(&input[1..], len_of_len)?)
+        }
+        0xc0..=0xf7 => (true, 1, (prefix - 0xc0) as usize),
+        0xf8..=0xff => {
+            let len_of_len = (prefix - 0xf7) as usize;
+            (true, 1 + len_of_len, read_length(&input[1..], len_of_len)?)
+        }
+    };
+
+    let end = header_len
+        .checked_add(payload_len)
+        .ok_or(DecoderError::InputTooShort)?;
+    if input.len() < end {
+        return Err(DecoderError::InputTooShort);
+    }
+    let payload = &input[header_len..end];
+    // Single bytes below 0x80 must be encoded as themselves
+    if !is_list && header_len == 1 && payload_len == 1 && payload[0] < 0x80 {
+        return Err(DecoderError::NonCanonical);
+    }
+
+    let item = if is_list { Item::List(payload) } else { Item::Bytes(payload) };
+    Ok((item, &input[..end], &input[end..]))
+}
+
+/// Decode `input` as exactly one list, returning its items' full encodings
+pub fn decode_list(input: &[u8]) -> Result<Vec<&[u8]>, DecoderError> {
+    let (item, _, rest) = decode_item(input)?;
+    if !rest.is_empty() {
+        return Err(DecoderError::TrailingBytes);
+    }
+    match item {
+        Item::List(payload) => split_items(payload),
+        Item::Bytes(_) => Err(DecoderError::UnexpectedType),
+    }
+}
+
+/// Split a list payload into its items' full encodings
+pub fn split_items(mut payload: &[u8]) -> Result<Vec<&[u8]>, DecoderError> {
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        let (_, encoded, rest) = decode_item(payload)?;
+        items.push(encoded);
+        payload = rest;
+    }
+    Ok(items)
+}
+
+/// Payload of an encoded byte string
+pub fn decode_bytes(encoded: &[u8]) -> Result<&[u8], DecoderError> {
+    match decode_item(encoded)? {
+        (Item::Bytes(bytes), _, []) => Ok(bytes),
+        (Item::Bytes(_), _, _) => Err(DecoderError::TrailingBytes),
+        (Item::List(_), _, _) => Err(DecoderError::UnexpectedType),
+    }
+}
+
+/// Big-endian integer of at most `N` bytes without leading zeros
+pub fn decode_uint<const N: usize>(encoded: &[u8]) -> Result<[u8; N], DecoderError> {
+    let bytes = decode_bytes(encoded)?;
+    if bytes.len() > N {
+        return Err(DecoderError::Overflow);
+    }
+    if bytes.first() == Some(&0) {
+        return Err(DecoderError::NonCanonical);
+    }
+    let mut out = [0u8; N];
+    out[N - bytes.len()..].copy_from_slice(bytes);
+    Ok(out)
+}
+
+/// Integer that fits in a `u64`
+pub fn decode_u64(encoded: &[u8]) -> Result<u64, DecoderError> {
+    Ok(u64::from_be_bytes(decode_uint::<8>(encoded)?))
+}
+
+/// Integer that fits in a `u128`
+pub fn decode_u128(encoded: &[u8]) -> Result<u128, DecoderError> {
+    Ok(u128::from_be_bytes(decode_uint::<16>(encoded)?))
+}
+
+/// Encode a big-endian integer, dropping leading zeros
+pub fn encode_uint(value: &[u8], out: &mut Vec<u8>) {
+    let start = value.iter().position(|&b| b != 0).unwrap_or(value.len());
+    encode_bytes(&value[start..], out);
+}
+
+/// Encode a byte string
+pub fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    match bytes {
+        [byte] if *byte < 0x80 => out.push(*byte),
+        _ => {
+            encode_header(0x80, bytes.len(), out);
+            out.extend_from_slice(bytes);
+        }
+    }
+}
+
+/// Encode a list whose items are already encoded in `payload`
+pub fn encode_list(payload: &[u8], out: &mut Vec<u8>) {
+    encode_header(0xc0, payload.len(), out);
+    out.extend_from_slice(payload);
+}
+
+fn encode_header(offset: u8, len: usize, out: &mut Vec<u8>) {
+    if len < 56 {
+        out.push(offset + len as u8);
+    } else {
+        let len_bytes = (len as u64).to_be_bytes();
+        let start = len_bytes.iter().position(|&b| b != 0).unwrap_or(7);
+        out.push(offset + 55 + (8 - start) as u8);
+        out.extend_from_slice(&len_bytes[start..]);
+    }
+}
+
+fn read_length(input: &[u8], len_of_len: usize) -> Result<usize, DecoderError> {
+    let bytes = input.get(..len_of_len).ok_or(DecoderError::InputTooShort)?;
+    if len_of_len > 8 {
+        return Err(DecoderError::Overflow);
+    }
+    if bytes[0] == 0 {
+        return Err(DecoderError::NonCanonical);
+    }
+    let mut buf = [0u8; 8];
+    buf[8 - len_of_len..].copy_from_slice(bytes);
+    let len = u64::from_be_bytes(buf);
+    // Long form is only valid for lengths that do not fit the short form
+    if len < 56 {
+        return Err(DecoderError::NonCanonical);
+    }
+    usize::try_from(len).map_err(|_| DecoderError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded_bytes(bytes: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_bytes(bytes, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_encode_bytes() {
+        assert_eq!(encoded_bytes(b""), vec![0x80]);
+        assert_eq!(encoded_bytes(b"\x0f"), vec![0x0f]);
+        assert_eq!(encoded_bytes(b"\x80"), vec![0x81, 0x80]);
+        assert_eq!(encoded_bytes(b"dog"), vec![0x83, b'd', b'o', b'g']);
+
+        let long = [0xaa; 56];
+        let encoded = encoded_bytes(&long);
+        assert_eq!(encoded[..2], [0xb8, 56]);
+        assert_eq!(decode_bytes(&encoded), Ok(&long[..]));
+    }
+
+    #[test]
+    fn test_encode_list() {
+        // [ "cat", "dog" ]
+        let mut payload = Vec::new();
+        encode_bytes(b"cat", &mut payload);
+        encode_bytes(b"dog", &mut payload);
+        let mut out = Vec::new();
+        encode_list(&payload, &mut out);
+        assert_eq!(out, b"\xc8\x83cat\x83dog");
+
+        let items = decode_list(&out).unwrap();
+        assert_eq!(items, vec![&b"\x83cat"[..], &b"\x83dog"[..]]);
+    }
+
+    #[test]
+    fn test_uint_round_trip() {
+        for value in [0u64, 1, 0x7f, 0x80, 0x400, u64::MAX] {
+            let mut out = Vec::new();
+            encode_uint(&value.to_be_bytes(), &mut out);
+            assert_eq!(decode_u64(&out), Ok(value));
+        }
+        let mut out = Vec::new();
+        encode_uint(&0x400u64.to_be_bytes(), &mut out);
+        assert_eq!(out, vec![0x82, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn test_rejects_non_canonical() {
+        // Single byte below 0x80 wrapped in a string header
+        assert_eq!(decode_bytes(&[0x81, 0x05]), Err(DecoderError::NonCanonical));
+        // Leading zero in an integer
+        assert_eq!(decode_u64(&[0x82, 0x00, 0x01]), Err(DecoderError::NonCanonical));
+        // Long form for a short length
+        assert_eq!(decode_bytes(&[0xb8, 0x01, 0xff]), Err(DecoderError::NonCanonical));
+        assert_eq!(decode_bytes(&[0x83, b'd', b'o']), Err(DecoderError::InputTooShort));
+        assert_eq!(decode_u64(&[0x89, 1, 2, 3, 4, 5, 6, 7, 8, 9]), Err(DecoderError::Overflow));
+        assert_eq!(decode_list(&[0xc0, 0x80]), Err(DecoderError::TrailingBytes));
+    }
+}
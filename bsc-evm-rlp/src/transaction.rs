@@ -0,0 +1,403 @@
+//! # Signed Transactions
+//!
+//! Decoding and encoding of the three transaction envelopes, with the
+//! signing hash each one's sender signs and the transaction hash that
+//! identifies it.
+//!
+//! ```text
+//! legacy:  rlp([nonce, gasPrice, gas, to, value, data, v, r, s])
+//! type 1:  0x01 || rlp([chainId, nonce, gasPrice, gas, to, value, data, accessList, yParity, r, s])
+//! type 2:  0x02 || rlp([chainId, nonce, maxPriorityFeePerGas, maxFeePerGas, gas, to, value, data, accessList, yParity, r, s])
+//! ```
+
+use crate::{keccak256, rlp, DecoderError};
+
+/// Addresses and storage keys a transaction pre-declares (EIP-2930)
+pub type AccessList = Vec<([u8; 20], Vec<[u8; 32]>)>;
+
+/// Transaction envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionType {
+    /// Untyped transaction, optionally EIP-155 replay protected
+    Legacy,
+    /// EIP-2930 access list transaction
+    AccessList,
+    /// EIP-1559 dynamic fee transaction
+    DynamicFee,
+}
+
+impl TransactionType {
+    /// EIP-2718 type byte
+    pub fn id(&self) -> u8 {
+        match self {
+            TransactionType::Legacy => 0,
+            TransactionType::AccessList => 1,
+            TransactionType::DynamicFee => 2,
+        }
+    }
+}
+
+/// A signed transaction of any supported type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTransaction {
+    pub tx_type: TransactionType,
+    /// Chain id, `None` only for legacy transactions without EIP-155
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    /// Tip per gas; the gas price for legacy and access list transactions
+    pub max_priority_fee_per_gas: u128,
+    /// Most paid per gas; the gas price for legacy and access list transactions
+    pub max_fee_per_gas: u128,
+    pub gas_limit: u64,
+    /// Recipient, `None` for contract creation
+    pub to: Option<[u8; 20]>,
+    pub value: u128,
+    pub data: Vec<u8>,
+    pub access_list: AccessList,
+    /// Parity of the signature's y coordinate, the secp256k1 recovery id
+    pub odd_y_parity: bool,
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+impl SignedTransaction {
+    /// Decode a raw transaction of any supported type
+    pub fn decode(raw: &[u8]) -> Result<Self, DecoderError> {
+        match raw.first() {
+            None => Err(DecoderError::InputTooShort),
+            Some(0xc0..=0xff) => Self::decode_legacy(raw),
+            Some(1) => Self::decode_typed(TransactionType::AccessList, &raw[1..]),
+            Some(2) => Self::decode_typed(TransactionType::DynamicFee, &raw[1..]),
+            Some(_) => Err(DecoderError::UnsupportedType),
+        }
+    }
+
+    fn decode_legacy(raw: &[u8]) -> Result<Self, DecoderError> {
+        let fields = rlp::decode_list(raw)?;
+        if fields.len() != 9 {
+            return Err(DecoderError::WrongFieldCount);
+        }
+
+        let v = rlp::decode_u64(fields[6])?;
+        let (chain_id, odd_y_parity) = match v {
+            27 | 28 => (None, v == 28),
+            35.. => (Some((v - 35) / 2), (v - 35) % 2 == 1),
+            _ => return Err(DecoderError::InvalidSignature),
+        };
+        let gas_price = rlp::decode_u128(fields[1])?;
+
+        Ok(Self {
+            tx_type: TransactionType::Legacy,
+            chain_id,
+            nonce: rlp::decode_u64(fields[0])?,
+            max_priority_fee_per_gas: gas_price,
+            max_fee_per_gas: gas_price,
+            gas_limit: rlp::decode_u64(fields[2])?,
+            to: decode_to(fields[3])?,
+            value: rlp::decode_u128(fields[4])?,
+            data: rlp::decode_bytes(fields[5])?.to_vec(),
+            access_list: Vec::new(),
+            odd_y_parity,
+            r: rlp::decode_uint(fields[7])?,
+            s: rlp::decode_uint(fields[8])?,
+        })
+    }
+
+    fn decode_typed(tx_type: TransactionType, payload: &[u8]) -> Result<Self, DecoderError> {
+        let fields = rlp::decode_list(payload)?;
+        // Dynamic fee transactions carry one extra fee field
+        let fee_fields = if tx_type == TransactionType::DynamicFee { 2 } else { 1 };
+        if fields.len() != 10 + fee_fields {
+            return Err(DecoderError::WrongFieldCount);
+        }
+
+        let (max_priority_fee_per_gas, max_fee_per_gas) = if fee_fields == 2 {
+            (rlp::decode_u128(fields[2])?, rlp::decode_u128(fields[3])?)
+        } else {
+            let gas_price = rlp::decode_u128(fields[2])?;
+            (gas_price, gas_price)
+        };
+        let rest = &fields[2 + fee_fields..];
+        let odd_y_parity = match rlp::decode_u64(rest[5])? {
+            0 => false,
+            1 => true,
+            _ => return Err(DecoderError::InvalidSignature),
+        };
+
+        Ok(Self {
+            tx_type,
+            chain_id: Some(rlp::decode_u64(fields[0])?),
+            nonce: rlp::decode_u64(fields[1])?,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit: rlp::decode_u64(rest[0])?,
+            to: decode_to(rest[1])?,
+            value: rlp::decode_u128(rest[2])?,
+            data: rlp::decode_bytes(rest[3])?.to_vec(),
+            access_list: decode_access_list(rest[4])?,
+            odd_y_parity,
+            r: rlp::decode_uint(rest[6])?,
+            s: rlp::decode_uint(rest[7])?,
+        })
+    }
+
+    /// Signature `v` as it appears in the encoding and in JSON-RPC responses
+    pub fn v(&self) -> u64 {
+        let parity = self.odd_y_parity as u64;
+        match (self.tx_type, self.chain_id) {
+            (TransactionType::Legacy, Some(chain_id)) => chain_id * 2 + 35 + parity,
+            (TransactionType::Legacy, None) => 27 + parity,
+            _ => parity,
+        }
+    }
+
+    /// Gas price actually paid when the block's base fee is `base_fee` (EIP-1559)
+    ///
+    /// `None` if the transaction's fee cap is below the base fee.
+    pub fn effective_gas_price(&self, base_fee: u128) -> Option<u128> {
+        match self.tx_type {
+            TransactionType::DynamicFee => {
+                if self.max_fee_per_gas < base_fee {
+                    return None;
+                }
+                Some(
+                    self.max_fee_per_gas
+                        .min(base_fee.saturating_add(self.max_priority_fee_per_gas)),
+                )
+            }
+            _ => Some(self.max_fee_per_gas),
+        }
+    }
+
+    /// Hash the sender signs
+    pub fn signing_hash(&self) -> [u8; 32] {
+        let mut payload = Vec::with_capacity(self.data.len() + 128);
+        match self.tx_type {
+            TransactionType::Legacy => {
+                self.encode_legacy_fields(&mut payload);
+                if let Some(chain_id) = self.chain_id {
+                    // EIP-155
+                    rlp::encode_uint(&chain_id.to_be_bytes(), &mut payload);
+                    rlp::encode_bytes(&[], &mut payload);
+                    rlp::encode_bytes(&[], &mut payload);
+                }
+                let mut encoded = Vec::with_capacity(payload.len() + 9);
+                rlp::encode_list(&payload, &mut encoded);
+                keccak256(&encoded)
+            }
+            _ => {
+                self.encode_typed_fields(&mut payload);
+                let mut encoded = vec![self.tx_type.id()];
+                rlp::encode_list(&payload, &mut encoded);
+                keccak256(&encoded)
+            }
+        }
+    }
+
+    /// Canonical encoding, as accepted by `eth_sendRawTransaction`
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(self.data.len() + 192);
+        let mut encoded = Vec::with_capacity(self.data.len() + 201);
+        match self.tx_type {
+            TransactionType::Legacy => self.encode_legacy_fields(&mut payload),
+            _ => {
+                self.encode_typed_fields(&mut payload);
+                encoded.push(self.tx_type.id());
+            }
+        }
+        rlp::encode_uint(&self.v().to_be_bytes(), &mut payload);
+        rlp::encode_uint(&self.r, &mut payload);
+        rlp::encode_uint(&self.s, &mut payload);
+        rlp::encode_list(&payload, &mut encoded);
+        encoded
+    }
+
+    /// Transaction hash, the hash of the signed encoding
+    pub fn hash(&self) -> [u8; 32] {
+        keccak256(&self.encode())
+    }
+
+    /// `[nonce, gasPrice, gas, to, value, data]`
+    fn encode_legacy_fields(&self, out: &mut Vec<u8>) {
+        rlp::encode_uint(&self.nonce.to_be_bytes(), out);
+        rlp::encode_uint(&self.max_fee_per_gas.to_be_bytes(), out);
+        self.encode_call_fields(out);
+    }
+
+    /// `[chainId, nonce, fees.., gas, to, value, data, accessList]`
+    fn encode_typed_fields(&self, out: &mut Vec<u8>) {
+        rlp::encode_uint(&self.chain_id.unwrap_or_default().to_be_bytes(), out);
+        rlp::encode_uint(&self.nonce.to_be_bytes(), out);
+        if self.tx_type == TransactionType::DynamicFee {
+            rlp::encode_uint(&self.max_priority_fee_per_gas.to_be_bytes(), out);
+        }
+        rlp::encode_uint(&self.max_fee_per_gas.to_be_bytes(), out);
+        self.encode_call_fields(out);
+        encode_access_list(&self.access_list, out);
+    }
+
+    /// `[gas, to, value, data]`
+    fn encode_call_fields(&self, out: &mut Vec<u8>) {
+        rlp::encode_uint(&self.gas_limit.to_be_bytes(), out);
+        rlp::encode_bytes(self.to.as_ref().map_or(&[][..], |to| &to[..]), out);
+        rlp::encode_uint(&self.value.to_be_bytes(), out);
+        rlp::encode_bytes(&self.data, out);
+    }
+}
+
+fn decode_to(encoded: &[u8]) -> Result<Option<[u8; 20]>, DecoderError> {
+    match rlp::decode_bytes(encoded)? {
+        [] => Ok(None),
+        address => address
+            .try_into()
+            .map(Some)
+            .map_err(|_| DecoderError::InvalidAddress),
+    }
+}
+
+fn decode_access_list(encoded: &[u8]) -> Result<AccessList, DecoderError> {
+    let entries = match rlp::decode_item(encoded)? {
+        (rlp::Item::List(payload), _, []) => rlp::split_items(payload)?,
+        _ => return Err(DecoderError::UnexpectedType),
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let fields = rlp::decode_list(entry)?;
+            if fields.len() != 2 {
+                return Err(DecoderError::WrongFieldCount);
+            }
+            let address = rlp::decode_bytes(fields[0])?
+                .try_into()
+                .map_err(|_| DecoderError::InvalidAddress)?;
+            let keys = rlp::decode_list(fields[1])?
+                .into_iter()
+                .map(|key| {
+                    rlp::decode_bytes(key)?
+                        .try_into()
+                        .map_err(|_| DecoderError::InvalidStorageKey)
+                })
+                .collect::<Result<Vec<[u8; 32]>, _>>()?;
+            Ok((address, keys))
+        })
+        .collect()
+}
+
+fn encode_access_list(access_list: &AccessList, out: &mut Vec<u8>) {
+    let mut entries = Vec::new();
+    for (address, keys) in access_list {
+        let mut key_payload = Vec::with_capacity(keys.len() * 33);
+        for key in keys {
+            rlp::encode_bytes(key, &mut key_payload);
+        }
+        let mut entry = Vec::with_capacity(key_payload.len() + 30);
+        rlp::encode_bytes(address, &mut entry);
+        rlp::encode_list(&key_payload, &mut entry);
+        rlp::encode_list(&entry, &mut entries);
+    }
+    rlp::encode_list(&entries, out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// The example transaction from EIP-155
+    const EIP155_EXAMPLE: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    fn typed(tx_type: TransactionType) -> SignedTransaction {
+        SignedTransaction {
+            tx_type,
+            chain_id: Some(16979),
+            nonce: 7,
+            max_priority_fee_per_gas: 1_000_000_000,
+            max_fee_per_gas: 30_000_000_000,
+            gas_limit: 100_000,
+            to: Some([0x35; 20]),
+            value: 12345,
+            data: vec![0xa9, 0x05, 0x9c, 0xbb],
+            access_list: vec![([0x11; 20], vec![[0x22; 32], [0x33; 32]]), ([0x44; 20], vec![])],
+            odd_y_parity: true,
+            r: [0x55; 32],
+            s: [0x66; 32],
+        }
+    }
+
+    #[test]
+    fn test_legacy_eip155_example() {
+        let raw = decode_hex(EIP155_EXAMPLE);
+        let tx = SignedTransaction::decode(&raw).unwrap();
+
+        assert_eq!(tx.tx_type, TransactionType::Legacy);
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.nonce, 9);
+        assert_eq!(tx.max_fee_per_gas, 20_000_000_000);
+        assert_eq!(tx.gas_limit, 21_000);
+        assert_eq!(tx.to, Some([0x35; 20]));
+        assert_eq!(tx.value, 1_000_000_000_000_000_000);
+        assert_eq!(tx.v(), 37);
+        assert_eq!(
+            tx.signing_hash().to_vec(),
+            decode_hex("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")
+        );
+        assert_eq!(tx.encode(), raw);
+        assert_eq!(tx.hash(), keccak256(&raw));
+    }
+
+    #[test]
+    fn test_typed_round_trip() {
+        for tx_type in [TransactionType::AccessList, TransactionType::DynamicFee] {
+            let mut tx = typed(tx_type);
+            if tx_type == TransactionType::AccessList {
+                tx.max_priority_fee_per_gas = tx.max_fee_per_gas;
+            }
+            let raw = tx.encode();
+            assert_eq!(raw[0], tx_type.id());
+            assert_eq!(SignedTransaction::decode(&raw), Ok(tx.clone()));
+            assert_eq!(tx.hash(), keccak256(&raw));
+            assert_eq!(tx.v(), 1);
+        }
+    }
+
+    #[test]
+    fn test_typed_signing_payload() {
+        let tx = typed(TransactionType::DynamicFee);
+        // The signing payload is the encoding without the signature fields
+        let raw = tx.encode();
+        let fields = rlp::decode_list(&raw[1..]).unwrap();
+        let unsigned: Vec<u8> = fields[..9].concat();
+        let mut expected = vec![2];
+        rlp::encode_list(&unsigned, &mut expected);
+        assert_eq!(tx.signing_hash(), keccak256(&expected));
+    }
+
+    #[test]
+    fn test_effective_gas_price() {
+        let tx = typed(TransactionType::DynamicFee);
+        assert_eq!(tx.effective_gas_price(10_000_000_000), Some(11_000_000_000));
+        assert_eq!(tx.effective_gas_price(29_500_000_000), Some(30_000_000_000));
+        assert_eq!(tx.effective_gas_price(40_000_000_000), None);
+
+        let legacy = SignedTransaction::decode(&decode_hex(EIP155_EXAMPLE)).unwrap();
+        assert_eq!(legacy.effective_gas_price(1), Some(20_000_000_000));
+    }
+
+    #[test]
+    fn test_rejects_invalid_envelopes() {
+        assert_eq!(SignedTransaction::decode(&[]), Err(DecoderError::InputTooShort));
+        assert_eq!(SignedTransaction::decode(&[0x03, 0xc0]), Err(DecoderError::UnsupportedType));
+        assert_eq!(SignedTransaction::decode(&[0x02, 0xc0]), Err(DecoderError::WrongFieldCount));
+
+        let mut raw = decode_hex(EIP155_EXAMPLE);
+        raw.push(0);
+        assert_eq!(SignedTransaction::decode(&raw), Err(DecoderError::TrailingBytes));
+    }
+}
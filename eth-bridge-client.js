@@ -5,14 +5,19 @@ const borsh = require('borsh');
 // Bridge program ID (will be set after deployment)
 let BRIDGE_PROGRAM_ID = null;
 
+// EVM chain id of the cluster the bridge program was built for
+// (ETH_BRIDGE_CHAIN_ID in the Rust program, BSSC mainnet by default)
+let BRIDGE_CHAIN_ID = 16979;
+
 // PDA seed prefix (must match Rust program)
 const PDA_SEED_PREFIX = Buffer.from('eth-bridge');
 
 /**
- * Set the bridge program ID
+ * Set the bridge program ID, and the chain id of the cluster it runs on
  */
-function setBridgeProgramId(programIdString) {
+function setBridgeProgramId(programIdString, chainId = 16979) {
     BRIDGE_PROGRAM_ID = new solanaWeb3.PublicKey(programIdString);
+    BRIDGE_CHAIN_ID = chainId;
 }
 
 /**
@@ -66,17 +71,71 @@ function createInitializePDAInstruction(payerPubkey, ethAddress) {
     });
 }
 
+/**
+ * Encode a u64 as Borsh does (little-endian)
+ */
+function encodeU64(value) {
+    const buffer = Buffer.alloc(8);
+    buffer.writeBigUInt64LE(BigInt(value));
+    return buffer;
+}
+
+/**
+ * EIP-712 typed data the source ETH address signs to authorize a transfer,
+ * for eth_signTypedData_v4 (must match signature.rs in the Rust program)
+ */
+function createTransferTypedData(destinationPubkey, amount, nonce, expiry) {
+    if (!BRIDGE_PROGRAM_ID) {
+        throw new Error('Bridge program ID not set. Call setBridgeProgramId() first');
+    }
+
+    return {
+        types: {
+            EIP712Domain: [
+                { name: 'name', type: 'string' },
+                { name: 'version', type: 'string' },
+                { name: 'chainId', type: 'uint256' },
+                { name: 'salt', type: 'bytes32' },
+            ],
+            Transfer: [
+                { name: 'destination', type: 'bytes32' },
+                { name: 'amount', type: 'uint64' },
+                { name: 'nonce', type: 'uint64' },
+                { name: 'expiry', type: 'uint64' },
+            ],
+        },
+        primaryType: 'Transfer',
+        domain: {
+            name: 'ETH Bridge',
+            version: '1',
+            chainId: BRIDGE_CHAIN_ID,
+            salt: '0x' + BRIDGE_PROGRAM_ID.toBuffer().toString('hex'),
+        },
+        message: {
+            destination: '0x' + destinationPubkey.toBuffer().toString('hex'),
+            amount: BigInt(amount).toString(),
+            nonce: BigInt(nonce).toString(),
+            expiry: BigInt(expiry).toString(),
+        },
+    };
+}
+
 /**
  * Create instruction to transfer SOL via PDA
+ *
+ * ethSignature signs createTransferTypedData() of the same destination,
+ * amount, nonce and expiry.
  */
-function createTransferInstruction(sourceEthAddress, destinationPubkey, amount, ethSignature) {
+function createTransferInstruction(sourceEthAddress, destinationPubkey, amount, nonce, expiry, ethSignature) {
     const { pda, ethAddressBytes } = derivePDA(sourceEthAddress);
     
     // Instruction discriminator (1 = Transfer)
     const instructionData = Buffer.concat([
         Buffer.from([1]), // Instruction index
         ethAddressBytes,  // Source ETH address (20 bytes)
-        Buffer.from(amount.toString(16).padStart(16, '0'), 'hex'), // u64 amount (8 bytes)
+        encodeU64(amount), // u64 amount (8 bytes)
+        encodeU64(nonce),  // u64 nonce of the source PDA (8 bytes)
+        encodeU64(expiry), // u64 Unix timestamp the signature expires at (8 bytes)
         ethSignature      // ETH signature (65 bytes)
     ]);
     
@@ -91,6 +150,23 @@ function createTransferInstruction(sourceEthAddress, destinationPubkey, amount,
     });
 }
 
+/**
+ * Get the nonce the next transfer from a PDA must be signed with
+ *
+ * PDAs not initialized yet, and those initialized before the nonce was
+ * added (22 bytes of data), are at nonce 0.
+ */
+async function getPDANonce(connection, ethAddress) {
+    const { pda } = derivePDA(ethAddress);
+    const accountInfo = await connection.getAccountInfo(pda);
+    
+    // eth_address (20) + is_initialized (1) + bump_seed (1), then the u64 nonce
+    if (!accountInfo || accountInfo.data.length < 30) {
+        return 0n;
+    }
+    return accountInfo.data.readBigUInt64LE(22);
+}
+
 /**
  * Check if PDA is initialized
  */
@@ -122,6 +198,9 @@ async function getPDABalance(connection, ethAddress) {
 /**
  * Initialize PDA if needed, then send transfer
  * This is the main function the RPC server will call
+ *
+ * ethSignatureHex signs createTransferTypedData() of the destination,
+ * amountLamports, nonce (see getPDANonce()) and expiry.
  */
 async function sendTransactionViaPDA(
     connection,
@@ -129,6 +208,8 @@ async function sendTransactionViaPDA(
     fromEthAddress,
     toEthAddress,
     amountLamports,
+    nonce,
+    expiry,
     ethSignatureHex
 ) {
     const instructions = [];
@@ -168,7 +249,7 @@ async function sendTransactionViaPDA(
     
     // Add transfer instruction
     instructions.push(
-        createTransferInstruction(fromEthAddress, destinationPubkey, amountLamports, ethSignature)
+        createTransferInstruction(fromEthAddress, destinationPubkey, amountLamports, nonce, expiry, ethSignature)
     );
     
     // Create and send transaction
@@ -232,9 +313,11 @@ module.exports = {
     derivePDA,
     isPDAInitialized,
     getPDABalance,
+    getPDANonce,
     sendTransactionViaPDA,
     fundPDA,
     createInitializePDAInstruction,
+    createTransferTypedData,
     createTransferInstruction,
 };
 
@@ -0,0 +1,489 @@
+//! # Account-Backed EVM State
+//!
+//! Persists EVM balances, nonces, contract code and contract storage in Solana
+//! accounts so that state survives across transactions. Every piece of state
+//! lives in its own program derived address, under the EVM state account it
+//! belongs to:
+//!
+//! - `["evm-account", evm_state, address]` - balance and nonce of an EVM address
+//! - `["evm-code", evm_state, address]` - runtime bytecode of a contract
+//! - `["evm-storage", evm_state, address, key]` - a single 32-byte storage slot
+//!
+//! Each state account, with the chain identity it holds, so has a state of its
+//! own: a transaction signed for one chain id can only act on the balances of
+//! the state account configured with it.
+//!
+//! Callers pass every state account an instruction may touch. Accounts that do
+//! not exist yet are created (and funded for rent by the payer) when the
+//! instruction writes to them. Reads of state whose account was not passed are
+//! recorded and reported, so clients can retry with the missing accounts.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+/// Seed prefix for balance/nonce accounts
+pub const ACCOUNT_SEED: &[u8] = b"evm-account";
+/// Seed prefix for contract code accounts
+pub const CODE_SEED: &[u8] = b"evm-code";
+/// Seed prefix for storage slot accounts
+pub const STORAGE_SEED: &[u8] = b"evm-storage";
+
+/// Data stored in an EVM state account
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum EvmStateAccount {
+    /// Account has been created but holds no state
+    Uninitialized,
+    /// Balance (in wei) and nonce of an EVM address
+    Account {
+        address: [u8; 20],
+        nonce: u64,
+        balance: u128,
+    },
+    /// Runtime bytecode of a contract
+    Code { address: [u8; 20], code: Vec<u8> },
+    /// A single storage slot of a contract
+    Storage {
+        address: [u8; 20],
+        key: [u8; 32],
+        value: [u8; 32],
+    },
+}
+
+/// Identifies one piece of EVM state and the PDA holding it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateKey {
+    Account([u8; 20]),
+    Code([u8; 20]),
+    Storage([u8; 20], [u8; 32]),
+}
+
+impl StateKey {
+    /// Seeds (without the bump) used to derive the PDA for this key
+    fn seeds<'a>(&'a self, evm_state: &'a Pubkey) -> Vec<&'a [u8]> {
+        match self {
+            StateKey::Account(address) => vec![ACCOUNT_SEED, evm_state.as_ref(), address],
+            StateKey::Code(address) => vec![CODE_SEED, evm_state.as_ref(), address],
+            StateKey::Storage(address, key) => {
+                vec![STORAGE_SEED, evm_state.as_ref(), address, key]
+            }
+        }
+    }
+
+    /// Derive the PDA holding this piece of the state of `evm_state`
+    pub fn find_address(&self, program_id: &Pubkey, evm_state: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&self.seeds(evm_state), program_id)
+    }
+}
+
+/// Derive the balance/nonce account of an EVM address
+pub fn derive_account_address(
+    address: &[u8; 20],
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+) -> (Pubkey, u8) {
+    StateKey::Account(*address).find_address(program_id, evm_state)
+}
+
+/// Derive the code account of an EVM contract
+pub fn derive_code_address(
+    address: &[u8; 20],
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+) -> (Pubkey, u8) {
+    StateKey::Code(*address).find_address(program_id, evm_state)
+}
+
+/// Derive the account holding a single storage slot of an EVM contract
+pub fn derive_storage_address(
+    address: &[u8; 20],
+    key: &[u8; 32],
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+) -> (Pubkey, u8) {
+    StateKey::Storage(*address, *key).find_address(program_id, evm_state)
+}
+
+/// Tracks which Solana accounts back an `EvmState` and what has changed
+pub struct AccountBacking {
+    program_id: Pubkey,
+    /// EVM state account the state belongs to
+    evm_state: Pubkey,
+    /// Keys of every account passed to the instruction
+    available: HashSet<Pubkey>,
+    /// State keys already resolved against the passed accounts
+    checked: RefCell<HashSet<StateKey>>,
+    /// PDAs that were read but not passed to the instruction
+    missing: RefCell<Vec<Pubkey>>,
+    /// State modified since loading
+    dirty: HashSet<StateKey>,
+}
+
+impl AccountBacking {
+    /// Check that the account holding `key` was passed, recording it if not
+    pub fn require(&self, key: StateKey) {
+        if !self.checked.borrow_mut().insert(key) {
+            return;
+        }
+        let (pda, _) = key.find_address(&self.program_id, &self.evm_state);
+        if !self.available.contains(&pda) {
+            self.missing.borrow_mut().push(pda);
+        }
+    }
+
+    /// Mark a piece of state as modified
+    pub fn mark_dirty(&mut self, key: StateKey) {
+        self.require(key);
+        self.dirty.insert(key);
+    }
+}
+
+impl super::EvmState {
+    /// Load the state of `evm_state` from the accounts passed to an instruction
+    pub fn load(
+        program_id: &Pubkey,
+        evm_state: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> Result<Self, ProgramError> {
+        let mut state = Self::new();
+        let mut checked = HashSet::new();
+
+        for account in accounts {
+            if account.owner != program_id || account.data_is_empty() {
+                continue;
+            }
+
+            let record = EvmStateAccount::deserialize(&mut &account.data.borrow()[..])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            let key = match record {
+                EvmStateAccount::Uninitialized => continue,
+                EvmStateAccount::Account {
+                    address,
+                    nonce,
+                    balance,
+                } => {
+                    state.set_balance(&address, balance);
+                    state.nonces.insert(address, nonce);
+                    StateKey::Account(address)
+                }
+                EvmStateAccount::Code { address, code } => {
+                    state.code.insert(address, code);
+                    StateKey::Code(address)
+                }
+                EvmStateAccount::Storage {
+                    address,
+                    key,
+                    value,
+                } => {
+                    state.set_storage(&address, &key, &value);
+                    StateKey::Storage(address, key)
+                }
+            };
+
+            // Only trust records that live at their canonical address
+            if key.find_address(program_id, evm_state).0 != *account.key {
+                msg!("EVM state account {} is not at its derived address", account.key);
+                return Err(ProgramError::InvalidSeeds);
+            }
+            checked.insert(key);
+        }
+
+        state.clear_journal();
+        state.backing = Some(AccountBacking {
+            program_id: *program_id,
+            evm_state: *evm_state,
+            available: accounts.iter().map(|account| *account.key).collect(),
+            checked: RefCell::new(checked),
+            missing: RefCell::new(Vec::new()),
+            dirty: HashSet::new(),
+        });
+        Ok(state)
+    }
+
+    /// Fail if execution read state whose account was not passed in
+    pub fn check_missing_accounts(&self) -> Result<(), ProgramError> {
+        let backing = match &self.backing {
+            Some(backing) => backing,
+            None => return Ok(()),
+        };
+        let missing = backing.missing.borrow();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        for pda in missing.iter() {
+            msg!("Missing EVM state account: {}", pda);
+        }
+        Err(ProgramError::NotEnoughAccountKeys)
+    }
+
+    /// State accounts execution read without them being passed in
+    pub fn missing_accounts(&self) -> Vec<Pubkey> {
+        self.backing
+            .as_ref()
+            .map(|backing| backing.missing.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    /// Write every modified piece of state back to its account
+    pub fn commit<'a>(
+        &self,
+        accounts: &[AccountInfo<'a>],
+        payer: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+    ) -> Result<(), ProgramError> {
+        self.check_missing_accounts()?;
+        let backing = match &self.backing {
+            Some(backing) => backing,
+            None => return Ok(()),
+        };
+
+        let by_key: HashMap<&Pubkey, &AccountInfo<'a>> =
+            accounts.iter().map(|account| (account.key, account)).collect();
+
+        for key in &backing.dirty {
+            let (pda, bump_seed) = key.find_address(&backing.program_id, &backing.evm_state);
+            let account = by_key
+                .get(&pda)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if !account.is_writable {
+                msg!("EVM state account {} must be writable", pda);
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let record = self.record_for(key);
+            let data = borsh::to_vec(&record).map_err(|_| ProgramError::InvalidAccountData)?;
+
+            if account.owner != &backing.program_id {
+                let mut seeds = key.seeds(&backing.evm_state);
+                let bump = [bump_seed];
+                seeds.push(&bump);
+                create_state_account(
+                    &backing.program_id,
+                    account,
+                    payer,
+                    system_program,
+                    data.len(),
+                    &seeds,
+                )?;
+            } else if account.data_len() < data.len() {
+                let rent = Rent::get()?;
+                let required = rent.minimum_balance(data.len());
+                if account.lamports() < required {
+                    let top_up = required - account.lamports();
+                    solana_program::program::invoke(
+                        &system_instruction::transfer(payer.key, account.key, top_up),
+                        &[payer.clone(), (*account).clone(), system_program.clone()],
+                    )?;
+                }
+                account.realloc(data.len(), false)?;
+            }
+
+            account.data.borrow_mut()[..data.len()].copy_from_slice(&data);
+        }
+
+        Ok(())
+    }
+
+    /// Build the account record for a piece of state
+    fn record_for(&self, key: &StateKey) -> EvmStateAccount {
+        match *key {
+            StateKey::Account(address) => EvmStateAccount::Account {
+                address,
+                nonce: self.nonces.get(&address).copied().unwrap_or(0),
+                balance: self.balances.get(&address).copied().unwrap_or(0),
+            },
+            StateKey::Code(address) => EvmStateAccount::Code {
+                address,
+                code: self.code.get(&address).cloned().unwrap_or_default(),
+            },
+            StateKey::Storage(address, key) => EvmStateAccount::Storage {
+                address,
+                key,
+                value: self
+                    .storage
+                    .get(&address)
+                    .and_then(|storage| storage.get(&key))
+                    .copied()
+                    .unwrap_or([0u8; 32]),
+            },
+        }
+    }
+}
+
+/// Create a program-owned PDA, tolerating lamports already sent to it
+fn create_state_account<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<(), ProgramError> {
+    if account.owner != &system_program::id() {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let rent = Rent::get()?;
+    let required = rent.minimum_balance(space);
+
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                required,
+                space as u64,
+                program_id,
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    if account.lamports() < required {
+        solana_program::program::invoke(
+            &system_instruction::transfer(payer.key, account.key, required - account.lamports()),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, program_id),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EvmState;
+
+    fn state_account(data: Vec<u8>) -> (u64, Vec<u8>) {
+        (1_000_000, data)
+    }
+
+    #[test]
+    fn test_state_round_trips_through_accounts() {
+        let program_id = Pubkey::new_unique();
+        let evm_state = Pubkey::new_unique();
+        let alice = [0xaa; 20];
+        let bob = [0xbb; 20];
+        let (alice_pda, _) = derive_account_address(&alice, &program_id, &evm_state);
+        let (bob_pda, _) = derive_account_address(&bob, &program_id, &evm_state);
+
+        let alice_record = EvmStateAccount::Account {
+            address: alice,
+            nonce: 3,
+            balance: 100,
+        };
+        let (mut alice_lamports, mut alice_data) =
+            state_account(borsh::to_vec(&alice_record).unwrap());
+        // Bob's account exists but has never been written
+        let (mut bob_lamports, mut bob_data) = state_account(vec![0u8; 64]);
+
+        let accounts = vec![
+            AccountInfo::new(
+                &alice_pda,
+                false,
+                true,
+                &mut alice_lamports,
+                &mut alice_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &bob_pda,
+                false,
+                true,
+                &mut bob_lamports,
+                &mut bob_data,
+                &program_id,
+                false,
+                0,
+            ),
+        ];
+
+        let mut state = EvmState::load(&program_id, &evm_state, &accounts).unwrap();
+        assert_eq!(state.get_balance(&alice), 100);
+        assert_eq!(state.get_nonce(&alice), 3);
+        assert_eq!(state.get_balance(&bob), 0);
+
+        state.transfer(&alice, &bob, 40).unwrap();
+
+        let payer_key = Pubkey::new_unique();
+        let mut payer_lamports = 0;
+        let mut payer_data = vec![];
+        let system_id = system_program::id();
+        let payer = AccountInfo::new(
+            &payer_key,
+            true,
+            true,
+            &mut payer_lamports,
+            &mut payer_data,
+            &system_id,
+            false,
+            0,
+        );
+        state.commit(&accounts, &payer, &payer).unwrap();
+
+        let reloaded = EvmState::load(&program_id, &evm_state, &accounts).unwrap();
+        assert_eq!(reloaded.get_balance(&alice), 60);
+        assert_eq!(reloaded.get_balance(&bob), 40);
+        assert_eq!(reloaded.get_nonce(&alice), 3);
+    }
+
+    #[test]
+    fn test_missing_state_accounts_are_reported() {
+        let program_id = Pubkey::new_unique();
+        let state = EvmState::load(&program_id, &Pubkey::new_unique(), &[]).unwrap();
+
+        assert_eq!(state.get_storage(&[1u8; 20], &[2u8; 32]), [0u8; 32]);
+        assert_eq!(
+            state.check_missing_accounts(),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn test_records_must_live_at_derived_address() {
+        let program_id = Pubkey::new_unique();
+        let record = EvmStateAccount::Code {
+            address: [7u8; 20],
+            code: vec![0x00],
+        };
+        // Held at its address under another state account
+        let (key, _) = derive_code_address(&[7u8; 20], &program_id, &Pubkey::new_unique());
+        let (mut lamports, mut data) = state_account(borsh::to_vec(&record).unwrap());
+        let accounts = vec![AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &program_id,
+            false,
+            0,
+        )];
+
+        assert_eq!(
+            EvmState::load(&program_id, &Pubkey::new_unique(), &accounts).err(),
+            Some(ProgramError::InvalidSeeds)
+        );
+    }
+}
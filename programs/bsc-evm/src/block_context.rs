@@ -0,0 +1,93 @@
+//! # Block Context
+//!
+//! Maps the Solana chain onto the EVM block environment. Each slot is treated
+//! as an EVM block:
+//!
+//! - NUMBER is the current slot and TIMESTAMP the `Clock` unix timestamp
+//! - BLOCKHASH(n) is the bank hash of slot `n` from the `SlotHashes` sysvar,
+//!   available for the 256 most recent slots
+//! - PREVRANDAO is the hash of the most recent slot before the current one
+//! - COINBASE is the zero address, which collects transaction fees
+
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, msg, program_error::ProgramError, sysvar,
+};
+
+/// Gas limit reported by GASLIMIT
+pub const BLOCK_GAS_LIMIT: u64 = 30_000_000;
+
+/// Number of recent blocks whose hash is available to BLOCKHASH
+pub const BLOCK_HASH_WINDOW: u64 = 256;
+
+/// Size of one `(slot, hash)` entry in the `SlotHashes` sysvar data
+const SLOT_HASH_ENTRY_SIZE: usize = 8 + 32;
+
+/// Block environment visible to executing contracts
+#[derive(Debug, Clone, Default)]
+pub struct BlockContext {
+    /// Block number (Solana slot)
+    pub number: u64,
+    /// Block timestamp in seconds
+    pub timestamp: u64,
+    /// Fee recipient
+    pub coinbase: [u8; 20],
+    /// Block gas limit
+    pub gas_limit: u64,
+    /// Randomness beacon value
+    pub prev_randao: [u8; 32],
+    /// Recent `(slot, hash)` pairs, most recent first
+    hashes: Vec<(u64, [u8; 32])>,
+}
+
+impl BlockContext {
+    /// Build the block context from the `Clock` sysvar and the `SlotHashes` sysvar account
+    pub fn from_sysvars(clock: &Clock, slot_hashes: &AccountInfo) -> Result<Self, ProgramError> {
+        if *slot_hashes.key != sysvar::slot_hashes::id() {
+            msg!("Expected the SlotHashes sysvar, got {}", slot_hashes.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Read only the entries BLOCKHASH can reach instead of deserializing the whole sysvar
+        let data = slot_hashes.try_borrow_data()?;
+        let len = data
+            .get(..8)
+            .map(|len| u64::from_le_bytes(len.try_into().unwrap()))
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let hashes = data[8..]
+            .chunks_exact(SLOT_HASH_ENTRY_SIZE)
+            .take(len.min(BLOCK_HASH_WINDOW) as usize)
+            .map(|entry| {
+                let slot = u64::from_le_bytes(entry[..8].try_into().unwrap());
+                let hash: [u8; 32] = entry[8..].try_into().unwrap();
+                (slot, hash)
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            number: clock.slot,
+            timestamp: clock.unix_timestamp.max(0) as u64,
+            coinbase: [0u8; 20],
+            gas_limit: BLOCK_GAS_LIMIT,
+            prev_randao: hashes.first().map(|(_, hash)| *hash).unwrap_or_default(),
+            hashes,
+        })
+    }
+
+    /// Hash of block `number`, or zero if it is not one of the 256 most recent blocks
+    pub fn block_hash(&self, number: u64) -> [u8; 32] {
+        if number >= self.number || self.number - number > BLOCK_HASH_WINDOW {
+            return [0u8; 32];
+        }
+        self.hashes
+            .iter()
+            .find(|(slot, _)| *slot == number)
+            .map(|(_, hash)| *hash)
+            .unwrap_or_default()
+    }
+
+    /// Record the hash of a past block
+    pub fn insert_block_hash(&mut self, number: u64, hash: [u8; 32]) {
+        let position = self.hashes.partition_point(|(slot, _)| *slot > number);
+        self.hashes.insert(position, (number, hash));
+    }
+}
@@ -0,0 +1,174 @@
+//! # Chain Identity
+//!
+//! The chain id, network name and native currency of a cluster's EVM live in
+//! the EVM state account, so the program, the RPC server and wallets all read
+//! them from one place:
+//!
+//! - the state account holds a Borsh encoded `Option<ChainConfig>`, written
+//!   once by [`EvmInstruction::InitializeChain`](crate::EvmInstruction::InitializeChain)
+//!   or included in genesis as the account's initial data, see
+//!   [`ChainConfig::pack`]
+//! - a state account holding no configuration (no data, or zeroed data)
+//!   serves BSSC mainnet, [`ChainConfig::default`]
+//! - CHAINID and the EIP-155 check of signed transactions use the configured
+//!   chain id
+//! - every instruction runs under the gas costs and opcodes of the configured
+//!   hardfork, see [`gas_schedule`](crate::gas_schedule), or of
+//!   [`Hardfork::default`] if none is configured
+//! - every state account has its own balances, code and storage, so a chain
+//!   id only ever applies to the state of the account configured with it
+
+use {
+    crate::{
+        gas_schedule::{GasSchedule, Hardfork},
+        transaction::CHAIN_ID,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    serde::{Deserialize, Serialize},
+    solana_program::{
+        account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    },
+};
+
+/// Largest chain id whose EIP-155 `v` fits in 64 bits (EIP-2294)
+pub const MAX_CHAIN_ID: u64 = u64::MAX / 2 - 36;
+
+/// Decimals of the native currency, whose balances are kept in wei
+pub const NATIVE_DECIMALS: u8 = 18;
+
+/// Longest network or currency name
+pub const MAX_NAME_LEN: usize = 64;
+
+/// Longest currency symbol
+pub const MAX_SYMBOL_LEN: usize = 16;
+
+/// Native currency descriptor, as wallets expect it (EIP-3085)
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeCurrency {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Identity of the chain a cluster's EVM serves
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainConfig {
+    /// Chain id returned by CHAINID and `eth_chainId`, and signed over by transactions
+    pub chain_id: u64,
+    pub network_name: String,
+    pub native_currency: NativeCurrency,
+    /// Hardfork whose rules the EVM follows, [`Hardfork::default`] if `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardfork: Option<Hardfork>,
+}
+
+impl Default for ChainConfig {
+    /// BSSC mainnet
+    fn default() -> Self {
+        Self {
+            chain_id: CHAIN_ID,
+            network_name: "BSSC Mainnet".to_string(),
+            native_currency: NativeCurrency {
+                name: "Binance Super Smart Chain".to_string(),
+                symbol: "BSSC".to_string(),
+                decimals: NATIVE_DECIMALS,
+            },
+            hardfork: None,
+        }
+    }
+}
+
+impl ChainConfig {
+    /// Size of the largest packed configuration, the least space a state account needs
+    pub const MAX_LEN: usize =
+        1 + 8 + (4 + MAX_NAME_LEN) + (4 + MAX_NAME_LEN) + (4 + MAX_SYMBOL_LEN) + 1 + 2;
+
+    /// State account data holding this configuration
+    pub fn pack(&self) -> Vec<u8> {
+        borsh::to_vec(&Some(self)).unwrap()
+    }
+
+    /// Configuration held by state account data, `None` if it holds none
+    ///
+    /// A configuration packed before the hardfork was added has no hardfork.
+    pub fn try_unpack(data: &[u8]) -> Result<Option<Self>, ProgramError> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        // Such a configuration may end the data, where the hardfork reads as a zero byte
+        let padded = [data, &[0]].concat();
+        <Option<Self> as BorshDeserialize>::deserialize(&mut &padded[..])
+            .map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Configuration served by state account data, the default if it holds none
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Self::try_unpack(data)?.unwrap_or_default())
+    }
+
+    /// Gas costs and opcodes of the configured hardfork
+    pub fn gas_schedule(&self) -> GasSchedule {
+        GasSchedule::for_hardfork(self.hardfork.unwrap_or_default())
+    }
+
+    /// Check the configuration is one wallets and EIP-155 signatures can use
+    pub fn validate(&self) -> Result<(), ProgramError> {
+        if self.chain_id == 0 || self.chain_id > MAX_CHAIN_ID {
+            msg!("Chain id must be between 1 and {}", MAX_CHAIN_ID);
+            return Err(ProgramError::InvalidArgument);
+        }
+        let currency = &self.native_currency;
+        if currency.decimals != NATIVE_DECIMALS {
+            msg!("The native currency must have {} decimals", NATIVE_DECIMALS);
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.network_name.is_empty()
+            || self.network_name.len() > MAX_NAME_LEN
+            || currency.name.is_empty()
+            || currency.name.len() > MAX_NAME_LEN
+            || currency.symbol.is_empty()
+            || currency.symbol.len() > MAX_SYMBOL_LEN
+        {
+            msg!(
+                "Names must have 1 to {} bytes and the symbol 1 to {}",
+                MAX_NAME_LEN,
+                MAX_SYMBOL_LEN
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+}
+
+/// Write `config` to an EVM state account holding no configuration yet
+///
+/// The state account must sign, so a configuration can only be set by whoever
+/// created the account. Balances, code and storage are derived from the state
+/// account's key, see [`account_state`](crate::account_state), so configuring
+/// a new state account starts a new, empty state and leaves every other one
+/// alone.
+pub fn initialize_chain(evm_state: &AccountInfo, config: &ChainConfig) -> ProgramResult {
+    if !evm_state.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !evm_state.is_writable {
+        return Err(ProgramError::InvalidArgument);
+    }
+    config.validate()?;
+
+    let mut data = evm_state.try_borrow_mut_data()?;
+    if data.iter().any(|byte| *byte != 0) {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let packed = config.pack();
+    if packed.len() > data.len() {
+        msg!("The EVM state account needs {} bytes of space", ChainConfig::MAX_LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    data[..packed.len()].copy_from_slice(&packed);
+
+    msg!("EVM chain id: {}", config.chain_id);
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! # Program Events
+//!
+//! Results and EVM logs are published through Solana transaction metadata so
+//! clients, indexers and the Ethereum RPC layer can read them without
+//! re-executing the transaction.
+//!
+//! ## Return data
+//!
+//! Every instruction that produces a result publishes it with
+//! `set_return_data`: the raw output of a contract call, the 20-byte address
+//! of a deployed contract, or the Borsh encoding of a query result. Results
+//! larger than [`MAX_RETURN_DATA`] are logged instead as
+//! `sol_log_data([RESULT_TAG, result])`.
+//!
+//! ## Logs
+//!
+//! Each EVM log of a successful transaction is emitted, in execution order,
+//! with `sol_log_data` as the fields
+//!
+//! ```text
+//! [LOG_TAG, address, topic_0, .., topic_n, data]
+//! ```
+//!
+//! where the address is 20 bytes and every topic 32 bytes. The fields appear
+//! base64 encoded on a `Program data:` log line.
+//!
+//! ## Transactions
+//!
+//! A signed transaction with the expected nonce is announced before it runs
+//! as `sol_log_data([TRANSACTION_TAG, hash])`, so subscribers to the
+//! program's logs learn Ethereum transaction hashes as soon as they execute.
+//!
+//! Once it has run, its status is logged as `sol_log_data([STATUS_TAG, [1]])`,
+//! or `[0]` if it reverted or halted. A failed transaction leaves the Solana
+//! transaction carrying it successful: its state changes are rolled back, but
+//! it consumes its nonce and pays for the gas it used.
+
+use {
+    crate::evm_interpreter::Log,
+    borsh::BorshSerialize,
+    solana_program::{
+        entrypoint::ProgramResult,
+        log::sol_log_data,
+        program::{set_return_data, MAX_RETURN_DATA},
+        program_error::ProgramError,
+    },
+};
+
+/// First field of every EVM log
+pub const LOG_TAG: &[u8] = b"EVM_LOG";
+
+/// First field of a result too large for return data
+pub const RESULT_TAG: &[u8] = b"EVM_RESULT";
+
+/// First field of the hash of an executed signed transaction
+pub const TRANSACTION_TAG: &[u8] = b"EVM_TX";
+
+/// First field of the status of an executed signed transaction
+pub const STATUS_TAG: &[u8] = b"EVM_STATUS";
+
+/// Most topics a log can have (LOG4)
+const MAX_TOPICS: usize = 4;
+
+/// Publish `data` as the instruction's return data
+pub fn publish_return_data(data: &[u8]) {
+    if data.len() <= MAX_RETURN_DATA {
+        set_return_data(data);
+    } else {
+        sol_log_data(&[RESULT_TAG, data]);
+    }
+}
+
+/// Publish the Borsh encoding of `result` as the instruction's return data
+pub fn publish_result<T: BorshSerialize>(result: &T) -> ProgramResult {
+    let data = borsh::to_vec(result).map_err(|_| ProgramError::InvalidAccountData)?;
+    publish_return_data(&data);
+    Ok(())
+}
+
+/// Emit `log` as program data
+pub fn emit_log(log: &Log) {
+    let mut fields: Vec<&[u8]> = Vec::with_capacity(log.topics.len() + 3);
+    fields.push(LOG_TAG);
+    fields.push(&log.address);
+    fields.extend(log.topics.iter().map(|topic| &topic[..]));
+    fields.push(&log.data);
+    sol_log_data(&fields);
+}
+
+/// Rebuild a log from the fields of a `Program data:` line
+///
+/// Returns `None` if the fields are not an EVM log.
+pub fn decode_log<T: AsRef<[u8]>>(fields: &[T]) -> Option<Log> {
+    let (tag, rest) = fields.split_first()?;
+    if tag.as_ref() != LOG_TAG || rest.len() < 2 || rest.len() > MAX_TOPICS + 2 {
+        return None;
+    }
+
+    let address = rest[0].as_ref().try_into().ok()?;
+    let topics = rest[1..rest.len() - 1]
+        .iter()
+        .map(|topic| topic.as_ref().try_into().ok())
+        .collect::<Option<Vec<[u8; 32]>>>()?;
+    let data = rest[rest.len() - 1].as_ref().to_vec();
+    Some(Log { address, topics, data })
+}
+
+/// Emit the hash of the signed transaction about to run
+pub fn emit_transaction_hash(hash: &[u8; 32]) {
+    sol_log_data(&[TRANSACTION_TAG, hash]);
+}
+
+/// Rebuild a transaction hash from the fields of a `Program data:` line
+///
+/// Returns `None` if the fields are not a transaction hash.
+pub fn decode_transaction_hash<T: AsRef<[u8]>>(fields: &[T]) -> Option<[u8; 32]> {
+    match fields {
+        [tag, hash] if tag.as_ref() == TRANSACTION_TAG => hash.as_ref().try_into().ok(),
+        _ => None,
+    }
+}
+
+/// Emit whether the signed transaction that just ran succeeded
+pub fn emit_transaction_status(success: bool) {
+    sol_log_data(&[STATUS_TAG, &[success as u8]]);
+}
+
+/// Rebuild a transaction status from the fields of a `Program data:` line
+///
+/// Returns `None` if the fields are not a transaction status.
+pub fn decode_transaction_status<T: AsRef<[u8]>>(fields: &[T]) -> Option<bool> {
+    match fields {
+        [tag, status] if tag.as_ref() == STATUS_TAG => match status.as_ref() {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        },
+        _ => None,
+    }
+}
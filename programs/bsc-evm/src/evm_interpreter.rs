@@ -3,8 +3,13 @@
 //! Full implementation of the Ethereum Virtual Machine for executing smart contracts
 //! on Solana with BNB as the gas token.
 
-use solana_program::program_error::ProgramError;
-use std::collections::HashMap;
+use crate::{
+    fees::{check_compute_budget, COMPUTE_BUDGET_EXCEEDED},
+    gas_schedule::{GasSchedule, Hardfork, SSTORE_SENTRY_GAS},
+    tracer::{FrameEnd, FrameStart, Step},
+    u256::U256,
+};
+use solana_program::{keccak, program_error::ProgramError};
 
 /// EVM opcode definitions
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -71,6 +76,8 @@ pub enum Opcode {
     CHAINID = 0x46,
     SELFBALANCE = 0x47,
     BASEFEE = 0x48,
+    BLOBHASH = 0x49,
+    BLOBBASEFEE = 0x4a,
 
     // 0x50 range - storage and execution
     POP = 0x50,
@@ -85,8 +92,12 @@ pub enum Opcode {
     MSIZE = 0x59,
     GAS = 0x5a,
     JUMPDEST = 0x5b,
+    TLOAD = 0x5c,
+    TSTORE = 0x5d,
+    MCOPY = 0x5e,
 
-    // 0x60 range - push
+    // 0x5f-0x7f range - push
+    PUSH0 = 0x5f,
     PUSH1 = 0x60,
     PUSH2 = 0x61,
     PUSH3 = 0x62,
@@ -231,6 +242,8 @@ impl Opcode {
             0x46 => Some(Opcode::CHAINID),
             0x47 => Some(Opcode::SELFBALANCE),
             0x48 => Some(Opcode::BASEFEE),
+            0x49 => Some(Opcode::BLOBHASH),
+            0x4a => Some(Opcode::BLOBBASEFEE),
             0x50 => Some(Opcode::POP),
             0x51 => Some(Opcode::MLOAD),
             0x52 => Some(Opcode::MSTORE),
@@ -243,6 +256,10 @@ impl Opcode {
             0x59 => Some(Opcode::MSIZE),
             0x5a => Some(Opcode::GAS),
             0x5b => Some(Opcode::JUMPDEST),
+            0x5c => Some(Opcode::TLOAD),
+            0x5d => Some(Opcode::TSTORE),
+            0x5e => Some(Opcode::MCOPY),
+            0x5f => Some(Opcode::PUSH0),
             0x60..=0x7f => Some(unsafe { std::mem::transmute(byte) }),
             0x80..=0x8f => Some(unsafe { std::mem::transmute(byte) }),
             0x90..=0x9f => Some(unsafe { std::mem::transmute(byte) }),
@@ -261,7 +278,10 @@ impl Opcode {
         }
     }
 
-    /// Get gas cost for this opcode
+    /// Get the fixed gas cost of this opcode
+    ///
+    /// Memory expansion, copy and data costs, and everything that depends on
+    /// the hardfork (see [`GasSchedule`]) are charged during execution.
     pub fn gas_cost(&self) -> u64 {
         match self {
             Opcode::STOP => 0,
@@ -269,32 +289,37 @@ impl Opcode {
             Opcode::SLT | Opcode::SGT | Opcode::EQ | Opcode::ISZERO | 
             Opcode::AND | Opcode::OR | Opcode::XOR | Opcode::BYTE |
             Opcode::CALLDATALOAD | Opcode::MLOAD | Opcode::MSTORE | 
-            Opcode::MSTORE8 => 3,
+            Opcode::MSTORE8 | Opcode::RETURNDATACOPY | Opcode::MCOPY | Opcode::BLOBHASH => 3,
             op if matches!(*op as u8, 0x60..=0x7f) => 3, // PUSH1-PUSH32
             op if matches!(*op as u8, 0x80..=0x8f) => 3, // DUP1-DUP16
             op if matches!(*op as u8, 0x90..=0x9f) => 3, // SWAP1-SWAP16
             
-            Opcode::MUL | Opcode::DIV | Opcode::SDIV | Opcode::MOD | Opcode::SMOD => 5,
-            Opcode::ADDMOD | Opcode::MULMOD | Opcode::SIGNEXTEND => 8,
+            Opcode::MUL
+            | Opcode::DIV
+            | Opcode::SDIV
+            | Opcode::MOD
+            | Opcode::SMOD
+            | Opcode::SIGNEXTEND => 5,
+            Opcode::ADDMOD | Opcode::MULMOD => 8,
+            Opcode::EXP => 10,
             Opcode::SHL | Opcode::SHR | Opcode::SAR => 3,
             
             Opcode::ADDRESS | Opcode::ORIGIN | Opcode::CALLER | Opcode::CALLVALUE |
-            Opcode::CALLDATASIZE | Opcode::CODESIZE | Opcode::GASPRICE |
+            Opcode::CALLDATASIZE | Opcode::CODESIZE | Opcode::GASPRICE | Opcode::RETURNDATASIZE |
             Opcode::COINBASE | Opcode::TIMESTAMP | Opcode::NUMBER |
-            Opcode::DIFFICULTY | Opcode::GASLIMIT | Opcode::CHAINID |
-            Opcode::SELFBALANCE | Opcode::PC | Opcode::MSIZE | Opcode::GAS => 2,
+            Opcode::DIFFICULTY | Opcode::GASLIMIT | Opcode::CHAINID | Opcode::BASEFEE |
+            Opcode::PC | Opcode::MSIZE | Opcode::GAS | Opcode::PUSH0 | Opcode::BLOBBASEFEE => 2,
             
             Opcode::JUMPDEST => 1,
             Opcode::POP => 2,
             Opcode::JUMP => 8,
             Opcode::JUMPI => 10,
             
-            Opcode::SLOAD => 800,
-            Opcode::SSTORE => 20000,
+            Opcode::TLOAD | Opcode::TSTORE => 100,
             
             Opcode::SHA3 => 30,
-            Opcode::BALANCE => 700,
-            Opcode::EXTCODESIZE | Opcode::EXTCODEHASH => 700,
+            Opcode::CALLDATACOPY | Opcode::CODECOPY => 3,
+            Opcode::SELFBALANCE => 5,
             Opcode::BLOCKHASH => 20,
             
             Opcode::LOG0 => 375,
@@ -304,15 +329,31 @@ impl Opcode {
             Opcode::LOG4 => 1875,
             
             Opcode::CREATE | Opcode::CREATE2 => 32000,
-            Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL => 700,
             Opcode::SELFDESTRUCT => 5000,
             
+            // SLOAD, SSTORE, BALANCE, EXTCODE*, CALL* and the halting opcodes
             _ => 0,
         }
     }
 }
 
+/// Maximum depth of nested message calls and creations
+pub const MAX_CALL_DEPTH: usize = 1024;
+/// Maximum number of items on the stack
+pub const MAX_STACK_SIZE: usize = 1024;
+/// Gas given to the callee for free when a call transfers value
+pub const CALL_STIPEND: u64 = 2300;
+/// Extra gas for a call that transfers value
+pub const CALL_VALUE_TRANSFER_GAS: u64 = 9000;
+/// Extra gas for a call that brings a new account into existence
+pub const CALL_NEW_ACCOUNT_GAS: u64 = 25000;
+/// Gas charged per byte of deployed runtime code
+pub const CODE_DEPOSIT_GAS: u64 = 200;
+/// Maximum runtime code size (EIP-170)
+pub const MAX_CODE_SIZE: usize = 24576;
+
 /// EVM execution context
+#[derive(Debug, Clone)]
 pub struct ExecutionContext {
     /// Contract address being executed
     pub address: [u8; 20],
@@ -332,8 +373,20 @@ pub struct ExecutionContext {
     pub block_number: u64,
     /// Block timestamp
     pub timestamp: u64,
+    /// Block fee recipient
+    pub coinbase: [u8; 20],
+    /// Block gas limit
+    pub block_gas_limit: u64,
+    /// Block base fee in wei
+    pub base_fee: u128,
+    /// Randomness beacon value (PREVRANDAO, formerly DIFFICULTY)
+    pub prev_randao: [u8; 32],
     /// Chain ID
     pub chain_id: u64,
+    /// Call depth (0 for the transaction's top-level frame)
+    pub depth: usize,
+    /// Whether state modifications are forbidden (inside STATICCALL)
+    pub is_static: bool,
 }
 
 /// EVM execution result
@@ -348,8 +401,33 @@ pub struct ExecutionResult {
     pub logs: Vec<Log>,
 }
 
-/// EVM log entry
+/// Outcome of a nested message call or contract creation
 #[derive(Debug, Clone)]
+pub struct FrameResult {
+    /// Whether the frame completed without reverting or halting exceptionally
+    pub success: bool,
+    /// Gas left over, returned to the caller
+    pub gas_left: u64,
+    /// Return (or revert) data
+    pub output: Vec<u8>,
+    /// Logs emitted by the frame, empty unless it succeeded
+    pub logs: Vec<Log>,
+}
+
+impl FrameResult {
+    /// An exceptional halt: all gas is consumed and no output is produced
+    fn exceptional_halt() -> Self {
+        Self {
+            success: false,
+            gas_left: 0,
+            output: Vec::new(),
+            logs: Vec::new(),
+        }
+    }
+}
+
+/// EVM log entry
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Log {
     pub address: [u8; 20],
     pub topics: Vec<[u8; 32]>,
@@ -359,7 +437,7 @@ pub struct Log {
 /// EVM Interpreter - executes bytecode
 pub struct EvmInterpreter {
     /// Stack (max 1024 items)
-    stack: Vec<[u8; 32]>,
+    stack: Vec<U256>,
     /// Memory
     memory: Vec<u8>,
     /// Program counter
@@ -368,12 +446,20 @@ pub struct EvmInterpreter {
     gas: u64,
     /// Return data
     return_data: Vec<u8>,
+    /// Output of the most recent nested call or creation
+    return_data_buffer: Vec<u8>,
+    /// Opcodes executed so far
+    steps: u64,
     /// Logs
     logs: Vec<Log>,
     /// Stopped flag
     stopped: bool,
     /// Reverted flag
     reverted: bool,
+    /// Gas left before the opcode being traced
+    step_gas: u64,
+    /// Whether the tracer was told the cost of the opcode being traced
+    cost_traced: bool,
 }
 
 impl EvmInterpreter {
@@ -384,9 +470,13 @@ impl EvmInterpreter {
             pc: 0,
             gas: gas_limit,
             return_data: Vec::new(),
+            return_data_buffer: Vec::new(),
+            steps: 0,
             logs: Vec::new(),
             stopped: false,
             reverted: false,
+            step_gas: gas_limit,
+            cost_traced: false,
         }
     }
 
@@ -399,18 +489,18 @@ impl EvmInterpreter {
     ) -> Result<ExecutionResult, ProgramError> {
         while self.pc < bytecode.len() && !self.stopped && !self.reverted {
             let opcode_byte = bytecode[self.pc];
-            let opcode = Opcode::from_u8(opcode_byte)
-                .ok_or(ProgramError::InvalidInstructionData)?;
-
-            // Check gas
-            let gas_cost = opcode.gas_cost();
-            if self.gas < gas_cost {
-                return Err(ProgramError::InsufficientFunds);
+            if state.is_traced() {
+                self.trace_step(opcode_byte, context, state);
             }
-            self.gas -= gas_cost;
 
-            // Execute opcode
-            self.execute_opcode(opcode, bytecode, context, state)?;
+            if let Err(err) = self.step(opcode_byte, bytecode, context, state) {
+                if state.is_traced() {
+                    let reason = self.halt_reason(opcode_byte, context, state, &err);
+                    state.trace(|tracer| tracer.fault(reason));
+                }
+                return Err(err);
+            }
+            self.trace_cost(state);
 
             if !self.stopped && !self.reverted {
                 self.pc += 1;
@@ -425,6 +515,95 @@ impl EvmInterpreter {
         })
     }
 
+    /// Charge for and execute the opcode `opcode_byte`
+    fn step(
+        &mut self,
+        opcode_byte: u8,
+        bytecode: &[u8],
+        context: &ExecutionContext,
+        state: &mut super::EvmState,
+    ) -> Result<(), ProgramError> {
+        let opcode = Opcode::from_u8(opcode_byte)
+            .filter(|opcode| state.schedule.is_enabled(*opcode))
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        // Check gas
+        self.use_gas(opcode.gas_cost())?;
+
+        // Stop while there is still compute budget left to fail cleanly
+        self.steps += 1;
+        if self.steps % COMPUTE_CHECK_INTERVAL == 0 {
+            check_compute_budget()?;
+        }
+
+        // Execute opcode
+        self.execute_opcode(opcode, bytecode, context, state)
+    }
+
+    /// Show the tracer the opcode about to execute
+    fn trace_step(&mut self, opcode: u8, context: &ExecutionContext, state: &super::EvmState) {
+        self.step_gas = self.gas;
+        self.cost_traced = false;
+        state.trace(|tracer| {
+            tracer.step(&Step {
+                pc: self.pc,
+                opcode,
+                gas: self.gas,
+                refund: state.refund(),
+                depth: context.depth,
+                address: context.address,
+                stack: &self.stack,
+                memory: &self.memory,
+                return_data: &self.return_data_buffer,
+            })
+        });
+    }
+
+    /// Tell the tracer what the opcode being traced was charged, once
+    ///
+    /// Message calls and creations report their cost before the nested frame
+    /// runs, and so before the gas it leaves over is returned.
+    fn trace_cost(&mut self, state: &super::EvmState) {
+        if state.is_traced() && !self.cost_traced {
+            self.cost_traced = true;
+            let cost = self.step_gas.saturating_sub(self.gas);
+            state.trace(|tracer| tracer.step_cost(cost));
+        }
+    }
+
+    /// Why executing `opcode` failed with `err`, in the words of geth's traces
+    fn halt_reason(
+        &self,
+        opcode: u8,
+        context: &ExecutionContext,
+        state: &super::EvmState,
+        err: &ProgramError,
+    ) -> &'static str {
+        if *err == COMPUTE_BUDGET_EXCEEDED {
+            return "compute budget exceeded";
+        }
+        let opcode = Opcode::from_u8(opcode).filter(|opcode| state.schedule.is_enabled(*opcode));
+        match (opcode, err) {
+            (_, ProgramError::InsufficientFunds) => "out of gas",
+            (None | Some(Opcode::INVALID), _) => "invalid opcode",
+            (Some(Opcode::JUMP | Opcode::JUMPI), ProgramError::InvalidInstructionData) => {
+                "invalid jump destination"
+            }
+            (Some(Opcode::CREATE | Opcode::CREATE2), ProgramError::InvalidInstructionData)
+                if !context.is_static =>
+            {
+                "max initcode size exceeded"
+            }
+            (_, ProgramError::InvalidInstructionData) => "write protection",
+            (_, ProgramError::InvalidAccountData) if self.stack.len() >= MAX_STACK_SIZE => {
+                "stack limit reached"
+            }
+            (_, ProgramError::InvalidAccountData) => "stack underflow",
+            (_, ProgramError::InvalidArgument) => "return data out of bounds",
+            _ => "execution halted",
+        }
+    }
+
     /// Execute a single opcode
     fn execute_opcode(
         &mut self,
@@ -438,104 +617,161 @@ impl EvmInterpreter {
             Opcode::ADD => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = u256_add(&a, &b);
-                self.push(&result)?;
+                self.push(a.wrapping_add(b))?;
             }
             Opcode::MUL => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = u256_mul(&a, &b);
-                self.push(&result)?;
+                self.push(a.wrapping_mul(b))?;
             }
             Opcode::SUB => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = u256_sub(&a, &b);
-                self.push(&result)?;
+                self.push(a.wrapping_sub(b))?;
             }
             Opcode::DIV => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = u256_div(&a, &b);
-                self.push(&result)?;
+                self.push(a.div(b))?;
+            }
+            Opcode::SDIV => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                self.push(a.sdiv(b))?;
             }
             Opcode::MOD => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = u256_mod(&a, &b);
-                self.push(&result)?;
+                self.push(a.rem(b))?;
+            }
+            Opcode::SMOD => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                self.push(a.smod(b))?;
+            }
+            Opcode::ADDMOD => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                let n = self.pop()?;
+                self.push(a.addmod(b, n))?;
+            }
+            Opcode::MULMOD => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                let n = self.pop()?;
+                self.push(a.mulmod(b, n))?;
+            }
+            Opcode::EXP => {
+                let base = self.pop()?;
+                let exponent = self.pop()?;
+                self.use_gas(50 * (exponent.bits() as u64).div_ceil(8))?;
+                self.push(base.wrapping_pow(exponent))?;
+            }
+            Opcode::SIGNEXTEND => {
+                let byte_index = self.pop()?;
+                let value = self.pop()?;
+                self.push(value.signextend(byte_index))?;
             }
 
             // Comparison operations
             Opcode::LT => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = if u256_lt(&a, &b) { u256_one() } else { u256_zero() };
-                self.push(&result)?;
+                self.push_bool(a < b)?;
             }
             Opcode::GT => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = if u256_gt(&a, &b) { u256_one() } else { u256_zero() };
-                self.push(&result)?;
+                self.push_bool(a > b)?;
+            }
+            Opcode::SLT => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                self.push_bool(a.slt(&b))?;
+            }
+            Opcode::SGT => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                self.push_bool(a.sgt(&b))?;
             }
             Opcode::EQ => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = if a == b { u256_one() } else { u256_zero() };
-                self.push(&result)?;
+                self.push_bool(a == b)?;
             }
             Opcode::ISZERO => {
                 let a = self.pop()?;
-                let result = if a == u256_zero() { u256_one() } else { u256_zero() };
-                self.push(&result)?;
+                self.push_bool(a.is_zero())?;
             }
 
             // Bitwise operations
             Opcode::AND => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = u256_and(&a, &b);
-                self.push(&result)?;
+                self.push(a & b)?;
             }
             Opcode::OR => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = u256_or(&a, &b);
-                self.push(&result)?;
+                self.push(a | b)?;
             }
             Opcode::XOR => {
                 let a = self.pop()?;
                 let b = self.pop()?;
-                let result = u256_xor(&a, &b);
-                self.push(&result)?;
+                self.push(a ^ b)?;
             }
             Opcode::NOT => {
                 let a = self.pop()?;
-                let result = u256_not(&a);
-                self.push(&result)?;
+                self.push(!a)?;
+            }
+            Opcode::BYTE => {
+                let index = self.pop()?;
+                let value = self.pop()?;
+                self.push(value.byte(index))?;
+            }
+            Opcode::SHL => {
+                let shift = self.pop()?;
+                let value = self.pop()?;
+                self.push(value.evm_shl(shift))?;
+            }
+            Opcode::SHR => {
+                let shift = self.pop()?;
+                let value = self.pop()?;
+                self.push(value.evm_shr(shift))?;
+            }
+            Opcode::SAR => {
+                let shift = self.pop()?;
+                let value = self.pop()?;
+                self.push(value.evm_sar(shift))?;
             }
 
             // Stack operations
             Opcode::POP => {
                 self.pop()?;
             }
+            Opcode::PUSH0 => {
+                self.push(U256::ZERO)?;
+            }
             op if matches!(op as u8, 0x60..=0x7f) => {
                 // PUSH1-PUSH32
                 let n = (op as u8 - 0x60 + 1) as usize;
-                let mut value = [0u8; 32];
+                let start = (self.pc + 1).min(bytecode.len());
                 let end = (self.pc + n + 1).min(bytecode.len());
-                let data = &bytecode[self.pc + 1..end];
-                value[32 - data.len()..].copy_from_slice(data);
-                self.push(&value)?;
+                // Bytes past the end of the code read as zero
+                let mut value = [0u8; 32];
+                value[32 - n..32 - n + (end - start)].copy_from_slice(&bytecode[start..end]);
+                self.push(U256::from_be_bytes(value))?;
                 self.pc += n;
             }
             op if matches!(op as u8, 0x80..=0x8f) => {
                 // DUP1-DUP16
                 let n = (op as u8 - 0x80) as usize;
-                let value = *self.stack.get(self.stack.len() - n - 1)
-                    .ok_or(ProgramError::InvalidAccountData)?;
-                self.push(&value)?;
+                let len = self.stack.len();
+                if len < n + 1 {
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                let value = self.stack[len - n - 1];
+                self.push(value)?;
             }
             op if matches!(op as u8, 0x90..=0x9f) => {
                 // SWAP1-SWAP16
@@ -549,58 +785,98 @@ impl EvmInterpreter {
 
             // Memory operations
             Opcode::MLOAD => {
-                let offset = u256_to_usize(&self.pop()?);
-                self.expand_memory(offset + 32)?;
-                let mut value = [0u8; 32];
-                value.copy_from_slice(&self.memory[offset..offset + 32]);
-                self.push(&value)?;
+                let offset = self.pop_offset()?;
+                self.expand_memory(offset, 32)?;
+                self.push(U256::from_be_slice(&self.memory[offset..offset + 32]))?;
             }
             Opcode::MSTORE => {
-                let offset = u256_to_usize(&self.pop()?);
+                let offset = self.pop_offset()?;
                 let value = self.pop()?;
-                self.expand_memory(offset + 32)?;
-                self.memory[offset..offset + 32].copy_from_slice(&value);
+                self.expand_memory(offset, 32)?;
+                self.memory[offset..offset + 32].copy_from_slice(&value.to_be_bytes());
             }
             Opcode::MSTORE8 => {
-                let offset = u256_to_usize(&self.pop()?);
+                let offset = self.pop_offset()?;
                 let value = self.pop()?;
-                self.expand_memory(offset + 1)?;
-                self.memory[offset] = value[31];
+                self.expand_memory(offset, 1)?;
+                self.memory[offset] = value.low_u64() as u8;
             }
             Opcode::MSIZE => {
-                let size = self.memory.len();
-                self.push(&usize_to_u256(size))?;
+                self.push(U256::from(self.memory.len()))?;
+            }
+            Opcode::MCOPY => {
+                let dest = self.pop()?;
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                // Expand to cover both regions before copying
+                let (offset, size) = self.memory_region(offset, size)?;
+                let (dest, _) = self.memory_region(dest, U256::from(size))?;
+                self.use_gas(3 * (size as u64).div_ceil(32))?;
+                self.memory.copy_within(offset..offset + size, dest);
+            }
+
+            // Cryptographic operations
+            Opcode::SHA3 => {
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                let data = self.read_memory(offset, size)?;
+                self.use_gas(6 * (data.len() as u64).div_ceil(32))?;
+                self.push(U256::from_be_bytes(keccak::hash(&data).to_bytes()))?;
             }
 
             // Storage operations
             Opcode::SLOAD => {
-                let key = self.pop()?;
+                let key = self.pop()?.to_be_bytes();
+                let cold = !state.warm_storage(&context.address, &key);
+                self.use_gas(state.schedule.sload_cost(cold))?;
                 let value = state.get_storage(&context.address, &key);
-                self.push(&value)?;
+                state.trace(|tracer| tracer.storage(&context.address, &key, &value));
+                self.push(U256::from_be_bytes(value))?;
             }
             Opcode::SSTORE => {
-                let key = self.pop()?;
-                let value = self.pop()?;
+                if context.is_static {
+                    return Err(STATIC_VIOLATION);
+                }
+                // EIP-2200: SSTORE needs more than the call stipend left
+                if self.gas <= SSTORE_SENTRY_GAS {
+                    return Err(ProgramError::InsufficientFunds);
+                }
+                let key = self.pop()?.to_be_bytes();
+                let value = self.pop()?.to_be_bytes();
+                let cold = !state.warm_storage(&context.address, &key);
+                let original = state.original_storage(&context.address, &key);
+                let current = state.get_storage(&context.address, &key);
+                let (cost, refund) = state.schedule.sstore_cost(&original, &current, &value, cold);
+                self.use_gas(cost)?;
+                state.add_refund(refund);
                 state.set_storage(&context.address, &key, &value);
+                state.trace(|tracer| tracer.storage(&context.address, &key, &value));
+            }
+            Opcode::TLOAD => {
+                let key = self.pop()?.to_be_bytes();
+                let value = state.get_transient_storage(&context.address, &key);
+                self.push(U256::from_be_bytes(value))?;
+            }
+            Opcode::TSTORE => {
+                if context.is_static {
+                    return Err(STATIC_VIOLATION);
+                }
+                let key = self.pop()?.to_be_bytes();
+                let value = self.pop()?.to_be_bytes();
+                state.set_transient_storage(&context.address, &key, &value);
             }
 
             // Control flow
             Opcode::JUMP => {
-                let dest = u256_to_usize(&self.pop()?);
-                if dest >= bytecode.len() || bytecode[dest] != Opcode::JUMPDEST as u8 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                self.pc = dest;
+                let dest = self.pop()?;
+                self.pc = Self::jump_destination(dest, bytecode)?;
                 return Ok(());
             }
             Opcode::JUMPI => {
-                let dest = u256_to_usize(&self.pop()?);
+                let dest = self.pop()?;
                 let cond = self.pop()?;
-                if cond != u256_zero() {
-                    if dest >= bytecode.len() || bytecode[dest] != Opcode::JUMPDEST as u8 {
-                        return Err(ProgramError::InvalidInstructionData);
-                    }
-                    self.pc = dest;
+                if !cond.is_zero() {
+                    self.pc = Self::jump_destination(dest, bytecode)?;
                     return Ok(());
                 }
             }
@@ -608,64 +884,130 @@ impl EvmInterpreter {
                 // Valid jump destination, no-op
             }
             Opcode::PC => {
-                self.push(&usize_to_u256(self.pc))?;
+                self.push(U256::from(self.pc))?;
             }
 
             // Context operations
             Opcode::ADDRESS => {
-                let mut value = [0u8; 32];
-                value[12..].copy_from_slice(&context.address);
-                self.push(&value)?;
+                self.push(U256::from_address(&context.address))?;
             }
             Opcode::CALLER => {
-                let mut value = [0u8; 32];
-                value[12..].copy_from_slice(&context.caller);
-                self.push(&value)?;
+                self.push(U256::from_address(&context.caller))?;
             }
             Opcode::CALLVALUE => {
-                self.push(&u128_to_u256(context.value))?;
+                self.push(U256::from(context.value))?;
             }
             Opcode::CALLDATALOAD => {
-                let offset = u256_to_usize(&self.pop()?);
+                let offset = self.pop()?;
                 let mut value = [0u8; 32];
-                if offset < context.data.len() {
+                if let Some(offset) = offset.to_usize().filter(|&o| o < context.data.len()) {
                     let end = (offset + 32).min(context.data.len());
                     value[..end - offset].copy_from_slice(&context.data[offset..end]);
                 }
-                self.push(&value)?;
+                self.push(U256::from_be_bytes(value))?;
             }
             Opcode::CALLDATASIZE => {
-                self.push(&usize_to_u256(context.data.len()))?;
+                self.push(U256::from(context.data.len()))?;
+            }
+            Opcode::CALLDATACOPY => {
+                self.copy_to_memory(&context.data)?;
+            }
+            Opcode::CODESIZE => {
+                self.push(U256::from(bytecode.len()))?;
+            }
+            Opcode::CODECOPY => {
+                self.copy_to_memory(bytecode)?;
             }
             Opcode::GASPRICE => {
-                self.push(&u128_to_u256(context.gas_price))?;
+                self.push(U256::from(context.gas_price))?;
+            }
+            Opcode::ORIGIN => {
+                self.push(U256::from_address(&context.origin))?;
+            }
+            Opcode::BALANCE => {
+                let address = self.pop()?.to_address();
+                self.access_account(&address, state)?;
+                self.push(U256::from(state.get_balance(&address)))?;
+            }
+            Opcode::SELFBALANCE => {
+                self.push(U256::from(state.get_balance(&context.address)))?;
+            }
+            Opcode::EXTCODESIZE => {
+                let address = self.pop()?.to_address();
+                self.access_account(&address, state)?;
+                self.push(U256::from(state.get_code(&address).len()))?;
+            }
+            Opcode::EXTCODECOPY => {
+                let address = self.pop()?.to_address();
+                self.access_account(&address, state)?;
+                self.copy_to_memory(&state.get_code(&address))?;
+            }
+            Opcode::EXTCODEHASH => {
+                let address = self.pop()?.to_address();
+                self.access_account(&address, state)?;
+                // Accounts that do not exist hash to zero, existing ones to keccak256(code)
+                if state.account_exists(&address) {
+                    let hash = keccak::hash(&state.get_code(&address)).to_bytes();
+                    self.push(U256::from_be_bytes(hash))?;
+                } else {
+                    self.push(U256::ZERO)?;
+                }
+            }
+
+            // Block operations
+            Opcode::BLOCKHASH => {
+                let number = self.pop()?;
+                let hash = number
+                    .to_u64()
+                    .map(|number| state.block.block_hash(number))
+                    .unwrap_or_default();
+                self.push(U256::from_be_bytes(hash))?;
+            }
+            Opcode::COINBASE => {
+                self.push(U256::from_address(&context.coinbase))?;
             }
             Opcode::CHAINID => {
-                self.push(&u64_to_u256(context.chain_id))?;
+                self.push(U256::from(context.chain_id))?;
             }
             Opcode::TIMESTAMP => {
-                self.push(&u64_to_u256(context.timestamp))?;
+                self.push(U256::from(context.timestamp))?;
             }
             Opcode::NUMBER => {
-                self.push(&u64_to_u256(context.block_number))?;
+                self.push(U256::from(context.block_number))?;
+            }
+            Opcode::DIFFICULTY => {
+                // PREVRANDAO since the merge
+                self.push(U256::from_be_bytes(context.prev_randao))?;
+            }
+            Opcode::GASLIMIT => {
+                self.push(U256::from(context.block_gas_limit))?;
+            }
+            Opcode::BASEFEE => {
+                self.push(U256::from(context.base_fee))?;
+            }
+            Opcode::BLOBHASH => {
+                // Blob transactions are not supported, so there are no blob hashes
+                self.pop()?;
+                self.push(U256::ZERO)?;
+            }
+            Opcode::BLOBBASEFEE => {
+                self.push(U256::ONE)?;
             }
             Opcode::GAS => {
-                self.push(&u64_to_u256(self.gas))?;
+                self.push(U256::from(self.gas))?;
             }
 
             // Return operations
             Opcode::RETURN => {
-                let offset = u256_to_usize(&self.pop()?);
-                let size = u256_to_usize(&self.pop()?);
-                self.expand_memory(offset + size)?;
-                self.return_data = self.memory[offset..offset + size].to_vec();
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                self.return_data = self.read_memory(offset, size)?;
                 self.stopped = true;
             }
             Opcode::REVERT => {
-                let offset = u256_to_usize(&self.pop()?);
-                let size = u256_to_usize(&self.pop()?);
-                self.expand_memory(offset + size)?;
-                self.return_data = self.memory[offset..offset + size].to_vec();
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                self.return_data = self.read_memory(offset, size)?;
                 self.reverted = true;
                 self.stopped = true;
             }
@@ -676,207 +1018,551 @@ impl EvmInterpreter {
             // Logging operations
             op if matches!(op as u8, 0xa0..=0xa4) => {
                 // LOG0-LOG4
+                if context.is_static {
+                    return Err(STATIC_VIOLATION);
+                }
                 let topic_count = (op as u8 - 0xa0) as usize;
-                let offset = u256_to_usize(&self.pop()?);
-                let size = u256_to_usize(&self.pop()?);
+                let offset = self.pop()?;
+                let size = self.pop()?;
                 
                 let mut topics = Vec::new();
                 for _ in 0..topic_count {
-                    topics.push(self.pop()?);
+                    topics.push(self.pop()?.to_be_bytes());
                 }
                 
-                self.expand_memory(offset + size)?;
-                let data = self.memory[offset..offset + size].to_vec();
+                let data = self.read_memory(offset, size)?;
+                self.use_gas(8 * data.len() as u64)?;
                 
-                self.logs.push(Log {
+                let log = Log {
                     address: context.address,
                     topics,
                     data,
-                });
+                };
+                state.trace(|tracer| tracer.log(&log));
+                self.logs.push(log);
             }
 
-            // Not yet implemented opcodes
-            _ => {
-                solana_program::log::sol_log(&format!("Opcode {:?} not yet implemented", opcode));
+            // Message calls and contract creation
+            Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL => {
+                self.message_call(opcode, context, state)?;
+            }
+            Opcode::CREATE | Opcode::CREATE2 => {
+                self.create(opcode, context, state)?;
+            }
+            Opcode::RETURNDATASIZE => {
+                self.push(U256::from(self.return_data_buffer.len()))?;
+            }
+            Opcode::RETURNDATACOPY => {
+                let dest = self.pop()?;
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                // Reading past the end of the return data is an exceptional halt
+                let end = offset
+                    .checked_add(size)
+                    .and_then(|end| end.to_usize())
+                    .filter(|&end| end <= self.return_data_buffer.len())
+                    .ok_or(ProgramError::InvalidArgument)?;
+                let start = end - size.low_u64() as usize;
+                let data = self.return_data_buffer[start..end].to_vec();
+                self.write_memory(dest, &data)?;
+                self.use_gas(3 * (data.len() as u64).div_ceil(32))?;
+            }
+            Opcode::SELFDESTRUCT => {
+                if context.is_static {
+                    return Err(STATIC_VIOLATION);
+                }
+                let beneficiary = self.pop()?.to_address();
+                if !state.warm_address(&beneficiary) && state.schedule.has_access_lists() {
+                    self.use_gas(state.schedule.cold_account_access)?;
+                }
+                let balance = state.get_balance(&context.address);
+                if balance > 0 && !state.account_exists(&beneficiary) {
+                    self.use_gas(CALL_NEW_ACCOUNT_GAS)?;
+                }
+                state.transfer(&context.address, &beneficiary, balance)?;
+                // Since Cancun (EIP-6780) only contracts created in this transaction are removed
+                if state.schedule.hardfork < Hardfork::Cancun
+                    || state.created_contracts.contains(&context.address)
+                {
+                    state.set_balance(&context.address, 0);
+                    state.delete_contract(&context.address);
+                }
+                // Zero from London (EIP-3529)
+                state.add_refund(state.schedule.selfdestruct_refund as i64);
+                self.stopped = true;
             }
+
+            // INVALID (EIP-141), like any opcode not handled above, is an exceptional halt
+            _ => return Err(ProgramError::InvalidInstructionData),
         }
 
         Ok(())
     }
 
-    fn push(&mut self, value: &[u8; 32]) -> Result<(), ProgramError> {
-        if self.stack.len() >= 1024 {
-            return Err(ProgramError::InvalidAccountData);
+    /// CALL, CALLCODE, DELEGATECALL and STATICCALL
+    fn message_call(
+        &mut self,
+        opcode: Opcode,
+        context: &ExecutionContext,
+        state: &mut super::EvmState,
+    ) -> Result<(), ProgramError> {
+        let requested_gas = self.pop()?;
+        let target = self.pop()?.to_address();
+        let value = match opcode {
+            Opcode::CALL | Opcode::CALLCODE => self.pop()?,
+            _ => U256::ZERO,
+        };
+        let args_offset = self.pop()?;
+        let args_size = self.pop()?;
+        let ret_offset = self.pop()?;
+        let ret_size = self.pop()?;
+
+        if opcode == Opcode::CALL && context.is_static && !value.is_zero() {
+            return Err(STATIC_VIOLATION);
         }
-        self.stack.push(*value);
-        Ok(())
-    }
 
-    fn pop(&mut self) -> Result<[u8; 32], ProgramError> {
-        self.stack.pop().ok_or(ProgramError::InvalidAccountData)
-    }
+        let input = self.read_memory(args_offset, args_size)?;
+        let (ret_offset, ret_size) = self.memory_region(ret_offset, ret_size)?;
 
-    fn expand_memory(&mut self, size: usize) -> Result<(), ProgramError> {
-        if size > self.memory.len() {
-            // Charge gas for memory expansion
-            let words = (size + 31) / 32;
-            let memory_cost = 3 * words + (words * words) / 512;
-            if self.gas < memory_cost as u64 {
-                return Err(ProgramError::InsufficientFunds);
+        self.access_account(&target, state)?;
+        if !value.is_zero() {
+            self.use_gas(CALL_VALUE_TRANSFER_GAS)?;
+            if opcode == Opcode::CALL && !state.account_exists(&target) {
+                self.use_gas(CALL_NEW_ACCOUNT_GAS)?;
             }
-            self.gas -= memory_cost as u64;
-            
-            self.memory.resize(size, 0);
         }
-        Ok(())
+
+        // EIP-150: forward at most all but one 64th of the remaining gas
+        let available = self.gas - self.gas / 64;
+        let mut call_gas = requested_gas.to_u64().map_or(available, |gas| gas.min(available));
+        self.gas -= call_gas;
+        if !value.is_zero() {
+            call_gas += CALL_STIPEND;
+        }
+
+        self.return_data_buffer.clear();
+
+        let value = match value.to_u128() {
+            Some(value)
+                if context.depth < MAX_CALL_DEPTH
+                    && state.get_balance(&context.address) >= value =>
+            {
+                value
+            }
+            _ => {
+                // Too deep or unaffordable: the call fails without executing
+                self.gas += call_gas;
+                return self.push(U256::ZERO);
+            }
+        };
+
+        let child = ExecutionContext {
+            address: match opcode {
+                Opcode::CALL | Opcode::STATICCALL => target,
+                _ => context.address,
+            },
+            caller: match opcode {
+                Opcode::DELEGATECALL => context.caller,
+                _ => context.address,
+            },
+            value: match opcode {
+                Opcode::DELEGATECALL => context.value,
+                _ => value,
+            },
+            data: input,
+            gas_limit: call_gas,
+            depth: context.depth + 1,
+            is_static: context.is_static || opcode == Opcode::STATICCALL,
+            ..context.clone()
+        };
+        let code = state.get_code(&target);
+
+        let checkpoint = state.checkpoint();
+        if opcode == Opcode::CALL {
+            state.transfer(&context.address, &target, value)?;
+        }
+        self.trace_cost(state);
+        state.trace(|tracer| {
+            tracer.enter(&FrameStart {
+                kind: opcode,
+                from: context.address,
+                to: target,
+                value: child.value,
+                gas: call_gas,
+                input: &child.data,
+                depth: child.depth,
+            })
+        });
+        let outcome = run_frame(&code, &child, state, checkpoint)?;
+        state.trace(|tracer| {
+            tracer.exit(&FrameEnd {
+                success: outcome.success,
+                gas_used: call_gas - outcome.gas_left,
+                output: &outcome.output,
+            })
+        });
+
+        self.gas += outcome.gas_left;
+        let copied = ret_size.min(outcome.output.len());
+        self.memory[ret_offset..ret_offset + copied].copy_from_slice(&outcome.output[..copied]);
+        if outcome.success {
+            self.logs.extend(outcome.logs);
+        }
+        self.return_data_buffer = outcome.output;
+        self.push_bool(outcome.success)
     }
-}
 
-// U256 helper functions
-fn u256_zero() -> [u8; 32] {
-    [0u8; 32]
-}
+    /// CREATE and CREATE2
+    fn create(
+        &mut self,
+        opcode: Opcode,
+        context: &ExecutionContext,
+        state: &mut super::EvmState,
+    ) -> Result<(), ProgramError> {
+        let value = self.pop()?;
+        let offset = self.pop()?;
+        let size = self.pop()?;
+        let salt = match opcode {
+            Opcode::CREATE2 => Some(self.pop()?),
+            _ => None,
+        };
 
-fn u256_one() -> [u8; 32] {
-    let mut result = [0u8; 32];
-    result[31] = 1;
-    result
-}
+        if context.is_static {
+            return Err(STATIC_VIOLATION);
+        }
+
+        let init_code = self.read_memory(offset, size)?;
+        let schedule = state.schedule;
+        if schedule.max_initcode_size.is_some_and(|max| init_code.len() > max) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let words = (init_code.len() as u64).div_ceil(32);
+        self.use_gas(schedule.initcode_word * words)?;
+        if salt.is_some() {
+            // CREATE2 hashes the init code to derive the address
+            self.use_gas(6 * words)?;
+        }
+        self.return_data_buffer.clear();
+
+        // EIP-150: the new frame gets all but one 64th of the remaining gas
+        let gas = self.gas - self.gas / 64;
+        self.gas -= gas;
+
+        let nonce = state.get_nonce(&context.address);
+        let value = match value.to_u128() {
+            Some(value)
+                if context.depth < MAX_CALL_DEPTH
+                    && nonce < u64::MAX
+                    && state.get_balance(&context.address) >= value =>
+            {
+                value
+            }
+            _ => {
+                self.gas += gas;
+                return self.push(U256::ZERO);
+            }
+        };
+        state.set_nonce(&context.address, nonce + 1);
+
+        let address = match salt {
+            Some(salt) => create2_address(&context.address, &salt.to_be_bytes(), &init_code),
+            None => create_address(&context.address, nonce),
+        };
+        let child = ExecutionContext {
+            address,
+            caller: context.address,
+            value,
+            data: Vec::new(),
+            gas_limit: gas,
+            depth: context.depth + 1,
+            is_static: false,
+            ..context.clone()
+        };
+        self.trace_cost(state);
+        state.trace(|tracer| {
+            tracer.enter(&FrameStart {
+                kind: opcode,
+                from: context.address,
+                to: address,
+                value,
+                gas,
+                input: &init_code,
+                depth: child.depth,
+            })
+        });
+        let outcome = create_contract(&init_code, &child, state)?;
+        if state.is_traced() {
+            let output = match outcome.success {
+                true => state.get_code(&address),
+                false => outcome.output.clone(),
+            };
+            state.trace(|tracer| {
+                tracer.exit(&FrameEnd {
+                    success: outcome.success,
+                    gas_used: gas - outcome.gas_left,
+                    output: &output,
+                })
+            });
+        }
 
-fn u256_add(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    let mut carry = 0u16;
-    for i in (0..32).rev() {
-        let sum = a[i] as u16 + b[i] as u16 + carry;
-        result[i] = sum as u8;
-        carry = sum >> 8;
+        self.gas += outcome.gas_left;
+        if outcome.success {
+            self.logs.extend(outcome.logs);
+            self.push(U256::from_address(&address))
+        } else {
+            self.return_data_buffer = outcome.output;
+            self.push(U256::ZERO)
+        }
     }
-    result
-}
 
-fn u256_sub(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    let mut borrow = 0i16;
-    for i in (0..32).rev() {
-        let diff = a[i] as i16 - b[i] as i16 - borrow;
-        result[i] = diff as u8;
-        borrow = if diff < 0 { 1 } else { 0 };
+    /// Charge for accessing an account, warm or cold (EIP-2929)
+    fn access_account(
+        &mut self,
+        address: &[u8; 20],
+        state: &mut super::EvmState,
+    ) -> Result<(), ProgramError> {
+        let cost = if state.warm_address(address) {
+            state.schedule.warm_account_access
+        } else {
+            state.schedule.cold_account_access
+        };
+        self.use_gas(cost)
     }
-    result
-}
 
-fn u256_mul(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    // Simplified multiplication (only works for small numbers)
-    let a_u128 = u128::from_be_bytes(a[16..32].try_into().unwrap());
-    let b_u128 = u128::from_be_bytes(b[16..32].try_into().unwrap());
-    let result_u128 = a_u128.wrapping_mul(b_u128);
-    u128_to_u256(result_u128)
-}
+    fn use_gas(&mut self, amount: u64) -> Result<(), ProgramError> {
+        if self.gas < amount {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        self.gas -= amount;
+        Ok(())
+    }
 
-fn u256_div(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    if *b == u256_zero() {
-        return u256_zero();
+    fn push(&mut self, value: U256) -> Result<(), ProgramError> {
+        if self.stack.len() >= MAX_STACK_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        self.stack.push(value);
+        Ok(())
     }
-    let a_u128 = u128::from_be_bytes(a[16..32].try_into().unwrap());
-    let b_u128 = u128::from_be_bytes(b[16..32].try_into().unwrap());
-    if b_u128 == 0 {
-        return u256_zero();
+
+    fn push_bool(&mut self, value: bool) -> Result<(), ProgramError> {
+        self.push(if value { U256::ONE } else { U256::ZERO })
     }
-    let result_u128 = a_u128 / b_u128;
-    u128_to_u256(result_u128)
-}
 
-fn u256_mod(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    if *b == u256_zero() {
-        return u256_zero();
+    fn pop(&mut self) -> Result<U256, ProgramError> {
+        self.stack.pop().ok_or(ProgramError::InvalidAccountData)
     }
-    let a_u128 = u128::from_be_bytes(a[16..32].try_into().unwrap());
-    let b_u128 = u128::from_be_bytes(b[16..32].try_into().unwrap());
-    if b_u128 == 0 {
-        return u256_zero();
+
+    /// Pop a memory offset; offsets that cannot be addressed run out of gas
+    fn pop_offset(&mut self) -> Result<usize, ProgramError> {
+        self.pop()?.to_usize().ok_or(ProgramError::InsufficientFunds)
     }
-    let result_u128 = a_u128 % b_u128;
-    u128_to_u256(result_u128)
-}
 
-fn u256_and(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    for i in 0..32 {
-        result[i] = a[i] & b[i];
+    /// Validate a jump target against the code
+    fn jump_destination(dest: U256, bytecode: &[u8]) -> Result<usize, ProgramError> {
+        match dest.to_usize() {
+            Some(dest) if dest < bytecode.len() && bytecode[dest] == Opcode::JUMPDEST as u8 => {
+                Ok(dest)
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
     }
-    result
-}
 
-fn u256_or(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    for i in 0..32 {
-        result[i] = a[i] | b[i];
+    /// Expand memory to cover a region, returning it as `(offset, size)`
+    fn memory_region(&mut self, offset: U256, size: U256) -> Result<(usize, usize), ProgramError> {
+        let size = size.to_usize().ok_or(ProgramError::InsufficientFunds)?;
+        if size == 0 {
+            return Ok((0, 0));
+        }
+        let offset = offset.to_usize().ok_or(ProgramError::InsufficientFunds)?;
+        self.expand_memory(offset, size)?;
+        Ok((offset, size))
     }
-    result
-}
 
-fn u256_xor(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    for i in 0..32 {
-        result[i] = a[i] ^ b[i];
+    /// Copy `size` bytes of memory starting at `offset`, expanding as needed
+    fn read_memory(&mut self, offset: U256, size: U256) -> Result<Vec<u8>, ProgramError> {
+        let (offset, size) = self.memory_region(offset, size)?;
+        Ok(self.memory[offset..offset + size].to_vec())
     }
-    result
-}
 
-fn u256_not(a: &[u8; 32]) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    for i in 0..32 {
-        result[i] = !a[i];
+    /// CALLDATACOPY, CODECOPY and EXTCODECOPY: copy part of `source` to memory,
+    /// reading zeros past its end
+    fn copy_to_memory(&mut self, source: &[u8]) -> Result<(), ProgramError> {
+        let dest = self.pop()?;
+        let offset = self.pop()?;
+        let size = self.pop()?;
+
+        let (dest, size) = self.memory_region(dest, size)?;
+        self.use_gas(3 * (size as u64).div_ceil(32))?;
+
+        let start = offset.to_usize().unwrap_or(usize::MAX).min(source.len());
+        let copied = (source.len() - start).min(size);
+        self.memory[dest..dest + copied].copy_from_slice(&source[start..start + copied]);
+        self.memory[dest + copied..dest + size].fill(0);
+        Ok(())
+    }
+
+    /// Write `data` to memory at `offset`, expanding as needed
+    fn write_memory(&mut self, offset: U256, data: &[u8]) -> Result<(), ProgramError> {
+        let (offset, size) = self.memory_region(offset, U256::from(data.len()))?;
+        self.memory[offset..offset + size].copy_from_slice(data);
+        Ok(())
     }
-    result
-}
 
-fn u256_lt(a: &[u8; 32], b: &[u8; 32]) -> bool {
-    for i in 0..32 {
-        if a[i] < b[i] {
-            return true;
+    /// Grow memory to cover `size` bytes at `offset`
+    fn expand_memory(&mut self, offset: usize, size: usize) -> Result<(), ProgramError> {
+        if size == 0 {
+            return Ok(());
         }
-        if a[i] > b[i] {
-            return false;
+        let end = offset.checked_add(size).ok_or(ProgramError::InsufficientFunds)?;
+        if end > self.memory.len() {
+            // Charge for the words added on top of the current memory size
+            let words = (end as u64).div_ceil(32);
+            let current_words = self.memory.len() as u64 / 32;
+            self.use_gas(Self::memory_cost(words) - Self::memory_cost(current_words))?;
+            
+            self.memory.resize(words as usize * 32, 0);
         }
+        Ok(())
+    }
+
+    /// Total cost of `words` words of memory
+    fn memory_cost(words: u64) -> u64 {
+        words
+            .saturating_mul(3)
+            .saturating_add(words.saturating_mul(words) / 512)
     }
-    false
 }
 
-fn u256_gt(a: &[u8; 32], b: &[u8; 32]) -> bool {
-    for i in 0..32 {
-        if a[i] > b[i] {
-            return true;
+/// Opcodes executed between checks of the remaining compute budget
+const COMPUTE_CHECK_INTERVAL: u64 = 64;
+
+/// State modification attempted inside a STATICCALL
+const STATIC_VIOLATION: ProgramError = ProgramError::InvalidInstructionData;
+
+/// Run `code` in a new frame, rolling state back to `checkpoint` unless it succeeds
+///
+/// Only running out of compute budget is returned as an error; every other
+/// failure is an exceptional halt of the frame.
+pub fn run_frame(
+    code: &[u8],
+    context: &ExecutionContext,
+    state: &mut super::EvmState,
+    checkpoint: usize,
+) -> Result<FrameResult, ProgramError> {
+    let outcome = match EvmInterpreter::new(context.gas_limit).execute(code, context, state) {
+        Ok(result) if result.success => FrameResult {
+            success: true,
+            gas_left: context.gas_limit - result.gas_used,
+            output: result.return_data,
+            logs: result.logs,
+        },
+        Ok(result) => {
+            state.revert_to(checkpoint);
+            FrameResult {
+                success: false,
+                gas_left: context.gas_limit - result.gas_used,
+                output: result.return_data,
+                logs: Vec::new(),
+            }
         }
-        if a[i] < b[i] {
-            return false;
+        Err(err) if err == COMPUTE_BUDGET_EXCEEDED => return Err(err),
+        Err(_) => {
+            state.revert_to(checkpoint);
+            FrameResult::exceptional_halt()
         }
-    }
-    false
+    };
+    Ok(outcome)
 }
 
-fn u256_to_usize(value: &[u8; 32]) -> usize {
-    let mut result = 0usize;
-    for &byte in &value[24..32] {
-        result = (result << 8) | byte as usize;
+/// Deploy a contract at `context.address` by running `init_code`
+///
+/// `context.caller` funds `context.value`. Whatever the init code returns
+/// becomes the runtime code of the new contract. As with [`run_frame`], only
+/// running out of compute budget is an error.
+pub fn create_contract(
+    init_code: &[u8],
+    context: &ExecutionContext,
+    state: &mut super::EvmState,
+) -> Result<FrameResult, ProgramError> {
+    let address = context.address;
+    state.warm_address(&address);
+
+    // Creating over an existing contract or used address fails
+    if state.get_nonce(&address) != 0 || !state.get_code(&address).is_empty() {
+        state.trace(|tracer| tracer.fault("contract address collision"));
+        return Ok(FrameResult::exceptional_halt());
+    }
+
+    let checkpoint = state.checkpoint();
+    state.set_nonce(&address, 1);
+    if state.transfer(&context.caller, &address, context.value).is_err() {
+        state.revert_to(checkpoint);
+        state.trace(|tracer| tracer.fault("insufficient balance for transfer"));
+        return Ok(FrameResult::exceptional_halt());
+    }
+    state.created_contracts.insert(address);
+
+    let mut outcome = run_frame(init_code, context, state, checkpoint)?;
+    if !outcome.success {
+        return Ok(outcome);
     }
-    result
-}
 
-fn usize_to_u256(value: usize) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    result[24..32].copy_from_slice(&value.to_be_bytes());
-    result
+    let code = std::mem::take(&mut outcome.output);
+    let deposit_cost = CODE_DEPOSIT_GAS * code.len() as u64;
+    // EIP-170 size limit, EIP-3541 reserved 0xEF prefix, and the code deposit
+    let error = if code.len() > MAX_CODE_SIZE {
+        Some("max code size exceeded")
+    } else if code.first() == Some(&0xef) {
+        Some("invalid code: must not begin with 0xef")
+    } else if outcome.gas_left < deposit_cost {
+        Some("contract creation code storage out of gas")
+    } else {
+        None
+    };
+    if let Some(error) = error {
+        state.revert_to(checkpoint);
+        state.trace(|tracer| tracer.fault(error));
+        return Ok(FrameResult::exceptional_halt());
+    }
+    outcome.gas_left -= deposit_cost;
+    state.deploy_contract(&address, code);
+    Ok(outcome)
 }
 
-fn u64_to_u256(value: u64) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    result[24..32].copy_from_slice(&value.to_be_bytes());
-    result
+/// Address of a contract created with CREATE: keccak256(rlp([sender, nonce]))[12..]
+pub fn create_address(sender: &[u8; 20], nonce: u64) -> [u8; 20] {
+    let nonce_bytes = nonce.to_be_bytes();
+    let nonce_bytes = &nonce_bytes[nonce_bytes.iter().position(|&b| b != 0).unwrap_or(8)..];
+
+    let mut payload = Vec::with_capacity(30);
+    payload.push(0x80 + 20);
+    payload.extend_from_slice(sender);
+    match nonce_bytes {
+        [] => payload.push(0x80),
+        [byte] if *byte < 0x80 => payload.push(*byte),
+        bytes => {
+            payload.push(0x80 + bytes.len() as u8);
+            payload.extend_from_slice(bytes);
+        }
+    }
+
+    let mut encoded = Vec::with_capacity(payload.len() + 1);
+    encoded.push(0xc0 + payload.len() as u8);
+    encoded.extend_from_slice(&payload);
+
+    let hash = keccak::hash(&encoded).to_bytes();
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
 }
 
-fn u128_to_u256(value: u128) -> [u8; 32] {
-    let mut result = [0u8; 32];
-    result[16..32].copy_from_slice(&value.to_be_bytes());
-    result
+/// Address of a contract created with CREATE2:
+/// keccak256(0xff ++ sender ++ salt ++ keccak256(init_code))[12..]
+pub fn create2_address(sender: &[u8; 20], salt: &[u8; 32], init_code: &[u8]) -> [u8; 20] {
+    let code_hash = keccak::hash(init_code).to_bytes();
+    let hash = keccak::hashv(&[&[0xff], sender, salt, &code_hash]).to_bytes();
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
 }
@@ -0,0 +1,178 @@
+//! # Gas Fees and Compute Budget
+//!
+//! ## Wei and lamports
+//!
+//! EVM balances and gas prices are denominated in wei, while fees are paid in
+//! lamports. One BNB is `10^18` wei and [`LAMPORTS_PER_BNB`] lamports. Dividing
+//! both by their greatest common divisor gives the exact exchange rate:
+//!
+//! ```text
+//! LAMPORTS_PER_UNIT lamports = WEI_PER_UNIT wei    (127 lamports = 25 gwei)
+//! ```
+//!
+//! Lamports deposited into the EVM state account back the balances of EVM
+//! accounts, see [`EvmInstruction::Deposit`](crate::EvmInstruction::Deposit).
+//! One lamport is not a whole number of wei, so [`lamports_to_wei`] only
+//! converts deposits that are whole multiples of these units and returns
+//! `None` otherwise.
+//!
+//! Senders pay for gas in wei from their balance. The lamports backing the gas
+//! fees, [`wei_to_lamports`] rounded down, are paid out of the state account to
+//! the payer that relayed the transaction, so the state account always holds
+//! at least the lamports backing every balance.
+//!
+//! ## Gas and compute units
+//!
+//! Executing EVM code consumes Solana compute units. [`gas_to_compute_units`]
+//! gives the compute budget a transaction should request for a gas limit. The
+//! mapping is an estimate, so the interpreter also watches the compute meter
+//! and stops with [`COMPUTE_BUDGET_EXCEEDED`] while [`COMPUTE_UNIT_RESERVE`]
+//! units are still left to fail the instruction cleanly.
+
+use solana_program::{
+    account_info::AccountInfo, native_token::LAMPORTS_PER_BNB, program::invoke,
+    program_error::ProgramError, system_instruction,
+};
+
+/// Wei in one BNB
+pub const WEI_PER_BNB: u128 = 1_000_000_000_000_000_000;
+
+const fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+const EXCHANGE_GCD: u128 = gcd(WEI_PER_BNB, LAMPORTS_PER_BNB as u128);
+
+/// Smallest lamport amount that is a whole number of wei
+pub const LAMPORTS_PER_UNIT: u64 = (LAMPORTS_PER_BNB as u128 / EXCHANGE_GCD) as u64;
+
+/// Wei equal to [`LAMPORTS_PER_UNIT`] lamports
+pub const WEI_PER_UNIT: u128 = WEI_PER_BNB / EXCHANGE_GCD;
+
+/// Compute units budgeted per unit of gas
+pub const COMPUTE_UNITS_PER_GAS: u64 = 10;
+
+/// Compute units kept free to commit state and return after execution stops
+pub const COMPUTE_UNIT_RESERVE: u64 = 50_000;
+
+/// Execution stopped because the Solana compute budget is nearly exhausted
+pub const COMPUTE_BUDGET_EXCEEDED: ProgramError = ProgramError::Custom(2);
+
+/// Convert lamports to wei, if the amount is a whole number of wei
+pub fn lamports_to_wei(lamports: u64) -> Option<u128> {
+    if lamports % LAMPORTS_PER_UNIT != 0 {
+        return None;
+    }
+    ((lamports / LAMPORTS_PER_UNIT) as u128).checked_mul(WEI_PER_UNIT)
+}
+
+/// Convert wei to lamports, rounding down to a whole lamport
+pub fn wei_to_lamports(wei: u128) -> Option<u64> {
+    (wei.checked_mul(LAMPORTS_PER_UNIT as u128)? / WEI_PER_UNIT)
+        .try_into()
+        .ok()
+}
+
+/// Compute units to request for executing `gas`
+pub fn gas_to_compute_units(gas: u64) -> u64 {
+    gas.saturating_mul(COMPUTE_UNITS_PER_GAS)
+        .saturating_add(COMPUTE_UNIT_RESERVE)
+}
+
+/// Gas that `compute_units` can pay for
+pub fn compute_units_to_gas(compute_units: u64) -> u64 {
+    compute_units.saturating_sub(COMPUTE_UNIT_RESERVE) / COMPUTE_UNITS_PER_GAS
+}
+
+/// Fail once the compute meter drops to the reserve
+///
+/// Off-chain there is no compute meter and this never fails.
+pub fn check_compute_budget() -> Result<(), ProgramError> {
+    #[cfg(target_os = "solana")]
+    if solana_program::compute_units::sol_remaining_compute_units() <= COMPUTE_UNIT_RESERVE {
+        solana_program::msg!("EVM execution stopped: compute budget exhausted");
+        return Err(COMPUTE_BUDGET_EXCEEDED);
+    }
+    Ok(())
+}
+
+/// Move `lamports` deposited by the payer into the state account
+pub fn deposit_lamports<'a>(
+    payer: &AccountInfo<'a>,
+    evm_state: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    lamports: u64,
+) -> Result<(), ProgramError> {
+    if lamports == 0 {
+        return Ok(());
+    }
+    invoke(
+        &system_instruction::transfer(payer.key, evm_state.key, lamports),
+        &[payer.clone(), evm_state.clone(), system_program.clone()],
+    )
+}
+
+/// Pay `lamports` of gas fee from the program-owned state account to the payer
+pub fn pay_gas_fee(
+    evm_state: &AccountInfo,
+    payer: &AccountInfo,
+    lamports: u64,
+) -> Result<(), ProgramError> {
+    if lamports == 0 {
+        return Ok(());
+    }
+    let mut state_lamports = evm_state.try_borrow_mut_lamports()?;
+    **state_lamports = state_lamports
+        .checked_sub(lamports)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    let mut payer_lamports = payer.try_borrow_mut_lamports()?;
+    **payer_lamports = payer_lamports
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exchange_units() {
+        assert_eq!(LAMPORTS_PER_UNIT, 127);
+        assert_eq!(WEI_PER_UNIT, 25_000_000_000);
+        assert_eq!(lamports_to_wei(LAMPORTS_PER_BNB), Some(WEI_PER_BNB));
+        assert_eq!(wei_to_lamports(WEI_PER_BNB), Some(LAMPORTS_PER_BNB));
+    }
+
+    #[test]
+    fn test_deposits_round_trip() {
+        for units in [0u64, 1, 2, 1_000, 40_000_000, 123_456_789] {
+            let lamports = units * LAMPORTS_PER_UNIT;
+            let wei = lamports_to_wei(lamports).unwrap();
+            assert_eq!(wei, units as u128 * WEI_PER_UNIT);
+            assert_eq!(wei_to_lamports(wei), Some(lamports));
+        }
+        assert_eq!(lamports_to_wei(1), None);
+    }
+
+    #[test]
+    fn test_fees_round_down() {
+        assert_eq!(wei_to_lamports(0), Some(0));
+        assert_eq!(wei_to_lamports(1), Some(0));
+        assert_eq!(wei_to_lamports(WEI_PER_UNIT - 1), Some(LAMPORTS_PER_UNIT - 1));
+        // 21000 gas at 20 gwei
+        assert_eq!(wei_to_lamports(21_000 * 20_000_000_000), Some(2_133_600));
+        assert_eq!(wei_to_lamports(u128::MAX), None);
+    }
+
+    #[test]
+    fn test_gas_compute_unit_mapping() {
+        assert_eq!(compute_units_to_gas(gas_to_compute_units(100_000)), 100_000);
+        assert_eq!(compute_units_to_gas(COMPUTE_UNIT_RESERVE), 0);
+    }
+}
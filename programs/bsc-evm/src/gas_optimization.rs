@@ -5,10 +5,11 @@ use solana_program::{
     sysvar::Sysvar,
 };
 use std::collections::HashMap;
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 /// Gas optimization configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct GasConfig {
     pub base_gas_price: u128,           // Base gas price in wei
     pub max_gas_price: u128,            // Maximum gas price
@@ -102,6 +103,11 @@ impl GasOptimizer {
         }
     }
 
+    /// Highest gas price, in wei, a transaction may pay
+    pub fn max_gas_price(&self) -> u128 {
+        self.config.max_gas_price
+    }
+
     /// Calculate dynamic gas price based on network congestion
     pub fn calculate_dynamic_gas_price(&mut self, base_gas_limit: u64) -> u128 {
         let congestion_ratio = self.calculate_congestion_ratio();
@@ -391,7 +397,7 @@ impl GasOptimizer {
 }
 
 /// Gas price recommendation structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct GasPriceRecommendation {
     pub slow: u128,
     pub standard: u128,
@@ -402,7 +408,7 @@ pub struct GasPriceRecommendation {
 }
 
 /// Gas optimization statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct GasOptimizationStats {
     pub average_gas_price: u128,
     pub total_batches_processed: usize,
@@ -0,0 +1,255 @@
+//! # Gas Schedule
+//!
+//! Fork-dependent gas costs. The interpreter charges each opcode's fixed cost
+//! from [`Opcode::gas_cost`](crate::evm_interpreter::Opcode::gas_cost) and
+//! takes everything that changed between hardforks from the active
+//! [`GasSchedule`]:
+//!
+//! - EIP-2929 warm/cold account and storage access (Berlin)
+//! - EIP-2200 SSTORE metering and refunds, with EIP-3529 reduced refunds (London)
+//! - EIP-3860 init code metering and size limit (Shanghai)
+//! - Opcodes introduced by later forks (BASEFEE, PUSH0, TLOAD/TSTORE, MCOPY, ...)
+
+use {
+    crate::evm_interpreter::Opcode,
+    borsh::{BorshDeserialize, BorshSerialize},
+    serde::{Deserialize, Serialize},
+};
+
+/// Ethereum hardforks with distinct gas rules
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Hardfork {
+    Istanbul,
+    Berlin,
+    London,
+    Shanghai,
+    #[default]
+    Cancun,
+}
+
+/// Gas costs for a hardfork
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasSchedule {
+    /// Hardfork these costs belong to
+    pub hardfork: Hardfork,
+    /// Cost of touching an account already accessed in this transaction
+    pub warm_account_access: u64,
+    /// Cost of touching an account for the first time in this transaction
+    pub cold_account_access: u64,
+    /// Cost of reading a warm storage slot
+    pub warm_storage_read: u64,
+    /// Surcharge for the first access to a storage slot in this transaction
+    pub cold_sload: u64,
+    /// SSTORE changing a slot from zero to non-zero
+    pub sstore_set: u64,
+    /// SSTORE changing a non-zero slot
+    pub sstore_reset: u64,
+    /// Refund for clearing a storage slot
+    pub sstore_clears_refund: u64,
+    /// Refund for SELFDESTRUCT
+    pub selfdestruct_refund: u64,
+    /// Refunds are capped at gas used divided by this
+    pub max_refund_quotient: u64,
+    /// Cost per 32-byte word of init code (EIP-3860)
+    pub initcode_word: u64,
+    /// Maximum init code size (EIP-3860)
+    pub max_initcode_size: Option<usize>,
+    /// Intrinsic cost per zero byte of calldata
+    pub tx_data_zero: u64,
+    /// Intrinsic cost per non-zero byte of calldata
+    pub tx_data_non_zero: u64,
+    /// Intrinsic cost per access list address
+    pub tx_access_list_address: u64,
+    /// Intrinsic cost per access list storage key
+    pub tx_access_list_storage_key: u64,
+}
+
+/// Intrinsic cost of every transaction
+pub const TX_BASE_GAS: u64 = 21000;
+/// Additional intrinsic cost of a contract creation transaction
+pub const TX_CREATE_GAS: u64 = 32000;
+/// SSTORE fails if no more than this much gas is left (EIP-2200)
+pub const SSTORE_SENTRY_GAS: u64 = 2300;
+
+impl GasSchedule {
+    /// Gas costs of `hardfork`
+    pub fn for_hardfork(hardfork: Hardfork) -> Self {
+        let istanbul = Self {
+            hardfork,
+            warm_account_access: 700,
+            cold_account_access: 700,
+            warm_storage_read: 800,
+            cold_sload: 0,
+            sstore_set: 20000,
+            sstore_reset: 5000,
+            sstore_clears_refund: 15000,
+            selfdestruct_refund: 24000,
+            max_refund_quotient: 2,
+            initcode_word: 0,
+            max_initcode_size: None,
+            tx_data_zero: 4,
+            tx_data_non_zero: 16,
+            tx_access_list_address: 0,
+            tx_access_list_storage_key: 0,
+        };
+        if hardfork == Hardfork::Istanbul {
+            return istanbul;
+        }
+
+        // EIP-2929 and EIP-2930
+        let berlin = Self {
+            warm_account_access: 100,
+            cold_account_access: 2600,
+            warm_storage_read: 100,
+            cold_sload: 2100,
+            sstore_reset: 5000 - 2100,
+            tx_access_list_address: 2400,
+            tx_access_list_storage_key: 1900,
+            ..istanbul
+        };
+        if hardfork == Hardfork::Berlin {
+            return berlin;
+        }
+
+        // EIP-3529
+        let london = Self {
+            sstore_clears_refund: 4800,
+            selfdestruct_refund: 0,
+            max_refund_quotient: 5,
+            ..berlin
+        };
+        if hardfork == Hardfork::London {
+            return london;
+        }
+
+        // EIP-3860
+        Self {
+            initcode_word: 2,
+            max_initcode_size: Some(2 * crate::evm_interpreter::MAX_CODE_SIZE),
+            ..london
+        }
+    }
+
+    /// Whether warm/cold access tracking applies (EIP-2929)
+    pub fn has_access_lists(&self) -> bool {
+        self.hardfork >= Hardfork::Berlin
+    }
+
+    /// Whether `opcode` exists in this hardfork
+    pub fn is_enabled(&self, opcode: Opcode) -> bool {
+        match opcode {
+            Opcode::BASEFEE => self.hardfork >= Hardfork::London,
+            Opcode::PUSH0 => self.hardfork >= Hardfork::Shanghai,
+            Opcode::TLOAD
+            | Opcode::TSTORE
+            | Opcode::MCOPY
+            | Opcode::BLOBHASH
+            | Opcode::BLOBBASEFEE => self.hardfork >= Hardfork::Cancun,
+            _ => true,
+        }
+    }
+
+    /// Cost of SLOAD on a cold or warm slot
+    pub fn sload_cost(&self, cold: bool) -> u64 {
+        if cold && self.has_access_lists() {
+            self.cold_sload
+        } else {
+            self.warm_storage_read
+        }
+    }
+
+    /// Intrinsic gas of a transaction, charged before any code runs
+    pub fn intrinsic_gas(
+        &self,
+        data: &[u8],
+        is_create: bool,
+        access_list: &[([u8; 20], Vec<[u8; 32]>)],
+    ) -> u64 {
+        let zero_bytes = data.iter().filter(|&&byte| byte == 0).count() as u64;
+        let non_zero_bytes = data.len() as u64 - zero_bytes;
+
+        let mut gas = TX_BASE_GAS
+            + zero_bytes * self.tx_data_zero
+            + non_zero_bytes * self.tx_data_non_zero;
+        if is_create {
+            gas += TX_CREATE_GAS + self.initcode_word * (data.len() as u64).div_ceil(32);
+        }
+        for (_, keys) in access_list {
+            gas += self.tx_access_list_address
+                + self.tx_access_list_storage_key * keys.len() as u64;
+        }
+        gas
+    }
+
+    /// Gas charged by SSTORE and the resulting refund change (EIP-2200, EIP-2929, EIP-3529)
+    ///
+    /// `original` is the slot's value at the start of the transaction and
+    /// `current` its value before this write.
+    pub fn sstore_cost(
+        &self,
+        original: &[u8; 32],
+        current: &[u8; 32],
+        new: &[u8; 32],
+        cold: bool,
+    ) -> (u64, i64) {
+        let zero = [0u8; 32];
+        let cold_cost = if cold { self.cold_sload } else { 0 };
+        let clears = self.sstore_clears_refund as i64;
+
+        if current == new {
+            return (cold_cost + self.warm_storage_read, 0);
+        }
+
+        if original == current {
+            if original == &zero {
+                return (cold_cost + self.sstore_set, 0);
+            }
+            let refund = if new == &zero { clears } else { 0 };
+            return (cold_cost + self.sstore_reset, refund);
+        }
+
+        // The slot was already written in this transaction
+        let mut refund = 0;
+        if original != &zero {
+            if current == &zero {
+                refund -= clears;
+            } else if new == &zero {
+                refund += clears;
+            }
+        }
+        if original == new {
+            refund += if original == &zero {
+                (self.sstore_set - self.warm_storage_read) as i64
+            } else {
+                (self.sstore_reset - self.warm_storage_read) as i64
+            };
+        }
+        (cold_cost + self.warm_storage_read, refund)
+    }
+
+    /// Refund actually granted at the end of a transaction
+    pub fn capped_refund(&self, gas_used: u64, refund: u64) -> u64 {
+        refund.min(gas_used / self.max_refund_quotient)
+    }
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self::for_hardfork(Hardfork::default())
+    }
+}
@@ -0,0 +1,373 @@
+//! Instruction builders for BSC EVM program
+//!
+//! Signed transactions, simulated calls and deposits read and write EVM
+//! state, and take the account, code and storage PDAs of the EVM state account
+//! they touch as `state_accounts`; see
+//! [`derive_account_address`](crate::account_state::derive_account_address),
+//! [`derive_code_address`](crate::account_state::derive_code_address) and
+//! [`derive_storage_address`](crate::account_state::derive_storage_address).
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use crate::{
+    chain_config::ChainConfig, EvmInstruction, EvmTransaction, GasConfig, PerformanceConfig,
+    Property, SecurityConfig,
+};
+
+/// Instruction touching EVM state, paid for by `payer`
+fn state_instruction(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    payer: &Pubkey,
+    state_accounts: &[Pubkey],
+    instruction: EvmInstruction,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*evm_state, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+    ];
+    accounts.extend(state_accounts.iter().map(|pubkey| AccountMeta::new(*pubkey, false)));
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.pack(),
+    }
+}
+
+/// Instruction that only needs the EVM state account
+fn query_instruction(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    instruction: EvmInstruction,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(*evm_state, false)],
+        data: instruction.pack(),
+    }
+}
+
+/// Create instruction to deploy a contract in a discarded state, see [`EvmInstruction`]
+pub fn deploy_contract(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    bytecode: Vec<u8>,
+    gas_limit: u64,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::DeployContract { bytecode, gas_limit },
+    )
+}
+
+/// Create instruction to call a contract in a discarded state, see [`EvmInstruction`]
+pub fn call_contract(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    to: [u8; 20],
+    data: Vec<u8>,
+    value: u128,
+    gas_limit: u64,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::CallContract { to, data, value, gas_limit },
+    )
+}
+
+/// Create instruction to transfer BNB in a discarded state, see [`EvmInstruction`]
+pub fn transfer(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    to: [u8; 20],
+    value: u128,
+) -> Instruction {
+    query_instruction(program_id, evm_state, EvmInstruction::Transfer { to, value })
+}
+
+/// Create instruction to set the gas price
+pub fn set_gas_price(program_id: &Pubkey, evm_state: &Pubkey, gas_price: u128) -> Instruction {
+    query_instruction(program_id, evm_state, EvmInstruction::SetGasPrice { gas_price })
+}
+
+/// Create instruction to bridge BNB from BSC
+pub fn bridge_from_bsc(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    amount: u128,
+    proof: Vec<u8>,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::BridgeFromBsc { amount, proof },
+    )
+}
+
+/// Create instruction to execute a batch in a discarded state, see [`EvmInstruction`]
+pub fn execute_batch(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    transactions: Vec<EvmTransaction>,
+    batch_id: u64,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::ExecuteBatch { transactions, batch_id },
+    )
+}
+
+/// Create instruction to get gas price recommendations
+pub fn get_gas_price_recommendations(program_id: &Pubkey, evm_state: &Pubkey) -> Instruction {
+    query_instruction(program_id, evm_state, EvmInstruction::GetGasPriceRecommendations)
+}
+
+/// Create instruction to get gas optimization statistics
+pub fn get_gas_optimization_stats(program_id: &Pubkey, evm_state: &Pubkey) -> Instruction {
+    query_instruction(program_id, evm_state, EvmInstruction::GetGasOptimizationStats)
+}
+
+/// Create instruction to estimate gas for a transaction
+pub fn estimate_gas(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    to: Option<[u8; 20]>,
+    data: Vec<u8>,
+    value: u128,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::EstimateGas { to, data, value },
+    )
+}
+
+/// Create instruction to configure gas optimization
+pub fn configure_gas_optimization(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    config: GasConfig,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::ConfigureGasOptimization { config },
+    )
+}
+
+/// Create instruction to audit a contract
+pub fn audit_contract(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    contract_address: [u8; 20],
+    bytecode: Vec<u8>,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::AuditContract { contract_address, bytecode },
+    )
+}
+
+/// Create instruction to formally verify a contract
+pub fn verify_contract(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    contract_address: [u8; 20],
+    bytecode: Vec<u8>,
+    properties: Vec<Property>,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::VerifyContract { contract_address, bytecode, properties },
+    )
+}
+
+/// Create instruction to check a contract's security status
+pub fn check_contract_security(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    contract_address: [u8; 20],
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::CheckContractSecurity { contract_address },
+    )
+}
+
+/// Create instruction to get security recommendations for a contract
+pub fn get_security_recommendations(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    contract_address: [u8; 20],
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::GetSecurityRecommendations { contract_address },
+    )
+}
+
+/// Create instruction to audit and deploy a contract in a discarded state, see [`EvmInstruction`]
+pub fn deploy_contract_secure(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    bytecode: Vec<u8>,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::DeployContractSecure { bytecode },
+    )
+}
+
+/// Create instruction to update the security configuration
+pub fn update_security_config(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    config: SecurityConfig,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::UpdateSecurityConfig { config },
+    )
+}
+
+/// Create instruction to get performance metrics
+pub fn get_performance_metrics(program_id: &Pubkey, evm_state: &Pubkey) -> Instruction {
+    query_instruction(program_id, evm_state, EvmInstruction::GetPerformanceMetrics)
+}
+
+/// Create instruction to get transaction analytics
+pub fn get_transaction_analytics(program_id: &Pubkey, evm_state: &Pubkey) -> Instruction {
+    query_instruction(program_id, evm_state, EvmInstruction::GetTransactionAnalytics)
+}
+
+/// Create instruction to get the network health report
+pub fn get_network_health_report(program_id: &Pubkey, evm_state: &Pubkey) -> Instruction {
+    query_instruction(program_id, evm_state, EvmInstruction::GetNetworkHealthReport)
+}
+
+/// Create instruction to get the performance dashboard
+pub fn get_performance_dashboard(program_id: &Pubkey, evm_state: &Pubkey) -> Instruction {
+    query_instruction(program_id, evm_state, EvmInstruction::GetPerformanceDashboard)
+}
+
+/// Create instruction to get optimization recommendations
+pub fn get_optimization_recommendations(program_id: &Pubkey, evm_state: &Pubkey) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::GetOptimizationRecommendations,
+    )
+}
+
+/// Create instruction to update the performance monitoring configuration
+pub fn update_performance_config(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    config: PerformanceConfig,
+) -> Instruction {
+    query_instruction(
+        program_id,
+        evm_state,
+        EvmInstruction::UpdatePerformanceConfig { config },
+    )
+}
+
+/// Create instruction to clear performance data
+pub fn clear_performance_data(program_id: &Pubkey, evm_state: &Pubkey) -> Instruction {
+    query_instruction(program_id, evm_state, EvmInstruction::ClearPerformanceData)
+}
+
+/// Create instruction to execute a raw signed Ethereum transaction
+pub fn execute_signed_transaction(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    payer: &Pubkey,
+    state_accounts: &[Pubkey],
+    raw: Vec<u8>,
+) -> Instruction {
+    state_instruction(
+        program_id,
+        evm_state,
+        payer,
+        state_accounts,
+        EvmInstruction::ExecuteSignedTransaction { raw },
+    )
+}
+
+/// Create instruction to simulate a call as `from`
+///
+/// The instruction never commits state; submit it with `simulateTransaction`
+/// and read the output from the return data.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_call(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    payer: &Pubkey,
+    state_accounts: &[Pubkey],
+    from: [u8; 20],
+    to: Option<[u8; 20]>,
+    data: Vec<u8>,
+    value: u128,
+    gas_limit: u64,
+) -> Instruction {
+    state_instruction(
+        program_id,
+        evm_state,
+        payer,
+        state_accounts,
+        EvmInstruction::SimulateCall { from, to, data, value, gas_limit },
+    )
+}
+
+/// Create instruction to set the chain identity of a new EVM state account
+///
+/// The state account must sign and have at least
+/// [`ChainConfig::MAX_LEN`] bytes of space.
+pub fn initialize_chain(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    config: ChainConfig,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*evm_state, true)],
+        data: EvmInstruction::InitializeChain { config }.pack(),
+    }
+}
+
+/// Create instruction to deposit `lamports` from `payer` into the EVM balance of `to`
+///
+/// `state_accounts` holds the account PDA of `to`; `lamports` must be a whole
+/// number of exchange units, see [`fees`](crate::fees).
+pub fn deposit(
+    program_id: &Pubkey,
+    evm_state: &Pubkey,
+    payer: &Pubkey,
+    state_accounts: &[Pubkey],
+    to: [u8; 20],
+    lamports: u64,
+) -> Instruction {
+    state_instruction(
+        program_id,
+        evm_state,
+        payer,
+        state_accounts,
+        EvmInstruction::Deposit { to, lamports },
+    )
+}
@@ -14,39 +14,87 @@
 #![cfg_attr(not(feature = "no-entrypoint"), allow(unused_imports))]
 
 use {
+    borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         account_info::{next_account_info, AccountInfo},
+        clock::Clock,
         entrypoint::{ProgramResult, HEAP_LENGTH, HEAP_START_ADDRESS},
         program_error::ProgramError,
         pubkey::Pubkey,
         rent::Rent,
         system_instruction,
+        sysvar::Sysvar,
+    },
+    std::{
+        cell::RefCell,
+        collections::{HashMap, HashSet},
+        rc::Rc,
     },
-    std::collections::HashMap,
 };
 
+// Include account-backed state module
+pub mod account_state;
+use account_state::{AccountBacking, StateKey};
+
+// Include 256-bit word module
+pub mod u256;
+
+// Include block context module
+pub mod block_context;
+use block_context::BlockContext;
+
+// Include gas schedule module
+pub mod gas_schedule;
+use gas_schedule::{GasSchedule, Hardfork};
+
+// Include instruction builders module
+pub mod instruction;
+
+// Include program events module
+pub mod events;
+
+// Include gas fee module
+pub mod fees;
+
+// Include chain identity module
+pub mod chain_config;
+use chain_config::ChainConfig;
+
+// Include signed transaction module
+pub mod transaction;
+use transaction::{decode_transaction, recover_sender, NONCE_MISMATCH};
+
 // Include EVM interpreter module
-mod evm_interpreter;
-use evm_interpreter::{EvmInterpreter, ExecutionContext, ExecutionResult, Log};
+pub mod evm_interpreter;
+use evm_interpreter::{
+    create_address, create_contract, run_frame, ExecutionContext, ExecutionResult, Log, Opcode,
+};
+
+// Include execution tracing module
+pub mod tracer;
+use tracer::{FrameEnd, FrameStart, Tracer};
 
 // Include gas optimization module
-mod gas_optimization;
+pub mod gas_optimization;
+pub use gas_optimization::GasConfig;
 use gas_optimization::{
-    GasOptimizer, GasConfig, GasPriceRecommendation, 
+    GasOptimizer, GasPriceRecommendation, 
     GasOptimizationStats, BatchTransaction, EvmTransaction as GasEvmTransaction,
 };
 
 // Include security module
-mod security;
+pub mod security;
+pub use security::{Property, PropertyType, SecurityConfig};
 use security::{
-    SecurityManager, SecurityConfig, SecurityAuditResult, FormalVerificationResult,
-    VulnerabilityType, VulnerabilitySeverity, Property, PropertyType, AuditEventType,
+    SecurityManager, SecurityAuditResult, FormalVerificationResult,
+    VulnerabilityType, VulnerabilitySeverity, AuditEventType,
 };
 
 // Include performance monitoring module
-mod performance_monitoring;
+pub mod performance_monitoring;
+pub use performance_monitoring::PerformanceConfig;
 use performance_monitoring::{
-    PerformanceMonitor, PerformanceConfig, PerformanceMetrics, TransactionAnalytics,
+    PerformanceMonitor, PerformanceMetrics, TransactionAnalytics,
     NetworkHealthReport, PerformanceDashboard, OptimizationRecommendation,
     RecommendationType, RecommendationPriority, ImplementationEffort,
 };
@@ -54,46 +102,294 @@ use performance_monitoring::{
 /// BSC EVM Program ID - This will be set during deployment
 solana_program::declare_id!("11111111111111111111111111111112");
 
+/// Previous value of a piece of state, recorded so nested frames can be rolled back
+enum JournalEntry {
+    Balance([u8; 20], Option<u128>),
+    Nonce([u8; 20], Option<u64>),
+    Storage([u8; 20], [u8; 32], Option<[u8; 32]>),
+    Code([u8; 20], Option<Vec<u8>>),
+    TransientStorage([u8; 20], [u8; 32], Option<[u8; 32]>),
+    AccessedAddress([u8; 20]),
+    AccessedStorage([u8; 20], [u8; 32]),
+    Refund(i64),
+}
+
 /// EVM State Management
 pub struct EvmState {
     /// Account balances (address -> balance in wei)
     balances: HashMap<[u8; 20], u128>,
+    /// Account nonces (address -> nonce)
+    nonces: HashMap<[u8; 20], u64>,
     /// Contract storage (address -> (key -> value))
     storage: HashMap<[u8; 20], HashMap<[u8; 32], [u8; 32]>>,
     /// Contract code (address -> bytecode)
     code: HashMap<[u8; 20], Vec<u8>>,
-    /// Block number
-    block_number: u64,
+    /// Current block environment
+    block: BlockContext,
+    /// Gas costs of the active hardfork
+    schedule: GasSchedule,
     /// Gas price in wei
     gas_price: u128,
-    /// Block timestamp
-    timestamp: u64,
+    /// Solana accounts backing this state (None for purely in-memory state)
+    backing: Option<AccountBacking>,
+    /// Undo log for the current transaction
+    journal: Vec<JournalEntry>,
+    /// Contracts created during the current transaction
+    created_contracts: HashSet<[u8; 20]>,
+    /// Storage values at the start of the current transaction, for slots written since
+    original_storage: HashMap<([u8; 20], [u8; 32]), [u8; 32]>,
+    /// Transient storage (EIP-1153), discarded after each transaction
+    transient_storage: HashMap<([u8; 20], [u8; 32]), [u8; 32]>,
+    /// Addresses accessed in the current transaction (EIP-2929)
+    accessed_addresses: HashSet<[u8; 20]>,
+    /// Storage slots accessed in the current transaction (EIP-2929)
+    accessed_storage: HashSet<([u8; 20], [u8; 32])>,
+    /// Gas refund counter of the current transaction
+    refund: i64,
+    /// Observer of execution, if it is being traced
+    tracer: Option<Rc<RefCell<dyn Tracer>>>,
 }
 
 impl EvmState {
     pub fn new() -> Self {
         Self {
             balances: HashMap::new(),
+            nonces: HashMap::new(),
             storage: HashMap::new(),
             code: HashMap::new(),
-            block_number: 0,
+            block: BlockContext::default(),
+            schedule: GasSchedule::default(),
             gas_price: 20_000_000_000, // 20 gwei default
-            timestamp: 0,
+            backing: None,
+            journal: Vec::new(),
+            created_contracts: HashSet::new(),
+            original_storage: HashMap::new(),
+            transient_storage: HashMap::new(),
+            accessed_addresses: HashSet::new(),
+            accessed_storage: HashSet::new(),
+            refund: 0,
+            tracer: None,
+        }
+    }
+
+    /// Gas costs of the active hardfork
+    pub fn gas_schedule(&self) -> &GasSchedule {
+        &self.schedule
+    }
+
+    /// Switch to the gas costs of another hardfork
+    pub fn set_gas_schedule(&mut self, schedule: GasSchedule) {
+        self.schedule = schedule;
+    }
+
+    /// Gas price in wei of unsigned calls and instructions
+    pub fn gas_price(&self) -> u128 {
+        self.gas_price
+    }
+
+    /// Current block environment
+    pub fn block_context(&self) -> &BlockContext {
+        &self.block
+    }
+
+    /// Replace the block environment
+    pub fn set_block_context(&mut self, block: BlockContext) {
+        self.block = block;
+    }
+
+    /// Trace the execution of the transactions that follow with `tracer`
+    pub fn set_tracer(&mut self, tracer: Rc<RefCell<dyn Tracer>>) {
+        self.tracer = Some(tracer);
+    }
+
+    /// Stop tracing execution
+    pub fn clear_tracer(&mut self) {
+        self.tracer = None;
+    }
+
+    /// Whether execution is being traced
+    pub fn is_traced(&self) -> bool {
+        self.tracer.is_some()
+    }
+
+    /// Show the tracer, if there is one, an event
+    fn trace<F: FnOnce(&mut dyn Tracer)>(&self, event: F) {
+        if let Some(tracer) = &self.tracer {
+            event(&mut *tracer.borrow_mut());
         }
     }
 
+    /// Record a read of state that must be backed by a passed account
+    fn touch(&self, key: StateKey) {
+        if let Some(backing) = &self.backing {
+            backing.require(key);
+        }
+    }
+
+    /// Record a write of state that must be persisted on commit
+    fn touch_mut(&mut self, key: StateKey) {
+        if let Some(backing) = &mut self.backing {
+            backing.mark_dirty(key);
+        }
+    }
+
+    /// Mark the current point in the journal
+    pub fn checkpoint(&self) -> usize {
+        self.journal.len()
+    }
+
+    /// Undo every change made since `checkpoint`
+    pub fn revert_to(&mut self, checkpoint: usize) {
+        while self.journal.len() > checkpoint {
+            let entry = match self.journal.pop() {
+                Some(entry) => entry,
+                None => break,
+            };
+            match entry {
+                JournalEntry::Balance(address, Some(balance)) => {
+                    self.balances.insert(address, balance);
+                }
+                JournalEntry::Balance(address, None) => {
+                    self.balances.remove(&address);
+                }
+                JournalEntry::Nonce(address, Some(nonce)) => {
+                    self.nonces.insert(address, nonce);
+                }
+                JournalEntry::Nonce(address, None) => {
+                    self.nonces.remove(&address);
+                }
+                JournalEntry::Storage(address, key, previous) => {
+                    let storage = self.storage.entry(address).or_insert_with(HashMap::new);
+                    match previous {
+                        Some(value) => storage.insert(key, value),
+                        None => storage.remove(&key),
+                    };
+                }
+                JournalEntry::Code(address, Some(code)) => {
+                    self.code.insert(address, code);
+                }
+                JournalEntry::Code(address, None) => {
+                    self.code.remove(&address);
+                }
+                JournalEntry::TransientStorage(address, key, Some(value)) => {
+                    self.transient_storage.insert((address, key), value);
+                }
+                JournalEntry::TransientStorage(address, key, None) => {
+                    self.transient_storage.remove(&(address, key));
+                }
+                JournalEntry::AccessedAddress(address) => {
+                    self.accessed_addresses.remove(&address);
+                }
+                JournalEntry::AccessedStorage(address, key) => {
+                    self.accessed_storage.remove(&(address, key));
+                }
+                JournalEntry::Refund(refund) => {
+                    self.refund = refund;
+                }
+            }
+        }
+    }
+
+    /// Forget the undo log and per-transaction bookkeeping once a transaction is final
+    pub fn clear_journal(&mut self) {
+        self.journal.clear();
+        self.created_contracts.clear();
+        self.original_storage.clear();
+        self.transient_storage.clear();
+        self.accessed_addresses.clear();
+        self.accessed_storage.clear();
+        self.refund = 0;
+    }
+
+    /// Mark an address as accessed, returning whether it already was (EIP-2929)
+    pub fn warm_address(&mut self, address: &[u8; 20]) -> bool {
+        if !self.accessed_addresses.insert(*address) {
+            return true;
+        }
+        self.journal.push(JournalEntry::AccessedAddress(*address));
+        false
+    }
+
+    /// Mark a storage slot as accessed, returning whether it already was (EIP-2929)
+    pub fn warm_storage(&mut self, address: &[u8; 20], key: &[u8; 32]) -> bool {
+        if !self.accessed_storage.insert((*address, *key)) {
+            return true;
+        }
+        self.journal.push(JournalEntry::AccessedStorage(*address, *key));
+        false
+    }
+
+    /// Value of a storage slot at the start of the current transaction
+    pub fn original_storage(&self, address: &[u8; 20], key: &[u8; 32]) -> [u8; 32] {
+        match self.original_storage.get(&(*address, *key)) {
+            Some(value) => *value,
+            None => self.get_storage(address, key),
+        }
+    }
+
+    /// Gas refund accumulated in the current transaction
+    pub fn refund(&self) -> u64 {
+        self.refund.max(0) as u64
+    }
+
+    /// Adjust the gas refund counter
+    pub fn add_refund(&mut self, amount: i64) {
+        if amount != 0 {
+            self.journal.push(JournalEntry::Refund(self.refund));
+            self.refund += amount;
+        }
+    }
+
+    /// Get transient storage value (EIP-1153)
+    pub fn get_transient_storage(&self, address: &[u8; 20], key: &[u8; 32]) -> [u8; 32] {
+        self.transient_storage
+            .get(&(*address, *key))
+            .copied()
+            .unwrap_or([0u8; 32])
+    }
+
+    /// Set transient storage value (EIP-1153)
+    pub fn set_transient_storage(&mut self, address: &[u8; 20], key: &[u8; 32], value: &[u8; 32]) {
+        let previous = self.transient_storage.insert((*address, *key), *value);
+        self.journal
+            .push(JournalEntry::TransientStorage(*address, *key, previous));
+    }
+
     /// Get account balance
     pub fn get_balance(&self, address: &[u8; 20]) -> u128 {
+        self.touch(StateKey::Account(*address));
         self.balances.get(address).copied().unwrap_or(0)
     }
 
     /// Set account balance
     pub fn set_balance(&mut self, address: &[u8; 20], balance: u128) {
-        if balance > 0 {
-            self.balances.insert(*address, balance);
+        self.touch_mut(StateKey::Account(*address));
+        let previous = if balance > 0 {
+            self.balances.insert(*address, balance)
         } else {
-            self.balances.remove(address);
-        }
+            self.balances.remove(address)
+        };
+        self.journal.push(JournalEntry::Balance(*address, previous));
+    }
+
+    /// Get account nonce
+    pub fn get_nonce(&self, address: &[u8; 20]) -> u64 {
+        self.touch(StateKey::Account(*address));
+        self.nonces.get(address).copied().unwrap_or(0)
+    }
+
+    /// Set account nonce
+    pub fn set_nonce(&mut self, address: &[u8; 20], nonce: u64) {
+        self.touch_mut(StateKey::Account(*address));
+        let previous = self.nonces.insert(*address, nonce);
+        self.journal.push(JournalEntry::Nonce(*address, previous));
+    }
+
+    /// Whether an address has a balance, nonce or code (EIP-161)
+    pub fn account_exists(&self, address: &[u8; 20]) -> bool {
+        self.get_balance(address) > 0
+            || self.get_nonce(address) > 0
+            || !self.get_code(address).is_empty()
     }
 
     /// Transfer BNB between accounts
@@ -102,6 +398,9 @@ impl EvmState {
         if from_balance < amount {
             return Err(ProgramError::InsufficientFunds);
         }
+        if from == to || amount == 0 {
+            return Ok(());
+        }
         
         self.set_balance(from, from_balance - amount);
         self.set_balance(to, self.get_balance(to) + amount);
@@ -110,12 +409,28 @@ impl EvmState {
 
     /// Deploy contract code
     pub fn deploy_contract(&mut self, address: &[u8; 20], bytecode: Vec<u8>) {
-        self.code.insert(*address, bytecode);
-        self.storage.insert(*address, HashMap::new());
+        self.touch_mut(StateKey::Code(*address));
+        let previous = self.code.insert(*address, bytecode);
+        self.journal.push(JournalEntry::Code(*address, previous));
+        self.storage.entry(*address).or_insert_with(HashMap::new);
+    }
+
+    /// Remove a contract's code and the storage written during this transaction
+    pub fn delete_contract(&mut self, address: &[u8; 20]) {
+        let keys: Vec<[u8; 32]> = self
+            .storage
+            .get(address)
+            .map(|storage| storage.keys().copied().collect())
+            .unwrap_or_default();
+        for key in keys {
+            self.set_storage(address, &key, &[0u8; 32]);
+        }
+        self.deploy_contract(address, Vec::new());
     }
 
     /// Get contract storage value
     pub fn get_storage(&self, address: &[u8; 20], key: &[u8; 32]) -> [u8; 32] {
+        self.touch(StateKey::Storage(*address, *key));
         self.storage
             .get(address)
             .and_then(|storage| storage.get(key))
@@ -123,22 +438,44 @@ impl EvmState {
             .unwrap_or([0u8; 32])
     }
 
+    /// Storage slots of a contract holding a non-zero value
+    pub fn storage_slots(&self, address: &[u8; 20]) -> HashMap<[u8; 32], [u8; 32]> {
+        self.storage
+            .get(address)
+            .map(|storage| {
+                storage
+                    .iter()
+                    .filter(|(_, value)| **value != [0u8; 32])
+                    .map(|(key, value)| (*key, *value))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Set contract storage value
     pub fn set_storage(&mut self, address: &[u8; 20], key: &[u8; 32], value: &[u8; 32]) {
-        self.storage
+        if !self.original_storage.contains_key(&(*address, *key)) {
+            let original = self.get_storage(address, key);
+            self.original_storage.insert((*address, *key), original);
+        }
+        self.touch_mut(StateKey::Storage(*address, *key));
+        let previous = self
+            .storage
             .entry(*address)
             .or_insert_with(HashMap::new)
             .insert(*key, *value);
+        self.journal.push(JournalEntry::Storage(*address, *key, previous));
     }
 
     /// Get contract code
     pub fn get_code(&self, address: &[u8; 20]) -> Vec<u8> {
+        self.touch(StateKey::Code(*address));
         self.code.get(address).cloned().unwrap_or_default()
     }
 }
 
 /// EVM Transaction
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
 pub struct EvmTransaction {
     pub nonce: u64,
     pub gas_price: u128,
@@ -146,13 +483,27 @@ pub struct EvmTransaction {
     pub to: Option<[u8; 20]>,
     pub value: u128,
     pub data: Vec<u8>,
-    pub v: u8,
+    /// Addresses and storage keys pre-declared by the transaction (EIP-2930)
+    pub access_list: Vec<([u8; 20], Vec<[u8; 32]>)>,
+    pub v: u64,
     pub r: [u8; 32],
     pub s: [u8; 32],
 }
 
+/// Version byte that prefixes every serialized [`EvmInstruction`]
+///
+/// Variants are Borsh encoded by their position, so new variants are only
+/// ever appended. Any other change to the layout bumps this version.
+pub const INSTRUCTION_VERSION: u8 = 1;
+
 /// EVM Instruction Types
-#[derive(Debug)]
+///
+/// Only `ExecuteSignedTransaction`, for the sender its signature recovers, and
+/// `Deposit` act on the persisted EVM state. `DeployContract`, `CallContract`,
+/// `Transfer`, `DeployContractSecure` and `ExecuteBatch` carry no signature
+/// to act for an EVM account with, so they run against an empty state that
+/// is discarded afterwards.
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
 pub enum EvmInstruction {
     /// Deploy a new contract
     DeployContract {
@@ -242,6 +593,85 @@ pub enum EvmInstruction {
     },
     /// Clear performance data
     ClearPerformanceData,
+    /// Execute a raw signed Ethereum transaction on behalf of its recovered sender
+    ExecuteSignedTransaction {
+        raw: Vec<u8>,
+    },
+    /// Execute a call as `from` without committing state or charging gas
+    ///
+    /// Used to serve `eth_call` and `eth_estimateGas` through transaction
+    /// simulation. A `None` recipient simulates a contract creation.
+    SimulateCall {
+        from: [u8; 20],
+        to: Option<[u8; 20]>,
+        data: Vec<u8>,
+        value: u128,
+        gas_limit: u64,
+    },
+    /// Set the chain identity, and the hardfork every later instruction runs
+    /// under, of an EVM state account that holds none yet
+    ///
+    /// Accounts:
+    /// 0. [writable, signer] EVM state account
+    InitializeChain {
+        config: ChainConfig,
+    },
+    /// Move `lamports` from the payer into the EVM state account and credit
+    /// `to` with the wei they are worth, to pay for value and gas
+    ///
+    /// `lamports` must be a whole number of exchange units, see [`fees`].
+    Deposit {
+        to: [u8; 20],
+        lamports: u64,
+    },
+}
+
+impl EvmInstruction {
+    /// Serialize as instruction data: the version byte followed by the Borsh encoding
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = vec![INSTRUCTION_VERSION];
+        borsh::to_writer(&mut data, self).unwrap();
+        data
+    }
+
+    /// Deserialize instruction data produced by [`EvmInstruction::pack`]
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        match data.split_first() {
+            Some((&INSTRUCTION_VERSION, rest)) => {
+                Self::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    /// Whether this instruction reads or writes persistent EVM state
+    pub fn touches_state(&self) -> bool {
+        matches!(
+            self,
+            EvmInstruction::ExecuteSignedTransaction { .. }
+                | EvmInstruction::SimulateCall { .. }
+                | EvmInstruction::Deposit { .. }
+        )
+    }
+
+    /// Most gas fee this instruction can owe, in wei
+    ///
+    /// Fails if a signed transaction's fee cap is above `max_gas_price`.
+    pub fn max_gas_fee(&self, max_gas_price: u128) -> Result<u128, ProgramError> {
+        match self {
+            // Signed transactions pay at most their own fee cap
+            EvmInstruction::ExecuteSignedTransaction { raw } => match decode_transaction(raw) {
+                Ok(tx) => {
+                    check_gas_price(tx.max_fee_per_gas, max_gas_price)?;
+                    (tx.gas_limit as u128)
+                        .checked_mul(tx.max_fee_per_gas)
+                        .ok_or(ProgramError::ArithmeticOverflow)
+                }
+                Err(_) => Ok(0),
+            },
+            _ => Ok(0),
+        }
+    }
 }
 
 /// BSC Bridge Implementation
@@ -261,15 +691,14 @@ impl BscBridge {
     }
 
     /// Verify bridge proof from BSC
-    pub fn verify_bridge_proof(&self, proof: &[u8], amount: u128) -> Result<bool, ProgramError> {
-        // In a real implementation, this would verify the cryptographic proof
-        // that BNB was locked on BSC and can be minted on Solana
-        // For now, we'll implement a simple verification
-        if proof.len() >= 32 && amount > 0 {
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+    ///
+    /// Locks on BSC are proven to the bsc-bridge program, whose light client
+    /// follows BSC headers and checks receipt proofs against them. This
+    /// program holds no BSC headers to check a proof against, so it accepts
+    /// none.
+    pub fn verify_bridge_proof(&self, _proof: &[u8], _amount: u128) -> Result<bool, ProgramError> {
+        solana_program::msg!("BSC lock proofs are verified by the bsc-bridge program");
+        Ok(false)
     }
 
     /// Process BNB bridge from BSC
@@ -285,6 +714,21 @@ impl BscBridge {
     }
 }
 
+/// The transaction reverted or halted exceptionally
+///
+/// Its state changes are rolled back, but the nonce it consumed and the fee for
+/// the gas it used are kept.
+pub const EXECUTION_REVERTED: ProgramError = ProgramError::Custom(1);
+
+/// Fail if `gas_price` is above `max_gas_price`
+fn check_gas_price(gas_price: u128, max_gas_price: u128) -> Result<(), ProgramError> {
+    if gas_price > max_gas_price {
+        solana_program::msg!("Gas price {} is above the maximum {}", gas_price, max_gas_price);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
 /// EVM Bytecode Executor with Advanced Gas Optimization, Security, and Performance Monitoring
 pub struct EvmExecutor {
     state: EvmState,
@@ -292,6 +736,14 @@ pub struct EvmExecutor {
     gas_optimizer: GasOptimizer,
     security_manager: SecurityManager,
     performance_monitor: PerformanceMonitor,
+    /// Gas fees paid by the senders of the transactions executed so far, in wei
+    gas_fees: u128,
+    /// Logs of the transactions executed so far
+    logs: Vec<Log>,
+    /// Output of the last reverted transaction
+    revert_data: Vec<u8>,
+    /// Chain id of CHAINID and of the signed transactions accepted
+    chain_id: u64,
 }
 
 impl EvmExecutor {
@@ -312,11 +764,116 @@ impl EvmExecutor {
             gas_optimizer,
             security_manager,
             performance_monitor,
+            gas_fees: 0,
+            logs: Vec::new(),
+            revert_data: Vec::new(),
+            chain_id: transaction::CHAIN_ID,
         }
     }
 
+    /// Chain id of CHAINID and of the signed transactions accepted
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Serve the chain `chain_id` instead of BSSC mainnet
+    pub fn set_chain_id(&mut self, chain_id: u64) {
+        self.chain_id = chain_id;
+    }
+
+    /// Gas fees paid by the senders of the transactions executed so far, in wei
+    pub fn gas_fees(&self) -> u128 {
+        self.gas_fees
+    }
+
+    /// Highest gas price, in wei, a transaction may pay or sign as its fee cap
+    pub fn max_gas_price(&self) -> u128 {
+        self.gas_optimizer.max_gas_price()
+    }
+
+    /// Owe the fee for `gas` at `gas_price`
+    fn charge_gas(&mut self, gas: u64, gas_price: u128) -> Result<(), ProgramError> {
+        self.gas_fees = (gas as u128)
+            .checked_mul(gas_price)
+            .and_then(|fee| self.gas_fees.checked_add(fee))
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Credit `to` with the wei that `lamports` deposited into the state account are worth
+    ///
+    /// Only whole exchange units convert, see [`fees`].
+    pub fn deposit(&mut self, to: &[u8; 20], lamports: u64) -> Result<(), ProgramError> {
+        let Some(wei) = fees::lamports_to_wei(lamports) else {
+            solana_program::msg!(
+                "Deposit of {} lamports is not a multiple of {} lamports",
+                lamports,
+                fees::LAMPORTS_PER_UNIT
+            );
+            return Err(ProgramError::InvalidArgument);
+        };
+        let balance = self
+            .state
+            .get_balance(to)
+            .checked_add(wei)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.state.set_balance(to, balance);
+        Ok(())
+    }
+
+    /// Logs of the transactions executed so far, in execution order
+    pub fn logs(&self) -> &[Log] {
+        &self.logs
+    }
+
+    /// Revert reason of the last transaction that reverted
+    pub fn revert_data(&self) -> &[u8] {
+        &self.revert_data
+    }
+
+    /// State the transactions execute against
+    pub fn state(&self) -> &EvmState {
+        &self.state
+    }
+
+    /// State the transactions execute against, to load, configure or trace
+    pub fn state_mut(&mut self) -> &mut EvmState {
+        &mut self.state
+    }
+
     /// Execute EVM transaction with gas optimization, security checks, and performance monitoring
+    ///
+    /// The sender pays for the gas from its balance, see [`gas_fees`](Self::gas_fees).
+    /// A transaction that reverts or halts returns [`EXECUTION_REVERTED`]: its
+    /// value transfer and state changes are rolled back, while a creation still
+    /// consumes the sender's nonce and the gas used is still charged.
     pub fn execute_transaction(&mut self, mut tx: EvmTransaction, sender: [u8; 20]) -> Result<Vec<u8>, ProgramError> {
+        // Optimize gas price and limit using the gas optimizer
+        if tx.gas_price == 0 {
+            tx.gas_price = self.gas_optimizer.calculate_dynamic_gas_price(tx.gas_limit);
+        }
+        
+        if tx.gas_limit < 21000 {
+            tx.gas_limit = self.gas_optimizer.estimate_gas_limit(tx.to, &tx.data, tx.value);
+        }
+
+        self.run_transaction(tx, sender)
+    }
+
+    /// Execute `tx` without paying for gas
+    ///
+    /// Simulated calls and instructions run against a discarded state run at a
+    /// zero gas price, as `eth_call` does, so the sender only has to fund the
+    /// value. The gas limit is used as given.
+    pub fn execute_call(&mut self, tx: EvmTransaction, sender: [u8; 20]) -> Result<Vec<u8>, ProgramError> {
+        self.run_transaction(EvmTransaction { gas_price: 0, ..tx }, sender)
+    }
+
+    /// Execute `tx` at exactly the gas price and limit it carries
+    ///
+    /// Signed transactions run this way, as the signer agreed to those and no
+    /// others. A gas limit below the intrinsic gas is rejected.
+    fn run_transaction(&mut self, tx: EvmTransaction, sender: [u8; 20]) -> Result<Vec<u8>, ProgramError> {
         let start_time = std::time::Instant::now();
         
         // Security check: Validate transaction before execution
@@ -328,55 +885,125 @@ impl EvmExecutor {
             "Transaction execution started".to_string(),
         );
 
-        // Optimize gas price and limit using the gas optimizer
-        if tx.gas_price == 0 {
-            tx.gas_price = self.gas_optimizer.calculate_dynamic_gas_price(tx.gas_limit);
-        }
-        
-        if tx.gas_limit < 21000 {
-            tx.gas_limit = self.gas_optimizer.estimate_gas_limit(tx.to, &tx.data, tx.value);
-        }
-
         // Security check: Validate gas limit
         if tx.gas_limit > self.security_manager.config.max_gas_limit {
             return Err(ProgramError::InvalidArgument);
         }
+        check_gas_price(tx.gas_price, self.max_gas_price())?;
         
-        // Calculate optimized gas cost
-        let gas_cost = tx.gas_limit as u128 * tx.gas_price;
-        let total_cost = tx.value + gas_cost;
-
-        // Check balance
-        if self.state.get_balance(&sender) < total_cost {
+        // Intrinsic gas is charged before any code runs
+        let schedule = *self.state.gas_schedule();
+        let intrinsic_gas = schedule.intrinsic_gas(&tx.data, tx.to.is_none(), &tx.access_list);
+        if tx.gas_limit < intrinsic_gas {
+            solana_program::msg!(
+                "Gas limit {} is below the intrinsic gas {}",
+                tx.gas_limit,
+                intrinsic_gas
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        let execution_gas = tx.gas_limit - intrinsic_gas;
+
+        // The sender pays for the whole gas limit up front, on top of the value
+        let gas_fee = (tx.gas_limit as u128)
+            .checked_mul(tx.gas_price)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let balance = self.state.get_balance(&sender);
+        if balance < gas_fee.checked_add(tx.value).ok_or(ProgramError::ArithmeticOverflow)? {
             return Err(ProgramError::InsufficientFunds);
         }
+        self.state.set_balance(&sender, balance - gas_fee);
 
-        // Deduct gas and value
-        self.state.transfer(&sender, &[0u8; 20], gas_cost)?; // Gas goes to block producer
-        if tx.value > 0 {
-            if let Some(to) = tx.to {
-                self.state.transfer(&sender, &to, tx.value)?;
+        // A creation consumes the sender's nonce whether or not it succeeds
+        let created = match tx.to {
+            Some(_) => None,
+            None => Some(self.consume_create_nonce(&sender)?),
+        };
+
+        // Start the transaction's substate with the EIP-2929 pre-warmed addresses
+        self.state.clear_journal();
+        self.warm_transaction_addresses(sender, tx.to);
+        for (address, keys) in &tx.access_list {
+            self.state.warm_address(address);
+            for key in keys {
+                self.state.warm_storage(address, key);
             }
         }
+        let checkpoint = self.state.checkpoint();
+
+        if self.state.is_traced() {
+            let (kind, to) = match tx.to {
+                Some(to) => (Opcode::CALL, to),
+                None => (Opcode::CREATE, created.unwrap_or_default()),
+            };
+            self.state.trace(|tracer| {
+                tracer.enter(&FrameStart {
+                    kind,
+                    from: sender,
+                    to,
+                    value: tx.value,
+                    gas: tx.gas_limit,
+                    input: &tx.data,
+                    depth: 0,
+                })
+            });
+        }
 
         // Execute transaction
-        let result = match tx.to {
+        let outcome = match tx.to {
             Some(to) => {
-                // Contract call or transfer
+                // Contract call or transfer, the callee receives the value
+                self.state.transfer(&sender, &to, tx.value)?;
                 let code = self.state.get_code(&to);
                 if code.is_empty() {
                     // Simple transfer
-                    Ok(vec![])
+                    Ok((true, vec![], 0))
                 } else {
                     // Contract execution
-                    self.execute_contract_call(&to, &tx.data, tx.gas_limit)
+                    self.execute_contract_call(sender, &to, tx.value, &tx.data, execution_gas)
                 }
             }
             None => {
-                // Contract deployment
-                self.execute_contract_deployment(&tx.data, tx.gas_limit)
+                // Contract deployment, the init code receives the value
+                let address = created.unwrap_or_default();
+                self.execute_contract_deployment(sender, address, &tx.data, tx.value, execution_gas)
+                    .map(|(success, gas_used)| (success, address.to_vec(), gas_used))
             }
         };
+        let (success, output, execution_gas_used) = outcome?;
+
+        // Only the nonce and the gas fee of a failed transaction are kept
+        if !success {
+            self.state.revert_to(checkpoint);
+        }
+
+        // Charge for the gas used, less the capped refund, and return the rest to the sender
+        let gas_used = intrinsic_gas + execution_gas_used;
+        let refund = schedule.capped_refund(gas_used, self.state.refund());
+        let gas_charged = gas_used - refund;
+        self.charge_gas(gas_charged, tx.gas_price)?;
+        let unused = (tx.gas_limit - gas_charged) as u128 * tx.gas_price;
+        self.state.set_balance(&sender, self.state.get_balance(&sender) + unused);
+        solana_program::log::sol_log(&format!(
+            "Transaction gas: used={}, refund={}",
+            gas_used, refund
+        ));
+        let result = if success { Ok(output) } else { Err(EXECUTION_REVERTED) };
+
+        if self.state.is_traced() {
+            let output = match (&result, tx.to) {
+                (Ok(address), None) => self.state.get_code(&address[..].try_into().unwrap()),
+                (Ok(output), Some(_)) => output.clone(),
+                (Err(_), _) => self.revert_data.clone(),
+            };
+            self.state.trace(|tracer| {
+                tracer.exit(&FrameEnd {
+                    success: result.is_ok(),
+                    gas_used: gas_charged,
+                    output: &output,
+                })
+            });
+        }
 
         // Record transaction for performance monitoring
         let execution_time = start_time.elapsed().as_millis() as f64;
@@ -400,8 +1027,71 @@ impl EvmExecutor {
         result
     }
 
+    /// Execute a raw signed transaction
+    ///
+    /// The sender is recovered from the signature and the transaction must
+    /// carry the sender's next nonce, which is then consumed. Whether it
+    /// succeeded is logged once it has run, see [`events`].
+    pub fn execute_signed_transaction(&mut self, raw: &[u8]) -> Result<Vec<u8>, ProgramError> {
+        let signed = decode_transaction(raw)?;
+        check_gas_price(signed.max_fee_per_gas, self.max_gas_price())?;
+        let sender = recover_sender(&signed, self.chain_id)?;
+        let tx = EvmTransaction::from_signed(&signed, self.state.gas_price)?;
+
+        if tx.nonce != self.state.get_nonce(&sender) {
+            return Err(NONCE_MISMATCH);
+        }
+        // Contract creation consumes the nonce when deriving the new address
+        if tx.to.is_some() {
+            let next_nonce = tx.nonce.checked_add(1).ok_or(NONCE_MISMATCH)?;
+            self.state.set_nonce(&sender, next_nonce);
+        }
+
+        events::emit_transaction_hash(&signed.hash());
+        let result = self.run_transaction(tx, sender);
+        match &result {
+            Ok(_) => events::emit_transaction_status(true),
+            Err(err) if *err == EXECUTION_REVERTED => events::emit_transaction_status(false),
+            Err(_) => {}
+        }
+        result
+    }
+
+    /// Mark the sender, recipient, precompiles and (from Shanghai) the coinbase as warm
+    fn warm_transaction_addresses(&mut self, sender: [u8; 20], to: Option<[u8; 20]>) {
+        let schedule = *self.state.gas_schedule();
+        if !schedule.has_access_lists() {
+            return;
+        }
+
+        self.state.warm_address(&sender);
+        if let Some(to) = to {
+            self.state.warm_address(&to);
+        }
+        let precompiles = if schedule.hardfork >= Hardfork::Cancun { 10 } else { 9 };
+        for index in 1..=precompiles {
+            let mut precompile = [0u8; 20];
+            precompile[19] = index;
+            self.state.warm_address(&precompile);
+        }
+        if schedule.hardfork >= Hardfork::Shanghai {
+            let coinbase = self.state.block.coinbase;
+            self.state.warm_address(&coinbase);
+        }
+    }
+
     /// Execute contract call with FULL EVM interpreter
-    fn execute_contract_call(&mut self, address: &[u8; 20], data: &[u8], gas_limit: u64) -> Result<Vec<u8>, ProgramError> {
+    ///
+    /// Returns whether the call succeeded, its output and the gas used. Only
+    /// running out of compute budget is an error.
+    fn execute_contract_call(
+        &mut self,
+        sender: [u8; 20],
+        address: &[u8; 20],
+        value: u128,
+        data: &[u8],
+        gas_limit: u64,
+    ) -> Result<(bool, Vec<u8>, u64), ProgramError> {
         let code = self.state.get_code(address);
         if code.is_empty() {
             return Err(ProgramError::InvalidAccountData);
@@ -410,43 +1100,96 @@ impl EvmExecutor {
         // Create execution context
         let context = ExecutionContext {
             address: *address,
-            caller: [0u8; 20], // Would be set from transaction
-            origin: [0u8; 20],  // Would be set from transaction
-            value: 0,
+            caller: sender,
+            origin: sender,
+            value,
             data: data.to_vec(),
             gas_limit,
             gas_price: self.state.gas_price,
-            block_number: self.state.block_number,
-            timestamp: self.state.timestamp,
-            chain_id: 16979, // BSSC Chain ID
+            block_number: self.state.block.number,
+            timestamp: self.state.block.timestamp,
+            coinbase: self.state.block.coinbase,
+            block_gas_limit: self.state.block.gas_limit,
+            base_fee: self.state.gas_price,
+            prev_randao: self.state.block.prev_randao,
+            chain_id: self.chain_id,
+            depth: 0,
+            is_static: false,
         };
 
-        // Create interpreter and execute bytecode
-        let mut interpreter = EvmInterpreter::new(gas_limit);
-        let result = interpreter.execute(&code, &context, &mut self.state)?;
+        // Execute bytecode in a frame of its own, a halt uses up all its gas
+        let checkpoint = self.state.checkpoint();
+        let outcome = run_frame(&code, &context, &mut self.state, checkpoint)?;
+        let gas_used = gas_limit - outcome.gas_left;
 
         solana_program::log::sol_log(&format!(
             "Contract executed: gas_used={}, success={}, return_data_len={}",
-            result.gas_used, result.success, result.return_data.len()
+            gas_used, outcome.success, outcome.output.len()
         ));
 
-        if !result.success {
-            return Err(ProgramError::Custom(1)); // Execution reverted
+        if !outcome.success {
+            self.revert_data = outcome.output;
+            return Ok((false, Vec::new(), gas_used));
         }
 
-        Ok(result.return_data)
+        self.logs.extend(outcome.logs);
+        Ok((true, outcome.output, gas_used))
+    }
+
+    /// Consume the nonce of `sender` for a contract creation, returning the new contract's address
+    fn consume_create_nonce(&mut self, sender: &[u8; 20]) -> Result<[u8; 20], ProgramError> {
+        let nonce = self.state.get_nonce(sender);
+        self.state.set_nonce(sender, nonce.checked_add(1).ok_or(ProgramError::InvalidArgument)?);
+        Ok(create_address(sender, nonce))
     }
 
     /// Execute contract deployment
-    fn execute_contract_deployment(&mut self, bytecode: &[u8], gas_limit: u64) -> Result<Vec<u8>, ProgramError> {
-        // Generate contract address (simplified)
-        let mut contract_address = [0u8; 20];
-        contract_address[0..4].copy_from_slice(&bytecode[0..4].try_into().unwrap_or([0u8; 4]));
-        
-        self.state.deploy_contract(&contract_address, bytecode.to_vec());
-        solana_program::log::sol_log(&format!("Deployed contract at {:?}", contract_address));
-        
-        Ok(contract_address.to_vec())
+    ///
+    /// The contract is created at `contract_address`, see
+    /// [`consume_create_nonce`](Self::consume_create_nonce). The init code runs
+    /// with `value` and whatever it returns becomes the runtime code. Returns
+    /// whether the deployment succeeded and the gas used.
+    fn execute_contract_deployment(
+        &mut self,
+        sender: [u8; 20],
+        contract_address: [u8; 20],
+        init_code: &[u8],
+        value: u128,
+        gas_limit: u64,
+    ) -> Result<(bool, u64), ProgramError> {
+        let context = ExecutionContext {
+            address: contract_address,
+            caller: sender,
+            origin: sender,
+            value,
+            data: Vec::new(),
+            gas_limit,
+            gas_price: self.state.gas_price,
+            block_number: self.state.block.number,
+            timestamp: self.state.block.timestamp,
+            coinbase: self.state.block.coinbase,
+            block_gas_limit: self.state.block.gas_limit,
+            base_fee: self.state.gas_price,
+            prev_randao: self.state.block.prev_randao,
+            chain_id: self.chain_id,
+            depth: 0,
+            is_static: false,
+        };
+        let outcome = create_contract(init_code, &context, &mut self.state)?;
+        let gas_used = gas_limit - outcome.gas_left;
+
+        solana_program::log::sol_log(&format!(
+            "Contract deployment: address={:?}, gas_used={}, success={}",
+            contract_address, gas_used, outcome.success
+        ));
+
+        if !outcome.success {
+            self.revert_data = outcome.output;
+            return Ok((false, gas_used));
+        }
+
+        self.logs.extend(outcome.logs);
+        Ok((true, gas_used))
     }
 
     /// Get account balance
@@ -454,6 +1197,11 @@ impl EvmExecutor {
         self.state.get_balance(address)
     }
 
+    /// Get account nonce
+    pub fn get_nonce(&self, address: &[u8; 20]) -> u64 {
+        self.state.get_nonce(address)
+    }
+
     /// Get contract storage
     pub fn get_storage(&self, address: &[u8; 20], key: &[u8; 32]) -> [u8; 32] {
         self.state.get_storage(address, key)
@@ -482,6 +1230,7 @@ impl EvmExecutor {
                 to: tx.to,
                 value: tx.value,
                 data: tx.data.clone(),
+                access_list: Vec::new(),
                 v: 0,
                 r: [0u8; 32],
                 s: [0u8; 32],
@@ -550,8 +1299,9 @@ impl EvmExecutor {
 
     /// Deploy contract with security checks
     pub fn deploy_contract_secure(&mut self, bytecode: &[u8], sender: [u8; 20]) -> Result<[u8; 20], ProgramError> {
-        // Perform security audit before deployment
-        let audit_result = self.security_manager.audit_contract([0u8; 20], bytecode)?;
+        // Perform security audit before deployment, against the address the contract will get
+        let contract_address = create_address(&sender, self.state.get_nonce(&sender));
+        let audit_result = self.security_manager.audit_contract(contract_address, bytecode)?;
         
         // Check if contract passes security requirements
         if audit_result.security_score < 0.7 || audit_result.vulnerabilities.iter().any(|v| v.severity == VulnerabilitySeverity::Critical) {
@@ -559,8 +1309,14 @@ impl EvmExecutor {
         }
 
         // Deploy contract if it passes security checks
-        let contract_address = [0u8; 20]; // Generate contract address
-        self.state.deploy_contract(&contract_address, bytecode.to_vec());
+        let gas_limit = self.security_manager.config.max_gas_limit;
+        let contract_address = self.consume_create_nonce(&sender)?;
+        let (success, gas_used) =
+            self.execute_contract_deployment(sender, contract_address, bytecode, 0, gas_limit)?;
+        self.charge_gas(gas_used, self.state.gas_price)?;
+        if !success {
+            return Err(EXECUTION_REVERTED);
+        }
 
         // Add to audit trail
         self.security_manager.add_audit_trail_entry(
@@ -611,6 +1367,19 @@ impl EvmExecutor {
 }
 
 /// Process EVM instruction
+///
+/// Accounts:
+/// 0. [] EVM state account owned by this program, holding the chain identity
+///
+/// Instructions that read or write EVM state (signed transactions, simulated
+/// calls and deposits) additionally expect the state account to be writable,
+/// as it holds the lamports backing EVM balances, and:
+/// 1. [signer, writable] Payer, funds rent for newly created state accounts and deposits,
+///    and is paid the gas fees of the signed transaction it relays
+/// 2. [] System program
+/// 3. [] SlotHashes sysvar
+/// 4. ..[writable] Every EVM account, code and storage PDA of the state account the
+///    instruction touches
 pub fn process_evm_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -625,14 +1394,84 @@ pub fn process_evm_instruction(
     }
 
     // Parse instruction
-    let instruction = parse_instruction(instruction_data)?;
-    
-    // Initialize executor (in a real implementation, this would be loaded from account data)
+    let instruction = EvmInstruction::unpack(instruction_data)?;
+    if let EvmInstruction::InitializeChain { config } = &instruction {
+        return chain_config::initialize_chain(evm_state_account, config);
+    }
+
+    let config = ChainConfig::unpack(&evm_state_account.try_borrow_data()?)?;
     let mut executor = EvmExecutor::new();
+    executor.set_chain_id(config.chain_id);
+    executor.state.set_gas_schedule(config.gas_schedule());
+
+    if !instruction.touches_state() {
+        return execute_instruction(&mut executor, instruction);
+    }
+    let simulation = matches!(instruction, EvmInstruction::SimulateCall { .. });
+
+    // Load persistent EVM state from the passed accounts, execute, then write it back
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let slot_hashes = next_account_info(accounts_iter)?;
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let state_accounts = accounts_iter.as_slice();
+    executor.state = EvmState::load(program_id, evm_state_account.key, state_accounts)?;
+    executor.state.set_gas_schedule(config.gas_schedule());
+    executor
+        .state
+        .set_block_context(BlockContext::from_sysvars(&Clock::get()?, slot_hashes)?);
+
+    // Simulations only publish their result, state and fees are discarded
+    if simulation {
+        run_instruction(&mut executor, instruction)?;
+        return executor.state.check_missing_accounts();
+    }
+    if let EvmInstruction::Deposit { lamports, .. } = instruction {
+        fees::deposit_lamports(payer, evm_state_account, system_program, lamports)?;
+    }
+    // A signed transaction that reverts still pays for its gas and consumes its nonce
+    let signed = matches!(instruction, EvmInstruction::ExecuteSignedTransaction { .. });
+
+    match run_instruction(&mut executor, instruction) {
+        Err(err) if signed && err == EXECUTION_REVERTED => {}
+        result => result?,
+    }
+    for log in executor.logs() {
+        events::emit_log(log);
+    }
 
+    // The sender paid for the gas in wei, the payer relaying it is paid the lamports backing them
+    let fee = fees::wei_to_lamports(executor.gas_fees()).ok_or(ProgramError::ArithmeticOverflow)?;
+    fees::pay_gas_fee(evm_state_account, payer, fee)?;
+    solana_program::log::sol_log(&format!("Gas fee: {} lamports", fee));
+
+    executor.state.commit(state_accounts, payer, system_program)
+}
+
+/// Execute a state-touching instruction, explaining failures
+///
+/// A failure caused by reading state whose account was not passed in is
+/// reported as the missing accounts. Otherwise the revert reason, if any, is
+/// published as return data.
+fn run_instruction(executor: &mut EvmExecutor, instruction: EvmInstruction) -> ProgramResult {
+    let result = execute_instruction(executor, instruction);
+    if result.is_err() {
+        executor.state.check_missing_accounts()?;
+        events::publish_return_data(executor.revert_data());
+    }
+    result
+}
+
+/// Execute a parsed instruction against the executor
+fn execute_instruction(executor: &mut EvmExecutor, instruction: EvmInstruction) -> ProgramResult {
     // Execute instruction
     match instruction {
-        EvmInstruction::DeployContract { bytecode, gas_limit } => {
+        EvmInstruction::DeployContract { bytecode, mut gas_limit } => {
+            if gas_limit < 21000 {
+                gas_limit = executor.estimate_gas(None, &bytecode, 0);
+            }
             let tx = EvmTransaction {
                 nonce: 0,
                 gas_price: executor.state.gas_price,
@@ -640,14 +1479,19 @@ pub fn process_evm_instruction(
                 to: None,
                 value: 0,
                 data: bytecode,
+                access_list: Vec::new(),
                 v: 0,
                 r: [0u8; 32],
                 s: [0u8; 32],
             };
             let sender = [0u8; 20]; // Placeholder sender
-            executor.execute_transaction(tx, sender)?;
+            let contract_address = executor.execute_call(tx, sender)?;
+            events::publish_return_data(&contract_address);
         }
-        EvmInstruction::CallContract { to, data, value, gas_limit } => {
+        EvmInstruction::CallContract { to, data, value, mut gas_limit } => {
+            if gas_limit < 21000 {
+                gas_limit = executor.estimate_gas(Some(to), &data, value);
+            }
             let tx = EvmTransaction {
                 nonce: 0,
                 gas_price: executor.state.gas_price,
@@ -655,12 +1499,14 @@ pub fn process_evm_instruction(
                 to: Some(to),
                 value,
                 data,
+                access_list: Vec::new(),
                 v: 0,
                 r: [0u8; 32],
                 s: [0u8; 32],
             };
             let sender = [0u8; 20]; // Placeholder sender
-            executor.execute_transaction(tx, sender)?;
+            let output = executor.execute_call(tx, sender)?;
+            events::publish_return_data(&output);
         }
         EvmInstruction::Transfer { to, value } => {
             let from = [0u8; 20]; // Placeholder sender
@@ -674,48 +1520,60 @@ pub fn process_evm_instruction(
             executor.bridge.process_bridge(to, amount, proof)?;
         }
         EvmInstruction::ExecuteBatch { transactions, batch_id } => {
-            // For now, create empty transactions - in production this would use the actual transactions
-            let gas_transactions: Vec<GasEvmTransaction> = vec![];
-            executor.execute_batch_transactions(gas_transactions, batch_id)?;
+            let gas_transactions = transactions
+                .into_iter()
+                .map(|tx| GasEvmTransaction {
+                    to: tx.to,
+                    value: tx.value,
+                    data: tx.data,
+                    gas_limit: tx.gas_limit,
+                    gas_price: tx.gas_price,
+                    nonce: tx.nonce,
+                    signature: [0u8; 65],
+                    from: [0u8; 20], // Placeholder sender
+                    batch_id: Some(batch_id),
+                    priority_fee: 0,
+                    max_fee_per_gas: tx.gas_price,
+                })
+                .collect();
+            let results = executor.execute_batch_transactions(gas_transactions, batch_id)?;
+            events::publish_result(&results)?;
         }
         EvmInstruction::GetGasPriceRecommendations => {
             let recommendations = executor.get_gas_price_recommendations();
-            // In a real implementation, this would return the recommendations
-            println!("Gas Price Recommendations: {:?}", recommendations);
+            events::publish_result(&recommendations)?;
         }
         EvmInstruction::GetGasOptimizationStats => {
             let stats = executor.get_gas_optimization_stats();
-            // In a real implementation, this would return the stats
-            println!("Gas Optimization Stats: {:?}", stats);
+            events::publish_result(&stats)?;
         }
         EvmInstruction::EstimateGas { to, data, value } => {
             let estimated_gas = executor.estimate_gas(to, &data, value);
-            // In a real implementation, this would return the estimated gas
-            println!("Estimated Gas: {}", estimated_gas);
+            events::publish_result(&estimated_gas)?;
         }
         EvmInstruction::ConfigureGasOptimization { config } => {
             executor.configure_gas_optimization(config);
         }
         EvmInstruction::AuditContract { contract_address, bytecode } => {
             let audit_result = executor.audit_contract(contract_address, &bytecode)?;
-            println!("Security Audit Result: {:?}", audit_result);
+            events::publish_result(&audit_result)?;
         }
         EvmInstruction::VerifyContract { contract_address, bytecode, properties } => {
             let verification_result = executor.verify_contract(contract_address, &bytecode, properties)?;
-            println!("Formal Verification Result: {:?}", verification_result);
+            events::publish_result(&verification_result)?;
         }
         EvmInstruction::CheckContractSecurity { contract_address } => {
             let is_secure = executor.is_contract_secure(&contract_address);
-            println!("Contract Security Status: {}", is_secure);
+            events::publish_result(&is_secure)?;
         }
         EvmInstruction::GetSecurityRecommendations { contract_address } => {
             let recommendations = executor.get_security_recommendations(&contract_address);
-            println!("Security Recommendations: {:?}", recommendations);
+            events::publish_result(&recommendations)?;
         }
         EvmInstruction::DeployContractSecure { bytecode } => {
             let sender = [0u8; 20]; // Placeholder sender
             let contract_address = executor.deploy_contract_secure(&bytecode, sender)?;
-            println!("Contract deployed securely at: {:?}", contract_address);
+            events::publish_return_data(&contract_address);
         }
         EvmInstruction::UpdateSecurityConfig { config } => {
             executor.update_security_config(config);
@@ -723,23 +1581,23 @@ pub fn process_evm_instruction(
         }
         EvmInstruction::GetPerformanceMetrics => {
             let metrics = executor.get_performance_metrics();
-            println!("Performance Metrics: {:?}", metrics);
+            events::publish_result(metrics)?;
         }
         EvmInstruction::GetTransactionAnalytics => {
             let analytics = executor.get_transaction_analytics();
-            println!("Transaction Analytics: {:?}", analytics);
+            events::publish_result(analytics)?;
         }
         EvmInstruction::GetNetworkHealthReport => {
             let health_report = executor.get_network_health_report();
-            println!("Network Health Report: {:?}", health_report);
+            events::publish_result(&health_report)?;
         }
         EvmInstruction::GetPerformanceDashboard => {
             let dashboard = executor.get_performance_dashboard();
-            println!("Performance Dashboard: {:?}", dashboard);
+            events::publish_result(&dashboard)?;
         }
         EvmInstruction::GetOptimizationRecommendations => {
             let recommendations = executor.get_optimization_recommendations();
-            println!("Optimization Recommendations: {:?}", recommendations);
+            events::publish_result(recommendations)?;
         }
         EvmInstruction::UpdatePerformanceConfig { config } => {
             executor.update_performance_config(config);
@@ -749,186 +1607,36 @@ pub fn process_evm_instruction(
             executor.clear_performance_data();
             println!("Performance data cleared");
         }
-    }
-
-    Ok(())
-}
-
-/// Parse instruction data
-fn parse_instruction(data: &[u8]) -> Result<EvmInstruction, ProgramError> {
-    if data.is_empty() {
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
-    match data[0] {
-        0 => {
-            // DeployContract
-            if data.len() < 9 {
-                return Err(ProgramError::InvalidInstructionData);
-            }
-            let gas_limit = u64::from_le_bytes(data[1..9].try_into().unwrap());
-            let bytecode = data[9..].to_vec();
-            Ok(EvmInstruction::DeployContract { bytecode, gas_limit })
-        }
-        1 => {
-            // CallContract
-            if data.len() < 37 {
-                return Err(ProgramError::InvalidInstructionData);
-            }
-            let mut to = [0u8; 20];
-            to.copy_from_slice(&data[1..21]);
-            let value = u128::from_le_bytes(data[21..37].try_into().unwrap());
-            let gas_limit = u64::from_le_bytes(data[37..45].try_into().unwrap());
-            let call_data = data[45..].to_vec();
-            Ok(EvmInstruction::CallContract { to, data: call_data, value, gas_limit })
-        }
-        2 => {
-            // Transfer
-            if data.len() < 37 {
-                return Err(ProgramError::InvalidInstructionData);
-            }
-            let mut to = [0u8; 20];
-            to.copy_from_slice(&data[1..21]);
-            let value = u128::from_le_bytes(data[21..37].try_into().unwrap());
-            Ok(EvmInstruction::Transfer { to, value })
-        }
-        3 => {
-            // SetGasPrice
-            if data.len() < 17 {
-                return Err(ProgramError::InvalidInstructionData);
-            }
-            let gas_price = u128::from_le_bytes(data[1..17].try_into().unwrap());
-            Ok(EvmInstruction::SetGasPrice { gas_price })
-        }
-        4 => {
-            // BridgeFromBsc
-            if data.len() < 17 {
-                return Err(ProgramError::InvalidInstructionData);
-            }
-            let amount = u128::from_le_bytes(data[1..17].try_into().unwrap());
-            let proof = data[17..].to_vec();
-            Ok(EvmInstruction::BridgeFromBsc { amount, proof })
-        }
-        5 => {
-            // ExecuteBatch
-            // For now, return a simplified version - in production this would deserialize the full batch
-            Ok(EvmInstruction::ExecuteBatch { 
-                transactions: vec![], 
-                batch_id: 0 
-            })
-        }
-        6 => {
-            // GetGasPriceRecommendations
-            Ok(EvmInstruction::GetGasPriceRecommendations)
+        EvmInstruction::ExecuteSignedTransaction { raw } => {
+            let output = executor.execute_signed_transaction(&raw)?;
+            events::publish_return_data(&output);
         }
-        7 => {
-            // GetGasOptimizationStats
-            Ok(EvmInstruction::GetGasOptimizationStats)
-        }
-        8 => {
-            // EstimateGas
-            if data.len() < 37 {
-                return Err(ProgramError::InvalidInstructionData);
-            }
-            let mut to = [0u8; 20];
-            to.copy_from_slice(&data[1..21]);
-            let value = u128::from_le_bytes(data[21..37].try_into().unwrap());
-            let data_bytes = data[37..].to_vec();
-            Ok(EvmInstruction::EstimateGas { 
-                to: Some(to), 
-                data: data_bytes, 
-                value 
-            })
-        }
-        9 => {
-            // ConfigureGasOptimization
-            // For now, return default config - in production this would deserialize the config
-            Ok(EvmInstruction::ConfigureGasOptimization { 
-                config: GasConfig::default() 
-            })
+        // Needs the state account, see `process_evm_instruction`
+        EvmInstruction::InitializeChain { .. } => {
+            return Err(ProgramError::InvalidInstructionData);
         }
-        10 => {
-            // AuditContract
-            // For now, return simplified version - in production this would deserialize the full data
-            Ok(EvmInstruction::AuditContract { 
-                contract_address: [0u8; 20], 
-                bytecode: vec![] 
-            })
-        }
-        11 => {
-            // VerifyContract
-            // For now, return simplified version - in production this would deserialize the full data
-            Ok(EvmInstruction::VerifyContract { 
-                contract_address: [0u8; 20], 
-                bytecode: vec![], 
-                properties: vec![] 
-            })
-        }
-        12 => {
-            // CheckContractSecurity
-            if data.len() < 21 {
-                return Err(ProgramError::InvalidInstructionData);
-            }
-            let mut contract_address = [0u8; 20];
-            contract_address.copy_from_slice(&data[1..21]);
-            Ok(EvmInstruction::CheckContractSecurity { contract_address })
-        }
-        13 => {
-            // GetSecurityRecommendations
-            if data.len() < 21 {
-                return Err(ProgramError::InvalidInstructionData);
-            }
-            let mut contract_address = [0u8; 20];
-            contract_address.copy_from_slice(&data[1..21]);
-            Ok(EvmInstruction::GetSecurityRecommendations { contract_address })
-        }
-        14 => {
-            // DeployContractSecure
-            // For now, return simplified version - in production this would deserialize the full bytecode
-            Ok(EvmInstruction::DeployContractSecure { 
-                bytecode: vec![] 
-            })
-        }
-        15 => {
-            // UpdateSecurityConfig
-            // For now, return default config - in production this would deserialize the config
-            Ok(EvmInstruction::UpdateSecurityConfig { 
-                config: SecurityConfig::default() 
-            })
-        }
-        16 => {
-            // GetPerformanceMetrics
-            Ok(EvmInstruction::GetPerformanceMetrics)
-        }
-        17 => {
-            // GetTransactionAnalytics
-            Ok(EvmInstruction::GetTransactionAnalytics)
-        }
-        18 => {
-            // GetNetworkHealthReport
-            Ok(EvmInstruction::GetNetworkHealthReport)
-        }
-        19 => {
-            // GetPerformanceDashboard
-            Ok(EvmInstruction::GetPerformanceDashboard)
-        }
-        20 => {
-            // GetOptimizationRecommendations
-            Ok(EvmInstruction::GetOptimizationRecommendations)
-        }
-        21 => {
-            // UpdatePerformanceConfig
-            // For now, return default config - in production this would deserialize the config
-            Ok(EvmInstruction::UpdatePerformanceConfig { 
-                config: PerformanceConfig::default() 
-            })
+        EvmInstruction::SimulateCall { from, to, data, value, gas_limit } => {
+            let tx = EvmTransaction {
+                nonce: executor.get_nonce(&from),
+                gas_price: executor.state.gas_price,
+                gas_limit,
+                to,
+                value,
+                data,
+                access_list: Vec::new(),
+                v: 0,
+                r: [0u8; 32],
+                s: [0u8; 32],
+            };
+            let output = executor.execute_call(tx, from)?;
+            events::publish_return_data(&output);
         }
-        22 => {
-            // ClearPerformanceData
-            Ok(EvmInstruction::ClearPerformanceData)
+        EvmInstruction::Deposit { to, lamports } => {
+            executor.deposit(&to, lamports)?;
         }
-        _ => Err(ProgramError::InvalidInstructionData),
     }
+
+    Ok(())
 }
 
 #[cfg(not(feature = "no-entrypoint"))]
@@ -5,12 +5,13 @@ use solana_program::{
     sysvar::Sysvar,
 };
 use std::collections::{HashMap, VecDeque};
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Performance monitoring configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct PerformanceConfig {
     /// Enable real-time metrics collection
     pub enable_real_time_metrics: bool,
@@ -43,7 +44,7 @@ impl Default for PerformanceConfig {
 }
 
 /// Real-time performance metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct PerformanceMetrics {
     /// Transactions per second
     pub tps: f64,
@@ -97,7 +98,7 @@ impl Default for PerformanceMetrics {
 }
 
 /// Transaction analytics data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct TransactionAnalytics {
     /// Total transactions processed
     pub total_transactions: u64,
@@ -130,7 +131,7 @@ pub struct TransactionAnalytics {
 }
 
 /// Contract activity tracking
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct ContractActivity {
     pub contract_address: [u8; 20],
     pub transaction_count: u64,
@@ -140,7 +141,7 @@ pub struct ContractActivity {
 }
 
 /// Network health status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum NetworkHealthStatus {
     Excellent,  // > 95% performance
     Good,       // 80-95% performance
@@ -150,7 +151,7 @@ pub enum NetworkHealthStatus {
 }
 
 /// Network health report
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct NetworkHealthReport {
     pub status: NetworkHealthStatus,
     pub overall_score: f64,
@@ -163,7 +164,7 @@ pub struct NetworkHealthReport {
 }
 
 /// Performance alert
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct PerformanceAlert {
     pub alert_type: AlertType,
     pub severity: AlertSeverity,
@@ -175,7 +176,7 @@ pub struct PerformanceAlert {
 }
 
 /// Types of performance alerts
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum AlertType {
     LowTPS,
     HighLatency,
@@ -190,7 +191,7 @@ pub enum AlertType {
 }
 
 /// Alert severity levels
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum AlertSeverity {
     Info,
     Warning,
@@ -199,7 +200,7 @@ pub enum AlertSeverity {
 }
 
 /// User experience metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct UserExperienceMetrics {
     /// Average time to confirmation
     pub avg_confirmation_time_ms: f64,
@@ -220,7 +221,7 @@ pub struct UserExperienceMetrics {
 }
 
 /// Performance optimization recommendation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct OptimizationRecommendation {
     pub recommendation_type: RecommendationType,
     pub priority: RecommendationPriority,
@@ -232,7 +233,7 @@ pub struct OptimizationRecommendation {
 }
 
 /// Types of optimization recommendations
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum RecommendationType {
     GasOptimization,
     BatchProcessing,
@@ -245,7 +246,7 @@ pub enum RecommendationType {
 }
 
 /// Priority levels for recommendations
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum RecommendationPriority {
     Low,
     Medium,
@@ -254,7 +255,7 @@ pub enum RecommendationPriority {
 }
 
 /// Implementation effort levels
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum ImplementationEffort {
     Low,     // < 1 day
     Medium,  // 1-7 days
@@ -263,7 +264,7 @@ pub enum ImplementationEffort {
 }
 
 /// Performance monitoring dashboard data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct PerformanceDashboard {
     pub current_metrics: PerformanceMetrics,
     pub historical_metrics: Vec<PerformanceMetrics>,
@@ -277,7 +278,7 @@ pub struct PerformanceDashboard {
 }
 
 /// Performance trends analysis
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct PerformanceTrends {
     pub tps_trend: TrendDirection,
     pub latency_trend: TrendDirection,
@@ -289,7 +290,7 @@ pub struct PerformanceTrends {
 }
 
 /// Trend direction indicators
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum TrendDirection {
     Improving,
     Stable,
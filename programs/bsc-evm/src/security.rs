@@ -5,10 +5,11 @@ use solana_program::{
     sysvar::Sysvar,
 };
 use std::collections::HashMap;
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 /// Security configuration for the EVM system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct SecurityConfig {
     /// Enable formal verification
     pub enable_formal_verification: bool,
@@ -193,7 +194,7 @@ impl Default for SecurityConfig {
 }
 
 /// Security vulnerability types
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq)]
 pub enum VulnerabilityType {
     /// Reentrancy attack
     Reentrancy,
@@ -224,7 +225,7 @@ pub enum VulnerabilityType {
 }
 
 /// Security audit result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct SecurityAuditResult {
     pub contract_address: [u8; 20],
     pub audit_timestamp: u64,
@@ -238,7 +239,7 @@ pub struct SecurityAuditResult {
 }
 
 /// Individual vulnerability details
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct Vulnerability {
     pub vulnerability_type: VulnerabilityType,
     pub severity: VulnerabilitySeverity,
@@ -249,7 +250,7 @@ pub struct Vulnerability {
 }
 
 /// Vulnerability severity levels
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq)]
 pub enum VulnerabilitySeverity {
     Critical,    // Immediate action required
     High,        // Fix within 24 hours
@@ -259,7 +260,7 @@ pub enum VulnerabilitySeverity {
 }
 
 /// Vulnerability location in contract
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct VulnerabilityLocation {
     pub function_name: Option<String>,
     pub bytecode_offset: u64,
@@ -268,7 +269,7 @@ pub struct VulnerabilityLocation {
 }
 
 /// Formal verification result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct FormalVerificationResult {
     pub contract_address: [u8; 20],
     pub verification_timestamp: u64,
@@ -280,7 +281,7 @@ pub struct FormalVerificationResult {
 }
 
 /// Property to be verified
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct Property {
     pub name: String,
     pub description: String,
@@ -291,7 +292,7 @@ pub struct Property {
 }
 
 /// Types of properties that can be verified
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub enum PropertyType {
     /// Invariant that should always hold
     Invariant,
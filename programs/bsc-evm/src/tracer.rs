@@ -0,0 +1,93 @@
+//! # Execution Tracing
+//!
+//! A [`Tracer`] installed with [`EvmState::set_tracer`](crate::EvmState::set_tracer)
+//! observes execution as it happens, for debuggers and the `debug_trace*`
+//! RPC methods:
+//!
+//! - [`Tracer::enter`] and [`Tracer::exit`] bracket every frame: the
+//!   transaction itself and each nested message call or creation that runs
+//! - [`Tracer::step`] sees every opcode before it executes, with the stack and
+//!   memory, and [`Tracer::step_cost`] the gas it was then charged
+//! - [`Tracer::storage`] sees every storage slot read or written, and
+//!   [`Tracer::log`] every log emitted
+//! - [`Tracer::fault`] sees why a frame halted exceptionally
+//!
+//! Without a tracer, execution only pays for checking there is none.
+
+use crate::{
+    evm_interpreter::{Log, Opcode},
+    u256::U256,
+};
+
+/// An opcode about to execute
+pub struct Step<'a> {
+    pub pc: usize,
+    /// Opcode byte, which may not be a defined opcode
+    pub opcode: u8,
+    /// Gas left before the opcode is charged
+    pub gas: u64,
+    /// Gas refund counter of the transaction
+    pub refund: u64,
+    /// Call depth, 0 for the transaction's frame
+    pub depth: usize,
+    /// Account whose storage the frame uses
+    pub address: [u8; 20],
+    /// Stack, top last
+    pub stack: &'a [U256],
+    pub memory: &'a [u8],
+    /// Output of the frame's most recent nested call or creation
+    pub return_data: &'a [u8],
+}
+
+/// A frame about to run
+pub struct FrameStart<'a> {
+    /// CALL, CALLCODE, DELEGATECALL, STATICCALL, CREATE or CREATE2; the
+    /// transaction's frame is a CALL or a CREATE
+    pub kind: Opcode,
+    pub from: [u8; 20],
+    /// Account called, or the address of the contract created
+    pub to: [u8; 20],
+    /// Value transferred, or passed along by DELEGATECALL
+    pub value: u128,
+    /// Gas given to the frame, the gas limit for the transaction's frame
+    pub gas: u64,
+    /// Call data, or init code
+    pub input: &'a [u8],
+    pub depth: usize,
+}
+
+/// A frame that finished
+pub struct FrameEnd<'a> {
+    pub success: bool,
+    /// Gas the frame used, after refunds for the transaction's frame
+    pub gas_used: u64,
+    /// Return or revert data, or the runtime code of a created contract
+    pub output: &'a [u8],
+}
+
+/// Observer of EVM execution
+///
+/// Every hook does nothing by default.
+pub trait Tracer {
+    /// A frame starts, at `frame.depth`
+    fn enter(&mut self, _frame: &FrameStart) {}
+
+    /// An opcode is about to execute in the current frame
+    fn step(&mut self, _step: &Step) {}
+
+    /// The last opcode stepped was charged `gas_cost`, including the gas it
+    /// gave a nested frame
+    fn step_cost(&mut self, _gas_cost: u64) {}
+
+    /// The storage slot `key` of `address` was read or written, and now holds `value`
+    fn storage(&mut self, _address: &[u8; 20], _key: &[u8; 32], _value: &[u8; 32]) {}
+
+    /// The current frame emitted `log`
+    fn log(&mut self, _log: &Log) {}
+
+    /// The current frame halted exceptionally, for this reason
+    fn fault(&mut self, _error: &str) {}
+
+    /// The current frame finished
+    fn exit(&mut self, _frame: &FrameEnd) {}
+}
@@ -0,0 +1,87 @@
+//! # Signed Transactions
+//!
+//! Raw signed Ethereum transactions are decoded with the shared
+//! `bsc-evm-rlp` crate and their sender is recovered with the
+//! `secp256k1_recover` syscall. Only transactions signed for the cluster's
+//! chain id (see [`chain_config`](crate::chain_config)) are accepted: legacy
+//! transactions must be EIP-155 replay protected and typed transactions must
+//! carry this chain id, so a transaction signed for another chain can never
+//! be replayed here.
+
+use {
+    crate::EvmTransaction,
+    solana_program::{keccak, program_error::ProgramError, secp256k1_recover::secp256k1_recover},
+};
+
+pub use bsc_evm_rlp::{SignedTransaction, TransactionType};
+
+/// Chain id of BSSC mainnet, served by clusters that configure no other (EIP-155)
+pub const CHAIN_ID: u64 = 16979;
+
+/// The transaction nonce is not the sender's next nonce
+pub const NONCE_MISMATCH: ProgramError = ProgramError::Custom(3);
+
+/// Half the secp256k1 curve order, the largest `s` allowed by EIP-2
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Decode a raw legacy, access list or dynamic fee transaction
+pub fn decode_transaction(raw: &[u8]) -> Result<SignedTransaction, ProgramError> {
+    SignedTransaction::decode(raw).map_err(|_| ProgramError::InvalidInstructionData)
+}
+
+/// Verify the signature and recover the sender's address
+///
+/// Fails unless the transaction is signed for `chain_id` with a low-`s`
+/// signature.
+pub fn recover_sender(tx: &SignedTransaction, chain_id: u64) -> Result<[u8; 20], ProgramError> {
+    if tx.chain_id != Some(chain_id) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    recover_signer(tx)
+}
+
+/// Recover the address that signed a transaction, whatever chain it was signed for
+///
+/// Fails unless the signature has a low `s`.
+pub fn recover_signer(tx: &SignedTransaction) -> Result<[u8; 20], ProgramError> {
+    if tx.s > SECP256K1_HALF_ORDER || tx.s == [0u8; 32] || tx.r == [0u8; 32] {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(&tx.r);
+    signature[32..].copy_from_slice(&tx.s);
+    let public_key = secp256k1_recover(&tx.signing_hash(), tx.odd_y_parity as u8, &signature)
+        .map_err(|_| ProgramError::MissingRequiredSignature)?;
+
+    let hash = keccak::hash(&public_key.to_bytes()).to_bytes();
+    let mut sender = [0u8; 20];
+    sender.copy_from_slice(&hash[12..]);
+    Ok(sender)
+}
+
+impl EvmTransaction {
+    /// Transaction to execute for `tx` when the base fee is `base_fee`
+    ///
+    /// Fails if a dynamic fee transaction's fee cap is below the base fee.
+    pub fn from_signed(tx: &SignedTransaction, base_fee: u128) -> Result<Self, ProgramError> {
+        let gas_price = tx
+            .effective_gas_price(base_fee)
+            .ok_or(ProgramError::InvalidArgument)?;
+        Ok(Self {
+            nonce: tx.nonce,
+            gas_price,
+            gas_limit: tx.gas_limit,
+            to: tx.to,
+            value: tx.value,
+            data: tx.data.clone(),
+            access_list: tx.access_list.clone(),
+            v: tx.v(),
+            r: tx.r,
+            s: tx.s,
+        })
+    }
+}
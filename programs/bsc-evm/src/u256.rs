@@ -0,0 +1,670 @@
+//! # 256-bit Unsigned Integer
+//!
+//! The EVM word type. Values are stored as four little-endian `u64` limbs and
+//! every operation follows the wrapping, two's complement semantics of the
+//! Ethereum yellow paper. Division by zero yields zero, as the EVM requires.
+
+use std::cmp::Ordering;
+
+/// EVM word: a 256-bit unsigned integer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct U256(pub [u64; 4]);
+
+impl U256 {
+    pub const ZERO: U256 = U256([0, 0, 0, 0]);
+    pub const ONE: U256 = U256([1, 0, 0, 0]);
+    pub const MAX: U256 = U256([u64::MAX; 4]);
+
+    /// Decode a big-endian 32-byte word
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let start = 32 - (i + 1) * 8;
+            *limb = u64::from_be_bytes(bytes[start..start + 8].try_into().unwrap());
+        }
+        U256(limbs)
+    }
+
+    /// Decode up to 32 big-endian bytes, left-padding with zeros
+    pub fn from_be_slice(bytes: &[u8]) -> Self {
+        let mut word = [0u8; 32];
+        let len = bytes.len().min(32);
+        word[32 - len..].copy_from_slice(&bytes[bytes.len() - len..]);
+        Self::from_be_bytes(word)
+    }
+
+    /// Encode as a big-endian 32-byte word
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, limb) in self.0.iter().enumerate() {
+            let start = 32 - (i + 1) * 8;
+            bytes[start..start + 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        U256([value, 0, 0, 0])
+    }
+
+    pub fn from_u128(value: u128) -> Self {
+        U256([value as u64, (value >> 64) as u64, 0, 0])
+    }
+
+    /// Convert an EVM address into a word
+    pub fn from_address(address: &[u8; 20]) -> Self {
+        Self::from_be_slice(address)
+    }
+
+    /// Low 160 bits as an EVM address
+    pub fn to_address(&self) -> [u8; 20] {
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&self.to_be_bytes()[12..]);
+        address
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    /// Low 64 bits
+    pub fn low_u64(&self) -> u64 {
+        self.0[0]
+    }
+
+    /// Low 128 bits
+    pub fn low_u128(&self) -> u128 {
+        self.0[0] as u128 | (self.0[1] as u128) << 64
+    }
+
+    /// Value as u64, if it fits
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.0[1..] == [0; 3] {
+            Some(self.0[0])
+        } else {
+            None
+        }
+    }
+
+    /// Value as u128, if it fits
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.0[2..] == [0; 2] {
+            Some(self.low_u128())
+        } else {
+            None
+        }
+    }
+
+    /// Value as usize, if it fits
+    pub fn to_usize(&self) -> Option<usize> {
+        self.to_u64().and_then(|value| usize::try_from(value).ok())
+    }
+
+    /// Number of significant bits
+    pub fn bits(&self) -> u32 {
+        for i in (0..4).rev() {
+            if self.0[i] != 0 {
+                return i as u32 * 64 + 64 - self.0[i].leading_zeros();
+            }
+        }
+        0
+    }
+
+    /// Bit `index` (0 = least significant)
+    pub fn bit(&self, index: usize) -> bool {
+        index < 256 && (self.0[index / 64] >> (index % 64)) & 1 == 1
+    }
+
+    /// Whether the value is negative when read as two's complement
+    pub fn is_negative(&self) -> bool {
+        self.bit(255)
+    }
+
+    pub fn overflowing_add(self, other: U256) -> (U256, bool) {
+        let mut result = [0u64; 4];
+        let mut carry = false;
+        for i in 0..4 {
+            let (sum, c1) = self.0[i].overflowing_add(other.0[i]);
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            result[i] = sum;
+            carry = c1 || c2;
+        }
+        (U256(result), carry)
+    }
+
+    pub fn overflowing_sub(self, other: U256) -> (U256, bool) {
+        let mut result = [0u64; 4];
+        let mut borrow = false;
+        for i in 0..4 {
+            let (diff, b1) = self.0[i].overflowing_sub(other.0[i]);
+            let (diff, b2) = diff.overflowing_sub(borrow as u64);
+            result[i] = diff;
+            borrow = b1 || b2;
+        }
+        (U256(result), borrow)
+    }
+
+    pub fn wrapping_add(self, other: U256) -> U256 {
+        self.overflowing_add(other).0
+    }
+
+    pub fn wrapping_sub(self, other: U256) -> U256 {
+        self.overflowing_sub(other).0
+    }
+
+    pub fn checked_add(self, other: U256) -> Option<U256> {
+        match self.overflowing_add(other) {
+            (sum, false) => Some(sum),
+            _ => None,
+        }
+    }
+
+    pub fn checked_sub(self, other: U256) -> Option<U256> {
+        match self.overflowing_sub(other) {
+            (diff, false) => Some(diff),
+            _ => None,
+        }
+    }
+
+    /// Full 512-bit product as eight little-endian limbs
+    fn full_mul(self, other: U256) -> [u64; 8] {
+        let mut result = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let t = self.0[i] as u128 * other.0[j] as u128 + result[i + j] as u128 + carry;
+                result[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            result[i + 4] = carry as u64;
+        }
+        result
+    }
+
+    pub fn wrapping_mul(self, other: U256) -> U256 {
+        let product = self.full_mul(other);
+        U256([product[0], product[1], product[2], product[3]])
+    }
+
+    /// Unsigned quotient and remainder; both are zero when dividing by zero
+    pub fn div_rem(self, other: U256) -> (U256, U256) {
+        if other.is_zero() {
+            return (U256::ZERO, U256::ZERO);
+        }
+        let (quotient, remainder) = div_rem_limbs(&self.0, &other.0);
+        (limbs_to_u256(&quotient), limbs_to_u256(&remainder))
+    }
+
+    /// EVM DIV
+    pub fn div(self, other: U256) -> U256 {
+        self.div_rem(other).0
+    }
+
+    /// EVM MOD
+    pub fn rem(self, other: U256) -> U256 {
+        self.div_rem(other).1
+    }
+
+    /// Two's complement negation
+    pub fn wrapping_neg(self) -> U256 {
+        U256::ZERO.wrapping_sub(self)
+    }
+
+    /// Absolute value when read as two's complement
+    fn abs(self) -> U256 {
+        if self.is_negative() {
+            self.wrapping_neg()
+        } else {
+            self
+        }
+    }
+
+    /// EVM SDIV
+    pub fn sdiv(self, other: U256) -> U256 {
+        if other.is_zero() {
+            return U256::ZERO;
+        }
+        let quotient = self.abs().div(other.abs());
+        if self.is_negative() != other.is_negative() {
+            quotient.wrapping_neg()
+        } else {
+            quotient
+        }
+    }
+
+    /// EVM SMOD: the result takes the sign of the dividend
+    pub fn smod(self, other: U256) -> U256 {
+        if other.is_zero() {
+            return U256::ZERO;
+        }
+        let remainder = self.abs().rem(other.abs());
+        if self.is_negative() {
+            remainder.wrapping_neg()
+        } else {
+            remainder
+        }
+    }
+
+    /// EVM ADDMOD: (a + b) % n computed without intermediate overflow
+    pub fn addmod(self, other: U256, modulus: U256) -> U256 {
+        if modulus.is_zero() {
+            return U256::ZERO;
+        }
+        let (sum, carry) = self.overflowing_add(other);
+        let wide = [sum.0[0], sum.0[1], sum.0[2], sum.0[3], carry as u64];
+        limbs_to_u256(&div_rem_limbs(&wide, &modulus.0).1)
+    }
+
+    /// EVM MULMOD: (a * b) % n computed without intermediate overflow
+    pub fn mulmod(self, other: U256, modulus: U256) -> U256 {
+        if modulus.is_zero() {
+            return U256::ZERO;
+        }
+        let product = self.full_mul(other);
+        limbs_to_u256(&div_rem_limbs(&product, &modulus.0).1)
+    }
+
+    /// EVM EXP: wrapping exponentiation by squaring
+    pub fn wrapping_pow(self, exponent: U256) -> U256 {
+        let mut result = U256::ONE;
+        let mut base = self;
+        for i in 0..exponent.bits() as usize {
+            if exponent.bit(i) {
+                result = result.wrapping_mul(base);
+            }
+            base = base.wrapping_mul(base);
+        }
+        result
+    }
+
+    /// EVM SIGNEXTEND: extend the sign of the `byte_index`-th lowest byte
+    pub fn signextend(self, byte_index: U256) -> U256 {
+        let byte_index = match byte_index.to_usize() {
+            Some(index) if index < 31 => index,
+            _ => return self,
+        };
+        let sign_bit = byte_index * 8 + 7;
+        let mask = U256::ONE.shl(sign_bit as u32).wrapping_sub(U256::ONE);
+        if self.bit(sign_bit) {
+            self | !mask
+        } else {
+            self & mask
+        }
+    }
+
+    /// EVM SLT
+    pub fn slt(&self, other: &U256) -> bool {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => self < other,
+        }
+    }
+
+    /// EVM SGT
+    pub fn sgt(&self, other: &U256) -> bool {
+        other.slt(self)
+    }
+
+    /// EVM BYTE: the `index`-th byte counting from the most significant end
+    pub fn byte(self, index: U256) -> U256 {
+        match index.to_usize() {
+            Some(index) if index < 32 => U256::from_u64(self.to_be_bytes()[index] as u64),
+            _ => U256::ZERO,
+        }
+    }
+
+    /// Logical left shift; shifts of 256 or more yield zero
+    pub fn shl(self, shift: u32) -> U256 {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let limb_shift = (shift / 64) as usize;
+        let bit_shift = shift % 64;
+        let mut result = [0u64; 4];
+        for i in limb_shift..4 {
+            result[i] = self.0[i - limb_shift] << bit_shift;
+            if bit_shift > 0 && i > limb_shift {
+                result[i] |= self.0[i - limb_shift - 1] >> (64 - bit_shift);
+            }
+        }
+        U256(result)
+    }
+
+    /// Logical right shift; shifts of 256 or more yield zero
+    pub fn shr(self, shift: u32) -> U256 {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let limb_shift = (shift / 64) as usize;
+        let bit_shift = shift % 64;
+        let mut result = [0u64; 4];
+        for i in 0..4 - limb_shift {
+            result[i] = self.0[i + limb_shift] >> bit_shift;
+            if bit_shift > 0 && i + limb_shift + 1 < 4 {
+                result[i] |= self.0[i + limb_shift + 1] << (64 - bit_shift);
+            }
+        }
+        U256(result)
+    }
+
+    /// Arithmetic right shift, filling with the sign bit
+    pub fn sar(self, shift: u32) -> U256 {
+        if !self.is_negative() {
+            return self.shr(shift);
+        }
+        if shift >= 256 {
+            return U256::MAX;
+        }
+        !((!self).shr(shift))
+    }
+
+    /// EVM SHL with a word-sized shift operand
+    pub fn evm_shl(self, shift: U256) -> U256 {
+        self.shl(shift_amount(shift))
+    }
+
+    /// EVM SHR with a word-sized shift operand
+    pub fn evm_shr(self, shift: U256) -> U256 {
+        self.shr(shift_amount(shift))
+    }
+
+    /// EVM SAR with a word-sized shift operand
+    pub fn evm_sar(self, shift: U256) -> U256 {
+        self.sar(shift_amount(shift))
+    }
+}
+
+/// Clamp a word-sized shift operand; anything of 256 or more shifts everything out
+fn shift_amount(shift: U256) -> u32 {
+    match shift.to_u64() {
+        Some(shift) if shift < 256 => shift as u32,
+        _ => 256,
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for i in (0..4).rev() {
+            match self.0[i].cmp(&other.0[i]) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::ops::BitAnd for U256 {
+    type Output = U256;
+    fn bitand(self, other: U256) -> U256 {
+        U256([
+            self.0[0] & other.0[0],
+            self.0[1] & other.0[1],
+            self.0[2] & other.0[2],
+            self.0[3] & other.0[3],
+        ])
+    }
+}
+
+impl std::ops::BitOr for U256 {
+    type Output = U256;
+    fn bitor(self, other: U256) -> U256 {
+        U256([
+            self.0[0] | other.0[0],
+            self.0[1] | other.0[1],
+            self.0[2] | other.0[2],
+            self.0[3] | other.0[3],
+        ])
+    }
+}
+
+impl std::ops::BitXor for U256 {
+    type Output = U256;
+    fn bitxor(self, other: U256) -> U256 {
+        U256([
+            self.0[0] ^ other.0[0],
+            self.0[1] ^ other.0[1],
+            self.0[2] ^ other.0[2],
+            self.0[3] ^ other.0[3],
+        ])
+    }
+}
+
+impl std::ops::Not for U256 {
+    type Output = U256;
+    fn not(self) -> U256 {
+        U256([!self.0[0], !self.0[1], !self.0[2], !self.0[3]])
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(value: u64) -> Self {
+        U256::from_u64(value)
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(value: u128) -> Self {
+        U256::from_u128(value)
+    }
+}
+
+impl From<usize> for U256 {
+    fn from(value: usize) -> Self {
+        U256::from_u64(value as u64)
+    }
+}
+
+/// Collapse little-endian limbs into a word (the value must fit)
+fn limbs_to_u256(limbs: &[u64]) -> U256 {
+    let mut result = [0u64; 4];
+    for (i, limb) in limbs.iter().take(4).enumerate() {
+        result[i] = *limb;
+    }
+    U256(result)
+}
+
+/// Number of significant limbs
+fn significant_limbs(limbs: &[u64]) -> usize {
+    limbs.iter().rposition(|&limb| limb != 0).map_or(0, |i| i + 1)
+}
+
+/// Long division of little-endian limb slices (Knuth, TAOCP vol. 2, 4.3.1 D)
+///
+/// The divisor must be non-zero. Returns `(quotient, remainder)`.
+fn div_rem_limbs(dividend: &[u64], divisor: &[u64]) -> (Vec<u64>, Vec<u64>) {
+    const BASE: u128 = 1 << 64;
+
+    let n = significant_limbs(divisor);
+    let len = significant_limbs(dividend);
+    assert!(n > 0, "division by zero");
+
+    if len < n {
+        return (vec![0], dividend[..len.max(1)].to_vec());
+    }
+
+    // Single-limb divisor: short division
+    if n == 1 {
+        let d = divisor[0] as u128;
+        let mut quotient = vec![0u64; len];
+        let mut remainder = 0u128;
+        for i in (0..len).rev() {
+            let current = (remainder << 64) | dividend[i] as u128;
+            quotient[i] = (current / d) as u64;
+            remainder = current % d;
+        }
+        return (quotient, vec![remainder as u64]);
+    }
+
+    // Normalize so the divisor's top limb has its high bit set
+    let shift = divisor[n - 1].leading_zeros();
+    let mut vn = vec![0u64; n];
+    for i in (1..n).rev() {
+        vn[i] = (divisor[i] << shift) | ((divisor[i - 1] as u128) >> (64 - shift)) as u64;
+    }
+    vn[0] = divisor[0] << shift;
+
+    let mut un = vec![0u64; len + 1];
+    un[len] = ((dividend[len - 1] as u128) >> (64 - shift)) as u64;
+    for i in (1..len).rev() {
+        un[i] = (dividend[i] << shift) | ((dividend[i - 1] as u128) >> (64 - shift)) as u64;
+    }
+    un[0] = dividend[0] << shift;
+
+    let m = len - n;
+    let mut quotient = vec![0u64; m + 1];
+
+    for j in (0..=m).rev() {
+        // Estimate the quotient digit from the top two limbs
+        let numerator = ((un[j + n] as u128) << 64) | un[j + n - 1] as u128;
+        let mut qhat = numerator / vn[n - 1] as u128;
+        let mut rhat = numerator % vn[n - 1] as u128;
+        while qhat >= BASE
+            || qhat * vn[n - 2] as u128 > ((rhat << 64) | un[j + n - 2] as u128)
+        {
+            qhat -= 1;
+            rhat += vn[n - 1] as u128;
+            if rhat >= BASE {
+                break;
+            }
+        }
+
+        // Multiply and subtract
+        let mut carry = 0u64;
+        let mut borrow = 0u64;
+        for i in 0..n {
+            let product = qhat * vn[i] as u128 + carry as u128;
+            carry = (product >> 64) as u64;
+            let (diff, b1) = un[i + j].overflowing_sub(product as u64);
+            let (diff, b2) = diff.overflowing_sub(borrow);
+            un[i + j] = diff;
+            borrow = b1 as u64 + b2 as u64;
+        }
+        let (diff, b1) = un[j + n].overflowing_sub(carry);
+        let (diff, b2) = diff.overflowing_sub(borrow);
+        un[j + n] = diff;
+
+        // The estimate was one too large: add the divisor back
+        if b1 || b2 {
+            qhat -= 1;
+            let mut carry = 0u128;
+            for i in 0..n {
+                let sum = un[i + j] as u128 + vn[i] as u128 + carry;
+                un[i + j] = sum as u64;
+                carry = sum >> 64;
+            }
+            un[j + n] = un[j + n].wrapping_add(carry as u64);
+        }
+
+        quotient[j] = qhat as u64;
+    }
+
+    // Denormalize the remainder
+    let mut remainder = vec![0u64; n];
+    for i in 0..n {
+        remainder[i] = (un[i] >> shift) | ((un[i + 1] as u128) << 64 >> shift) as u64;
+    }
+
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(hex: &str) -> U256 {
+        let hex = hex.trim_start_matches("0x");
+        let padded = format!("{:0>64}", hex);
+        let mut bytes = [0u8; 32];
+        for i in 0..32 {
+            bytes[i] = u8::from_str_radix(&padded[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        U256::from_be_bytes(bytes)
+    }
+
+    #[test]
+    fn test_byte_round_trip() {
+        let value = word("0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20");
+        assert_eq!(U256::from_be_bytes(value.to_be_bytes()), value);
+        assert_eq!(value.to_be_bytes()[0], 0x01);
+        assert_eq!(value.low_u64(), 0x191a1b1c1d1e1f20);
+    }
+
+    #[test]
+    fn test_wrapping_arithmetic() {
+        assert_eq!(U256::MAX.wrapping_add(U256::ONE), U256::ZERO);
+        assert_eq!(U256::ZERO.wrapping_sub(U256::ONE), U256::MAX);
+        assert_eq!(U256::MAX.wrapping_mul(U256::MAX), U256::ONE);
+        assert_eq!(
+            U256::from_u128(u128::MAX).wrapping_mul(U256::from_u128(u128::MAX)),
+            word("fffffffffffffffffffffffffffffffe00000000000000000000000000000001")
+        );
+    }
+
+    #[test]
+    fn test_division() {
+        let a = word("fffffffffffffffffffffffffffffffe00000000000000000000000000000001");
+        let b = U256::from_u128(u128::MAX);
+        assert_eq!(a.div(b), b);
+        assert_eq!(a.rem(b), U256::ZERO);
+        assert_eq!(U256::MAX.div(U256::ZERO), U256::ZERO);
+        assert_eq!(U256::from_u64(7).rem(U256::from_u64(3)), U256::ONE);
+
+        let c = word("1000000000000000000000000000000000000000000000000000000000000001");
+        let d = word("300000000000000000000000000000005");
+        let (q, r) = c.div_rem(d);
+        assert_eq!(q.wrapping_mul(d).wrapping_add(r), c);
+        assert!(r < d);
+    }
+
+    #[test]
+    fn test_signed_arithmetic() {
+        let minus_one = U256::MAX;
+        let minus_two = U256::from_u64(2).wrapping_neg();
+        let min = U256::ONE.shl(255);
+        assert_eq!(minus_two.sdiv(minus_one), U256::from_u64(2));
+        assert_eq!(U256::from_u64(4).sdiv(minus_two), minus_two);
+        assert_eq!(min.sdiv(minus_one), min);
+        assert_eq!(U256::from_u64(8).wrapping_neg().smod(U256::from_u64(3)), minus_two);
+        assert!(minus_one.slt(&U256::ZERO));
+        assert!(U256::ZERO.sgt(&minus_one));
+    }
+
+    #[test]
+    fn test_modular_arithmetic() {
+        assert_eq!(U256::MAX.addmod(U256::from_u64(2), U256::from_u64(2)), U256::ONE);
+        assert_eq!(
+            U256::MAX.mulmod(U256::MAX, U256::from_u64(12)),
+            U256::from_u64(9)
+        );
+        assert_eq!(U256::ONE.addmod(U256::ONE, U256::ZERO), U256::ZERO);
+    }
+
+    #[test]
+    fn test_exp_and_signextend() {
+        assert_eq!(U256::from_u64(2).wrapping_pow(U256::from_u64(255)), U256::ONE.shl(255));
+        assert_eq!(U256::from_u64(2).wrapping_pow(U256::from_u64(256)), U256::ZERO);
+        assert_eq!(U256::ZERO.wrapping_pow(U256::ZERO), U256::ONE);
+        assert_eq!(U256::from_u64(0xff).signextend(U256::ZERO), U256::MAX);
+        assert_eq!(U256::from_u64(0x7f).signextend(U256::ZERO), U256::from_u64(0x7f));
+        assert_eq!(U256::from_u64(0x1ff).signextend(U256::ZERO), U256::MAX);
+    }
+
+    #[test]
+    fn test_shifts_and_byte() {
+        assert_eq!(U256::ONE.evm_shl(U256::from_u64(255)), U256::ONE.shl(255));
+        assert_eq!(U256::ONE.evm_shl(U256::from_u64(256)), U256::ZERO);
+        assert_eq!(U256::MAX.evm_shr(U256::from_u64(255)), U256::ONE);
+        assert_eq!(U256::MAX.evm_sar(U256::from_u64(300)), U256::MAX);
+        assert_eq!(U256::ONE.shl(255).evm_sar(U256::from_u64(254)), word("fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe"));
+        assert_eq!(U256::from_u64(0xabcd).byte(U256::from_u64(30)), U256::from_u64(0xab));
+        assert_eq!(U256::MAX.byte(U256::from_u64(32)), U256::ZERO);
+    }
+}
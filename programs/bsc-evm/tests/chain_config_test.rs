@@ -0,0 +1,162 @@
+//! Chain identity stored in the EVM state account.
+
+use bsc_evm::{
+    chain_config::{initialize_chain, ChainConfig, NativeCurrency, MAX_CHAIN_ID},
+    gas_schedule::{GasSchedule, Hardfork},
+    instruction,
+    transaction::CHAIN_ID,
+    EvmInstruction,
+};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+fn testnet() -> ChainConfig {
+    ChainConfig {
+        chain_id: 16980,
+        network_name: "BSSC Testnet".to_string(),
+        native_currency: NativeCurrency {
+            name: "Test BSSC".to_string(),
+            symbol: "tBSSC".to_string(),
+            decimals: 18,
+        },
+        hardfork: Some(Hardfork::London),
+    }
+}
+
+/// Run `initialize_chain` against a state account holding `data`
+fn initialize(data: &mut [u8], is_signer: bool, config: &ChainConfig) -> Result<(), ProgramError> {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 0;
+    let account = AccountInfo::new(&key, is_signer, true, &mut lamports, data, &owner, false, 0);
+    initialize_chain(&account, config)
+}
+
+#[test]
+fn test_unconfigured_account_serves_mainnet() {
+    assert_eq!(ChainConfig::unpack(&[]).unwrap(), ChainConfig::default());
+    assert_eq!(ChainConfig::unpack(&[0u8; ChainConfig::MAX_LEN]).unwrap(), ChainConfig::default());
+    assert_eq!(ChainConfig::try_unpack(&[0u8; 8]).unwrap(), None);
+    assert_eq!(ChainConfig::default().chain_id, CHAIN_ID);
+    assert_eq!(ChainConfig::default().validate(), Ok(()));
+}
+
+#[test]
+fn test_pack_round_trip() {
+    let mut data = testnet().pack();
+    assert!(data.len() <= ChainConfig::MAX_LEN);
+    assert_eq!(ChainConfig::try_unpack(&data).unwrap(), Some(testnet()));
+
+    // Space left over in the account is ignored
+    data.resize(ChainConfig::MAX_LEN, 0);
+    assert_eq!(ChainConfig::unpack(&data).unwrap(), testnet());
+
+    assert_eq!(ChainConfig::unpack(&[1, 2]), Err(ProgramError::InvalidAccountData));
+}
+
+#[test]
+fn test_largest_config_fits() {
+    let config = ChainConfig {
+        chain_id: MAX_CHAIN_ID,
+        network_name: "n".repeat(64),
+        native_currency: NativeCurrency {
+            name: "c".repeat(64),
+            symbol: "s".repeat(16),
+            decimals: 18,
+        },
+        hardfork: Some(Hardfork::Cancun),
+    };
+    assert_eq!(config.validate(), Ok(()));
+    assert_eq!(config.pack().len(), ChainConfig::MAX_LEN);
+}
+
+#[test]
+fn test_config_packed_before_hardfork() {
+    // Packed without the hardfork, ending the data or followed by zeros
+    let config = ChainConfig { hardfork: None, ..testnet() };
+    let mut data = config.pack();
+    assert_eq!(data.pop(), Some(0));
+    assert_eq!(ChainConfig::unpack(&data).unwrap(), config);
+    data.resize(ChainConfig::MAX_LEN, 0);
+    assert_eq!(ChainConfig::unpack(&data).unwrap(), config);
+}
+
+#[test]
+fn test_hardfork_gas_schedule() {
+    assert_eq!(testnet().gas_schedule(), GasSchedule::for_hardfork(Hardfork::London));
+    assert_eq!(ChainConfig::default().gas_schedule(), GasSchedule::default());
+    assert_eq!(GasSchedule::default().hardfork, Hardfork::Cancun);
+}
+
+#[test]
+fn test_validate() {
+    let invalid = [
+        ChainConfig { chain_id: 0, ..testnet() },
+        ChainConfig { chain_id: MAX_CHAIN_ID + 1, ..testnet() },
+        ChainConfig { network_name: String::new(), ..testnet() },
+        ChainConfig { network_name: "n".repeat(65), ..testnet() },
+    ];
+    for config in invalid {
+        assert_eq!(config.validate(), Err(ProgramError::InvalidArgument), "{:?}", config);
+    }
+
+    let mut config = testnet();
+    config.native_currency.decimals = 6;
+    assert_eq!(config.validate(), Err(ProgramError::InvalidArgument));
+    config.native_currency.decimals = 18;
+    config.native_currency.symbol = "s".repeat(17);
+    assert_eq!(config.validate(), Err(ProgramError::InvalidArgument));
+}
+
+#[test]
+fn test_initialize_chain() {
+    let mut data = vec![0u8; ChainConfig::MAX_LEN];
+    assert_eq!(
+        initialize(&mut data, false, &testnet()),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+    initialize(&mut data, true, &testnet()).unwrap();
+    assert_eq!(ChainConfig::unpack(&data).unwrap(), testnet());
+
+    // The identity of a chain never changes
+    assert_eq!(
+        initialize(&mut data, true, &ChainConfig::default()),
+        Err(ProgramError::AccountAlreadyInitialized)
+    );
+
+    let mut data = vec![0u8; 8];
+    assert_eq!(initialize(&mut data, true, &testnet()), Err(ProgramError::AccountDataTooSmall));
+}
+
+#[test]
+fn test_initialize_chain_instruction() {
+    let program_id = Pubkey::new_unique();
+    let evm_state = Pubkey::new_unique();
+    let ix = instruction::initialize_chain(&program_id, &evm_state, testnet());
+    assert_eq!(ix.accounts.len(), 1);
+    assert!(ix.accounts[0].is_signer && ix.accounts[0].is_writable);
+    assert_eq!(
+        EvmInstruction::unpack(&ix.data).unwrap(),
+        EvmInstruction::InitializeChain { config: testnet() }
+    );
+}
+
+#[test]
+fn test_json_config() {
+    let config: ChainConfig = serde_json::from_str(
+        r#"{
+            "chainId": 16980,
+            "networkName": "BSSC Testnet",
+            "nativeCurrency": { "name": "Test BSSC", "symbol": "tBSSC", "decimals": 18 },
+            "hardfork": "london"
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(config, testnet());
+}
+
+#[test]
+fn test_mainnet_config_is_default() {
+    let config: ChainConfig =
+        serde_json::from_str(include_str!("../../../mainnet-config.json")).unwrap();
+    assert_eq!(config, ChainConfig::default());
+}
@@ -0,0 +1,107 @@
+//! SHA3, environment and block opcodes.
+
+use bsc_evm::{
+    block_context::BlockContext,
+    evm_interpreter::{EvmInterpreter, ExecutionContext},
+    EvmState,
+};
+
+const CONTRACT: [u8; 20] = [0x11; 20];
+
+fn run(state: &mut EvmState, code: &[u8], data: Vec<u8>) {
+    let context = ExecutionContext {
+        address: CONTRACT,
+        caller: [0xaa; 20],
+        origin: [0xaa; 20],
+        value: 0,
+        data,
+        gas_limit: 1_000_000,
+        gas_price: 1,
+        block_number: state.block_context().number,
+        timestamp: state.block_context().timestamp,
+        coinbase: [0u8; 20],
+        block_gas_limit: 30_000_000,
+        base_fee: 0,
+        prev_randao: [0u8; 32],
+        chain_id: 16979,
+        depth: 0,
+        is_static: false,
+    };
+    let result = EvmInterpreter::new(context.gas_limit)
+        .execute(code, &context, state)
+        .unwrap();
+    assert!(result.success);
+}
+
+fn slot(state: &EvmState, index: u8) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    key[31] = index;
+    state.get_storage(&CONTRACT, &key)
+}
+
+fn hex(s: &str) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+    }
+    out
+}
+
+#[test]
+fn test_sha3() {
+    let mut state = EvmState::new();
+    let code = [
+        0x60, 0x20, 0x60, 0x00, 0x20, 0x60, 0x00, 0x55, // SSTORE(0, SHA3(0, 32))
+        0x60, 0x00, 0x60, 0x00, 0x20, 0x60, 0x01, 0x55, // SSTORE(1, SHA3(0, 0))
+        0x00,
+    ];
+    run(&mut state, &code, Vec::new());
+
+    assert_eq!(
+        slot(&state, 0),
+        hex("290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563")
+    );
+    assert_eq!(
+        slot(&state, 1),
+        hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+    );
+}
+
+#[test]
+fn test_calldatacopy_pads_with_zeros() {
+    let mut state = EvmState::new();
+    let code = [
+        0x60, 0x20, 0x60, 0x01, 0x60, 0x00, 0x37, // CALLDATACOPY(0, 1, 32)
+        0x60, 0x00, 0x51, 0x60, 0x00, 0x55, // SSTORE(0, MLOAD(0))
+        0x38, 0x60, 0x01, 0x55, // SSTORE(1, CODESIZE)
+        0x00,
+    ];
+    run(&mut state, &code, vec![1, 2, 3]);
+
+    let mut expected = [0u8; 32];
+    expected[..2].copy_from_slice(&[2, 3]);
+    assert_eq!(slot(&state, 0), expected);
+    assert_eq!(slot(&state, 1)[31], code.len() as u8);
+}
+
+#[test]
+fn test_blockhash_window() {
+    let mut state = EvmState::new();
+    let mut block = BlockContext::default();
+    block.number = 1000;
+    block.insert_block_hash(999, [7u8; 32]);
+    block.insert_block_hash(700, [9u8; 32]);
+    state.set_block_context(block);
+
+    let code = [
+        0x61, 0x03, 0xe7, 0x40, 0x60, 0x00, 0x55, // SSTORE(0, BLOCKHASH(999))
+        0x61, 0x02, 0xbc, 0x40, 0x60, 0x01, 0x55, // SSTORE(1, BLOCKHASH(700))
+        0x43, 0x60, 0x02, 0x55, // SSTORE(2, NUMBER)
+        0x00,
+    ];
+    run(&mut state, &code, Vec::new());
+
+    assert_eq!(slot(&state, 0), [7u8; 32]);
+    assert_eq!(slot(&state, 1), [0u8; 32]);
+    assert_eq!(slot(&state, 2)[30..], [0x03, 0xe8]);
+}
@@ -0,0 +1,99 @@
+//! Logs collected by the executor and their program data encoding.
+
+use bsc_evm::{
+    events::{decode_log, decode_transaction_hash, LOG_TAG, TRANSACTION_TAG},
+    evm_interpreter::{create_address, Log},
+    security::VulnerabilitySeverity,
+    EvmExecutor, EvmTransaction,
+};
+
+fn deployment(init_code: Vec<u8>) -> EvmTransaction {
+    EvmTransaction {
+        nonce: 0,
+        gas_price: 20_000_000_000,
+        gas_limit: 200_000,
+        to: None,
+        value: 0,
+        data: init_code,
+        access_list: Vec::new(),
+        v: 0,
+        r: [0u8; 32],
+        s: [0u8; 32],
+    }
+}
+
+#[test]
+fn test_executor_collects_logs() {
+    let mut executor = EvmExecutor::new();
+    // Init code: MSTORE8(0, 0xab) LOG1(0, 1, 7) RETURN(0, 0)
+    let init_code = vec![
+        0x60, 0xab, 0x60, 0x00, 0x53, 0x60, 0x07, 0x60, 0x01, 0x60, 0x00, 0xa1, 0x60, 0x00,
+        0x60, 0x00, 0xf3,
+    ];
+    let output = executor.execute_call(deployment(init_code), [0xaa; 20]).unwrap();
+
+    let address = create_address(&[0xaa; 20], 0);
+    assert_eq!(output, address.to_vec());
+    let mut topic = [0u8; 32];
+    topic[31] = 7;
+    assert_eq!(
+        executor.logs(),
+        &[Log { address, topics: vec![topic], data: vec![0xab] }]
+    );
+}
+
+#[test]
+fn test_reverted_transaction_keeps_revert_data_but_no_logs() {
+    let mut executor = EvmExecutor::new();
+    // Init code: MSTORE8(0, 0x2a) LOG0(0, 0) REVERT(0, 1)
+    let init_code = vec![
+        0x60, 0x2a, 0x60, 0x00, 0x53, 0x60, 0x00, 0x60, 0x00, 0xa0, 0x60, 0x01, 0x60, 0x00, 0xfd,
+    ];
+    assert!(executor.execute_call(deployment(init_code), [0xaa; 20]).is_err());
+    assert!(executor.logs().is_empty());
+    assert_eq!(executor.revert_data(), &[0x2a]);
+}
+
+#[test]
+fn test_decode_log_fields() {
+    let address = [0x11u8; 20];
+    let topic = [0x22u8; 32];
+    let data = vec![1u8, 2, 3];
+    let fields: Vec<&[u8]> = vec![LOG_TAG, &address, &topic, &data];
+    assert_eq!(
+        decode_log(&fields),
+        Some(Log { address, topics: vec![topic], data: data.clone() })
+    );
+
+    // Anonymous log without data
+    let fields: Vec<&[u8]> = vec![LOG_TAG, &address, &[]];
+    assert_eq!(
+        decode_log(&fields),
+        Some(Log { address, topics: Vec::new(), data: Vec::new() })
+    );
+
+    // Wrong tag, short address, short topic and too many topics
+    assert_eq!(decode_log(&[b"OTHER".as_slice(), &address, &data]), None);
+    assert_eq!(decode_log(&[LOG_TAG, &address[..19], &data]), None);
+    assert_eq!(decode_log(&[LOG_TAG, &address, &topic[..31], &data]), None);
+    let fields: Vec<&[u8]> = vec![LOG_TAG, &address, &topic, &topic, &topic, &topic, &topic, &data];
+    assert_eq!(decode_log(&fields), None);
+}
+
+#[test]
+fn test_decode_transaction_hash_fields() {
+    let hash = [0x33u8; 32];
+    assert_eq!(decode_transaction_hash(&[TRANSACTION_TAG, &hash]), Some(hash));
+
+    // Wrong tag, short hash and trailing field
+    assert_eq!(decode_transaction_hash(&[LOG_TAG, &hash]), None);
+    assert_eq!(decode_transaction_hash(&[TRANSACTION_TAG, &hash[..31]]), None);
+    assert_eq!(decode_transaction_hash(&[TRANSACTION_TAG, &hash, &hash]), None);
+}
+
+#[test]
+fn test_results_are_borsh_encoded() {
+    let data = borsh::to_vec(&VulnerabilitySeverity::High).unwrap();
+    let decoded: VulnerabilitySeverity = borsh::from_slice(&data).unwrap();
+    assert_eq!(decoded, VulnerabilitySeverity::High);
+}
@@ -0,0 +1,123 @@
+//! Gas fees paid by the sender.
+
+use bsc_evm::{
+    fees::{wei_to_lamports, LAMPORTS_PER_UNIT, WEI_PER_UNIT},
+    EvmExecutor, EvmTransaction,
+};
+use solana_program::program_error::ProgramError;
+
+const SENDER: [u8; 20] = [0xaa; 20];
+
+/// Lamports deposited for the sender, 0.2 BNB
+const DEPOSIT: u64 = 8_000_000 * LAMPORTS_PER_UNIT;
+
+fn transaction(to: Option<[u8; 20]>, data: Vec<u8>) -> EvmTransaction {
+    EvmTransaction {
+        nonce: 0,
+        gas_price: 20_000_000_000,
+        gas_limit: 200_000,
+        to,
+        value: 0,
+        data,
+        access_list: Vec::new(),
+        v: 0,
+        r: [0u8; 32],
+        s: [0u8; 32],
+    }
+}
+
+/// Executor where the sender has deposited [`DEPOSIT`] lamports
+fn funded_executor() -> EvmExecutor {
+    let mut executor = EvmExecutor::new();
+    executor.deposit(&SENDER, DEPOSIT).unwrap();
+    executor
+}
+
+#[test]
+fn test_transfer_pays_intrinsic_gas() {
+    let mut executor = funded_executor();
+    let balance = executor.get_balance(&SENDER);
+    executor
+        .execute_transaction(transaction(Some([0x22; 20]), Vec::new()), SENDER)
+        .unwrap();
+
+    assert_eq!(executor.gas_fees(), 21_000 * 20_000_000_000);
+    assert_eq!(executor.get_balance(&SENDER), balance - executor.gas_fees());
+    assert_eq!(wei_to_lamports(executor.gas_fees()), Some(2_133_600));
+}
+
+#[test]
+fn test_unused_gas_is_not_charged() {
+    let mut executor = funded_executor();
+    let balance = executor.get_balance(&SENDER);
+    // Init code: PUSH1 0 PUSH1 0 RETURN, deploying empty code
+    let init_code = vec![0x60, 0x00, 0x60, 0x00, 0xf3];
+    executor
+        .execute_transaction(transaction(None, init_code), SENDER)
+        .unwrap();
+
+    // Three non-zero and two zero bytes of calldata plus one word of init code
+    let intrinsic = 21_000 + 32_000 + 3 * 16 + 2 * 4 + 2;
+    assert_eq!(executor.gas_fees(), (intrinsic + 6) * 20_000_000_000);
+    assert_eq!(executor.get_balance(&SENDER), balance - executor.gas_fees());
+}
+
+#[test]
+fn test_access_list_is_charged() {
+    let mut executor = funded_executor();
+    let mut tx = transaction(Some([0x22; 20]), Vec::new());
+    tx.access_list = vec![([0x33; 20], vec![[0u8; 32]])];
+    executor.execute_transaction(tx, SENDER).unwrap();
+
+    assert_eq!(executor.gas_fees(), (21_000 + 2400 + 1900) * 20_000_000_000);
+}
+
+#[test]
+fn test_sender_funds_gas_limit_and_value() {
+    // The whole gas limit must be covered up front, not just the gas used
+    let mut executor = EvmExecutor::new();
+    let gas_limit_fee = 200_000 * 20_000_000_000;
+    executor.state_mut().set_balance(&SENDER, gas_limit_fee - 1);
+    assert_eq!(
+        executor.execute_transaction(transaction(Some([0x22; 20]), Vec::new()), SENDER),
+        Err(ProgramError::InsufficientFunds)
+    );
+
+    let mut tx = transaction(Some([0x22; 20]), Vec::new());
+    tx.value = 1;
+    executor.state_mut().set_balance(&SENDER, gas_limit_fee);
+    assert_eq!(
+        executor.execute_transaction(tx.clone(), SENDER),
+        Err(ProgramError::InsufficientFunds)
+    );
+    executor.state_mut().set_balance(&SENDER, gas_limit_fee + 1);
+    executor.execute_transaction(tx, SENDER).unwrap();
+    assert_eq!(executor.get_balance(&[0x22; 20]), 1);
+    assert_eq!(executor.get_balance(&SENDER), gas_limit_fee - executor.gas_fees());
+    assert_eq!(executor.gas_fees(), 21_000 * 20_000_000_000);
+
+    // A call is free, only the value must be funded
+    let mut executor = EvmExecutor::new();
+    executor
+        .execute_call(transaction(Some([0x22; 20]), Vec::new()), SENDER)
+        .unwrap();
+    assert_eq!(executor.gas_fees(), 0);
+}
+
+#[test]
+fn test_deposits_are_whole_units() {
+    let mut executor = funded_executor();
+    assert_eq!(
+        executor.get_balance(&SENDER),
+        8_000_000 * WEI_PER_UNIT
+    );
+    assert_eq!(
+        executor.deposit(&SENDER, LAMPORTS_PER_UNIT + 1),
+        Err(ProgramError::InvalidArgument)
+    );
+    executor.deposit(&SENDER, LAMPORTS_PER_UNIT).unwrap();
+    assert_eq!(
+        wei_to_lamports(executor.get_balance(&SENDER)),
+        Some(DEPOSIT + LAMPORTS_PER_UNIT)
+    );
+}
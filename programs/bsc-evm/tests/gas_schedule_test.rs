@@ -0,0 +1,168 @@
+//! Per-hardfork gas accounting, checked against the tables in EIP-2200 and EIP-3529.
+
+use bsc_evm::{
+    evm_interpreter::{EvmInterpreter, ExecutionContext, ExecutionResult},
+    gas_schedule::{GasSchedule, Hardfork},
+    EvmState,
+};
+use solana_program::program_error::ProgramError;
+
+const CONTRACT: [u8; 20] = [0x11; 20];
+
+fn decode(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn execute(state: &mut EvmState, code: &[u8]) -> Result<ExecutionResult, ProgramError> {
+    let context = ExecutionContext {
+        address: CONTRACT,
+        caller: [0xaa; 20],
+        origin: [0xaa; 20],
+        value: 0,
+        data: Vec::new(),
+        gas_limit: 100_000,
+        gas_price: 1,
+        block_number: 1,
+        timestamp: 1,
+        coinbase: [0u8; 20],
+        block_gas_limit: 30_000_000,
+        base_fee: 0,
+        prev_randao: [0u8; 32],
+        chain_id: 16979,
+        depth: 0,
+        is_static: false,
+    };
+    EvmInterpreter::new(context.gas_limit).execute(code, &context, state)
+}
+
+/// Run SSTORE test code against slot 0 holding `original`, returning gas used and refund
+fn sstore_gas(hardfork: Hardfork, code: &str, original: u8) -> (u64, u64) {
+    let mut state = EvmState::new();
+    state.set_gas_schedule(GasSchedule::for_hardfork(hardfork));
+    let mut value = [0u8; 32];
+    value[31] = original;
+    state.set_storage(&CONTRACT, &[0u8; 32], &value);
+    state.clear_journal();
+    // The EIP tables assume the slot is already warm
+    state.warm_storage(&CONTRACT, &[0u8; 32]);
+
+    let result = execute(&mut state, &decode(code)).unwrap();
+    assert!(result.success);
+    (result.gas_used, state.refund())
+}
+
+#[test]
+fn test_sstore_istanbul() {
+    let cases = [
+        ("60006000556000600055", 0, 1612, 0),
+        ("60006000556001600055", 0, 20812, 0),
+        ("60016000556000600055", 0, 20812, 19200),
+        ("60006000556000600055", 1, 5812, 15000),
+        ("60026000556001600055", 1, 5812, 4200),
+        ("600160005560006000556001600055", 0, 40818, 19200),
+        ("600060005560016000556000600055", 1, 10818, 19200),
+    ];
+    for (code, original, gas, refund) in cases {
+        assert_eq!(sstore_gas(Hardfork::Istanbul, code, original), (gas, refund), "{}", code);
+    }
+}
+
+#[test]
+fn test_sstore_london() {
+    let cases = [
+        ("60006000556000600055", 0, 212, 0),
+        ("60006000556001600055", 0, 20112, 0),
+        ("60016000556000600055", 0, 20112, 19900),
+        ("60006000556000600055", 1, 3012, 4800),
+        ("60006000556001600055", 1, 3012, 2800),
+        ("60026000556001600055", 1, 3012, 2800),
+        ("60016000556001600055", 1, 212, 0),
+        ("600160005560006000556001600055", 0, 40118, 19900),
+        ("600060005560016000556000600055", 1, 5918, 7600),
+    ];
+    for (code, original, gas, refund) in cases {
+        assert_eq!(sstore_gas(Hardfork::London, code, original), (gas, refund), "{}", code);
+    }
+}
+
+#[test]
+fn test_cold_and_warm_account_access() {
+    // PUSH20 addr BALANCE PUSH20 addr BALANCE
+    let mut code = Vec::new();
+    for _ in 0..2 {
+        code.push(0x73);
+        code.extend_from_slice(&[0x22; 20]);
+        code.push(0x31);
+    }
+
+    let mut state = EvmState::new();
+    state.set_gas_schedule(GasSchedule::for_hardfork(Hardfork::Berlin));
+    assert_eq!(execute(&mut state, &code).unwrap().gas_used, 3 + 2600 + 3 + 100);
+
+    let mut state = EvmState::new();
+    state.set_gas_schedule(GasSchedule::for_hardfork(Hardfork::Istanbul));
+    assert_eq!(execute(&mut state, &code).unwrap().gas_used, 3 + 700 + 3 + 700);
+}
+
+#[test]
+fn test_opcodes_gated_by_hardfork() {
+    // PUSH0 STOP
+    let code = [0x5f, 0x00];
+
+    let mut state = EvmState::new();
+    state.set_gas_schedule(GasSchedule::for_hardfork(Hardfork::London));
+    assert!(execute(&mut state, &code).is_err());
+
+    let mut state = EvmState::new();
+    state.set_gas_schedule(GasSchedule::for_hardfork(Hardfork::Shanghai));
+    assert_eq!(execute(&mut state, &code).unwrap().gas_used, 2);
+}
+
+/// Run SELFDESTRUCT on an existing contract, returning whether its code survives and the refund
+fn selfdestruct(hardfork: Hardfork) -> (bool, u64) {
+    let mut state = EvmState::new();
+    state.set_gas_schedule(GasSchedule::for_hardfork(hardfork));
+    // PUSH20 beneficiary SELFDESTRUCT
+    let code = decode("73bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbff");
+    state.deploy_contract(&CONTRACT, code.clone());
+    state.set_balance(&CONTRACT, 5);
+    state.clear_journal();
+
+    assert!(execute(&mut state, &code).unwrap().success);
+    assert_eq!(state.get_balance(&CONTRACT), 0);
+    assert_eq!(state.get_balance(&[0xbb; 20]), 5);
+    (!state.get_code(&CONTRACT).is_empty(), state.refund())
+}
+
+#[test]
+fn test_selfdestruct_before_cancun() {
+    assert_eq!(selfdestruct(Hardfork::Istanbul), (false, 24000));
+    assert_eq!(selfdestruct(Hardfork::Berlin), (false, 24000));
+    assert_eq!(selfdestruct(Hardfork::London), (false, 0));
+    assert_eq!(selfdestruct(Hardfork::Shanghai), (false, 0));
+}
+
+#[test]
+fn test_selfdestruct_cancun() {
+    // EIP-6780: a contract not created in this transaction keeps its code
+    assert_eq!(selfdestruct(Hardfork::Cancun), (true, 0));
+}
+
+#[test]
+fn test_intrinsic_gas() {
+    let schedule = GasSchedule::for_hardfork(Hardfork::Shanghai);
+    assert_eq!(schedule.intrinsic_gas(&[], false, &[]), 21000);
+    assert_eq!(schedule.intrinsic_gas(&[0, 1], false, &[]), 21000 + 4 + 16);
+    // Contract creation with 33 bytes of init code: two words of EIP-3860 metering
+    assert_eq!(
+        schedule.intrinsic_gas(&[1; 33], true, &[]),
+        21000 + 33 * 16 + 32000 + 2 * 2
+    );
+    assert_eq!(
+        schedule.intrinsic_gas(&[], false, &[([0u8; 20], vec![[0u8; 32]; 2])]),
+        21000 + 2400 + 2 * 1900
+    );
+}
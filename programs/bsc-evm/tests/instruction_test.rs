@@ -0,0 +1,133 @@
+//! Instruction wire format and builders.
+
+use bsc_evm::{
+    instruction, EvmInstruction, EvmTransaction, GasConfig, Property, PropertyType,
+    SecurityConfig, INSTRUCTION_VERSION,
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, system_program, sysvar};
+
+fn round_trip(instruction: &EvmInstruction) {
+    let data = instruction.pack();
+    assert_eq!(data[0], INSTRUCTION_VERSION);
+    assert_eq!(&EvmInstruction::unpack(&data).unwrap(), instruction);
+}
+
+#[test]
+fn test_payloads_round_trip() {
+    let transaction = EvmTransaction {
+        nonce: 3,
+        gas_price: 20_000_000_000,
+        gas_limit: 50_000,
+        to: Some([0x22; 20]),
+        value: 7,
+        data: vec![1, 2, 3],
+        access_list: vec![([0x33; 20], vec![[0x44; 32]])],
+        v: 0,
+        r: [0u8; 32],
+        s: [0u8; 32],
+    };
+    let mut security_config = SecurityConfig::default();
+    security_config.forbidden_opcodes = vec![0xff];
+    let mut gas_config = GasConfig::default();
+    gas_config.batch_discount = 0.5;
+
+    for instruction in [
+        EvmInstruction::CallContract {
+            to: [0x11; 20],
+            data: vec![0xa9, 0x05, 0x9c, 0xbb],
+            value: u128::MAX,
+            gas_limit: 100_000,
+        },
+        EvmInstruction::ExecuteBatch {
+            transactions: vec![transaction.clone(), transaction],
+            batch_id: 9,
+        },
+        EvmInstruction::ConfigureGasOptimization { config: gas_config },
+        EvmInstruction::VerifyContract {
+            contract_address: [0x55; 20],
+            bytecode: vec![0x60, 0x00],
+            properties: vec![Property {
+                name: "balance".to_string(),
+                description: "Balances never go negative".to_string(),
+                property_type: PropertyType::Invariant,
+                assertion: "balance >= 0".to_string(),
+                verified: false,
+                proof: None,
+            }],
+        },
+        EvmInstruction::DeployContractSecure { bytecode: vec![0x60, 0x80] },
+        EvmInstruction::UpdateSecurityConfig { config: security_config },
+        EvmInstruction::ClearPerformanceData,
+        EvmInstruction::ExecuteSignedTransaction { raw: vec![0xf8, 0x6c] },
+        EvmInstruction::SimulateCall {
+            from: [0x66; 20],
+            to: None,
+            data: vec![0x60, 0x00],
+            value: 0,
+            gas_limit: 30_000_000,
+        },
+    ] {
+        round_trip(&instruction);
+    }
+}
+
+#[test]
+fn test_rejects_bad_data() {
+    let data = EvmInstruction::Transfer { to: [0x11; 20], value: 5 }.pack();
+
+    let mut wrong_version = data.clone();
+    wrong_version[0] = INSTRUCTION_VERSION + 1;
+    assert_eq!(
+        EvmInstruction::unpack(&wrong_version),
+        Err(ProgramError::InvalidInstructionData)
+    );
+    assert_eq!(
+        EvmInstruction::unpack(&data[..data.len() - 1]),
+        Err(ProgramError::InvalidInstructionData)
+    );
+    assert_eq!(EvmInstruction::unpack(&[]), Err(ProgramError::InvalidInstructionData));
+}
+
+#[test]
+fn test_state_instruction_accounts() {
+    let program_id = Pubkey::new_unique();
+    let evm_state = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+    let state_account = Pubkey::new_unique();
+
+    let ix = instruction::execute_signed_transaction(
+        &program_id,
+        &evm_state,
+        &payer,
+        &[state_account],
+        vec![0xc0],
+    );
+    assert_eq!(ix.program_id, program_id);
+    let keys: Vec<_> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(
+        keys,
+        vec![evm_state, payer, system_program::id(), sysvar::slot_hashes::id(), state_account]
+    );
+    assert!(ix.accounts[0].is_writable);
+    assert!(ix.accounts[1].is_signer);
+    assert!(ix.accounts[4].is_writable);
+    assert!(matches!(
+        EvmInstruction::unpack(&ix.data).unwrap(),
+        EvmInstruction::ExecuteSignedTransaction { .. }
+    ));
+
+    // Deposits credit an EVM account, so they take its state account
+    let ix =
+        instruction::deposit(&program_id, &evm_state, &payer, &[state_account], [0x11; 20], 127);
+    assert_eq!(ix.accounts.len(), 5);
+    assert!(EvmInstruction::unpack(&ix.data).unwrap().touches_state());
+
+    let ix = instruction::get_performance_metrics(&program_id, &evm_state);
+    assert_eq!(ix.accounts.len(), 1);
+    assert!(!ix.accounts[0].is_writable);
+
+    // Unsigned calls act for no EVM account and take no state accounts
+    let ix = instruction::call_contract(&program_id, &evm_state, [0x11; 20], vec![], 0, 21_000);
+    assert_eq!(ix.accounts.len(), 1);
+    assert!(!EvmInstruction::unpack(&ix.data).unwrap().touches_state());
+}
@@ -0,0 +1,165 @@
+//! Nested message calls and contract creation.
+
+use bsc_evm::{
+    evm_interpreter::{create2_address, create_address, EvmInterpreter, ExecutionContext},
+    EvmState,
+};
+
+const CALLER: [u8; 20] = [0xaa; 20];
+const CONTRACT: [u8; 20] = [0x11; 20];
+const CALLEE: [u8; 20] = [0x22; 20];
+
+fn context(address: [u8; 20]) -> ExecutionContext {
+    ExecutionContext {
+        address,
+        caller: CALLER,
+        origin: CALLER,
+        value: 0,
+        data: Vec::new(),
+        gas_limit: 1_000_000,
+        gas_price: 1,
+        block_number: 1,
+        timestamp: 1,
+        coinbase: [0u8; 20],
+        block_gas_limit: 30_000_000,
+        base_fee: 0,
+        prev_randao: [0u8; 32],
+        chain_id: 16979,
+        depth: 0,
+        is_static: false,
+    }
+}
+
+/// Code that calls `CALLEE` with the given call opcode and stores the success flag in slot 0
+fn call_and_store_result(opcode: u8) -> Vec<u8> {
+    let mut code = vec![
+        0x60, 0x00, // retSize
+        0x60, 0x00, // retOffset
+        0x60, 0x00, // argsSize
+        0x60, 0x00, // argsOffset
+    ];
+    if opcode == 0xf1 {
+        code.extend_from_slice(&[0x60, 0x00]); // value
+    }
+    code.push(0x73); // PUSH20 callee
+    code.extend_from_slice(&CALLEE);
+    code.extend_from_slice(&[0x62, 0x00, 0xff, 0xff]); // gas
+    code.push(opcode);
+    code.extend_from_slice(&[0x60, 0x00, 0x55, 0x00]); // PUSH1 0 SSTORE STOP
+    code
+}
+
+fn run(state: &mut EvmState, code: &[u8]) -> bool {
+    let context = context(CONTRACT);
+    EvmInterpreter::new(context.gas_limit)
+        .execute(code, &context, state)
+        .unwrap()
+        .success
+}
+
+#[test]
+fn test_create_addresses() {
+    let sender = [
+        0x6a, 0xc7, 0xea, 0x33, 0xf8, 0x83, 0x1e, 0xa9, 0xdc, 0xc5, 0x33, 0x93, 0xaa, 0xa8, 0x8b,
+        0x25, 0xa7, 0x85, 0xdb, 0xf0,
+    ];
+    assert_eq!(
+        create_address(&sender, 0),
+        [
+            0xcd, 0x23, 0x4a, 0x47, 0x1b, 0x72, 0xba, 0x2f, 0x1c, 0xcf, 0x0a, 0x70, 0xfc, 0xab,
+            0xa6, 0x48, 0xa5, 0xee, 0xcd, 0x8d
+        ]
+    );
+    assert_eq!(
+        create_address(&sender, 1),
+        [
+            0x34, 0x3c, 0x43, 0xa3, 0x7d, 0x37, 0xdf, 0xf0, 0x8a, 0xe8, 0xc4, 0xa1, 0x15, 0x44,
+            0xc7, 0x18, 0xab, 0xb4, 0xfc, 0xf8
+        ]
+    );
+
+    // First example from EIP-1014
+    assert_eq!(
+        create2_address(&[0u8; 20], &[0u8; 32], &[0x00]),
+        [
+            0x4d, 0x1a, 0x2e, 0x2b, 0xb4, 0xf8, 0x8f, 0x02, 0x50, 0xf2, 0x6f, 0xff, 0xf0, 0x98,
+            0xb0, 0xb3, 0x0b, 0x26, 0xbf, 0x38
+        ]
+    );
+}
+
+#[test]
+fn test_call_commits_callee_writes() {
+    let mut state = EvmState::new();
+    // PUSH1 1 PUSH1 0 SSTORE STOP
+    state.deploy_contract(&CALLEE, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+
+    assert!(run(&mut state, &call_and_store_result(0xf1)));
+    assert_eq!(state.get_storage(&CONTRACT, &[0u8; 32])[31], 1);
+    assert_eq!(state.get_storage(&CALLEE, &[0u8; 32])[31], 1);
+}
+
+#[test]
+fn test_reverted_call_rolls_back_callee_writes() {
+    let mut state = EvmState::new();
+    // PUSH1 1 PUSH1 0 SSTORE PUSH1 0 PUSH1 0 REVERT
+    state.deploy_contract(
+        &CALLEE,
+        vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xfd],
+    );
+
+    assert!(run(&mut state, &call_and_store_result(0xf1)));
+    assert_eq!(state.get_storage(&CONTRACT, &[0u8; 32]), [0u8; 32]);
+    assert_eq!(state.get_storage(&CALLEE, &[0u8; 32]), [0u8; 32]);
+}
+
+#[test]
+fn test_invalid_opcode_halts_callee() {
+    let mut state = EvmState::new();
+    // PUSH1 1 PUSH1 0 SSTORE INVALID, with code after it that never runs
+    state.deploy_contract(&CALLEE, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0xfe, 0x00]);
+
+    let context = context(CONTRACT);
+    let result = EvmInterpreter::new(context.gas_limit)
+        .execute(&call_and_store_result(0xf1), &context, &mut state)
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(state.get_storage(&CONTRACT, &[0u8; 32]), [0u8; 32]);
+    assert_eq!(state.get_storage(&CALLEE, &[0u8; 32]), [0u8; 32]);
+    // The callee consumed all the gas it was given
+    assert!(result.gas_used > 0xffff);
+}
+
+#[test]
+fn test_staticcall_rejects_writes() {
+    let mut state = EvmState::new();
+    // PUSH1 1 PUSH1 0 SSTORE STOP
+    state.deploy_contract(&CALLEE, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+
+    assert!(run(&mut state, &call_and_store_result(0xfa)));
+    assert_eq!(state.get_storage(&CONTRACT, &[0u8; 32]), [0u8; 32]);
+    assert_eq!(state.get_storage(&CALLEE, &[0u8; 32]), [0u8; 32]);
+}
+
+#[test]
+fn test_create_runs_init_code() {
+    let mut state = EvmState::new();
+    // Init code: PUSH1 0x2a PUSH1 0 MSTORE8 PUSH1 1 PUSH1 0 RETURN
+    let init_code = [0x60, 0x2a, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+    let mut code = vec![0x69]; // PUSH10 init code
+    code.extend_from_slice(&init_code);
+    code.extend_from_slice(&[
+        0x60, 0x00, 0x52, // PUSH1 0 MSTORE (init code lands at bytes 22..32)
+        0x60, 0x0a, 0x60, 0x16, 0x60, 0x00, 0xf0, // CREATE(0, 22, 10)
+        0x60, 0x00, 0x55, 0x00, // PUSH1 0 SSTORE STOP
+    ]);
+
+    let nonce = state.get_nonce(&CONTRACT);
+    assert!(run(&mut state, &code));
+
+    let created = create_address(&CONTRACT, nonce);
+    assert_eq!(state.get_storage(&CONTRACT, &[0u8; 32])[12..], created);
+    assert_eq!(state.get_code(&created), vec![0x2a]);
+    assert_eq!(state.get_nonce(&created), 1);
+    assert_eq!(state.get_nonce(&CONTRACT), nonce + 1);
+}
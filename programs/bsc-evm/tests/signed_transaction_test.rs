@@ -0,0 +1,243 @@
+//! Raw signed transactions of every type, EIP-155 replay protection and nonces.
+
+use bsc_evm::{
+    transaction::{
+        decode_transaction, recover_sender, recover_signer, SignedTransaction, TransactionType,
+        CHAIN_ID, NONCE_MISMATCH,
+    },
+    EvmExecutor, EvmInstruction, EXECUTION_REVERTED,
+};
+use solana_program::program_error::ProgramError;
+
+fn decode(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// The example transaction from EIP-155, signed for chain id 1
+const EIP155_EXAMPLE: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+/// Sign `tx` for `chain_id` with `secret` and return the raw encoding
+fn sign(mut tx: SignedTransaction, chain_id: u64, secret: [u8; 32]) -> Vec<u8> {
+    tx.chain_id = Some(chain_id);
+    let message = libsecp256k1::Message::parse(&tx.signing_hash());
+    let secret = libsecp256k1::SecretKey::parse(&secret).unwrap();
+    let (signature, recovery_id) = libsecp256k1::sign(&message, &secret);
+    let signature = signature.serialize();
+
+    tx.odd_y_parity = recovery_id.serialize() == 1;
+    tx.r.copy_from_slice(&signature[..32]);
+    tx.s.copy_from_slice(&signature[32..]);
+    tx.encode()
+}
+
+fn transfer(tx_type: TransactionType, nonce: u64) -> SignedTransaction {
+    SignedTransaction {
+        tx_type,
+        chain_id: None,
+        nonce,
+        max_priority_fee_per_gas: 20_000_000_000,
+        max_fee_per_gas: 20_000_000_000,
+        gas_limit: 21_000,
+        to: Some([0x35; 20]),
+        value: 0,
+        data: Vec::new(),
+        access_list: Vec::new(),
+        odd_y_parity: false,
+        r: [0u8; 32],
+        s: [0u8; 32],
+    }
+}
+
+/// Address of the secret key 0x4646...46 used in EIP-155
+const SENDER: &str = "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f";
+
+/// Balance of the sender, 1 BNB, enough for the gas and value of every transaction
+const BALANCE: u128 = 1_000_000_000_000_000_000;
+
+/// Executor where the sender holds [`BALANCE`]
+fn funded_executor() -> EvmExecutor {
+    let mut executor = EvmExecutor::new();
+    executor
+        .state_mut()
+        .set_balance(&decode(SENDER).try_into().unwrap(), BALANCE);
+    executor
+}
+
+#[test]
+fn test_mainnet_transaction_is_rejected() {
+    let tx = decode_transaction(&decode(EIP155_EXAMPLE)).unwrap();
+    assert_eq!(tx.chain_id, Some(1));
+    assert_eq!(recover_sender(&tx, CHAIN_ID), Err(ProgramError::InvalidArgument));
+    assert_eq!(recover_signer(&tx), recover_sender(&tx, 1));
+}
+
+#[test]
+fn test_recover_sender() {
+    for tx_type in [
+        TransactionType::Legacy,
+        TransactionType::AccessList,
+        TransactionType::DynamicFee,
+    ] {
+        let raw = sign(transfer(tx_type, 0), CHAIN_ID, [0x46; 32]);
+        let tx = decode_transaction(&raw).unwrap();
+        assert_eq!(tx.tx_type, tx_type);
+        assert_eq!(recover_sender(&tx, CHAIN_ID).unwrap().to_vec(), decode(SENDER));
+    }
+}
+
+#[test]
+fn test_rejects_malformed_encoding() {
+    let mut raw = decode(EIP155_EXAMPLE);
+    raw.push(0);
+    assert_eq!(decode_transaction(&raw), Err(ProgramError::InvalidInstructionData));
+    assert!(decode_transaction(&raw[..raw.len() - 2]).is_err());
+}
+
+#[test]
+fn test_nonce_is_checked_and_incremented() {
+    let mut sender = [0u8; 20];
+    sender.copy_from_slice(&decode(SENDER));
+    let mut executor = funded_executor();
+
+    let first = sign(transfer(TransactionType::Legacy, 0), CHAIN_ID, [0x46; 32]);
+    executor.execute_signed_transaction(&first).unwrap();
+    assert_eq!(executor.get_nonce(&sender), 1);
+
+    // Replaying the same transaction fails
+    assert_eq!(executor.execute_signed_transaction(&first), Err(NONCE_MISMATCH));
+
+    let skipped = sign(transfer(TransactionType::Legacy, 2), CHAIN_ID, [0x46; 32]);
+    assert_eq!(executor.execute_signed_transaction(&skipped), Err(NONCE_MISMATCH));
+
+    let second = sign(transfer(TransactionType::DynamicFee, 1), CHAIN_ID, [0x46; 32]);
+    executor.execute_signed_transaction(&second).unwrap();
+    assert_eq!(executor.get_nonce(&sender), 2);
+}
+
+#[test]
+fn test_other_chains_are_rejected() {
+    let raw = sign(transfer(TransactionType::Legacy, 0), 56, [0x46; 32]);
+    let mut executor = EvmExecutor::new();
+    assert_eq!(
+        executor.execute_signed_transaction(&raw),
+        Err(ProgramError::InvalidArgument)
+    );
+}
+
+#[test]
+fn test_configured_chain_is_accepted() {
+    let raw = sign(transfer(TransactionType::DynamicFee, 0), 1337, [0x46; 32]);
+    let mut executor = funded_executor();
+    executor.set_chain_id(1337);
+    executor.execute_signed_transaction(&raw).unwrap();
+
+    // Transactions for BSSC mainnet are replays on this chain
+    let raw = sign(transfer(TransactionType::DynamicFee, 1), CHAIN_ID, [0x46; 32]);
+    assert_eq!(
+        executor.execute_signed_transaction(&raw),
+        Err(ProgramError::InvalidArgument)
+    );
+}
+
+#[test]
+fn test_failed_transaction_keeps_nonce_and_fee() {
+    let mut sender = [0u8; 20];
+    sender.copy_from_slice(&decode(SENDER));
+    let mut executor = funded_executor();
+    // Runtime code: SSTORE(0, 1) REVERT(0, 0)
+    let contract = [0x35; 20];
+    let code = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xfd];
+    executor.state_mut().deploy_contract(&contract, code);
+
+    let mut call = transfer(TransactionType::Legacy, 0);
+    call.gas_limit = 100_000;
+    call.value = 400;
+    let raw = sign(call, CHAIN_ID, [0x46; 32]);
+    assert_eq!(executor.execute_signed_transaction(&raw), Err(EXECUTION_REVERTED));
+
+    // The value transfer and the storage write are rolled back, the gas used
+    // until the revert is charged and the rest is not
+    let fees = executor.gas_fees();
+    assert!(fees > 21_000 * 20_000_000_000 && fees < 100_000 * 20_000_000_000);
+    assert_eq!(executor.get_nonce(&sender), 1);
+    assert_eq!(executor.get_balance(&sender), BALANCE - fees);
+    assert_eq!(executor.get_balance(&contract), 0);
+    assert_eq!(executor.get_storage(&contract, &[0u8; 32]), [0u8; 32]);
+
+    // A creation whose init code halts (ADD on an empty stack) consumes its
+    // nonce and all its gas
+    let mut create = transfer(TransactionType::Legacy, 1);
+    create.gas_limit = 100_000;
+    create.to = None;
+    create.data = vec![0x01];
+    let raw = sign(create, CHAIN_ID, [0x46; 32]);
+    assert_eq!(executor.execute_signed_transaction(&raw), Err(EXECUTION_REVERTED));
+    assert_eq!(executor.get_nonce(&sender), 2);
+    assert_eq!(executor.gas_fees() - fees, 100_000 * 20_000_000_000);
+    assert_eq!(executor.get_balance(&sender), BALANCE - executor.gas_fees());
+}
+
+#[test]
+fn test_sender_pays_for_gas() {
+    // An unfunded sender cannot pay for its gas limit
+    let raw = sign(transfer(TransactionType::DynamicFee, 0), CHAIN_ID, [0x46; 32]);
+    let mut executor = EvmExecutor::new();
+    assert_eq!(
+        executor.execute_signed_transaction(&raw),
+        Err(ProgramError::InsufficientFunds)
+    );
+    assert_eq!(executor.gas_fees(), 0);
+}
+
+#[test]
+fn test_signed_gas_is_not_adjusted() {
+    // A gas limit below the intrinsic gas is rejected, not raised to an estimate
+    let mut short = transfer(TransactionType::Legacy, 0);
+    short.gas_limit = 20_999;
+    let raw = sign(short, CHAIN_ID, [0x46; 32]);
+    let mut executor = EvmExecutor::new();
+    assert_eq!(
+        executor.execute_signed_transaction(&raw),
+        Err(ProgramError::InvalidArgument)
+    );
+    assert_eq!(executor.gas_fees(), 0);
+
+    // A zero gas price is paid as signed, not replaced with a dynamic price
+    let mut free = transfer(TransactionType::Legacy, 0);
+    free.max_fee_per_gas = 0;
+    let raw = sign(free, CHAIN_ID, [0x46; 32]);
+    let mut executor = EvmExecutor::new();
+    executor.execute_signed_transaction(&raw).unwrap();
+    assert_eq!(executor.gas_fees(), 0);
+}
+
+#[test]
+fn test_gas_price_is_capped() {
+    // A fee cap above the maximum is rejected before it is reserved
+    let executor = EvmExecutor::new();
+    let mut costly = transfer(TransactionType::DynamicFee, 0);
+    costly.max_fee_per_gas = executor.max_gas_price() + 1;
+    let raw = sign(costly, CHAIN_ID, [0x46; 32]);
+    let mut executor = EvmExecutor::new();
+    assert_eq!(
+        executor.execute_signed_transaction(&raw),
+        Err(ProgramError::InvalidArgument)
+    );
+    assert_eq!(executor.gas_fees(), 0);
+    assert_eq!(
+        EvmInstruction::ExecuteSignedTransaction { raw }.max_gas_fee(executor.max_gas_price()),
+        Err(ProgramError::InvalidArgument)
+    );
+
+    // A fee cap at the maximum is accepted
+    let mut capped = transfer(TransactionType::DynamicFee, 0);
+    capped.max_fee_per_gas = executor.max_gas_price();
+    let raw = sign(capped, CHAIN_ID, [0x46; 32]);
+    assert_eq!(
+        EvmInstruction::ExecuteSignedTransaction { raw }.max_gas_fee(executor.max_gas_price()),
+        Ok(21_000 * executor.max_gas_price())
+    );
+}
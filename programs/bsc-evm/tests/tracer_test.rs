@@ -0,0 +1,262 @@
+//! Execution tracing.
+
+use bsc_evm::{
+    evm_interpreter::{EvmInterpreter, ExecutionContext, Log, Opcode},
+    tracer::{FrameEnd, FrameStart, Step, Tracer},
+    EvmExecutor, EvmState, EvmTransaction,
+};
+use std::{cell::RefCell, rc::Rc};
+
+const CALLER: [u8; 20] = [0xaa; 20];
+const CONTRACT: [u8; 20] = [0x11; 20];
+const CALLEE: [u8; 20] = [0x22; 20];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    Enter { kind: Opcode, to: [u8; 20], gas: u64, depth: usize },
+    Step { pc: usize, opcode: u8, gas: u64, depth: usize, stack: usize },
+    Cost(u64),
+    Storage([u8; 20], u8, u8),
+    Log(usize),
+    Fault(String),
+    Exit { success: bool, gas_used: u64, output: Vec<u8> },
+}
+
+/// Records every event
+#[derive(Default)]
+struct Recorder {
+    events: Vec<Event>,
+}
+
+impl Tracer for Recorder {
+    fn enter(&mut self, frame: &FrameStart) {
+        self.events.push(Event::Enter {
+            kind: frame.kind,
+            to: frame.to,
+            gas: frame.gas,
+            depth: frame.depth,
+        });
+    }
+
+    fn step(&mut self, step: &Step) {
+        self.events.push(Event::Step {
+            pc: step.pc,
+            opcode: step.opcode,
+            gas: step.gas,
+            depth: step.depth,
+            stack: step.stack.len(),
+        });
+    }
+
+    fn step_cost(&mut self, gas_cost: u64) {
+        self.events.push(Event::Cost(gas_cost));
+    }
+
+    fn storage(&mut self, address: &[u8; 20], key: &[u8; 32], value: &[u8; 32]) {
+        self.events.push(Event::Storage(*address, key[31], value[31]));
+    }
+
+    fn log(&mut self, log: &Log) {
+        self.events.push(Event::Log(log.topics.len()));
+    }
+
+    fn fault(&mut self, error: &str) {
+        self.events.push(Event::Fault(error.to_string()));
+    }
+
+    fn exit(&mut self, frame: &FrameEnd) {
+        self.events.push(Event::Exit {
+            success: frame.success,
+            gas_used: frame.gas_used,
+            output: frame.output.to_vec(),
+        });
+    }
+}
+
+fn context(address: [u8; 20]) -> ExecutionContext {
+    ExecutionContext {
+        address,
+        caller: CALLER,
+        origin: CALLER,
+        value: 0,
+        data: Vec::new(),
+        gas_limit: 1_000_000,
+        gas_price: 1,
+        block_number: 1,
+        timestamp: 1,
+        coinbase: [0u8; 20],
+        block_gas_limit: 30_000_000,
+        base_fee: 0,
+        prev_randao: [0u8; 32],
+        chain_id: 16979,
+        depth: 0,
+        is_static: false,
+    }
+}
+
+/// Run `code` at `CONTRACT` in a traced state, returning the events
+fn trace(state: &mut EvmState, code: &[u8]) -> Vec<Event> {
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    state.set_tracer(recorder.clone());
+    let context = context(CONTRACT);
+    let _ = EvmInterpreter::new(context.gas_limit).execute(code, &context, state);
+    state.clear_tracer();
+    let events = recorder.borrow().events.clone();
+    events
+}
+
+/// Code that calls `CALLEE` with 0xffff gas
+fn call_callee() -> Vec<u8> {
+    let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73];
+    code.extend_from_slice(&CALLEE);
+    code.extend_from_slice(&[0x62, 0x00, 0xff, 0xff, 0xf1, 0x00]);
+    code
+}
+
+#[test]
+fn test_steps_and_costs() {
+    let mut state = EvmState::new();
+    // PUSH1 1 PUSH1 0 SSTORE PUSH1 0 SLOAD STOP
+    let events = trace(&mut state, &[0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x54, 0x00]);
+
+    let steps: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Step { pc, opcode, stack, .. } => Some((*pc, *opcode, *stack)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        steps,
+        [(0, 0x60, 0), (2, 0x60, 1), (4, 0x55, 2), (5, 0x60, 0), (7, 0x54, 1), (8, 0x00, 1)]
+    );
+
+    // Each step is followed by its cost, and the gas left adds up
+    let stepped: Vec<_> =
+        events.iter().filter(|event| !matches!(event, Event::Storage(..))).collect();
+    let mut gas = 1_000_000;
+    for pair in stepped.chunks(2) {
+        let [Event::Step { gas: step_gas, .. }, Event::Cost(cost)] = pair else {
+            panic!("unexpected events {:?}", pair);
+        };
+        assert_eq!(*step_gas, gas);
+        gas -= cost;
+    }
+
+    // A cold write from zero, then a warm read
+    assert!(events.contains(&Event::Cost(22_100)));
+    assert!(events.contains(&Event::Cost(100)));
+    let storage: Vec<_> =
+        events.iter().filter(|event| matches!(event, Event::Storage(..))).collect();
+    assert_eq!(storage, [&Event::Storage(CONTRACT, 0, 1), &Event::Storage(CONTRACT, 0, 1)]);
+}
+
+#[test]
+fn test_nested_frames() {
+    let mut state = EvmState::new();
+    // PUSH1 0 PUSH1 0 LOG0 PUSH1 7 PUSH1 0 MSTORE8 PUSH1 1 PUSH1 0 RETURN
+    let callee = vec![
+        0x60, 0x00, 0x60, 0x00, 0xa0, 0x60, 0x07, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3,
+    ];
+    state.deploy_contract(&CALLEE, callee);
+    let events = trace(&mut state, &call_callee());
+
+    // The cost of CALL, including the gas forwarded, comes before the nested frame
+    let call =
+        events.iter().position(|event| matches!(event, Event::Step { opcode: 0xf1, .. })).unwrap();
+    let Event::Cost(cost) = events[call + 1] else {
+        panic!("expected the cost of CALL, got {:?}", events[call + 1]);
+    };
+    assert_eq!(cost, 2600 + 0xffff);
+    assert_eq!(
+        events[call + 2],
+        Event::Enter { kind: Opcode::CALL, to: CALLEE, gas: 0xffff, depth: 1 }
+    );
+    assert!(matches!(events[call + 3], Event::Step { pc: 0, depth: 1, gas: 0xffff, .. }));
+    assert!(events.contains(&Event::Log(0)));
+
+    let exit = events.iter().position(|event| matches!(event, Event::Exit { .. })).unwrap();
+    let Event::Exit { success, gas_used, output } = &events[exit] else { unreachable!() };
+    assert!(*success);
+    assert_eq!(output, &[7]);
+    assert!(*gas_used > 375 && *gas_used < 1000);
+
+    // The caller carries on at depth 0, and the cost of CALL is not reported again
+    assert!(matches!(events[exit + 1], Event::Step { opcode: 0x00, depth: 0, .. }));
+    assert_eq!(events[exit + 2], Event::Cost(0));
+}
+
+#[test]
+fn test_faults() {
+    let mut state = EvmState::new();
+    // PUSH1 3 JUMP: not a JUMPDEST
+    state.deploy_contract(&CALLEE, vec![0x60, 0x03, 0x56, 0x00]);
+    let events = trace(&mut state, &call_callee());
+    let fault = events.iter().position(|event| matches!(event, Event::Fault(_))).unwrap();
+    assert_eq!(events[fault], Event::Fault("invalid jump destination".to_string()));
+    assert!(matches!(events[fault - 1], Event::Step { opcode: 0x56, depth: 1, .. }));
+    assert_eq!(
+        events[fault + 1],
+        Event::Exit { success: false, gas_used: 0xffff, output: Vec::new() }
+    );
+
+    let mut state = EvmState::new();
+    assert_eq!(
+        trace(&mut state, &[0x01]).last(),
+        Some(&Event::Fault("stack underflow".to_string()))
+    );
+    assert_eq!(
+        trace(&mut state, &[0x0c]).last(),
+        Some(&Event::Fault("invalid opcode".to_string()))
+    );
+    assert_eq!(
+        trace(&mut state, &[0x60, 0x01, 0x60, 0x00, 0x60, 0x00, 0x3e]).last(),
+        Some(&Event::Fault("return data out of bounds".to_string()))
+    );
+}
+
+#[test]
+fn test_transaction_frame() {
+    let mut executor = EvmExecutor::new();
+    // PUSH1 0 PUSH1 0 REVERT
+    executor.state_mut().deploy_contract(&CONTRACT, vec![0x60, 0x00, 0x60, 0x00, 0xfd]);
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    executor.state_mut().set_tracer(recorder.clone());
+
+    let tx = EvmTransaction {
+        nonce: 0,
+        gas_price: 20_000_000_000,
+        gas_limit: 100_000,
+        to: Some(CONTRACT),
+        value: 0,
+        data: Vec::new(),
+        access_list: Vec::new(),
+        v: 0,
+        r: [0u8; 32],
+        s: [0u8; 32],
+    };
+    assert!(executor.execute_call(tx.clone(), CALLER).is_err());
+    {
+        let events = &recorder.borrow().events;
+        assert_eq!(
+            events[0],
+            Event::Enter { kind: Opcode::CALL, to: CONTRACT, gas: 100_000, depth: 0 }
+        );
+        // A revert is charged the gas it used, not the whole limit
+        assert_eq!(
+            events.last(),
+            Some(&Event::Exit { success: false, gas_used: 21_006, output: Vec::new() })
+        );
+    }
+
+    // A transfer has a frame of its own and no steps
+    recorder.borrow_mut().events.clear();
+    executor.execute_call(EvmTransaction { to: Some(CALLEE), ..tx }, CALLER).unwrap();
+    assert_eq!(
+        recorder.borrow().events,
+        [
+            Event::Enter { kind: Opcode::CALL, to: CALLEE, gas: 100_000, depth: 0 },
+            Event::Exit { success: true, gas_used: 21_000, output: Vec::new() },
+        ]
+    );
+}
@@ -7,7 +7,9 @@ use solana_program::{
     system_program,
 };
 
-use crate::BridgeInstruction;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::{BridgeInstruction, InnerInstruction};
 
 /// Create instruction to initialize a PDA for an Ethereum address
 pub fn initialize_pda(
@@ -31,11 +33,16 @@ pub fn initialize_pda(
 }
 
 /// Create instruction to transfer SOL via PDA
+///
+/// `eth_signature` signs [`transfer_digest`](crate::signature::transfer_digest)
+/// of the same destination, amount, nonce and expiry.
 pub fn transfer(
     program_id: &Pubkey,
     source_eth_address: [u8; 20],
     destination: &Pubkey,
     amount: u64,
+    nonce: u64,
+    expiry: u64,
     eth_signature: [u8; 65],
 ) -> Instruction {
     let (source_pda, _bump) = crate::derive_pda(&source_eth_address, program_id);
@@ -50,6 +57,78 @@ pub fn transfer(
         data: BridgeInstruction::Transfer {
             eth_address: source_eth_address,
             amount,
+            nonce,
+            expiry,
+            eth_signature,
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+/// Create instruction to create the associated token account of `mint` for the PDA of `eth_address`
+pub fn create_token_account(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    eth_address: [u8; 20],
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Instruction {
+    let (pda, _bump) = crate::derive_pda(&eth_address, program_id);
+    let token_account = get_associated_token_address_with_program_id(&pda, mint, token_program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(token_account, false),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+        data: BridgeInstruction::CreateTokenAccount { eth_address }
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Create instruction to transfer tokens of `mint` from the PDA's associated token account
+///
+/// `eth_signature` signs [`token_transfer_digest`](crate::signature::token_transfer_digest)
+/// of the same mint, destination, amount, decimals, nonce and expiry.
+#[allow(clippy::too_many_arguments)]
+pub fn token_transfer(
+    program_id: &Pubkey,
+    source_eth_address: [u8; 20],
+    mint: &Pubkey,
+    destination: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    nonce: u64,
+    expiry: u64,
+    eth_signature: [u8; 65],
+) -> Instruction {
+    let (source_pda, _bump) = crate::derive_pda(&source_eth_address, program_id);
+    let source = get_associated_token_address_with_program_id(&source_pda, mint, token_program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(source_pda, false),
+            AccountMeta::new(source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+        ],
+        data: BridgeInstruction::TokenTransfer {
+            eth_address: source_eth_address,
+            amount,
+            decimals,
+            nonce,
+            expiry,
             eth_signature,
         }
         .try_to_vec()
@@ -57,3 +136,53 @@ pub fn transfer(
     }
 }
 
+/// Create instruction to invoke `instructions` with the PDA of `eth_address` as signer
+///
+/// `eth_signature` signs [`execute_digest`](crate::signature::execute_digest)
+/// of the same instructions, nonce and expiry. The accounts of the
+/// instructions are passed once each, writable and signer if any instruction
+/// needs them so, except that the PDA is signed for by the program.
+pub fn execute(
+    program_id: &Pubkey,
+    eth_address: [u8; 20],
+    instructions: Vec<InnerInstruction>,
+    nonce: u64,
+    expiry: u64,
+    eth_signature: [u8; 65],
+) -> Instruction {
+    let (pda, _bump) = crate::derive_pda(&eth_address, program_id);
+
+    let mut accounts = vec![AccountMeta::new(pda, false)];
+    for instruction in &instructions {
+        let metas = instruction.accounts.iter().map(|meta| {
+            AccountMeta {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer && meta.pubkey != pda,
+                is_writable: meta.is_writable,
+            }
+        });
+        for meta in metas.chain([AccountMeta::new_readonly(instruction.program_id, false)]) {
+            match accounts.iter_mut().find(|account| account.pubkey == meta.pubkey) {
+                Some(account) => {
+                    account.is_signer |= meta.is_signer;
+                    account.is_writable |= meta.is_writable;
+                }
+                None => accounts.push(meta),
+            }
+        }
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: BridgeInstruction::Execute {
+            eth_address,
+            instructions,
+            nonce,
+            expiry,
+            eth_signature,
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
@@ -8,23 +8,31 @@
 //! - ETH address (0x123...) -> PDA (deterministic Solana address)
 //! - PDA is controlled by this program
 //! - MetaMask signs ETH transaction -> RPC converts to Solana instruction
-//! - Program verifies ETH signature and executes on behalf of user
+//! - Program verifies ETH signature and executes on behalf of user, see
+//!   [`signature`] for the message signed
 
 pub mod instruction;
+pub mod signature;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint,
     entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
     msg,
-    program::invoke_signed,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
     pubkey::Pubkey,
     system_instruction,
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
 };
 
+#[cfg(not(feature = "no-entrypoint"))]
 entrypoint!(process_instruction);
 
 /// Program instructions
@@ -32,6 +40,9 @@ entrypoint!(process_instruction);
 pub enum BridgeInstruction {
     /// Initialize a PDA account for an Ethereum address
     ///
+    /// A PDA initialized before the nonce was added is grown to hold it, at
+    /// nonce 0, the payer funding the extra rent.
+    ///
     /// Accounts:
     /// 0. [signer] Payer account
     /// 1. [writable] PDA account to initialize
@@ -42,6 +53,10 @@ pub enum BridgeInstruction {
 
     /// Transfer SOL from one PDA to another (or regular account)
     ///
+    /// The sender signs the transfer as EIP-712 typed data, see [`signature`].
+    /// The PDA keeps the rent-exempt minimum, so it is never deleted and its
+    /// nonce never starts over.
+    ///
     /// Accounts:
     /// 0. [writable] Source PDA (derived from sender's ETH address)
     /// 1. [writable] Destination account
@@ -49,8 +64,100 @@ pub enum BridgeInstruction {
     Transfer {
         eth_address: [u8; 20],  // Sender's ETH address (for PDA derivation)
         amount: u64,            // Amount in lamports
+        nonce: u64,             // Source PDA's current nonce
+        expiry: u64,            // Unix timestamp the signature expires at
         eth_signature: [u8; 65], // ECDSA signature from MetaMask
     },
+
+    /// Invoke Solana instructions with the PDA as a signer
+    ///
+    /// The sender signs the instructions, with their accounts, as EIP-712
+    /// typed data, see [`signature`]. The instructions cannot invoke this
+    /// program. The PDA holds this program's data, so the system program
+    /// cannot transfer its lamports: use Transfer for that.
+    ///
+    /// Accounts:
+    /// 0. [writable] Sender's PDA
+    /// 1. ..Every account and program the instructions use, signers and
+    ///    writable as the instructions need them except for the PDA
+    Execute {
+        eth_address: [u8; 20],
+        instructions: Vec<InnerInstruction>,
+        nonce: u64,
+        expiry: u64,
+        eth_signature: [u8; 65],
+    },
+
+    /// Create the associated token account of a PDA
+    ///
+    /// Anyone can pay for it, as for any associated token account, and
+    /// creating one that exists does nothing.
+    ///
+    /// Accounts:
+    /// 0. [signer, writable] Payer account
+    /// 1. [writable] PDA's associated token account
+    /// 2. [] PDA (derived from the ETH address)
+    /// 3. [] Token mint
+    /// 4. [] System program
+    /// 5. [] SPL Token or Token-2022 program
+    /// 6. [] Associated token account program
+    CreateTokenAccount {
+        eth_address: [u8; 20],
+    },
+
+    /// Transfer SPL or Token-2022 tokens out of a PDA's associated token account
+    ///
+    /// The sender signs the transfer as EIP-712 typed data, see [`signature`].
+    /// Mints with a transfer hook are not supported.
+    ///
+    /// Accounts:
+    /// 0. [writable] Source PDA (derived from sender's ETH address)
+    /// 1. [writable] Source PDA's associated token account
+    /// 2. [] Token mint
+    /// 3. [writable] Destination token account
+    /// 4. [] SPL Token or Token-2022 program
+    TokenTransfer {
+        eth_address: [u8; 20],
+        amount: u64,   // Amount in base units of the mint
+        decimals: u8,  // Decimals of the mint
+        nonce: u64,
+        expiry: u64,
+        eth_signature: [u8; 65],
+    },
+}
+
+/// Solana instruction invoked on behalf of an Ethereum address
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InnerInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<InnerAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+/// Account of an [`InnerInstruction`]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InnerAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl From<&InnerInstruction> for Instruction {
+    fn from(instruction: &InnerInstruction) -> Self {
+        Instruction {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        }
+    }
 }
 
 /// PDA account data structure
@@ -59,6 +166,26 @@ pub struct PDAAccount {
     pub eth_address: [u8; 20],
     pub is_initialized: bool,
     pub bump_seed: u8,
+    /// Nonce the next Transfer, TokenTransfer or Execute must be signed with
+    pub nonce: u64,
+}
+
+impl PDAAccount {
+    /// Size of the serialized account data
+    pub const LEN: usize = 20 + 1 + 1 + 8;
+
+    /// Size of the account data of PDAs initialized before the nonce was added
+    pub const LEGACY_LEN: usize = 20 + 1 + 1;
+
+    /// Deserialize the account data, reading a legacy account as nonce 0
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() == Self::LEGACY_LEN {
+            let mut padded = data.to_vec();
+            padded.resize(Self::LEN, 0);
+            return Ok(Self::try_from_slice(&padded)?);
+        }
+        Ok(Self::try_from_slice(data)?)
+    }
 }
 
 /// Seeds for PDA derivation
@@ -91,10 +218,62 @@ pub fn process_instruction(
         BridgeInstruction::Transfer {
             eth_address,
             amount,
+            nonce,
+            expiry,
             eth_signature,
         } => {
             msg!("Instruction: Transfer");
-            process_transfer(program_id, accounts, eth_address, amount, eth_signature)
+            process_transfer(
+                program_id,
+                accounts,
+                eth_address,
+                amount,
+                nonce,
+                expiry,
+                eth_signature,
+            )
+        }
+        BridgeInstruction::Execute {
+            eth_address,
+            instructions,
+            nonce,
+            expiry,
+            eth_signature,
+        } => {
+            msg!("Instruction: Execute");
+            process_execute(
+                program_id,
+                accounts,
+                eth_address,
+                instructions,
+                nonce,
+                expiry,
+                eth_signature,
+            )
+        }
+        BridgeInstruction::CreateTokenAccount { eth_address } => {
+            msg!("Instruction: CreateTokenAccount");
+            process_create_token_account(program_id, accounts, eth_address)
+        }
+        BridgeInstruction::TokenTransfer {
+            eth_address,
+            amount,
+            decimals,
+            nonce,
+            expiry,
+            eth_signature,
+        } => {
+            msg!("Instruction: TokenTransfer");
+            process_token_transfer(
+                program_id,
+                accounts,
+                eth_address,
+                amount,
+                decimals,
+                nonce,
+                expiry,
+                eth_signature,
+            )
         }
     }
 }
@@ -116,6 +295,24 @@ fn process_initialize_pda(
         return Err(ProgramError::InvalidAccountData);
     }
 
+    // Grow a legacy account to hold the nonce, the payer funding its rent
+    if pda_account.data_len() == PDAAccount::LEGACY_LEN {
+        let pda_data = PDAAccount::unpack(&pda_account.data.borrow())?;
+        let rent_lamports = Rent::get()?
+            .minimum_balance(PDAAccount::LEN)
+            .saturating_sub(pda_account.lamports());
+        if rent_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, pda_account.key, rent_lamports),
+                &[payer.clone(), pda_account.clone(), system_program.clone()],
+            )?;
+        }
+        pda_account.realloc(PDAAccount::LEN, false)?;
+        pda_data.serialize(&mut &mut pda_account.data.borrow_mut()[..])?;
+        msg!("PDA migrated for ETH address: {:?}", eth_address);
+        return Ok(());
+    }
+
     // Check if already initialized
     if pda_account.data_len() > 0 {
         msg!("PDA already initialized");
@@ -124,7 +321,7 @@ fn process_initialize_pda(
 
     // Create PDA account
     let rent = Rent::get()?;
-    let space = std::mem::size_of::<PDAAccount>();
+    let space = PDAAccount::LEN;
     let rent_lamports = rent.minimum_balance(space);
 
     let seeds: &[&[u8]] = &[PDA_SEED_PREFIX, &eth_address, &[bump_seed]];
@@ -146,6 +343,7 @@ fn process_initialize_pda(
         eth_address,
         is_initialized: true,
         bump_seed,
+        nonce: 0,
     };
 
     pda_data.serialize(&mut &mut pda_account.data.borrow_mut()[..])?;
@@ -159,35 +357,24 @@ fn process_transfer(
     accounts: &[AccountInfo],
     eth_address: [u8; 20],
     amount: u64,
-    _eth_signature: [u8; 65], // TODO: Verify ECDSA signature
+    nonce: u64,
+    expiry: u64,
+    eth_signature: [u8; 65],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let source_pda = next_account_info(account_info_iter)?;
     let destination = next_account_info(account_info_iter)?;
     let _system_program = next_account_info(account_info_iter)?;
 
-    // Verify source PDA
-    let (expected_pda, bump_seed) = derive_pda(&eth_address, program_id);
-    if expected_pda != *source_pda.key {
-        msg!("Error: Invalid source PDA");
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let digest = signature::transfer_digest(program_id, destination.key, amount, nonce, expiry);
+    authorize(program_id, source_pda, &eth_address, &digest, nonce, expiry, &eth_signature)?;
 
-    // Verify PDA is initialized
-    let pda_data = PDAAccount::try_from_slice(&source_pda.data.borrow())?;
-    if !pda_data.is_initialized {
-        msg!("Error: PDA not initialized");
-        return Err(ProgramError::UninitializedAccount);
-    }
-
-    // TODO: Verify Ethereum signature
-    // For now, we trust the RPC server to validate signatures
-    // In production, implement secp256k1 signature verification here
-
-    // Check sufficient balance
-    let source_lamports = source_pda.lamports();
-    if source_lamports < amount {
-        msg!("Error: Insufficient funds");
+    // An emptied PDA would be deleted along with its nonce, making old signatures replayable
+    let available = source_pda
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(PDAAccount::LEN));
+    if available < amount {
+        msg!("Error: Insufficient funds, {} lamports are available", available);
         return Err(ProgramError::InsufficientFunds);
     }
 
@@ -204,6 +391,199 @@ fn process_transfer(
     Ok(())
 }
 
+fn process_execute(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    eth_address: [u8; 20],
+    instructions: Vec<InnerInstruction>,
+    nonce: u64,
+    expiry: u64,
+    eth_signature: [u8; 65],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pda_account = next_account_info(account_info_iter)?;
+
+    // The bridge's own instructions are authorized by ETH signatures, not by the PDA
+    if instructions.iter().any(|instruction| instruction.program_id == *program_id) {
+        msg!("Error: Execute cannot invoke the bridge");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let digest = signature::execute_digest(program_id, &instructions, nonce, expiry);
+    let bump_seed =
+        authorize(program_id, pda_account, &eth_address, &digest, nonce, expiry, &eth_signature)?;
+
+    let seeds: &[&[u8]] = &[PDA_SEED_PREFIX, &eth_address, &[bump_seed]];
+    for instruction in &instructions {
+        invoke_signed(&Instruction::from(instruction), accounts, &[seeds])?;
+    }
+
+    msg!("Executed {} instructions for ETH address: {:?}", instructions.len(), eth_address);
+    Ok(())
+}
+
+fn process_create_token_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    eth_address: [u8; 20],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer = next_account_info(account_info_iter)?;
+    let token_account = next_account_info(account_info_iter)?;
+    let pda_account = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let associated_token_program = next_account_info(account_info_iter)?;
+
+    // Verify PDA
+    let (expected_pda, _bump_seed) = derive_pda(&eth_address, program_id);
+    if expected_pda != *pda_account.key {
+        msg!("Error: Invalid PDA");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    spl_token_2022::check_spl_token_program_account(token_program.key)?;
+    if *associated_token_program.key != spl_associated_token_account::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    invoke(
+        &create_associated_token_account_idempotent(
+            payer.key,
+            pda_account.key,
+            mint.key,
+            token_program.key,
+        ),
+        &[
+            payer.clone(),
+            token_account.clone(),
+            pda_account.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )?;
+
+    msg!("Token account {} ready for ETH address: {:?}", token_account.key, eth_address);
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_token_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    eth_address: [u8; 20],
+    amount: u64,
+    decimals: u8,
+    nonce: u64,
+    expiry: u64,
+    eth_signature: [u8; 65],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let source_pda = next_account_info(account_info_iter)?;
+    let source = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let destination = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    let digest = signature::token_transfer_digest(
+        program_id,
+        mint.key,
+        destination.key,
+        amount,
+        decimals,
+        nonce,
+        expiry,
+    );
+    let bump_seed =
+        authorize(program_id, source_pda, &eth_address, &digest, nonce, expiry, &eth_signature)?;
+
+    // Tokens only leave the PDA's associated token account of the mint signed for
+    spl_token_2022::check_spl_token_program_account(token_program.key)?;
+    let expected_source =
+        get_associated_token_address_with_program_id(source_pda.key, mint.key, token_program.key);
+    if expected_source != *source.key {
+        msg!("Error: Source is not the PDA's associated token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let seeds: &[&[u8]] = &[PDA_SEED_PREFIX, &eth_address, &[bump_seed]];
+    invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            source.key,
+            mint.key,
+            destination.key,
+            source_pda.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            source.clone(),
+            mint.clone(),
+            destination.clone(),
+            source_pda.clone(),
+            token_program.clone(),
+        ],
+        &[seeds],
+    )?;
+
+    msg!("Transferred {} tokens of {} from PDA to {}", amount, mint.key, destination.key);
+    Ok(())
+}
+
+/// Check the owner of `eth_address` signed `digest` with the nonce of its PDA, and consume the nonce
+///
+/// Returns the PDA's bump seed.
+fn authorize(
+    program_id: &Pubkey,
+    pda_account: &AccountInfo,
+    eth_address: &[u8; 20],
+    digest: &[u8; 32],
+    nonce: u64,
+    expiry: u64,
+    eth_signature: &[u8; 65],
+) -> Result<u8, ProgramError> {
+    // Verify source PDA
+    let (expected_pda, bump_seed) = derive_pda(eth_address, program_id);
+    if expected_pda != *pda_account.key {
+        msg!("Error: Invalid source PDA");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Verify PDA is initialized
+    let mut pda_data = PDAAccount::unpack(&pda_account.data.borrow())?;
+    if !pda_data.is_initialized {
+        msg!("Error: PDA not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Verify the owner of the ETH address signed this very instruction
+    if signature::recover_signer(digest, eth_signature)? != *eth_address {
+        msg!("Error: Not signed by {:?}", eth_address);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if nonce != pda_data.nonce {
+        msg!("Error: Expected nonce {}, got {}", pda_data.nonce, nonce);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if Clock::get()?.unix_timestamp >= i64::try_from(expiry).unwrap_or(i64::MAX) {
+        msg!("Error: Signature expired");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Consume the nonce so the signature cannot be replayed, growing a legacy
+    // account to hold it out of the PDA's own lamports
+    pda_data.nonce = pda_data.nonce.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+    if pda_account.data_len() < PDAAccount::LEN {
+        pda_account.realloc(PDAAccount::LEN, false)?;
+    }
+    pda_data.serialize(&mut &mut pda_account.data.borrow_mut()[..])?;
+    Ok(bump_seed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -220,5 +600,112 @@ mod tests {
         assert_eq!(pda1, pda2);
         assert_eq!(bump1, bump2);
     }
+
+    #[test]
+    fn test_execute_instruction_accounts() {
+        let program_id = Pubkey::new_unique();
+        let eth_address = [0x11; 20];
+        let (pda, _) = derive_pda(&eth_address, &program_id);
+        let (mint, token_program, payer) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let inner = |accounts: Vec<InnerAccountMeta>| InnerInstruction {
+            program_id: token_program,
+            accounts,
+            data: vec![],
+        };
+        let meta = |pubkey, is_signer, is_writable| InnerAccountMeta {
+            pubkey,
+            is_signer,
+            is_writable,
+        };
+        let instructions = vec![
+            inner(vec![meta(mint, false, false), meta(pda, true, false)]),
+            inner(vec![meta(mint, false, true), meta(payer, true, true)]),
+        ];
+
+        let ix = instruction::execute(&program_id, eth_address, instructions.clone(), 3, 60, [0; 65]);
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(pda, false),
+                AccountMeta::new(mint, false),
+                AccountMeta::new_readonly(token_program, false),
+                AccountMeta::new(payer, true),
+            ]
+        );
+        match BridgeInstruction::try_from_slice(&ix.data).unwrap() {
+            BridgeInstruction::Execute { instructions: decoded, nonce, expiry, .. } => {
+                assert_eq!((decoded, nonce, expiry), (instructions.clone(), 3, 60));
+            }
+            other => panic!("unexpected instruction {:?}", other),
+        }
+
+        // The PDA signs the invoked instructions
+        let invoked = Instruction::from(&instructions[0]);
+        assert_eq!(invoked.accounts[1], AccountMeta::new_readonly(pda, true));
+    }
+
+    #[test]
+    fn test_token_instruction_accounts() {
+        let program_id = Pubkey::new_unique();
+        let eth_address = [0x11; 20];
+        let (pda, _) = derive_pda(&eth_address, &program_id);
+        let (payer, mint, destination) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let spl_token = solana_program::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+        for token_program_id in [spl_token, spl_token_2022::id()] {
+            let token_account =
+                get_associated_token_address_with_program_id(&pda, &mint, &token_program_id);
+            let ix = instruction::create_token_account(
+                &program_id,
+                &payer,
+                eth_address,
+                &mint,
+                &token_program_id,
+            );
+            assert_eq!(ix.accounts[1], AccountMeta::new(token_account, false));
+            assert_eq!(ix.accounts[2], AccountMeta::new_readonly(pda, false));
+            assert_eq!(ix.accounts[5].pubkey, token_program_id);
+
+            let ix = instruction::token_transfer(
+                &program_id,
+                eth_address,
+                &mint,
+                &destination,
+                &token_program_id,
+                1_000,
+                6,
+                0,
+                60,
+                [0; 65],
+            );
+            assert_eq!(ix.accounts[0], AccountMeta::new(pda, false));
+            assert_eq!(ix.accounts[1], AccountMeta::new(token_account, false));
+            assert_eq!(ix.accounts[3], AccountMeta::new(destination, false));
+            assert!(matches!(
+                BridgeInstruction::try_from_slice(&ix.data).unwrap(),
+                BridgeInstruction::TokenTransfer { amount: 1_000, decimals: 6, .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_pda_account_len() {
+        let pda_data = PDAAccount {
+            eth_address: [0x11; 20],
+            is_initialized: true,
+            bump_seed: 255,
+            nonce: u64::MAX,
+        };
+        let data = pda_data.try_to_vec().unwrap();
+        assert_eq!(data.len(), PDAAccount::LEN);
+        assert_eq!(PDAAccount::unpack(&data).unwrap().nonce, u64::MAX);
+
+        // Accounts initialized before the nonce was added
+        let legacy = PDAAccount::unpack(&data[..PDAAccount::LEGACY_LEN]).unwrap();
+        assert_eq!((legacy.eth_address, legacy.bump_seed, legacy.nonce), ([0x11; 20], 255, 0));
+        assert!(PDAAccount::unpack(&data[..PDAAccount::LEGACY_LEN + 1]).is_err());
+    }
 }
 
@@ -0,0 +1,388 @@
+//! # Transfer Authorization
+//!
+//! A Transfer moves lamports out of a PDA, and an Execute signs with it, only
+//! with the signature of the Ethereum address the PDA belongs to. Wallets sign
+//! either as EIP-712 typed data (`eth_signTypedData_v4`):
+//!
+//! - the domain is `EIP712Domain(string name,string version,uint256 chainId,bytes32 salt)`
+//!   with name [`DOMAIN_NAME`], version [`DOMAIN_VERSION`], chain id
+//!   [`CHAIN_ID`] and the bridge program id as salt, so a signature is only
+//!   valid for one deployment on one cluster
+//! - a Transfer signs `Transfer(bytes32 destination,uint64 amount,uint64 nonce,uint64 expiry)`,
+//!   the destination being the Solana account credited
+//! - a TokenTransfer signs
+//!   `TokenTransfer(bytes32 mint,bytes32 destination,uint64 amount,uint8 decimals,uint64 nonce,uint64 expiry)`,
+//!   the destination being the token account credited
+//! - an Execute signs `Execute(Instruction[] instructions,uint64 nonce,uint64 expiry)`
+//!   with `Instruction(bytes32 programId,AccountMeta[] accounts,bytes data)`
+//!   and `AccountMeta(bytes32 pubkey,bool isSigner,bool isWritable)`, so the
+//!   signature covers every account the instructions use and how
+//! - `nonce` must be the PDA's current nonce, which each Transfer,
+//!   TokenTransfer and Execute increments, and `expiry` a Unix timestamp after which the signature is
+//!   rejected
+
+use {
+    crate::{InnerAccountMeta, InnerInstruction},
+    solana_program::{
+        keccak, program_error::ProgramError, pubkey::Pubkey, secp256k1_recover::secp256k1_recover,
+    },
+};
+
+/// Name of the EIP-712 signing domain
+pub const DOMAIN_NAME: &str = "ETH Bridge";
+
+/// Version of the EIP-712 signing domain
+pub const DOMAIN_VERSION: &str = "1";
+
+/// Chain id of the cluster the bridge is built for, the `chainId` of its signing domain
+///
+/// A program cannot tell which cluster it runs on, so the bridge is built for
+/// a cluster other than BSSC mainnet with `ETH_BRIDGE_CHAIN_ID` set to the EVM
+/// chain id of that cluster, the one wallets sign for there. A bridge deployed
+/// at the same program id on two clusters then accepts different signatures.
+pub const CHAIN_ID: u64 = match option_env!("ETH_BRIDGE_CHAIN_ID") {
+    Some(chain_id) => parse_chain_id(chain_id),
+    None => 16979,
+};
+
+const DOMAIN_TYPE: &[u8] = b"EIP712Domain(string name,string version,uint256 chainId,bytes32 salt)";
+
+const TRANSFER_TYPE: &[u8] =
+    b"Transfer(bytes32 destination,uint64 amount,uint64 nonce,uint64 expiry)";
+
+const TOKEN_TRANSFER_TYPE: &[u8] = b"TokenTransfer(bytes32 mint,bytes32 destination,uint64 amount,\
+    uint8 decimals,uint64 nonce,uint64 expiry)";
+
+const ACCOUNT_META_TYPE: &[u8] = b"AccountMeta(bytes32 pubkey,bool isSigner,bool isWritable)";
+
+/// Instruction type, followed by the type it references
+const INSTRUCTION_TYPE: &[u8] = b"Instruction(bytes32 programId,AccountMeta[] accounts,bytes data)\
+    AccountMeta(bytes32 pubkey,bool isSigner,bool isWritable)";
+
+/// Execute type, followed by the types it references in alphabetical order
+const EXECUTE_TYPE: &[u8] = b"Execute(Instruction[] instructions,uint64 nonce,uint64 expiry)\
+    AccountMeta(bytes32 pubkey,bool isSigner,bool isWritable)\
+    Instruction(bytes32 programId,AccountMeta[] accounts,bytes data)";
+
+/// Half the order of the secp256k1 curve, the largest `s` accepted (EIP-2)
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Parse the decimal `ETH_BRIDGE_CHAIN_ID`, failing the build if it is not one
+const fn parse_chain_id(digits: &str) -> u64 {
+    let digits = digits.as_bytes();
+    assert!(!digits.is_empty(), "ETH_BRIDGE_CHAIN_ID is empty");
+    let mut chain_id: u64 = 0;
+    let mut i = 0;
+    while i < digits.len() {
+        assert!(
+            digits[i].is_ascii_digit(),
+            "ETH_BRIDGE_CHAIN_ID is not a decimal number"
+        );
+        chain_id = chain_id * 10 + (digits[i] - b'0') as u64;
+        i += 1;
+    }
+    chain_id
+}
+
+/// EIP-712 domain separator of the bridge deployed as `program_id`
+pub fn domain_separator(program_id: &Pubkey) -> [u8; 32] {
+    chain_domain_separator(CHAIN_ID, program_id)
+}
+
+/// EIP-712 domain separator of the bridge deployed as `program_id` on the cluster of `chain_id`
+fn chain_domain_separator(chain_id: u64, program_id: &Pubkey) -> [u8; 32] {
+    keccak::hashv(&[
+        &keccak::hash(DOMAIN_TYPE).to_bytes(),
+        &keccak::hash(DOMAIN_NAME.as_bytes()).to_bytes(),
+        &keccak::hash(DOMAIN_VERSION.as_bytes()).to_bytes(),
+        &uint_word(chain_id),
+        program_id.as_ref(),
+    ])
+    .to_bytes()
+}
+
+/// Digest an Ethereum address signs to authorize a transfer
+pub fn transfer_digest(
+    program_id: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+    nonce: u64,
+    expiry: u64,
+) -> [u8; 32] {
+    let struct_hash = keccak::hashv(&[
+        &keccak::hash(TRANSFER_TYPE).to_bytes(),
+        destination.as_ref(),
+        &uint_word(amount),
+        &uint_word(nonce),
+        &uint_word(expiry),
+    ])
+    .to_bytes();
+    typed_data_digest(program_id, &struct_hash)
+}
+
+/// Digest an Ethereum address signs to authorize a token transfer
+pub fn token_transfer_digest(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    nonce: u64,
+    expiry: u64,
+) -> [u8; 32] {
+    let struct_hash = keccak::hashv(&[
+        &keccak::hash(TOKEN_TRANSFER_TYPE).to_bytes(),
+        mint.as_ref(),
+        destination.as_ref(),
+        &uint_word(amount),
+        &uint_word(decimals.into()),
+        &uint_word(nonce),
+        &uint_word(expiry),
+    ])
+    .to_bytes();
+    typed_data_digest(program_id, &struct_hash)
+}
+
+/// Digest an Ethereum address signs to authorize an Execute
+pub fn execute_digest(
+    program_id: &Pubkey,
+    instructions: &[InnerInstruction],
+    nonce: u64,
+    expiry: u64,
+) -> [u8; 32] {
+    let instruction_hashes: Vec<[u8; 32]> = instructions.iter().map(instruction_hash).collect();
+    let struct_hash = keccak::hashv(&[
+        &keccak::hash(EXECUTE_TYPE).to_bytes(),
+        &array_hash(&instruction_hashes),
+        &uint_word(nonce),
+        &uint_word(expiry),
+    ])
+    .to_bytes();
+    typed_data_digest(program_id, &struct_hash)
+}
+
+/// Hash of the account list of an instruction, as the Execute digest includes it
+pub fn accounts_hash(accounts: &[InnerAccountMeta]) -> [u8; 32] {
+    let account_hashes: Vec<[u8; 32]> = accounts
+        .iter()
+        .map(|meta| {
+            keccak::hashv(&[
+                &keccak::hash(ACCOUNT_META_TYPE).to_bytes(),
+                meta.pubkey.as_ref(),
+                &uint_word(meta.is_signer.into()),
+                &uint_word(meta.is_writable.into()),
+            ])
+            .to_bytes()
+        })
+        .collect();
+    array_hash(&account_hashes)
+}
+
+/// EIP-712 struct hash of an instruction
+fn instruction_hash(instruction: &InnerInstruction) -> [u8; 32] {
+    keccak::hashv(&[
+        &keccak::hash(INSTRUCTION_TYPE).to_bytes(),
+        instruction.program_id.as_ref(),
+        &accounts_hash(&instruction.accounts),
+        &keccak::hash(&instruction.data).to_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// EIP-712 encoding of an array of structs, given their hashes
+fn array_hash(hashes: &[[u8; 32]]) -> [u8; 32] {
+    keccak::hashv(&hashes.iter().map(|hash| &hash[..]).collect::<Vec<_>>()).to_bytes()
+}
+
+/// Digest signed for the message `struct_hash` in the bridge's domain (EIP-712)
+fn typed_data_digest(program_id: &Pubkey, struct_hash: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[b"\x19\x01", &domain_separator(program_id), struct_hash]).to_bytes()
+}
+
+/// Recover the Ethereum address that signed `digest`
+///
+/// The signature is `r || s || v`, with `v` 27 or 28 as wallets return it, or
+/// 0 or 1. Fails unless the signature has a low `s`.
+pub fn recover_signer(digest: &[u8; 32], signature: &[u8; 65]) -> Result<[u8; 20], ProgramError> {
+    let (r, s) = (&signature[..32], &signature[32..64]);
+    if s > &SECP256K1_HALF_ORDER[..] || s == [0u8; 32] || r == [0u8; 32] {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let recovery_id = match signature[64] {
+        0 | 27 => 0,
+        1 | 28 => 1,
+        _ => return Err(ProgramError::MissingRequiredSignature),
+    };
+
+    let public_key = secp256k1_recover(digest, recovery_id, &signature[..64])
+        .map_err(|_| ProgramError::MissingRequiredSignature)?;
+    let hash = keccak::hash(&public_key.to_bytes()).to_bytes();
+    let mut signer = [0u8; 20];
+    signer.copy_from_slice(&hash[12..]);
+    Ok(signer)
+}
+
+/// ABI encoding of an unsigned integer
+fn uint_word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sign `digest` with `secret`, returning the signature and the signer's address
+    fn sign(digest: &[u8; 32], secret: [u8; 32]) -> ([u8; 65], [u8; 20]) {
+        let secret = libsecp256k1::SecretKey::parse(&secret).unwrap();
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&libsecp256k1::Message::parse(digest), &secret);
+        let mut signed = [0u8; 65];
+        signed[..64].copy_from_slice(&signature.serialize());
+        signed[64] = 27 + recovery_id.serialize();
+
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret).serialize();
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&keccak::hash(&public_key[1..]).to_bytes()[12..]);
+        (signed, address)
+    }
+
+    #[test]
+    fn test_recover_signer() {
+        let digest = transfer_digest(&Pubkey::new_unique(), &Pubkey::new_unique(), 1_000, 0, 60);
+        let (mut signature, address) = sign(&digest, [7u8; 32]);
+        assert_eq!(recover_signer(&digest, &signature), Ok(address));
+
+        // Recovery ids without the 27 offset
+        signature[64] -= 27;
+        assert_eq!(recover_signer(&digest, &signature), Ok(address));
+
+        // Another digest recovers another address
+        let other = transfer_digest(&Pubkey::new_unique(), &Pubkey::new_unique(), 1_000, 0, 60);
+        assert_ne!(recover_signer(&other, &signature), Ok(address));
+
+        signature[64] = 2;
+        assert_eq!(
+            recover_signer(&digest, &signature),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+
+    #[test]
+    fn test_high_s_rejected() {
+        let digest = [1u8; 32];
+        let (mut signature, _) = sign(&digest, [7u8; 32]);
+        // The same signature with s replaced by n - s, which also recovers the signer
+        let order_low = 2 * u128::from_be_bytes(SECP256K1_HALF_ORDER[16..].try_into().unwrap()) + 1;
+        let order_high = u128::MAX - 1;
+        let low = u128::from_be_bytes(signature[48..64].try_into().unwrap());
+        let high = u128::from_be_bytes(signature[32..48].try_into().unwrap());
+        let (low, borrow) = order_low.overflowing_sub(low);
+        let high = order_high - high - u128::from(borrow);
+        signature[32..48].copy_from_slice(&high.to_be_bytes());
+        signature[48..64].copy_from_slice(&low.to_be_bytes());
+        signature[64] ^= 1;
+        assert_eq!(
+            recover_signer(&digest, &signature),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        assert_eq!(
+            recover_signer(&digest, &[0u8; 65]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+
+    #[test]
+    fn test_digest_covers_every_field() {
+        let program_id = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let digest = transfer_digest(&program_id, &destination, 5, 1, 100);
+        assert_eq!(digest, transfer_digest(&program_id, &destination, 5, 1, 100));
+        for other in [
+            transfer_digest(&Pubkey::new_unique(), &destination, 5, 1, 100),
+            transfer_digest(&program_id, &Pubkey::new_unique(), 5, 1, 100),
+            transfer_digest(&program_id, &destination, 6, 1, 100),
+            transfer_digest(&program_id, &destination, 5, 2, 100),
+            transfer_digest(&program_id, &destination, 5, 1, 101),
+        ] {
+            assert_ne!(digest, other);
+        }
+    }
+
+    #[test]
+    fn test_domain_covers_cluster() {
+        let program_id = Pubkey::new_unique();
+        assert_eq!(
+            domain_separator(&program_id),
+            chain_domain_separator(CHAIN_ID, &program_id)
+        );
+        assert_ne!(
+            domain_separator(&program_id),
+            chain_domain_separator(CHAIN_ID + 1, &program_id)
+        );
+        assert_eq!(parse_chain_id("16979"), 16979);
+    }
+
+    #[test]
+    fn test_token_transfer_digest() {
+        let program_id = Pubkey::new_unique();
+        let (mint, destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let digest = token_transfer_digest(&program_id, &mint, &destination, 5, 6, 1, 100);
+        for other in [
+            token_transfer_digest(&program_id, &destination, &mint, 5, 6, 1, 100),
+            token_transfer_digest(&program_id, &mint, &destination, 5, 9, 1, 100),
+            token_transfer_digest(&program_id, &mint, &destination, 5, 6, 2, 100),
+            // A lamport transfer of the same amount to the same account
+            transfer_digest(&program_id, &destination, 5, 1, 100),
+        ] {
+            assert_ne!(digest, other);
+        }
+        assert!(TOKEN_TRANSFER_TYPE.ends_with(b"amount,uint8 decimals,uint64 nonce,uint64 expiry)"));
+    }
+
+    #[test]
+    fn test_type_encodings() {
+        // Line continuations leave no whitespace between the types
+        assert!(EXECUTE_TYPE.ends_with(
+            b"expiry)AccountMeta(bytes32 pubkey,bool isSigner,bool isWritable)Instruction(bytes32 \
+              programId,AccountMeta[] accounts,bytes data)"
+        ));
+        assert!(INSTRUCTION_TYPE
+            .ends_with(b"data)AccountMeta(bytes32 pubkey,bool isSigner,bool isWritable)"));
+    }
+
+    #[test]
+    fn test_execute_digest_covers_accounts() {
+        let program_id = Pubkey::new_unique();
+        let account =
+            InnerAccountMeta { pubkey: Pubkey::new_unique(), is_signer: true, is_writable: false };
+        let instruction = InnerInstruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![account.clone()],
+            data: vec![1, 2, 3],
+        };
+        let digest = execute_digest(&program_id, std::slice::from_ref(&instruction), 0, 100);
+
+        let writable = InnerAccountMeta { is_writable: true, ..account.clone() };
+        let not_signer = InnerAccountMeta { is_signer: false, ..account.clone() };
+        for accounts in [vec![], vec![writable], vec![not_signer], vec![account.clone(), account]] {
+            assert_ne!(accounts_hash(&accounts), accounts_hash(&instruction.accounts));
+            let other = InnerInstruction { accounts, ..instruction.clone() };
+            assert_ne!(execute_digest(&program_id, &[other], 0, 100), digest);
+        }
+
+        let other_data = InnerInstruction { data: vec![1, 2], ..instruction.clone() };
+        assert_ne!(execute_digest(&program_id, &[other_data], 0, 100), digest);
+        assert_ne!(execute_digest(&program_id, &[], 0, 100), digest);
+        assert_ne!(execute_digest(&program_id, std::slice::from_ref(&instruction), 1, 100), digest);
+        assert_ne!(
+            execute_digest(&program_id, &[instruction.clone(), instruction], 0, 100),
+            digest
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! The bridge program: PDAs authorized by Ethereum signatures, the
+//! instructions they sign for, and PDAs initialized before the nonce was added.
+
+use borsh::BorshSerialize;
+use eth_bridge::{
+    derive_pda, instruction, process_instruction, signature, InnerAccountMeta, InnerInstruction,
+    PDAAccount,
+};
+use solana_program::{
+    clock::Clock, keccak, program_option::COption, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+    system_instruction,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use spl_token_2022::state::{Account as TokenAccount, AccountState, Mint};
+
+/// Secret key of the Ethereum account the tests transfer from
+const SECRET: [u8; 32] = [7; 32];
+
+/// Lamports the PDAs start with
+const BALANCE: u64 = 1_000_000_000;
+
+/// Sign `digest` with `secret`, returning the signature and the signer's address
+fn sign(digest: &[u8; 32], secret: [u8; 32]) -> ([u8; 65], [u8; 20]) {
+    let secret = libsecp256k1::SecretKey::parse(&secret).unwrap();
+    let (signature, recovery_id) =
+        libsecp256k1::sign(&libsecp256k1::Message::parse(digest), &secret);
+    let mut signed = [0u8; 65];
+    signed[..64].copy_from_slice(&signature.serialize());
+    signed[64] = 27 + recovery_id.serialize();
+
+    let public_key = libsecp256k1::PublicKey::from_secret_key(&secret).serialize();
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&keccak::hash(&public_key[1..]).to_bytes()[12..]);
+    (signed, address)
+}
+
+/// Address of the Ethereum account with `secret`
+fn eth_address(secret: [u8; 32]) -> [u8; 20] {
+    sign(&[1; 32], secret).1
+}
+
+/// Start the program with `accounts`
+async fn start(program_id: Pubkey, accounts: Vec<(Pubkey, Account)>) -> ProgramTestContext {
+    let mut program_test =
+        ProgramTest::new("eth_bridge", program_id, processor!(process_instruction));
+    for (address, account) in accounts {
+        program_test.add_account(address, account);
+    }
+    program_test.start_with_context().await
+}
+
+/// Account of `owner` holding `lamports` and `data`
+fn account_with(owner: &Pubkey, lamports: u64, data: Vec<u8>) -> Account {
+    Account {
+        lamports,
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Process `instruction`, signed by the payer, in a new block
+async fn send(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+) -> Result<(), TransactionError> {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+fn instruction_error(error: InstructionError) -> Result<(), TransactionError> {
+    Err(TransactionError::InstructionError(0, error))
+}
+
+async fn now(context: &mut ProgramTestContext) -> u64 {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp as u64
+}
+
+async fn account(context: &mut ProgramTestContext, address: Pubkey) -> Account {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Transfer of `amount` to `destination` at `nonce`, signed with `secret`
+fn transfer(
+    program_id: &Pubkey,
+    secret: [u8; 32],
+    destination: &Pubkey,
+    amount: u64,
+    nonce: u64,
+    expiry: u64,
+) -> Instruction {
+    let digest = signature::transfer_digest(program_id, destination, amount, nonce, expiry);
+    let (eth_signature, _) = sign(&digest, secret);
+    instruction::transfer(
+        program_id,
+        eth_address(SECRET),
+        destination,
+        amount,
+        nonce,
+        expiry,
+        eth_signature,
+    )
+}
+
+/// Account data of an initialized PDA
+fn pda_data(eth_address: [u8; 20], program_id: &Pubkey, nonce: u64) -> Vec<u8> {
+    let (_, bump_seed) = derive_pda(&eth_address, program_id);
+    PDAAccount {
+        eth_address,
+        is_initialized: true,
+        bump_seed,
+        nonce,
+    }
+    .try_to_vec()
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_transfer_authorization() {
+    let program_id = Pubkey::new_unique();
+    let (pda, _) = derive_pda(&eth_address(SECRET), &program_id);
+    let data = pda_data(eth_address(SECRET), &program_id, 0);
+    let accounts = vec![(pda, account_with(&program_id, BALANCE, data))];
+    let mut context = start(program_id, accounts).await;
+    let destination = Pubkey::new_unique();
+    let expiry = now(&mut context).await + 3_600;
+
+    // Signed by another Ethereum account
+    let forged = transfer(&program_id, [8; 32], &destination, 1_000_000, 0, expiry);
+    assert_eq!(
+        send(&mut context, forged).await,
+        instruction_error(InstructionError::MissingRequiredSignature)
+    );
+
+    // Signed with an expiry that has passed
+    let expired = transfer(
+        &program_id,
+        SECRET,
+        &destination,
+        1_000_000,
+        0,
+        now(&mut context).await,
+    );
+    assert_eq!(
+        send(&mut context, expired).await,
+        instruction_error(InstructionError::InvalidArgument)
+    );
+
+    // Signed at a nonce other than the PDA's
+    let ahead = transfer(&program_id, SECRET, &destination, 1_000_000, 1, expiry);
+    assert_eq!(
+        send(&mut context, ahead.clone()).await,
+        instruction_error(InstructionError::InvalidArgument)
+    );
+
+    let signed = transfer(&program_id, SECRET, &destination, 1_000_000, 0, expiry);
+    send(&mut context, signed.clone()).await.unwrap();
+    assert_eq!(account(&mut context, destination).await.lamports, 1_000_000);
+    let pda_account = account(&mut context, pda).await;
+    assert_eq!(pda_account.lamports, BALANCE - 1_000_000);
+    assert_eq!(PDAAccount::unpack(&pda_account.data).unwrap().nonce, 1);
+
+    // The signature cannot be replayed, in a new transaction or otherwise
+    assert_eq!(
+        send(&mut context, signed).await,
+        instruction_error(InstructionError::InvalidArgument)
+    );
+    send(&mut context, ahead).await.unwrap();
+    assert_eq!(account(&mut context, destination).await.lamports, 2_000_000);
+}
+
+#[tokio::test]
+async fn test_transfer_keeps_rent() {
+    let program_id = Pubkey::new_unique();
+    let (pda, _) = derive_pda(&eth_address(SECRET), &program_id);
+    let data = pda_data(eth_address(SECRET), &program_id, 0);
+    let accounts = vec![(pda, account_with(&program_id, BALANCE, data))];
+    let mut context = start(program_id, accounts).await;
+    let destination = Pubkey::new_unique();
+    let expiry = now(&mut context).await + 3_600;
+
+    // Emptying the PDA would delete it and start its nonce over
+    let whole = transfer(&program_id, SECRET, &destination, BALANCE, 0, expiry);
+    assert_eq!(
+        send(&mut context, whole).await,
+        instruction_error(InstructionError::InsufficientFunds)
+    );
+
+    let rent = Rent::default().minimum_balance(PDAAccount::LEN);
+    let available = transfer(&program_id, SECRET, &destination, BALANCE - rent, 0, expiry);
+    send(&mut context, available).await.unwrap();
+    let pda_account = account(&mut context, pda).await;
+    assert_eq!(pda_account.lamports, rent);
+    assert_eq!(PDAAccount::unpack(&pda_account.data).unwrap().nonce, 1);
+}
+
+#[tokio::test]
+async fn test_legacy_pda() {
+    let program_id = Pubkey::new_unique();
+    let other_eth_address = eth_address([8; 32]);
+    let (pda, _) = derive_pda(&eth_address(SECRET), &program_id);
+    let (other_pda, _) = derive_pda(&other_eth_address, &program_id);
+    let legacy =
+        |eth_address| pda_data(eth_address, &program_id, 0)[..PDAAccount::LEGACY_LEN].to_vec();
+    let legacy_rent = Rent::default().minimum_balance(PDAAccount::LEGACY_LEN);
+    let accounts = vec![
+        (
+            pda,
+            account_with(&program_id, BALANCE, legacy(eth_address(SECRET))),
+        ),
+        (
+            other_pda,
+            account_with(&program_id, legacy_rent, legacy(other_eth_address)),
+        ),
+    ];
+    let mut context = start(program_id, accounts).await;
+    let destination = Pubkey::new_unique();
+    let expiry = now(&mut context).await + 3_600;
+
+    // Transferring from a legacy PDA at nonce 0 grows it to hold the nonce
+    send(
+        &mut context,
+        transfer(&program_id, SECRET, &destination, 1_000_000, 0, expiry),
+    )
+    .await
+    .unwrap();
+    let pda_account = account(&mut context, pda).await;
+    assert_eq!(
+        pda_account.data,
+        pda_data(eth_address(SECRET), &program_id, 1)
+    );
+    assert_eq!(pda_account.lamports, BALANCE - 1_000_000);
+
+    // Initializing a legacy PDA grows it, the payer funding the rent
+    let payer = context.payer.pubkey();
+    let initialize = instruction::initialize_pda(&program_id, &payer, other_eth_address);
+    send(&mut context, initialize.clone()).await.unwrap();
+    let other_account = account(&mut context, other_pda).await;
+    assert_eq!(
+        other_account.data,
+        pda_data(other_eth_address, &program_id, 0)
+    );
+    assert_eq!(
+        other_account.lamports,
+        Rent::default().minimum_balance(PDAAccount::LEN)
+    );
+    send(&mut context, initialize).await.unwrap();
+    assert_eq!(
+        account(&mut context, other_pda).await.data.len(),
+        PDAAccount::LEN
+    );
+}
+
+#[tokio::test]
+async fn test_execute() {
+    let program_id = Pubkey::new_unique();
+    let (pda, _) = derive_pda(&eth_address(SECRET), &program_id);
+    let (mint, token_account, delegate) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let token_program = spl_token_2022::id();
+    let mut mint_data = vec![0; Mint::LEN];
+    Mint {
+        supply: 1_000,
+        decimals: 6,
+        is_initialized: true,
+        ..Mint::default()
+    }
+    .pack_into_slice(&mut mint_data);
+    let mut token_data = vec![0; TokenAccount::LEN];
+    TokenAccount {
+        mint,
+        owner: pda,
+        amount: 1_000,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    }
+    .pack_into_slice(&mut token_data);
+    let rent = Rent::default();
+    let data = pda_data(eth_address(SECRET), &program_id, 0);
+    let accounts = vec![
+        (pda, account_with(&program_id, BALANCE, data)),
+        (
+            mint,
+            account_with(&token_program, rent.minimum_balance(Mint::LEN), mint_data),
+        ),
+        (
+            token_account,
+            account_with(
+                &token_program,
+                rent.minimum_balance(TokenAccount::LEN),
+                token_data,
+            ),
+        ),
+    ];
+    let mut context = start(program_id, accounts).await;
+    let expiry = now(&mut context).await + 3_600;
+
+    // Execute of `instruction` at `nonce`, signed by the PDA's owner
+    let execute = |instruction: Instruction, nonce| {
+        let inner = InnerInstruction {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| InnerAccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data,
+        };
+        let digest =
+            signature::execute_digest(&program_id, std::slice::from_ref(&inner), nonce, expiry);
+        let (eth_signature, _) = sign(&digest, SECRET);
+        instruction::execute(
+            &program_id,
+            eth_address(SECRET),
+            vec![inner],
+            nonce,
+            expiry,
+            eth_signature,
+        )
+    };
+
+    // The PDA signs for the token account it owns
+    let approve = spl_token_2022::instruction::approve(
+        &token_program,
+        &token_account,
+        &delegate,
+        &pda,
+        &[],
+        400,
+    )
+    .unwrap();
+    send(&mut context, execute(approve, 0)).await.unwrap();
+    let token = TokenAccount::unpack(&account(&mut context, token_account).await.data).unwrap();
+    assert_eq!(
+        (token.delegate, token.delegated_amount),
+        (COption::Some(delegate), 400)
+    );
+
+    // The system program does not transfer from an account holding data
+    let pay = system_instruction::transfer(&pda, &delegate, 1_000_000);
+    assert_eq!(
+        send(&mut context, execute(pay, 1)).await,
+        instruction_error(InstructionError::InvalidArgument)
+    );
+}
@@ -0,0 +1,601 @@
+//! # Ethereum Block Model
+//!
+//! Presents confirmed Solana blocks as Ethereum blocks:
+//!
+//! - The block number is the slot and the timestamp the block time
+//! - The block hash is the slot as a big-endian `u64` followed by the first
+//!   24 bytes of the Solana blockhash, so a hash can be mapped back to its slot
+//!   without an index; the parent hash is built the same way from the parent
+//!   slot and previous blockhash
+//! - The transactions are the `ExecuteSignedTransaction` instructions sent to
+//!   the bsc-evm program, in block order. An instruction of a failed Solana
+//!   transaction, or whose Ethereum transaction reverted, is included with
+//!   status 0
+//!
+//! Receipts are built from the transaction metadata: the gas used and the EVM
+//! logs come from the program's log messages, see [`bsc_evm::events`].
+
+use {
+    base64::{prelude::BASE64_STANDARD, Engine},
+    bsc_evm::{
+        events::{decode_log, decode_transaction_status},
+        evm_interpreter::{create_address, Log},
+        transaction::recover_signer,
+        EvmInstruction,
+    },
+    bsc_evm_rlp::{keccak256, SignedTransaction, TransactionType},
+    serde_json::{json, Value},
+    solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature},
+    solana_transaction_status::{EncodedTransactionWithStatusMeta, UiConfirmedBlock},
+    std::str::FromStr,
+};
+
+/// Gas limit of every block
+pub const BLOCK_GAS_LIMIT: u64 = bsc_evm::block_context::BLOCK_GAS_LIMIT;
+
+/// Keccak-256 of the RLP encoding of an empty uncle list
+const EMPTY_UNCLES_HASH: &str =
+    "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347";
+
+/// Ethereum hash of the block at `slot` with Solana blockhash `blockhash`
+pub fn block_hash(slot: u64, blockhash: &Hash) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash[..8].copy_from_slice(&slot.to_be_bytes());
+    hash[8..].copy_from_slice(&blockhash.as_ref()[..24]);
+    hash
+}
+
+/// Slot of the block with Ethereum hash `hash`
+pub fn block_hash_slot(hash: &[u8; 32]) -> u64 {
+    u64::from_be_bytes(hash[..8].try_into().unwrap())
+}
+
+/// An Ethereum transaction relayed in a Solana transaction
+#[derive(Debug, Clone)]
+pub struct BlockTransaction {
+    pub hash: [u8; 32],
+    /// Signature of the Solana transaction carrying it
+    pub signature: Signature,
+    pub from: [u8; 20],
+    pub transaction: SignedTransaction,
+    pub success: bool,
+    /// Gas used after refunds
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+}
+
+impl BlockTransaction {
+    /// Address of the contract the transaction created, if it is a successful creation
+    pub fn contract_address(&self) -> Option<[u8; 20]> {
+        match self.transaction.to {
+            None if self.success => Some(create_address(&self.from, self.transaction.nonce)),
+            _ => None,
+        }
+    }
+}
+
+/// A confirmed Solana block seen as an Ethereum block
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub number: u64,
+    pub hash: [u8; 32],
+    pub parent_hash: [u8; 32],
+    pub timestamp: u64,
+    /// Base fee the transactions paid, in wei
+    pub base_fee: u128,
+    pub transactions: Vec<BlockTransaction>,
+}
+
+impl Block {
+    /// Build the block at `slot` from its `getBlock` response
+    ///
+    /// The block must have been fetched with full, base64 encoded transactions.
+    pub fn from_confirmed_block(
+        slot: u64,
+        block: &UiConfirmedBlock,
+        evm_program_id: &Pubkey,
+        base_fee: u128,
+    ) -> Option<Self> {
+        let blockhash = Hash::from_str(&block.blockhash).ok()?;
+        let previous_blockhash = Hash::from_str(&block.previous_blockhash).ok()?;
+        let transactions = block
+            .transactions
+            .iter()
+            .flatten()
+            .flat_map(|transaction| evm_transactions(transaction, evm_program_id))
+            .collect();
+
+        Some(Self {
+            number: slot,
+            hash: block_hash(slot, &blockhash),
+            parent_hash: block_hash(block.parent_slot, &previous_blockhash),
+            timestamp: block.block_time.unwrap_or_default().max(0) as u64,
+            base_fee,
+            transactions,
+        })
+    }
+
+    /// Gas used by all transactions of the block
+    pub fn gas_used(&self) -> u64 {
+        self.transactions.iter().map(|tx| tx.gas_used).sum()
+    }
+
+    /// Position of the transaction `hash` carried by the Solana transaction `signature`
+    pub fn position(&self, hash: &[u8; 32], signature: &Signature) -> Option<usize> {
+        self.transactions
+            .iter()
+            .position(|tx| tx.hash == *hash && tx.signature == *signature)
+    }
+
+    /// `eth_getBlockBy*` object, with full transaction objects if `full`
+    pub fn to_json(&self, full: bool) -> Value {
+        let transactions: Vec<Value> = (0..self.transactions.len())
+            .map(|index| {
+                if full {
+                    self.transaction_json(index)
+                } else {
+                    json!(data(&self.transactions[index].hash))
+                }
+            })
+            .collect();
+
+        let mut block = self.header_json();
+        block["totalDifficulty"] = json!("0x0");
+        block["size"] = json!("0x0");
+        block["transactions"] = json!(transactions);
+        block["uncles"] = json!([]);
+        block
+    }
+
+    /// Header object, as sent to `newHeads` subscribers
+    pub fn header_json(&self) -> Value {
+        let mut bloom = [0u8; 256];
+        for tx in &self.transactions {
+            accrue_logs_bloom(&mut bloom, &tx.logs);
+        }
+
+        json!({
+            "number": quantity(self.number as u128),
+            "hash": data(&self.hash),
+            "parentHash": data(&self.parent_hash),
+            "nonce": "0x0000000000000000",
+            "mixHash": data(&[0u8; 32]),
+            "sha3Uncles": EMPTY_UNCLES_HASH,
+            "logsBloom": data(&bloom),
+            "transactionsRoot": data(&[0u8; 32]),
+            "stateRoot": data(&[0u8; 32]),
+            "receiptsRoot": data(&[0u8; 32]),
+            "miner": data(&[0u8; 20]),
+            "difficulty": "0x0",
+            "extraData": "0x",
+            "gasLimit": quantity(BLOCK_GAS_LIMIT as u128),
+            "gasUsed": quantity(self.gas_used() as u128),
+            "timestamp": quantity(self.timestamp as u128),
+            "baseFeePerGas": quantity(self.base_fee),
+        })
+    }
+
+    /// `eth_getTransactionByHash` object of the transaction at `index`
+    pub fn transaction_json(&self, index: usize) -> Value {
+        let BlockTransaction {
+            hash,
+            from,
+            transaction: tx,
+            ..
+        } = &self.transactions[index];
+
+        let mut object = json!({
+            "blockHash": data(&self.hash),
+            "blockNumber": quantity(self.number as u128),
+            "transactionIndex": quantity(index as u128),
+            "hash": data(hash),
+            "type": quantity(tx.tx_type.id() as u128),
+            "nonce": quantity(tx.nonce as u128),
+            "from": data(from),
+            "to": tx.to.map(|to| data(&to)),
+            "value": quantity(tx.value),
+            "gas": quantity(tx.gas_limit as u128),
+            "gasPrice": quantity(self.effective_gas_price(tx)),
+            "input": data(&tx.data),
+            "v": quantity(tx.v() as u128),
+            "r": quantity_bytes(&tx.r),
+            "s": quantity_bytes(&tx.s),
+        });
+        if let Some(chain_id) = tx.chain_id {
+            object["chainId"] = json!(quantity(chain_id as u128));
+        }
+        if tx.tx_type != TransactionType::Legacy {
+            object["yParity"] = json!(quantity(tx.odd_y_parity as u128));
+            object["accessList"] = tx
+                .access_list
+                .iter()
+                .map(|(address, keys)| {
+                    json!({
+                        "address": data(address),
+                        "storageKeys": keys.iter().map(|key| data(key)).collect::<Vec<_>>(),
+                    })
+                })
+                .collect();
+        }
+        if tx.tx_type == TransactionType::DynamicFee {
+            object["maxFeePerGas"] = json!(quantity(tx.max_fee_per_gas));
+            object["maxPriorityFeePerGas"] = json!(quantity(tx.max_priority_fee_per_gas));
+        }
+        object
+    }
+
+    /// `eth_getTransactionReceipt` object of the transaction at `index`
+    pub fn receipt_json(&self, index: usize) -> Value {
+        let tx = &self.transactions[index];
+        let first_log_index: usize = self.transactions[..index]
+            .iter()
+            .map(|tx| tx.logs.len())
+            .sum();
+        let cumulative_gas_used: u64 = self.transactions[..=index]
+            .iter()
+            .map(|tx| tx.gas_used)
+            .sum();
+        let mut bloom = [0u8; 256];
+        accrue_logs_bloom(&mut bloom, &tx.logs);
+
+        json!({
+            "transactionHash": data(&tx.hash),
+            "transactionIndex": quantity(index as u128),
+            "blockHash": data(&self.hash),
+            "blockNumber": quantity(self.number as u128),
+            "from": data(&tx.from),
+            "to": tx.transaction.to.map(|to| data(&to)),
+            "type": quantity(tx.transaction.tx_type.id() as u128),
+            "status": quantity(tx.success as u128),
+            "gasUsed": quantity(tx.gas_used as u128),
+            "cumulativeGasUsed": quantity(cumulative_gas_used as u128),
+            "effectiveGasPrice": quantity(self.effective_gas_price(&tx.transaction)),
+            "contractAddress": tx.contract_address().map(|address| data(&address)),
+            "logs": tx
+                .logs
+                .iter()
+                .enumerate()
+                .map(|(offset, log)| self.log_json(index, first_log_index + offset, log))
+                .collect::<Vec<_>>(),
+            "logsBloom": data(&bloom),
+        })
+    }
+
+    /// Log objects of every log in the block `matches` accepts, in order
+    pub fn matching_logs<F>(&self, matches: F) -> Vec<Value>
+    where
+        F: Fn(&Log) -> bool,
+    {
+        let mut logs = Vec::new();
+        let mut log_index = 0;
+        for (index, tx) in self.transactions.iter().enumerate() {
+            for log in &tx.logs {
+                if matches(log) {
+                    logs.push(self.log_json(index, log_index, log));
+                }
+                log_index += 1;
+            }
+        }
+        logs
+    }
+
+    /// Log object of `log`, emitted by the transaction at `index`
+    fn log_json(&self, index: usize, log_index: usize, log: &Log) -> Value {
+        json!({
+            "address": data(&log.address),
+            "topics": log.topics.iter().map(|topic| data(topic)).collect::<Vec<_>>(),
+            "data": data(&log.data),
+            "blockNumber": quantity(self.number as u128),
+            "blockHash": data(&self.hash),
+            "transactionHash": data(&self.transactions[index].hash),
+            "transactionIndex": quantity(index as u128),
+            "logIndex": quantity(log_index as u128),
+            "removed": false,
+        })
+    }
+
+    fn effective_gas_price(&self, tx: &SignedTransaction) -> u128 {
+        tx.effective_gas_price(self.base_fee)
+            .unwrap_or(tx.max_fee_per_gas)
+    }
+}
+
+/// Ethereum transactions relayed in one Solana transaction
+fn evm_transactions(
+    transaction: &EncodedTransactionWithStatusMeta,
+    evm_program_id: &Pubkey,
+) -> Vec<BlockTransaction> {
+    let (Some(decoded), Some(meta)) = (transaction.transaction.decode(), &transaction.meta) else {
+        return Vec::new();
+    };
+    let Some(&signature) = decoded.signatures.first() else {
+        return Vec::new();
+    };
+    let executed = meta.err.is_none();
+    let log_messages: Option<&Vec<String>> = meta.log_messages.as_ref().into();
+    let invocations = invocation_logs(
+        log_messages.map_or(&[][..], |logs| logs),
+        &evm_program_id.to_string(),
+    );
+
+    let account_keys = decoded.message.static_account_keys();
+    decoded
+        .message
+        .instructions()
+        .iter()
+        .filter(|instruction| {
+            account_keys.get(instruction.program_id_index as usize) == Some(evm_program_id)
+        })
+        .zip(
+            invocations
+                .into_iter()
+                .map(Some)
+                .chain(std::iter::repeat(None)),
+        )
+        .filter_map(|(instruction, logs)| {
+            let EvmInstruction::ExecuteSignedTransaction { raw } =
+                EvmInstruction::unpack(&instruction.data).ok()?
+            else {
+                return None;
+            };
+            let tx = SignedTransaction::decode(&raw).ok()?;
+            // The program checked the chain id when it executed the transaction
+            let from = recover_signer(&tx).ok()?;
+            let logs = logs.unwrap_or_default();
+            // A reverted transaction still pays for the gas it used
+            let success = executed && transaction_status(&logs) != Some(false);
+
+            Some(BlockTransaction {
+                hash: tx.hash(),
+                signature,
+                from,
+                transaction: tx,
+                success,
+                gas_used: if executed { gas_used(&logs) } else { 0 },
+                logs: if success { evm_logs(&logs) } else { Vec::new() },
+            })
+        })
+        .collect()
+}
+
+/// Log messages of each top-level invocation of `program_id`, excluding those of its CPIs
+fn invocation_logs<'a>(logs: &'a [String], program_id: &str) -> Vec<Vec<&'a str>> {
+    let mut invocations: Vec<Vec<&str>> = Vec::new();
+    let mut stack: Vec<&str> = Vec::new();
+
+    for log in logs {
+        if let Some(event) = log.strip_prefix("Program ") {
+            if let Some((program, _)) = event.split_once(" invoke [") {
+                if stack.is_empty() && program == program_id {
+                    invocations.push(Vec::new());
+                }
+                stack.push(program);
+                continue;
+            }
+            let returned = event
+                .strip_suffix(" success")
+                .or_else(|| event.split_once(" failed: ").map(|(program, _)| program));
+            if returned.is_some() && returned == stack.last().copied() {
+                stack.pop();
+                continue;
+            }
+        }
+        if stack.len() == 1 && stack[0] == program_id {
+            if let Some(invocation) = invocations.last_mut() {
+                invocation.push(log);
+            }
+        }
+    }
+    invocations
+}
+
+/// Gas used after refunds, from the `Transaction gas` log message
+fn gas_used(logs: &[&str]) -> u64 {
+    logs.iter()
+        .find_map(|log| {
+            let gas = log.strip_prefix("Program log: Transaction gas: used=")?;
+            let (used, refund) = gas.split_once(", refund=")?;
+            let used: u64 = used.parse().ok()?;
+            Some(used.saturating_sub(refund.parse().ok()?))
+        })
+        .unwrap_or_default()
+}
+
+/// Status the program logged for the transaction, if it logged one
+fn transaction_status(logs: &[&str]) -> Option<bool> {
+    logs.iter()
+        .filter_map(|log| program_data(log))
+        .find_map(|fields| decode_transaction_status(&fields))
+}
+
+/// EVM logs emitted as program data
+fn evm_logs(logs: &[&str]) -> Vec<Log> {
+    logs.iter()
+        .filter_map(|log| program_data(log))
+        .filter_map(|fields| decode_log(&fields))
+        .collect()
+}
+
+/// Decoded fields of a `Program data:` log message
+pub fn program_data(log: &str) -> Option<Vec<Vec<u8>>> {
+    log.strip_prefix("Program data: ")?
+        .split(' ')
+        .map(|field| BASE64_STANDARD.decode(field).ok())
+        .collect()
+}
+
+/// Add the address and topics of `logs` to a 2048-bit logs bloom filter
+pub fn accrue_logs_bloom(bloom: &mut [u8; 256], logs: &[Log]) {
+    for log in logs {
+        accrue_bloom(bloom, &log.address);
+        for topic in &log.topics {
+            accrue_bloom(bloom, topic);
+        }
+    }
+}
+
+/// Set the three bits the Keccak-256 hash of `input` selects
+fn accrue_bloom(bloom: &mut [u8; 256], input: &[u8]) {
+    let hash = keccak256(input);
+    for pair in hash[..6].chunks_exact(2) {
+        let bit = (u16::from_be_bytes([pair[0], pair[1]]) & 2047) as usize;
+        bloom[255 - bit / 8] |= 1 << (bit % 8);
+    }
+}
+
+/// Hex encoding of a quantity
+pub fn quantity(value: u128) -> String {
+    format!("0x{:x}", value)
+}
+
+/// Hex encoding of a big-endian quantity without leading zeros
+pub fn quantity_bytes(value: &[u8]) -> String {
+    let digits = hex::encode(value);
+    match digits.trim_start_matches('0') {
+        "" => "0x0".to_string(),
+        digits => format!("0x{}", digits),
+    }
+}
+
+/// Hex encoding of unformatted data
+pub fn data(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_hash_maps_back_to_slot() {
+        let blockhash = Hash::new_unique();
+        let hash = block_hash(123_456, &blockhash);
+        assert_eq!(block_hash_slot(&hash), 123_456);
+        assert_eq!(&hash[8..], &blockhash.as_ref()[..24]);
+    }
+
+    #[test]
+    fn test_invocation_logs() {
+        let program = Pubkey::new_unique().to_string();
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", program),
+            "Program log: Transaction gas: used=30000, refund=4800".to_string(),
+            "Program 11111111111111111111111111111111 invoke [2]".to_string(),
+            "Program data: AAAA".to_string(),
+            "Program 11111111111111111111111111111111 success".to_string(),
+            "Program data: RVZNX0xPRw==".to_string(),
+            format!("Program {} success", program),
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]".to_string(),
+            "Program log: other".to_string(),
+            "Program ComputeBudget111111111111111111111111111111 success".to_string(),
+            format!("Program {} invoke [1]", program),
+            format!("Program {} failed: custom program error: 0x1", program),
+        ]
+        .into_iter()
+        .collect();
+
+        let invocations = invocation_logs(&logs, &program);
+        assert_eq!(
+            invocations,
+            vec![
+                vec![
+                    "Program log: Transaction gas: used=30000, refund=4800",
+                    "Program data: RVZNX0xPRw==",
+                ],
+                vec![],
+            ]
+        );
+        assert_eq!(gas_used(&invocations[0]), 25_200);
+        assert_eq!(gas_used(&invocations[1]), 0);
+    }
+
+    #[test]
+    fn test_evm_logs() {
+        let address = [0x11u8; 20];
+        let topic = [0x22u8; 32];
+        let line = format!(
+            "Program data: {} {} {} {}",
+            BASE64_STANDARD.encode(bsc_evm::events::LOG_TAG),
+            BASE64_STANDARD.encode(address),
+            BASE64_STANDARD.encode(topic),
+            BASE64_STANDARD.encode([1, 2, 3]),
+        );
+        let logs = evm_logs(&[line.as_str(), "Program data: RVZNX1JFU1VMVA== AA=="]);
+        assert_eq!(
+            logs,
+            vec![Log {
+                address,
+                topics: vec![topic],
+                data: vec![1, 2, 3],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_transaction_status() {
+        let status = |success: u8| {
+            format!(
+                "Program data: {} {}",
+                BASE64_STANDARD.encode(bsc_evm::events::STATUS_TAG),
+                BASE64_STANDARD.encode([success]),
+            )
+        };
+        let (reverted, succeeded) = (status(0), status(1));
+        assert_eq!(transaction_status(&[reverted.as_str()]), Some(false));
+        assert_eq!(transaction_status(&["Program log: other", succeeded.as_str()]), Some(true));
+        assert_eq!(transaction_status(&["Program data: RVZNX1RY AA=="]), None);
+    }
+
+    #[test]
+    fn test_logs_bloom() {
+        // A log of the zero address with no topics sets the bits of its address only
+        let mut bloom = [0u8; 256];
+        accrue_logs_bloom(
+            &mut bloom,
+            &[Log {
+                address: [0u8; 20],
+                topics: Vec::new(),
+                data: Vec::new(),
+            }],
+        );
+        let bits = bloom.iter().map(|byte| byte.count_ones()).sum::<u32>();
+        assert!((1..=3).contains(&bits));
+
+        let hash = keccak256(&[0u8; 20]);
+        let bit = (u16::from_be_bytes([hash[0], hash[1]]) & 2047) as usize;
+        assert_ne!(bloom[255 - bit / 8] & (1 << (bit % 8)), 0);
+    }
+
+    #[test]
+    fn test_header_is_block_without_body() {
+        let block = Block {
+            number: 5,
+            hash: [0x11; 32],
+            parent_hash: [0x22; 32],
+            timestamp: 1_700_000_000,
+            base_fee: 7,
+            transactions: Vec::new(),
+        };
+        let header = block.header_json();
+        assert_eq!(header["number"], "0x5");
+        assert_eq!(header["baseFeePerGas"], "0x7");
+        assert!(header.get("transactions").is_none());
+
+        let mut full = block.to_json(true);
+        assert_eq!(full["transactions"], json!([]));
+        for key in ["totalDifficulty", "size", "transactions", "uncles"] {
+            full.as_object_mut().unwrap().remove(key);
+        }
+        assert_eq!(full, header);
+    }
+
+    #[test]
+    fn test_quantities() {
+        assert_eq!(quantity(0), "0x0");
+        assert_eq!(quantity(21_000), "0x5208");
+        assert_eq!(quantity_bytes(&[0u8; 32]), "0x0");
+        assert_eq!(quantity_bytes(&[0, 0, 1, 0]), "0x100");
+        assert_eq!(data(&[]), "0x");
+        assert_eq!(data(&[0, 1]), "0x0001");
+    }
+}
@@ -0,0 +1,284 @@
+//! # Log Filters
+//!
+//! Address and topic filters for `eth_getLogs`, and the filters installed
+//! with `eth_newFilter` and `eth_newBlockFilter` that clients poll with
+//! `eth_getFilterChanges`. An installed filter remembers the next block it
+//! has not reported yet, so every poll returns only what is new; filters
+//! that are not polled for [`FILTER_TIMEOUT`] are removed.
+
+use {
+    crate::{parse_eth_address, parse_hash, RpcError},
+    bsc_evm::evm_interpreter::Log,
+    serde_json::Value,
+    std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    },
+};
+
+/// How long an installed filter lives without being polled
+pub const FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Most topic positions a filter can constrain (LOG4)
+const MAX_TOPICS: usize = 4;
+
+/// Criteria of `eth_getLogs` and `eth_newFilter`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// First block to search, a number or tag
+    pub from_block: Option<String>,
+    /// Last block to search, a number or tag
+    pub to_block: Option<String>,
+    /// Search only this block (EIP-234)
+    pub block_hash: Option<[u8; 32]>,
+    /// Emitting contracts, any if empty
+    pub addresses: Vec<[u8; 20]>,
+    /// Accepted topics by position, `None` accepts any
+    pub topics: Vec<Option<Vec<[u8; 32]>>>,
+}
+
+impl LogFilter {
+    /// Parse a filter object
+    pub fn parse(filter_object: &Value) -> Result<Self, RpcError> {
+        if !filter_object.is_object() {
+            return Err(RpcError::InvalidParams);
+        }
+        let block_tag = |key: &str| match &filter_object[key] {
+            Value::Null => Ok(None),
+            Value::String(tag) => Ok(Some(tag.clone())),
+            _ => Err(RpcError::InvalidParams),
+        };
+        let from_block = block_tag("fromBlock")?;
+        let to_block = block_tag("toBlock")?;
+        let block_hash = filter_object["blockHash"]
+            .as_str()
+            .map(parse_hash)
+            .transpose()?;
+        if block_hash.is_some() && (from_block.is_some() || to_block.is_some()) {
+            return Err(RpcError::InvalidParams);
+        }
+
+        let addresses = match &filter_object["address"] {
+            Value::Null => Vec::new(),
+            Value::String(address) => vec![parse_eth_address(address)?],
+            Value::Array(addresses) => addresses
+                .iter()
+                .map(|address| {
+                    address
+                        .as_str()
+                        .map_or(Err(RpcError::InvalidParams), parse_eth_address)
+                })
+                .collect::<Result<_, _>>()?,
+            _ => return Err(RpcError::InvalidParams),
+        };
+
+        let topics = match &filter_object["topics"] {
+            Value::Null => Vec::new(),
+            Value::Array(positions) if positions.len() <= MAX_TOPICS => positions
+                .iter()
+                .map(parse_topic_position)
+                .collect::<Result<_, _>>()?,
+            _ => return Err(RpcError::InvalidParams),
+        };
+
+        Ok(Self {
+            from_block,
+            to_block,
+            block_hash,
+            addresses,
+            topics,
+        })
+    }
+
+    /// Whether `log` satisfies the address and topic criteria
+    pub fn matches(&self, log: &Log) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        self.topics
+            .iter()
+            .enumerate()
+            .all(|(position, accepted)| match accepted {
+                None => true,
+                Some(accepted) => log
+                    .topics
+                    .get(position)
+                    .is_some_and(|topic| accepted.contains(topic)),
+            })
+    }
+}
+
+/// Parse the accepted topics at one position: null, a topic or a list of topics
+fn parse_topic_position(position: &Value) -> Result<Option<Vec<[u8; 32]>>, RpcError> {
+    match position {
+        Value::Null => Ok(None),
+        Value::String(topic) => Ok(Some(vec![parse_hash(topic)?])),
+        Value::Array(topics) => {
+            let topics = topics
+                .iter()
+                .map(|topic| {
+                    topic
+                        .as_str()
+                        .map_or(Err(RpcError::InvalidParams), parse_hash)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            // An empty list places no constraint, like null
+            Ok((!topics.is_empty()).then_some(topics))
+        }
+        _ => Err(RpcError::InvalidParams),
+    }
+}
+
+/// What an installed filter reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterKind {
+    /// Logs matching the filter
+    Logs(LogFilter),
+    /// Hashes of new blocks
+    Blocks,
+}
+
+/// A filter installed by a client
+#[derive(Debug, Clone)]
+pub struct InstalledFilter {
+    pub kind: FilterKind,
+    /// First block not reported yet
+    pub next_block: u64,
+    last_poll: Instant,
+}
+
+/// Filters installed on this server
+#[derive(Debug, Default)]
+pub struct Filters {
+    next_id: u64,
+    installed: HashMap<u64, InstalledFilter>,
+}
+
+impl Filters {
+    /// Install a filter reporting changes from `next_block` on, and return its id
+    pub fn install(&mut self, kind: FilterKind, next_block: u64) -> u64 {
+        self.remove_expired();
+        self.next_id += 1;
+        self.installed.insert(
+            self.next_id,
+            InstalledFilter {
+                kind,
+                next_block,
+                last_poll: Instant::now(),
+            },
+        );
+        self.next_id
+    }
+
+    /// Filter `id`, counting as a poll
+    pub fn poll(&mut self, id: u64) -> Option<InstalledFilter> {
+        self.remove_expired();
+        let filter = self.installed.get_mut(&id)?;
+        filter.last_poll = Instant::now();
+        Some(filter.clone())
+    }
+
+    /// Record that filter `id` reported everything before `next_block`
+    pub fn advance(&mut self, id: u64, next_block: u64) {
+        if let Some(filter) = self.installed.get_mut(&id) {
+            filter.next_block = filter.next_block.max(next_block);
+        }
+    }
+
+    /// Remove filter `id`, returning whether it was installed
+    pub fn uninstall(&mut self, id: u64) -> bool {
+        self.installed.remove(&id).is_some()
+    }
+
+    fn remove_expired(&mut self) {
+        self.installed
+            .retain(|_, filter| filter.last_poll.elapsed() < FILTER_TIMEOUT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    fn log(address: u8, topics: &[u8]) -> Log {
+        Log {
+            address: [address; 20],
+            topics: topics.iter().map(|topic| [*topic; 32]).collect(),
+            data: Vec::new(),
+        }
+    }
+
+    fn hash(byte: u8) -> String {
+        format!("0x{}", hex::encode([byte; 32]))
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let filter = LogFilter::parse(&json!({
+            "fromBlock": "0x10",
+            "toBlock": "latest",
+            "address": ["0x1111111111111111111111111111111111111111"],
+            "topics": [hash(0xaa), null, [hash(0xbb), hash(0xcc)], []],
+        }))
+        .unwrap();
+        assert_eq!(
+            filter,
+            LogFilter {
+                from_block: Some("0x10".to_string()),
+                to_block: Some("latest".to_string()),
+                block_hash: None,
+                addresses: vec![[0x11; 20]],
+                topics: vec![
+                    Some(vec![[0xaa; 32]]),
+                    None,
+                    Some(vec![[0xbb; 32], [0xcc; 32]]),
+                    None,
+                ],
+            }
+        );
+
+        assert_eq!(LogFilter::parse(&json!({})).unwrap(), LogFilter::default());
+        assert!(LogFilter::parse(&json!({ "blockHash": hash(1), "fromBlock": "0x1" })).is_err());
+        assert!(LogFilter::parse(&json!({ "topics": [null, null, null, null, null] })).is_err());
+        assert!(LogFilter::parse(&json!({ "address": "0x1234" })).is_err());
+        assert!(LogFilter::parse(&json!({ "topics": ["0x1234"] })).is_err());
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let filter = LogFilter {
+            addresses: vec![[0x11; 20], [0x22; 20]],
+            topics: vec![
+                Some(vec![[0xaa; 32]]),
+                None,
+                Some(vec![[0xbb; 32], [0xcc; 32]]),
+            ],
+            ..LogFilter::default()
+        };
+        assert!(filter.matches(&log(0x11, &[0xaa, 0x00, 0xbb])));
+        assert!(filter.matches(&log(0x22, &[0xaa, 0x01, 0xcc, 0x02])));
+        assert!(!filter.matches(&log(0x33, &[0xaa, 0x00, 0xbb])));
+        assert!(!filter.matches(&log(0x11, &[0xab, 0x00, 0xbb])));
+        assert!(!filter.matches(&log(0x11, &[0xaa, 0x00, 0xdd])));
+        // Constrained positions must exist
+        assert!(!filter.matches(&log(0x11, &[0xaa, 0x00])));
+
+        assert!(LogFilter::default().matches(&log(0x44, &[])));
+    }
+
+    #[test]
+    fn test_installed_filters() {
+        let mut filters = Filters::default();
+        let blocks = filters.install(FilterKind::Blocks, 100);
+        let logs = filters.install(FilterKind::Logs(LogFilter::default()), 100);
+        assert_ne!(blocks, logs);
+
+        filters.advance(blocks, 150);
+        assert_eq!(filters.poll(blocks).unwrap().next_block, 150);
+        assert_eq!(filters.poll(logs).unwrap().next_block, 100);
+
+        assert!(filters.uninstall(blocks));
+        assert!(!filters.uninstall(blocks));
+        assert!(filters.poll(blocks).is_none());
+    }
+}
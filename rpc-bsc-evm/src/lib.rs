@@ -6,49 +6,232 @@
 //! Supported RPC Methods:
 //! - eth_blockNumber
 //! - eth_getBalance
+//! - eth_getTransactionCount
 //! - eth_getStorageAt
 //! - eth_getCode
 //! - eth_sendRawTransaction
 //! - eth_call
 //! - eth_estimateGas
+//! - eth_getBlockByNumber
+//! - eth_getBlockByHash
+//! - eth_getTransactionByHash
+//! - eth_getTransactionReceipt
+//! - eth_getLogs
+//! - eth_newFilter, eth_newBlockFilter, eth_getFilterChanges, eth_getFilterLogs
+//!   and eth_uninstallFilter
 //! - web3_clientVersion
 //! - net_version
+//! - eth_chainId
+//! - bssc_chainConfig
+//! - debug_traceTransaction
+//! - debug_traceCall
+//! - bssc_getTokenBalances
+//!
+//! `eth_getBalance`, `eth_getTransactionCount`, `eth_getStorageAt` and
+//! `eth_getCode` read the bsc-evm state accounts directly, at the commitment
+//! level the block tag maps onto: `pending` reads processed state, `latest`
+//! and `safe` confirmed state and `finalized` finalized state.
+//!
+//! The chain id, network name and native currency are read from the bsc-evm
+//! state account, see [`bsc_evm::chain_config`], so `eth_chainId`,
+//! `net_version` and the EIP-155 check of relayed transactions always agree
+//! with the program.
+//!
+//! `eth_call` and `eth_estimateGas` run a bsc-evm `SimulateCall` instruction
+//! through the cluster's `simulateTransaction` method, so they see exactly
+//! the state and gas accounting an executed transaction would.
+//!
+//! `eth_sendRawTransaction` relays signed transactions in an
+//! `ExecuteSignedTransaction` instruction sent by the relayer keypair, which is
+//! repaid the gas fee from the sender's EVM balance, and records the Solana
+//! signature of each in a [`TransactionIndex`]. Blocks,
+//! transactions and receipts are built from confirmed Solana blocks, see
+//! [`blocks`], and so are the logs returned by `eth_getLogs` and filters, see
+//! [`filters`].
+//!
+//! `debug_traceTransaction` and `debug_traceCall` replay execution in this
+//! server with its own copy of the bsc-evm interpreter, see [`trace`].
+//! `debug_traceCall` replays against the state a block tag names, like
+//! `eth_call`. A transaction is replayed against the state its block started
+//! from, after the transactions that preceded it in the block, which takes a
+//! [`SlotState`] reading a validator's recent banks.
+//!
+//! `bssc_getTokenBalances` lists the SPL and Token-2022 tokens an address
+//! holds through its eth-bridge PDA as ERC-20 style balances, see [`tokens`].
+//! It is only served once the eth-bridge program is configured. The token
+//! addresses it returns are not contracts `eth_call` can query.
+//!
+//! `eth_subscribe` and `eth_unsubscribe` are served over WebSocket by the
+//! [`pubsub`] service, which answers every other method like this server.
+//! The [`service`] module runs both endpoints, standalone or next to a
+//! validator.
+
+pub mod blocks;
+pub mod filters;
+pub mod pubsub;
+pub mod service;
+pub mod tokens;
+pub mod trace;
+pub mod transaction_index;
 
 use {
-    serde::{Deserialize, Serialize},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    borsh::BorshDeserialize,
+    bsc_evm::{
+        account_state::{derive_account_address, derive_code_address, EvmStateAccount, StateKey},
+        block_context::BlockContext,
+        chain_config::ChainConfig,
+        fees::gas_to_compute_units,
+        transaction::recover_sender,
+        EvmExecutor, EvmState, EvmTransaction, GasConfig,
+    },
+    bsc_evm_rlp::SignedTransaction,
     serde_json::{json, Value},
+    blocks::{block_hash_slot, quantity, Block, BlockTransaction},
+    filters::{FilterKind, Filters, LogFilter},
     solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        client_error::{Error as ClientError, ErrorKind as ClientErrorKind},
+        config::{
+            RpcBlockConfig, RpcSendTransactionConfig, RpcSimulateTransactionConfig,
+            RpcTransactionConfig,
+        },
+        request::{self, TokenAccountsFilter},
+    },
     solana_sdk::{
+        account::{from_account, Account},
+        account_info::AccountInfo,
+        clock::Clock,
+        commitment_config::CommitmentConfig,
+        compute_budget::ComputeBudgetInstruction,
+        instruction::{Instruction, InstructionError},
+        program_error::ProgramError,
         pubkey::Pubkey,
-        signature::{Keypair, Signer},
-        system_instruction,
+        signature::{Keypair, Signature, Signer},
+        sysvar,
+        transaction::{Transaction, TransactionError},
+    },
+    solana_transaction_status::{TransactionDetails, UiTransactionEncoding},
+    std::{
+        str::FromStr,
+        sync::{Arc, Mutex, OnceLock},
     },
-    std::collections::HashMap,
+    tokens::{TokenBalance, TOKEN_PROGRAM_IDS},
+    trace::TraceOptions,
 };
 
+pub use {trace::SlotState, transaction_index::TransactionIndex};
+
+/// Gas limit of calls that do not set one, and the most gas an estimate returns
+const CALL_GAS_CAP: u64 = 50_000_000;
+
+/// Least gas any transaction needs
+const TX_BASE_GAS: u64 = 21_000;
+
+/// Most compute units a Solana transaction can request
+const MAX_COMPUTE_UNITS: u64 = 1_400_000;
+
+/// Times a simulation is retried with the state accounts it reported missing
+const MAX_SIMULATION_ROUNDS: usize = 8;
+
+/// bsc-evm error code of a reverted transaction
+const EVM_REVERTED: u32 = 1;
+
+/// bsc-evm error code of a signed transaction with the wrong nonce
+const EVM_NONCE_MISMATCH: u32 = 3;
+
+/// Most blocks one `eth_getLogs` request or filter poll searches
+const MAX_LOG_BLOCK_RANGE: u64 = 2_000;
+
+/// Most logs one `eth_getLogs` request or filter poll returns
+const MAX_LOG_RESULTS: usize = 10_000;
+
+/// Most requests one JSON-RPC batch can hold
+const MAX_BATCH_SIZE: usize = 1_000;
+
+/// Selector of the `Error(string)` revert reason
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
 /// BSC EVM RPC Server
 pub struct BscEvmRpcServer {
     rpc_client: RpcClient,
     evm_program_id: Pubkey,
-    chain_id: u64,
+    /// State account of the bsc-evm program
+    evm_state: Pubkey,
+    /// Relayer paying the Solana and gas fees of simulated and relayed transactions
+    payer: Keypair,
+    /// Solana signatures of the relayed transactions
+    transaction_index: Mutex<TransactionIndex>,
+    /// Filters installed by clients
+    filters: Mutex<Filters>,
+    /// Chain identity of the EVM state account, once it holds one
+    chain_config: OnceLock<ChainConfig>,
+    /// State of recent slots, which tracing transactions needs
+    slot_state: Option<Arc<dyn SlotState>>,
+    /// eth-bridge program holding the tokens of Ethereum addresses
+    eth_bridge_program_id: Option<Pubkey>,
     gas_price: u128,
 }
 
 impl BscEvmRpcServer {
-    pub fn new(rpc_url: String, evm_program_id: Pubkey) -> Self {
+    pub fn new(rpc_url: String, evm_program_id: Pubkey, evm_state: Pubkey, payer: Keypair) -> Self {
         Self {
-            rpc_client: RpcClient::new(rpc_url),
+            rpc_client: RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()),
             evm_program_id,
-            chain_id: 97, // BSC Testnet chain ID
+            evm_state,
+            payer,
+            transaction_index: Mutex::new(TransactionIndex::in_memory()),
+            filters: Mutex::new(Filters::default()),
+            chain_config: OnceLock::new(),
+            slot_state: None,
+            eth_bridge_program_id: None,
             gas_price: 20_000_000_000, // 20 gwei
         }
     }
 
+    /// Record relayed transactions in `transaction_index` instead of in memory
+    pub fn with_transaction_index(mut self, transaction_index: TransactionIndex) -> Self {
+        self.transaction_index = Mutex::new(transaction_index);
+        self
+    }
+
+    /// Replay transactions for tracing against the state `slot_state` keeps
+    pub fn with_slot_state(mut self, slot_state: Arc<dyn SlotState>) -> Self {
+        self.slot_state = Some(slot_state);
+        self
+    }
+
+    /// List the tokens held through the eth-bridge program `program_id`
+    pub fn with_eth_bridge(mut self, program_id: Pubkey) -> Self {
+        self.eth_bridge_program_id = Some(program_id);
+        self
+    }
+
+    /// Solana signature of the relayed Ethereum transaction `hash`
+    pub fn solana_signature(&self, hash: &[u8; 32]) -> Option<Signature> {
+        self.transaction_index.lock().unwrap().signature(hash)
+    }
+
+    /// Hash of the Ethereum transaction relayed in the Solana transaction `signature`
+    pub fn ethereum_hash(&self, signature: &Signature) -> Option<[u8; 32]> {
+        self.transaction_index.lock().unwrap().hash(signature)
+    }
+
+    /// Answer a JSON-RPC 2.0 request object
+    pub async fn handle_json_request(&self, request: &Value) -> Value {
+        let result = match parse_jsonrpc_request(request) {
+            Ok((method, params)) => self.handle_request(method, params).await,
+            Err(err) => Err(err),
+        };
+        jsonrpc_response(request["id"].clone(), result)
+    }
+
     /// Handle RPC request
     pub async fn handle_request(&self, method: &str, params: Value) -> Result<Value, RpcError> {
         match method {
             "eth_blockNumber" => self.eth_block_number().await,
             "eth_getBalance" => self.eth_get_balance(params).await,
+            "eth_getTransactionCount" => self.eth_get_transaction_count(params).await,
             "eth_getStorageAt" => self.eth_get_storage_at(params).await,
             "eth_getCode" => self.eth_get_code(params).await,
             "eth_sendRawTransaction" => self.eth_send_raw_transaction(params).await,
@@ -56,9 +239,22 @@ impl BscEvmRpcServer {
             "eth_estimateGas" => self.eth_estimate_gas(params).await,
             "web3_clientVersion" => self.web3_client_version().await,
             "net_version" => self.net_version().await,
+            "eth_chainId" => self.eth_chain_id().await,
+            "bssc_chainConfig" => self.bssc_chain_config().await,
             "eth_gasPrice" => self.eth_gas_price().await,
             "eth_getTransactionReceipt" => self.eth_get_transaction_receipt(params).await,
             "eth_getTransactionByHash" => self.eth_get_transaction_by_hash(params).await,
+            "eth_getBlockByNumber" => self.eth_get_block_by_number(params).await,
+            "eth_getBlockByHash" => self.eth_get_block_by_hash(params).await,
+            "eth_getLogs" => self.eth_get_logs(params).await,
+            "eth_newFilter" => self.eth_new_filter(params).await,
+            "eth_newBlockFilter" => self.eth_new_block_filter().await,
+            "eth_getFilterChanges" => self.eth_get_filter_changes(params).await,
+            "eth_getFilterLogs" => self.eth_get_filter_logs(params).await,
+            "eth_uninstallFilter" => self.eth_uninstall_filter(params).await,
+            "debug_traceTransaction" => self.debug_trace_transaction(params).await,
+            "debug_traceCall" => self.debug_trace_call(params).await,
+            "bssc_getTokenBalances" => self.bssc_get_token_balances(params).await,
             _ => Err(RpcError::MethodNotFound),
         }
     }
@@ -73,43 +269,42 @@ impl BscEvmRpcServer {
     /// eth_getBalance - Returns the balance of an address
     async fn eth_get_balance(&self, params: Value) -> Result<Value, RpcError> {
         let address = params[0].as_str().ok_or(RpcError::InvalidParams)?;
-        let _block = params.get(1); // Ignore block parameter for now
+        let eth_address = parse_eth_address(address)?;
+        let commitment = self.state_commitment(&params[1])?;
 
-        // Convert Ethereum address to Solana account
+        let (_, balance) = self.get_account(&eth_address, commitment)?;
+        Ok(json!(quantity(balance)))
+    }
+
+    /// eth_getTransactionCount - Returns the nonce of an address
+    async fn eth_get_transaction_count(&self, params: Value) -> Result<Value, RpcError> {
+        let address = params[0].as_str().ok_or(RpcError::InvalidParams)?;
         let eth_address = parse_eth_address(address)?;
-        let balance = self.get_balance(&eth_address).await?;
+        let commitment = self.state_commitment(&params[1])?;
 
-        Ok(json!(format!("0x{:x}", balance)))
+        let (nonce, _) = self.get_account(&eth_address, commitment)?;
+        Ok(json!(quantity(nonce.into())))
     }
 
     /// eth_getStorageAt - Returns storage value at given position
     async fn eth_get_storage_at(&self, params: Value) -> Result<Value, RpcError> {
         let address = params[0].as_str().ok_or(RpcError::InvalidParams)?;
         let position = params[1].as_str().ok_or(RpcError::InvalidParams)?;
-        let _block = params.get(2);
-
         let eth_address = parse_eth_address(address)?;
-        let storage_key = parse_hex(position)?;
-        
-        if storage_key.len() != 32 {
-            return Err(RpcError::InvalidParams);
-        }
-
-        let mut key = [0u8; 32];
-        key.copy_from_slice(&storage_key);
-        let value = self.get_storage(&eth_address, &key).await?;
+        let key = parse_storage_key(position)?;
+        let commitment = self.state_commitment(&params[2])?;
 
+        let value = self.get_storage(&eth_address, &key, commitment)?;
         Ok(json!(format!("0x{}", hex::encode(value))))
     }
 
     /// eth_getCode - Returns code at given address
     async fn eth_get_code(&self, params: Value) -> Result<Value, RpcError> {
         let address = params[0].as_str().ok_or(RpcError::InvalidParams)?;
-        let _block = params.get(1);
-
         let eth_address = parse_eth_address(address)?;
-        let code = self.get_code(&eth_address).await?;
+        let commitment = self.state_commitment(&params[1])?;
 
+        let code = self.get_code(&eth_address, commitment)?;
         Ok(json!(format!("0x{}", hex::encode(code))))
     }
 
@@ -129,30 +324,34 @@ impl BscEvmRpcServer {
 
     /// eth_call - Executes a message call without creating a transaction
     async fn eth_call(&self, params: Value) -> Result<Value, RpcError> {
-        let call_object = &params[0];
-        let _block = params.get(1);
+        let call = parse_call_request(&params[0])?;
+        let commitment = self.state_commitment(&params[1])?;
 
-        let to = call_object["to"].as_str().map(parse_eth_address).transpose()?;
-        let data = call_object["data"]
-            .as_str()
-            .map(|s| parse_hex(s).unwrap_or_default())
-            .unwrap_or_default();
-        let value = call_object["value"]
-            .as_str()
-            .map(|s| parse_hex(s).unwrap_or_default())
-            .unwrap_or_default();
-
-        let result = self.call_contract(to, &data, &value).await?;
-        Ok(json!(format!("0x{}", hex::encode(result))))
+        let gas_limit = call.gas.unwrap_or(CALL_GAS_CAP);
+        let result = self.call_contract(&call, gas_limit, commitment).await?;
+        Ok(json!(format!("0x{}", hex::encode(result.output))))
     }
 
     /// eth_estimateGas - Estimates gas for a transaction
+    ///
+    /// Finds the lowest gas limit the call succeeds with by binary search,
+    /// starting from the gas the call used when given the full allowance.
     async fn eth_estimate_gas(&self, params: Value) -> Result<Value, RpcError> {
-        let _call_object = &params[0];
-        let _block = params.get(1);
+        let call = parse_call_request(&params[0])?;
+        let commitment = self.state_commitment(&params[1])?;
+
+        // A call that fails with the full allowance fails with any gas limit
+        let cap = call.gas.unwrap_or(CALL_GAS_CAP);
+        let result = self.call_contract(&call, cap, commitment).await?;
+        let gas_used = result.gas_used.unwrap_or(TX_BASE_GAS).max(TX_BASE_GAS);
 
-        // Return a reasonable gas estimate
-        Ok(json!("0x5208")) // 21000 gas
+        let mut succeeds = |gas_limit| match self.simulate_call(&call, gas_limit, commitment) {
+            Ok(_) => Ok(true),
+            Err(RpcError::ExecutionReverted(_) | RpcError::ExecutionFailed(_)) => Ok(false),
+            Err(err) => Err(err),
+        };
+        let estimate = search_gas_limit(gas_used - 1, cap, &mut succeeds)?;
+        Ok(json!(format!("0x{:x}", estimate)))
     }
 
     /// web3_clientVersion - Returns client version
@@ -162,7 +361,22 @@ impl BscEvmRpcServer {
 
     /// net_version - Returns network version
     async fn net_version(&self) -> Result<Value, RpcError> {
-        Ok(json!(self.chain_id.to_string()))
+        Ok(json!(self.chain_config()?.chain_id.to_string()))
+    }
+
+    /// eth_chainId - Returns the chain id transactions must be signed for
+    async fn eth_chain_id(&self) -> Result<Value, RpcError> {
+        Ok(json!(quantity(self.chain_config()?.chain_id.into())))
+    }
+
+    /// bssc_chainConfig - Returns the chain identity in the form wallets add chains with (EIP-3085)
+    async fn bssc_chain_config(&self) -> Result<Value, RpcError> {
+        let config = self.chain_config()?;
+        Ok(json!({
+            "chainId": quantity(config.chain_id.into()),
+            "chainName": config.network_name,
+            "nativeCurrency": config.native_currency,
+        }))
     }
 
     /// eth_gasPrice - Returns current gas price
@@ -172,86 +386,917 @@ impl BscEvmRpcServer {
 
     /// eth_getTransactionReceipt - Returns transaction receipt
     async fn eth_get_transaction_receipt(&self, params: Value) -> Result<Value, RpcError> {
-        let _tx_hash = params[0].as_str().ok_or(RpcError::InvalidParams)?;
-        
-        // Return null for now (transaction not found)
-        Ok(Value::Null)
+        let tx_hash = params[0].as_str().ok_or(RpcError::InvalidParams)?;
+        let hash = parse_hash(tx_hash)?;
+
+        Ok(match self.find_transaction(&hash)? {
+            Some((block, index)) => block.receipt_json(index),
+            None => Value::Null,
+        })
     }
 
     /// eth_getTransactionByHash - Returns transaction by hash
     async fn eth_get_transaction_by_hash(&self, params: Value) -> Result<Value, RpcError> {
-        let _tx_hash = params[0].as_str().ok_or(RpcError::InvalidParams)?;
-        
-        // Return null for now (transaction not found)
-        Ok(Value::Null)
+        let tx_hash = params[0].as_str().ok_or(RpcError::InvalidParams)?;
+        let hash = parse_hash(tx_hash)?;
+
+        Ok(match self.find_transaction(&hash)? {
+            Some((block, index)) => block.transaction_json(index),
+            None => Value::Null,
+        })
+    }
+
+    /// eth_getBlockByNumber - Returns block by number or tag
+    async fn eth_get_block_by_number(&self, params: Value) -> Result<Value, RpcError> {
+        let tag = params[0].as_str().ok_or(RpcError::InvalidParams)?;
+        let full = params[1].as_bool().unwrap_or(false);
+
+        let slot = self.block_slot(tag)?;
+        Ok(match self.get_block(slot)? {
+            Some(block) => block.to_json(full),
+            None => Value::Null,
+        })
+    }
+
+    /// eth_getBlockByHash - Returns block by hash
+    async fn eth_get_block_by_hash(&self, params: Value) -> Result<Value, RpcError> {
+        let block_hash = params[0].as_str().ok_or(RpcError::InvalidParams)?;
+        let full = params[1].as_bool().unwrap_or(false);
+
+        let hash = parse_hash(block_hash)?;
+        Ok(match self.get_block(block_hash_slot(&hash))? {
+            Some(block) if block.hash == hash => block.to_json(full),
+            _ => Value::Null,
+        })
+    }
+
+    /// eth_getLogs - Returns the logs matching a filter
+    async fn eth_get_logs(&self, params: Value) -> Result<Value, RpcError> {
+        let filter = LogFilter::parse(&params[0])?;
+        Ok(json!(self.filter_logs(&filter)?))
+    }
+
+    /// eth_newFilter - Installs a filter for logs emitted from now on
+    async fn eth_new_filter(&self, params: Value) -> Result<Value, RpcError> {
+        let filter = LogFilter::parse(&params[0])?;
+        if filter.block_hash.is_some() {
+            return Err(RpcError::InvalidParams);
+        }
+        self.install_filter(FilterKind::Logs(filter))
+    }
+
+    /// eth_newBlockFilter - Installs a filter for blocks confirmed from now on
+    async fn eth_new_block_filter(&self) -> Result<Value, RpcError> {
+        self.install_filter(FilterKind::Blocks)
+    }
+
+    /// eth_getFilterChanges - Returns what a filter matched since it was last polled
+    ///
+    /// A poll covers at most [`MAX_LOG_BLOCK_RANGE`] blocks and
+    /// [`MAX_LOG_RESULTS`] logs; the rest is returned by the next polls.
+    async fn eth_get_filter_changes(&self, params: Value) -> Result<Value, RpcError> {
+        let id = parse_filter_id(&params[0])?;
+        let filter = self
+            .filters
+            .lock()
+            .unwrap()
+            .poll(id)
+            .ok_or(RpcError::FilterNotFound)?;
+
+        let latest = self.block_slot("latest")?;
+        let mut last = latest.min(filter.next_block.saturating_add(MAX_LOG_BLOCK_RANGE - 1));
+        if let FilterKind::Logs(LogFilter { to_block: Some(to_block), .. }) = &filter.kind {
+            last = last.min(self.block_slot(to_block)?);
+        }
+        if last < filter.next_block {
+            return Ok(json!([]));
+        }
+
+        let (changes, next_block) = match &filter.kind {
+            FilterKind::Logs(log_filter) => {
+                let page = self.collect_logs(log_filter, filter.next_block, last)?;
+                (page.logs, page.last_complete + 1)
+            }
+            FilterKind::Blocks => {
+                let mut hashes = Vec::new();
+                for slot in self.confirmed_slots(filter.next_block, last)? {
+                    if let Some(block) = self.fetch_block(slot, TransactionDetails::None)? {
+                        hashes.push(json!(blocks::data(&block.hash)));
+                    }
+                }
+                (hashes, last + 1)
+            }
+        };
+        self.filters.lock().unwrap().advance(id, next_block);
+        Ok(json!(changes))
+    }
+
+    /// eth_getFilterLogs - Returns every log matching a log filter
+    async fn eth_get_filter_logs(&self, params: Value) -> Result<Value, RpcError> {
+        let id = parse_filter_id(&params[0])?;
+        let filter = self
+            .filters
+            .lock()
+            .unwrap()
+            .poll(id)
+            .ok_or(RpcError::FilterNotFound)?;
+        match filter.kind {
+            FilterKind::Logs(log_filter) => Ok(json!(self.filter_logs(&log_filter)?)),
+            FilterKind::Blocks => Err(RpcError::FilterNotFound),
+        }
+    }
+
+    /// eth_uninstallFilter - Removes a filter
+    async fn eth_uninstall_filter(&self, params: Value) -> Result<Value, RpcError> {
+        let id = parse_filter_id(&params[0])?;
+        Ok(json!(self.filters.lock().unwrap().uninstall(id)))
+    }
+
+    /// debug_traceTransaction - Traces a relayed transaction as it executed in its block
+    async fn debug_trace_transaction(&self, params: Value) -> Result<Value, RpcError> {
+        let tx_hash = params[0].as_str().ok_or(RpcError::InvalidParams)?;
+        let hash = parse_hash(tx_hash)?;
+        let options = TraceOptions::parse(&params[1])?;
+
+        let (block, index) = self
+            .find_transaction(&hash)?
+            .ok_or(RpcError::TransactionNotFound)?;
+        let slot_state = self.slot_state.as_ref().ok_or(RpcError::HistoricalState)?;
+        let fetch = |keys: &[Pubkey]| {
+            slot_state
+                .accounts_at_start(block.number, keys)
+                .ok_or(RpcError::HistoricalState)
+        };
+
+        // Failed transactions left no trace in the state
+        let preceding: Vec<_> = block.transactions[..index]
+            .iter()
+            .filter(|tx| tx.success)
+            .collect();
+        let target = Replay::Transaction(&block.transactions[index]);
+        self.replay(fetch, &preceding, target, &options)
+    }
+
+    /// debug_traceCall - Traces a message call without creating a transaction
+    async fn debug_trace_call(&self, params: Value) -> Result<Value, RpcError> {
+        let call = parse_call_request(&params[0])?;
+        let commitment = self.state_commitment(&params[1])?;
+        let options = TraceOptions::parse(&params[2])?;
+
+        let fetch = |keys: &[Pubkey]| self.get_accounts(keys, commitment);
+        self.replay(fetch, &[], Replay::Call(&call), &options)
+    }
+
+    /// bssc_getTokenBalances - Returns the tokens an address holds through the eth-bridge
+    async fn bssc_get_token_balances(&self, params: Value) -> Result<Value, RpcError> {
+        let eth_bridge_program_id = self.eth_bridge_program_id.ok_or(RpcError::MethodNotFound)?;
+        let address = params[0].as_str().ok_or(RpcError::InvalidParams)?;
+        let eth_address = parse_eth_address(address)?;
+        let commitment = self.state_commitment(&params[1])?;
+
+        let (pda, _) = eth_bridge::derive_pda(&eth_address, &eth_bridge_program_id);
+        let mut balances = Vec::new();
+        for token_program in TOKEN_PROGRAM_IDS {
+            let accounts = self
+                .rpc_client
+                .get_token_accounts_by_owner_with_commitment(
+                    &pda,
+                    TokenAccountsFilter::ProgramId(token_program),
+                    commitment,
+                )
+                .map_err(|_| RpcError::InternalError)?
+                .value;
+            for keyed in accounts {
+                let token_account =
+                    Pubkey::from_str(&keyed.pubkey).map_err(|_| RpcError::InternalError)?;
+                let account =
+                    serde_json::to_value(&keyed.account).map_err(|_| RpcError::InternalError)?;
+                balances.extend(TokenBalance::from_parsed_account(
+                    token_account,
+                    token_program,
+                    &account,
+                ));
+            }
+        }
+
+        Ok(json!(balances.iter().map(TokenBalance::to_json).collect::<Vec<_>>()))
     }
 
     // Helper methods
 
-    async fn get_balance(&self, address: &[u8; 20]) -> Result<u128, RpcError> {
-        // In a real implementation, this would query the EVM state
-        // For now, return a placeholder balance
-        Ok(1000000000000000000) // 1 BNB in wei
+    /// Install a filter reporting changes after the latest block
+    fn install_filter(&self, kind: FilterKind) -> Result<Value, RpcError> {
+        let latest = self.block_slot("latest")?;
+        let id = self.filters.lock().unwrap().install(kind, latest + 1);
+        Ok(json!(quantity(id as u128)))
     }
 
-    async fn get_storage(&self, address: &[u8; 20], key: &[u8; 32]) -> Result<[u8; 32], RpcError> {
-        // In a real implementation, this would query contract storage
-        Ok([0u8; 32])
+    /// Every log matching `filter` in its block range or block
+    fn filter_logs(&self, filter: &LogFilter) -> Result<Vec<Value>, RpcError> {
+        if let Some(hash) = &filter.block_hash {
+            return match self.get_block(block_hash_slot(hash))? {
+                Some(block) if block.hash == *hash => Ok(block.matching_logs(|log| filter.matches(log))),
+                _ => Err(RpcError::InvalidParams),
+            };
+        }
+
+        let first = self.block_slot(filter.from_block.as_deref().unwrap_or("latest"))?;
+        let last = self.block_slot(filter.to_block.as_deref().unwrap_or("latest"))?;
+        if last < first {
+            return Ok(Vec::new());
+        }
+        if last - first >= MAX_LOG_BLOCK_RANGE {
+            return Err(RpcError::LimitExceeded(format!(
+                "block range is limited to {} blocks",
+                MAX_LOG_BLOCK_RANGE
+            )));
+        }
+
+        let page = self.collect_logs(filter, first, last)?;
+        if page.last_complete < last {
+            return Err(RpcError::LimitExceeded(format!(
+                "query returned more than {} results. Try with this block range [0x{:x}, 0x{:x}].",
+                MAX_LOG_RESULTS, first, page.last_complete
+            )));
+        }
+        Ok(page.logs)
+    }
+
+    /// Logs matching `filter` in the blocks `first..=last`, up to [`MAX_LOG_RESULTS`]
+    ///
+    /// Stops before the block that would exceed the limit, but always
+    /// includes the first block that has logs.
+    fn collect_logs(&self, filter: &LogFilter, first: u64, last: u64) -> Result<LogsPage, RpcError> {
+        let mut page = LogsPage {
+            logs: Vec::new(),
+            last_complete: last,
+        };
+        for slot in self.confirmed_slots(first, last)? {
+            let Some(block) = self.get_block(slot)? else {
+                continue;
+            };
+            let logs = block.matching_logs(|log| filter.matches(log));
+            if !page.logs.is_empty() && page.logs.len() + logs.len() > MAX_LOG_RESULTS {
+                page.last_complete = slot - 1;
+                break;
+            }
+            page.logs.extend(logs);
+        }
+        Ok(page)
     }
 
-    async fn get_code(&self, address: &[u8; 20]) -> Result<Vec<u8>, RpcError> {
-        // In a real implementation, this would return contract bytecode
-        Ok(vec![])
+    /// Slots in `first..=last` that have a confirmed block
+    fn confirmed_slots(&self, first: u64, last: u64) -> Result<Vec<u64>, RpcError> {
+        self.rpc_client
+            .get_blocks_with_commitment(first, Some(last), self.rpc_client.commitment())
+            .map_err(|_| RpcError::InternalError)
     }
 
-    async fn execute_transaction(&self, tx: EthTransaction) -> Result<Vec<u8>, RpcError> {
-        // In a real implementation, this would execute the transaction on Solana
-        // and return the transaction hash
-        Ok(vec![0u8; 32])
+    /// Slot of the block named by a block number or tag
+    fn block_slot(&self, tag: &str) -> Result<u64, RpcError> {
+        let slot = match tag {
+            "latest" | "pending" | "safe" => self.rpc_client.get_slot(),
+            "finalized" => self
+                .rpc_client
+                .get_slot_with_commitment(CommitmentConfig::finalized()),
+            "earliest" => self.rpc_client.get_first_available_block(),
+            number => {
+                let number = parse_quantity(number)?;
+                return u64::try_from(number).map_err(|_| RpcError::InvalidParams);
+            }
+        };
+        slot.map_err(|_| RpcError::InternalError)
+    }
+
+    /// Block at `slot`, `None` if the slot was skipped or its block is not available
+    fn get_block(&self, slot: u64) -> Result<Option<Block>, RpcError> {
+        self.fetch_block(slot, TransactionDetails::Full)
+    }
+
+    /// Block at `slot`, without its transactions unless `details` is full
+    fn fetch_block(&self, slot: u64, details: TransactionDetails) -> Result<Option<Block>, RpcError> {
+        let config = RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            transaction_details: Some(details),
+            rewards: Some(false),
+            commitment: Some(self.rpc_client.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        match self.rpc_client.get_block_with_config(slot, config) {
+            Ok(block) => Ok(Block::from_confirmed_block(
+                slot,
+                &block,
+                &self.evm_program_id,
+                self.gas_price,
+            )),
+            Err(err) if is_missing(&err) => Ok(None),
+            Err(_) => Err(RpcError::InternalError),
+        }
+    }
+
+    /// Block holding the relayed transaction `hash` and the transaction's position in it
+    ///
+    /// `None` if the transaction was not relayed by this server or is not
+    /// confirmed yet.
+    fn find_transaction(&self, hash: &[u8; 32]) -> Result<Option<(Block, usize)>, RpcError> {
+        let Some(signature) = self.solana_signature(hash) else {
+            return Ok(None);
+        };
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(self.rpc_client.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        let slot = match self.rpc_client.get_transaction_with_config(&signature, config) {
+            Ok(transaction) => transaction.slot,
+            Err(err) if is_missing(&err) => return Ok(None),
+            Err(_) => return Err(RpcError::InternalError),
+        };
+
+        Ok(self.get_block(slot)?.and_then(|block| {
+            let index = block.position(hash, &signature)?;
+            Some((block, index))
+        }))
+    }
+
+    /// Chain identity held by the EVM state account
+    ///
+    /// An account holding none serves the default chain. Once it holds one
+    /// the identity is cached, as it never changes.
+    fn chain_config(&self) -> Result<ChainConfig, RpcError> {
+        if let Some(config) = self.chain_config.get() {
+            return Ok(config.clone());
+        }
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&self.evm_state, self.rpc_client.commitment())
+            .map_err(|_| RpcError::InternalError)?
+            .value;
+        let data = account
+            .filter(|account| account.owner == self.evm_program_id)
+            .map(|account| account.data)
+            .unwrap_or_default();
+        match ChainConfig::try_unpack(&data).map_err(|_| RpcError::InternalError)? {
+            Some(config) => Ok(self.chain_config.get_or_init(|| config).clone()),
+            None => Ok(ChainConfig::default()),
+        }
+    }
+
+    /// Commitment to read EVM state at for a block number, tag or EIP-1898 block object
+    ///
+    /// Solana keeps no historical account state, so only the current state is
+    /// served: tags map onto commitment levels, and a block number or hash
+    /// must name the latest block or a later one.
+    fn state_commitment(&self, block: &Value) -> Result<CommitmentConfig, RpcError> {
+        let slot = match block {
+            Value::Null => return Ok(self.rpc_client.commitment()),
+            Value::String(tag) => match tag_commitment(tag) {
+                Some(commitment) => return Ok(commitment),
+                None if tag == "earliest" => return Err(RpcError::HistoricalState),
+                None => parse_quantity(tag)?,
+            },
+            Value::Object(object) => match (object.get("blockNumber"), object.get("blockHash")) {
+                (Some(Value::String(number)), None) => parse_quantity(number)?,
+                (None, Some(Value::String(hash))) => block_hash_slot(&parse_hash(hash)?).into(),
+                _ => return Err(RpcError::InvalidParams),
+            },
+            _ => return Err(RpcError::InvalidParams),
+        };
+
+        let latest = self.rpc_client.get_slot().map_err(|_| RpcError::InternalError)?;
+        if slot < u128::from(latest) {
+            return Err(RpcError::HistoricalState);
+        }
+        Ok(self.rpc_client.commitment())
+    }
+
+    /// Record of the state account holding `key`, `None` if it was never written
+    fn get_state(
+        &self,
+        key: StateKey,
+        commitment: CommitmentConfig,
+    ) -> Result<Option<EvmStateAccount>, RpcError> {
+        let address = key.find_address(&self.evm_program_id, &self.evm_state).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&address, commitment)
+            .map_err(|_| RpcError::InternalError)?
+            .value;
+        Ok(account.and_then(|account| decode_state_account(&account, &self.evm_program_id)))
+    }
+
+    /// Accounts at `keys`, `None` for those that do not exist
+    fn get_accounts(
+        &self,
+        keys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<Option<Account>>, RpcError> {
+        let mut accounts = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(request::MAX_MULTIPLE_ACCOUNTS) {
+            let response = self
+                .rpc_client
+                .get_multiple_accounts_with_commitment(chunk, commitment)
+                .map_err(|_| RpcError::InternalError)?;
+            accounts.extend(response.value);
+        }
+        Ok(accounts)
+    }
+
+    /// Nonce and balance of `address`
+    fn get_account(
+        &self,
+        address: &[u8; 20],
+        commitment: CommitmentConfig,
+    ) -> Result<(u64, u128), RpcError> {
+        let state = self.get_state(StateKey::Account(*address), commitment)?;
+        Ok(match state {
+            Some(EvmStateAccount::Account { nonce, balance, .. }) => (nonce, balance),
+            _ => (0, 0),
+        })
+    }
+
+    /// Value of the storage slot `key` of the contract at `address`
+    fn get_storage(
+        &self,
+        address: &[u8; 20],
+        key: &[u8; 32],
+        commitment: CommitmentConfig,
+    ) -> Result<[u8; 32], RpcError> {
+        let state = self.get_state(StateKey::Storage(*address, *key), commitment)?;
+        Ok(match state {
+            Some(EvmStateAccount::Storage { value, .. }) => value,
+            _ => [0u8; 32],
+        })
+    }
+
+    /// Runtime bytecode of the contract at `address`, empty for other addresses
+    fn get_code(
+        &self,
+        address: &[u8; 20],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<u8>, RpcError> {
+        let state = self.get_state(StateKey::Code(*address), commitment)?;
+        Ok(match state {
+            Some(EvmStateAccount::Code { code, .. }) => code,
+            _ => vec![],
+        })
+    }
+
+    /// Relay a signed transaction to the cluster and return its hash
+    ///
+    /// The sender's balance must cover the gas limit at the fee cap plus the
+    /// value, and the fee cap must not exceed the program's maximum gas price,
+    /// so the relayer only spends Solana fees on transactions that pay it back.
+    /// The transaction is then simulated, which finds the state accounts it
+    /// touches and rejects it if it could not be executed, for example for
+    /// its nonce. A transaction that would revert is relayed like any other,
+    /// and pays for the gas it uses.
+    async fn execute_transaction(&self, tx: SignedTransaction) -> Result<Vec<u8>, RpcError> {
+        let hash = tx.hash();
+        if self.solana_signature(&hash).is_some() {
+            return Err(RpcError::TransactionRejected("already known".to_string()));
+        }
+        let sender = recover_sender(&tx, self.chain_config()?.chain_id).map_err(|err| {
+            RpcError::TransactionRejected(match err {
+                ProgramError::InvalidArgument => "invalid chain id".to_string(),
+                _ => "invalid signature".to_string(),
+            })
+        })?;
+        let (_, balance) = self.get_account(&sender, self.rpc_client.commitment())?;
+        check_affordable(&tx, balance)?;
+
+        let raw = tx.encode();
+        let instruction = |state_accounts: &[Pubkey]| {
+            bsc_evm::instruction::execute_signed_transaction(
+                &self.evm_program_id,
+                &self.evm_state,
+                &self.payer.pubkey(),
+                state_accounts,
+                raw.clone(),
+            )
+        };
+        let compute_units = compute_unit_limit(tx.gas_limit);
+        let (state_accounts, _) = self.simulate(
+            self.initial_state_accounts(&sender, tx.to),
+            compute_units,
+            self.rpc_client.commitment(),
+            &instruction,
+        )?;
+
+        let recent_blockhash = self
+            .rpc_client
+            .get_latest_blockhash()
+            .map_err(|_| RpcError::InternalError)?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(compute_units),
+                instruction(&state_accounts),
+            ],
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            recent_blockhash,
+        );
+        let config = RpcSendTransactionConfig {
+            // The simulation above was the preflight check
+            skip_preflight: true,
+            ..RpcSendTransactionConfig::default()
+        };
+        let signature = self
+            .rpc_client
+            .send_transaction_with_config(&transaction, config)
+            .map_err(|_| RpcError::InternalError)?;
+
+        self.transaction_index
+            .lock()
+            .unwrap()
+            .insert(hash, signature)
+            .map_err(|_| RpcError::InternalError)?;
+        Ok(hash.to_vec())
     }
 
-    async fn call_contract(&self, to: Option<[u8; 20]>, data: &[u8], value: &[u8]) -> Result<Vec<u8>, RpcError> {
-        // In a real implementation, this would execute a contract call
-        Ok(vec![])
+    async fn call_contract(
+        &self,
+        call: &CallRequest,
+        gas_limit: u64,
+        commitment: CommitmentConfig,
+    ) -> Result<CallResult, RpcError> {
+        self.simulate_call(call, gas_limit, commitment)
+    }
+
+    /// Simulate `call` with `gas_limit` against the latest state at `commitment`
+    fn simulate_call(
+        &self,
+        call: &CallRequest,
+        gas_limit: u64,
+        commitment: CommitmentConfig,
+    ) -> Result<CallResult, RpcError> {
+        let instruction = |state_accounts: &[Pubkey]| {
+            bsc_evm::instruction::simulate_call(
+                &self.evm_program_id,
+                &self.evm_state,
+                &self.payer.pubkey(),
+                state_accounts,
+                call.from,
+                call.to,
+                call.data.clone(),
+                call.value,
+                gas_limit,
+            )
+        };
+        let (_, result) = self.simulate(
+            self.initial_state_accounts(&call.from, call.to),
+            compute_unit_limit(gas_limit),
+            commitment,
+            &instruction,
+        )?;
+        Ok(result)
+    }
+
+    /// State accounts a transaction from `from` to `to` touches for certain
+    fn initial_state_accounts(&self, from: &[u8; 20], to: Option<[u8; 20]>) -> Vec<Pubkey> {
+        let mut state_accounts =
+            vec![derive_account_address(from, &self.evm_program_id, &self.evm_state).0];
+        if let Some(to) = to {
+            state_accounts
+                .push(derive_account_address(&to, &self.evm_program_id, &self.evm_state).0);
+            state_accounts.push(derive_code_address(&to, &self.evm_program_id, &self.evm_state).0);
+        }
+        state_accounts
+    }
+
+    /// Simulate the bsc-evm instruction `instruction` builds for the given state accounts
+    ///
+    /// The EVM state accounts an instruction touches are only known once it
+    /// runs, so the simulation is repeated with every account the program
+    /// reports missing until it has all of them, with `compute_units` budgeted
+    /// each time and the state read at `commitment`. Returns those accounts
+    /// along with the result.
+    fn simulate<F>(
+        &self,
+        mut state_accounts: Vec<Pubkey>,
+        compute_units: u32,
+        commitment: CommitmentConfig,
+        instruction: &F,
+    ) -> Result<(Vec<Pubkey>, CallResult), RpcError>
+    where
+        F: Fn(&[Pubkey]) -> Instruction,
+    {
+        for _ in 0..MAX_SIMULATION_ROUNDS {
+            let instructions = [
+                ComputeBudgetInstruction::set_compute_unit_limit(compute_units),
+                instruction(&state_accounts),
+            ];
+            let transaction = Transaction::new_with_payer(&instructions, Some(&self.payer.pubkey()));
+            let config = RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(commitment),
+                ..RpcSimulateTransactionConfig::default()
+            };
+            let simulation = self
+                .rpc_client
+                .simulate_transaction_with_config(&transaction, config)
+                .map_err(|_| RpcError::InternalError)?
+                .value;
+
+            let logs = simulation.logs.unwrap_or_default();
+            let output = match simulation.return_data {
+                Some(return_data) if return_data.program_id == self.evm_program_id.to_string() => {
+                    BASE64_STANDARD
+                        .decode(return_data.data.0)
+                        .map_err(|_| RpcError::InternalError)?
+                }
+                _ => Vec::new(),
+            };
+
+            match simulation.err {
+                None => {
+                    let result = CallResult {
+                        output,
+                        gas_used: gas_used_from_logs(&logs),
+                    };
+                    return Ok((state_accounts, result));
+                }
+                Some(TransactionError::InstructionError(
+                    _,
+                    InstructionError::Custom(EVM_REVERTED),
+                )) => return Err(RpcError::ExecutionReverted(output)),
+                Some(TransactionError::InstructionError(
+                    _,
+                    InstructionError::Custom(EVM_NONCE_MISMATCH),
+                )) => return Err(RpcError::TransactionRejected("invalid nonce".to_string())),
+                Some(TransactionError::InstructionError(_, InstructionError::NotEnoughAccountKeys)) => {
+                    let missing = missing_accounts_from_logs(&logs);
+                    if missing.is_empty() {
+                        return Err(RpcError::ExecutionFailed(
+                            InstructionError::NotEnoughAccountKeys.to_string(),
+                        ));
+                    }
+                    state_accounts.extend(missing);
+                }
+                Some(err) => return Err(RpcError::ExecutionFailed(err.to_string())),
+            }
+        }
+
+        Err(RpcError::ExecutionFailed(
+            "call touches too many state accounts".to_string(),
+        ))
+    }
+
+    /// Trace `target`, executed after `preceding` against the accounts `fetch` returns
+    ///
+    /// `fetch` also provides the Clock and SlotHashes sysvars the block
+    /// environment is built from. Like [`simulate`](Self::simulate), the
+    /// replay is repeated with every state account execution reports missing
+    /// until it has all of them.
+    fn replay<F>(
+        &self,
+        fetch: F,
+        preceding: &[&BlockTransaction],
+        target: Replay,
+        options: &TraceOptions,
+    ) -> Result<Value, RpcError>
+    where
+        F: Fn(&[Pubkey]) -> Result<Vec<Option<Account>>, RpcError>,
+    {
+        trace::silence_program_logs();
+        let config = self.chain_config()?;
+
+        let sysvars = fetch(&[sysvar::clock::id(), sysvar::slot_hashes::id()])?;
+        let [Some(clock), Some(slot_hashes)] = &sysvars[..] else {
+            return Err(RpcError::InternalError);
+        };
+        let clock: Clock = from_account(clock).ok_or(RpcError::InternalError)?;
+        let mut slot_hashes = (sysvar::slot_hashes::id(), slot_hashes.clone());
+        let block = BlockContext::from_sysvars(&clock, &AccountInfo::from(&mut slot_hashes))
+            .map_err(|_| RpcError::InternalError)?;
+
+        let mut state_accounts = Vec::new();
+        for tx in preceding {
+            state_accounts.extend(self.initial_state_accounts(&tx.from, tx.transaction.to));
+        }
+        state_accounts.extend(match target {
+            Replay::Transaction(tx) => self.initial_state_accounts(&tx.from, tx.transaction.to),
+            Replay::Call(call) => self.initial_state_accounts(&call.from, call.to),
+        });
+        state_accounts.sort();
+        state_accounts.dedup();
+
+        for _ in 0..MAX_SIMULATION_ROUNDS {
+            // Accounts that do not exist are passed empty, as the program sees them
+            let mut accounts: Vec<(Pubkey, Account)> = state_accounts
+                .iter()
+                .copied()
+                .zip(fetch(&state_accounts)?.into_iter().map(Option::unwrap_or_default))
+                .collect();
+            let account_infos: Vec<AccountInfo> = accounts.iter_mut().map(AccountInfo::from).collect();
+
+            let mut executor = EvmExecutor::new();
+            executor.set_chain_id(config.chain_id);
+            *executor.state_mut() =
+                EvmState::load(&self.evm_program_id, &self.evm_state, &account_infos)
+                    .map_err(|_| RpcError::InternalError)?;
+            executor.state_mut().set_gas_schedule(config.gas_schedule());
+            executor.state_mut().set_block_context(block.clone());
+            for tx in preceding {
+                let _ = executor.execute_signed_transaction(&tx.transaction.encode());
+            }
+
+            let tracer = options.start();
+            executor.state_mut().set_tracer(tracer.tracer());
+            let result = match target {
+                Replay::Transaction(tx) => executor.execute_signed_transaction(&tx.transaction.encode()),
+                Replay::Call(call) => {
+                    let tx = EvmTransaction {
+                        nonce: executor.get_nonce(&call.from),
+                        gas_price: executor.state().gas_price(),
+                        gas_limit: call.gas.unwrap_or(CALL_GAS_CAP),
+                        to: call.to,
+                        value: call.value,
+                        data: call.data.clone(),
+                        access_list: Vec::new(),
+                        v: 0,
+                        r: [0u8; 32],
+                        s: [0u8; 32],
+                    };
+                    executor.execute_call(tx, call.from)
+                }
+            };
+            executor.state_mut().clear_tracer();
+
+            let missing = executor.state().missing_accounts();
+            if missing.is_empty() {
+                return match (tracer.result(), result) {
+                    (Some(trace), _) => trace,
+                    // The transaction was rejected before it started executing
+                    (None, Err(ProgramError::Custom(EVM_NONCE_MISMATCH))) => {
+                        Err(RpcError::TransactionRejected("invalid nonce".to_string()))
+                    }
+                    (None, Err(err)) => Err(RpcError::ExecutionFailed(err.to_string())),
+                    (None, Ok(_)) => Err(RpcError::InternalError),
+                };
+            }
+            state_accounts.extend(missing);
+        }
+
+        Err(RpcError::ExecutionFailed(
+            "call touches too many state accounts".to_string(),
+        ))
     }
 }
 
-/// Ethereum Transaction Structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EthTransaction {
-    pub nonce: u64,
-    pub gas_price: u128,
-    pub gas_limit: u64,
-    pub to: Option<[u8; 20]>,
-    pub value: u128,
-    pub data: Vec<u8>,
-    pub v: u8,
-    pub r: [u8; 32],
-    pub s: [u8; 32],
+/// What a trace replays
+#[derive(Clone, Copy)]
+enum Replay<'a> {
+    /// A transaction of a block
+    Transaction(&'a BlockTransaction),
+    /// A message call of a `debug_traceCall` request
+    Call(&'a CallRequest),
+}
+
+/// Message call of an `eth_call`, `eth_estimateGas` or `debug_traceCall` request
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CallRequest {
+    from: [u8; 20],
+    to: Option<[u8; 20]>,
+    data: Vec<u8>,
+    value: u128,
+    gas: Option<u64>,
+}
+
+/// Logs found in a range of blocks
+struct LogsPage {
+    logs: Vec<Value>,
+    /// Last block whose logs are all included
+    last_complete: u64,
+}
+
+/// Result of a successful simulated call
+struct CallResult {
+    /// Return data of the call, or the address of a created contract
+    output: Vec<u8>,
+    /// Gas used before refunds, as logged by the program
+    gas_used: Option<u64>,
 }
 
 /// RPC Error Types
 #[derive(Debug)]
 pub enum RpcError {
+    /// The message is not valid JSON
+    ParseError,
+    /// The message is not a JSON-RPC 2.0 request
+    InvalidRequest,
     MethodNotFound,
     InvalidParams,
     InternalError,
-    TransactionRejected,
+    /// The transaction was not relayed, for this reason
+    TransactionRejected(String),
+    /// The call reverted with this output
+    ExecutionReverted(Vec<u8>),
+    /// The call could not be executed
+    ExecutionFailed(String),
+    /// The request would return too much, for this reason
+    LimitExceeded(String),
+    /// No filter with the given id is installed
+    FilterNotFound,
+    /// State was requested at a block older than the latest
+    HistoricalState,
+    /// No confirmed transaction relayed by this server has the given hash
+    TransactionNotFound,
 }
 
 impl std::fmt::Display for RpcError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            RpcError::ParseError => write!(f, "Parse error"),
+            RpcError::InvalidRequest => write!(f, "Invalid request"),
             RpcError::MethodNotFound => write!(f, "Method not found"),
             RpcError::InvalidParams => write!(f, "Invalid parameters"),
             RpcError::InternalError => write!(f, "Internal error"),
-            RpcError::TransactionRejected => write!(f, "Transaction rejected"),
+            RpcError::TransactionRejected(reason) => write!(f, "Transaction rejected: {}", reason),
+            RpcError::ExecutionReverted(output) => match revert_reason(output) {
+                Some(reason) => write!(f, "execution reverted: {}", reason),
+                None => write!(f, "execution reverted"),
+            },
+            RpcError::ExecutionFailed(reason) => write!(f, "execution failed: {}", reason),
+            RpcError::LimitExceeded(reason) => write!(f, "{}", reason),
+            RpcError::FilterNotFound => write!(f, "filter not found"),
+            RpcError::HistoricalState => write!(f, "historical state is not available"),
+            RpcError::TransactionNotFound => write!(f, "transaction not found"),
         }
     }
 }
 
 impl std::error::Error for RpcError {}
 
+impl RpcError {
+    /// JSON-RPC error code
+    pub fn code(&self) -> i64 {
+        match self {
+            RpcError::ParseError => -32700,
+            RpcError::InvalidRequest => -32600,
+            RpcError::MethodNotFound => -32601,
+            RpcError::InvalidParams => -32602,
+            RpcError::InternalError => -32603,
+            RpcError::ExecutionReverted(_) => 3,
+            RpcError::LimitExceeded(_) => -32005,
+            RpcError::TransactionRejected(_)
+            | RpcError::ExecutionFailed(_)
+            | RpcError::FilterNotFound
+            | RpcError::HistoricalState
+            | RpcError::TransactionNotFound => -32000,
+        }
+    }
+
+    /// JSON-RPC error object, with the revert output as data if the call reverted
+    pub fn to_json(&self) -> Value {
+        let mut error = json!({
+            "code": self.code(),
+            "message": self.to_string(),
+        });
+        if let RpcError::ExecutionReverted(output) = self {
+            error["data"] = json!(blocks::data(output));
+        }
+        error
+    }
+}
+
+/// Requests of a JSON-RPC message, and whether they were sent as a batch
+pub fn parse_jsonrpc_message(message: &[u8]) -> Result<(Vec<Value>, bool), RpcError> {
+    match serde_json::from_slice(message).map_err(|_| RpcError::ParseError)? {
+        Value::Array(requests) if requests.is_empty() => Err(RpcError::InvalidRequest),
+        Value::Array(requests) if requests.len() > MAX_BATCH_SIZE => Err(RpcError::LimitExceeded(
+            format!("batches are limited to {} requests", MAX_BATCH_SIZE),
+        )),
+        Value::Array(requests) => Ok((requests, true)),
+        request => Ok((vec![request], false)),
+    }
+}
+
+/// Method and parameters of a JSON-RPC 2.0 request object
+///
+/// Missing parameters are an empty list.
+pub fn parse_jsonrpc_request(request: &Value) -> Result<(&str, Value), RpcError> {
+    if request["jsonrpc"] != "2.0" {
+        return Err(RpcError::InvalidRequest);
+    }
+    let method = request["method"].as_str().ok_or(RpcError::InvalidRequest)?;
+    let params = match request.get("params") {
+        None | Some(Value::Null) => json!([]),
+        Some(params @ (Value::Array(_) | Value::Object(_))) => params.clone(),
+        Some(_) => return Err(RpcError::InvalidRequest),
+    };
+    Ok((method, params))
+}
+
+/// JSON-RPC 2.0 response to the request `id`
+pub fn jsonrpc_response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(err) => json!({ "jsonrpc": "2.0", "id": id, "error": err.to_json() }),
+    }
+}
+
 /// Parse Ethereum address from hex string
 fn parse_eth_address(s: &str) -> Result<[u8; 20], RpcError> {
     let hex = if s.starts_with("0x") { &s[2..] } else { s };
@@ -272,28 +1317,179 @@ fn parse_hex(s: &str) -> Result<Vec<u8>, RpcError> {
     hex::decode(hex).map_err(|_| RpcError::InvalidParams)
 }
 
-/// Parse Ethereum transaction from raw bytes
-fn parse_eth_transaction(data: &[u8]) -> Result<EthTransaction, RpcError> {
-    // Simplified transaction parsing
-    // In a real implementation, this would properly parse RLP-encoded transactions
-    
-    if data.len() < 32 {
+/// Parse a storage slot, left-padding positions given as short quantities such as `0x0`
+fn parse_storage_key(s: &str) -> Result<[u8; 32], RpcError> {
+    let hex = s.strip_prefix("0x").unwrap_or(s);
+    if hex.len() > 64 {
+        return Err(RpcError::InvalidParams);
+    }
+    parse_hash(&format!("{:0>64}", hex))
+}
+
+/// Parse a 32-byte transaction or block hash
+fn parse_hash(s: &str) -> Result<[u8; 32], RpcError> {
+    parse_hex(s)?.try_into().map_err(|_| RpcError::InvalidParams)
+}
+
+/// Parse the id of an installed filter
+fn parse_filter_id(id: &Value) -> Result<u64, RpcError> {
+    let id = parse_quantity(id.as_str().ok_or(RpcError::InvalidParams)?)?;
+    u64::try_from(id).map_err(|_| RpcError::InvalidParams)
+}
+
+/// Commitment level serving the state of the block tag `tag`
+///
+/// Confirmed blocks are the latest this server serves, and as they are
+/// voted on by a supermajority they are also the closest match of `safe`.
+fn tag_commitment(tag: &str) -> Option<CommitmentConfig> {
+    match tag {
+        "pending" => Some(CommitmentConfig::processed()),
+        "latest" | "safe" => Some(CommitmentConfig::confirmed()),
+        "finalized" => Some(CommitmentConfig::finalized()),
+        _ => None,
+    }
+}
+
+/// Record held by a bsc-evm state account, `None` unless the program owns it
+///
+/// Accounts are never shrunk, so the record may be followed by stale bytes.
+fn decode_state_account(account: &Account, evm_program_id: &Pubkey) -> Option<EvmStateAccount> {
+    if account.owner != *evm_program_id {
+        return None;
+    }
+    EvmStateAccount::deserialize(&mut account.data.as_slice()).ok()
+}
+
+/// Whether the cluster answered that the requested block or transaction does not exist
+fn is_missing(err: &ClientError) -> bool {
+    matches!(
+        err.kind(),
+        ClientErrorKind::RpcError(request::RpcError::RpcResponseError { .. })
+            | ClientErrorKind::RpcError(request::RpcError::ForUser(_))
+            | ClientErrorKind::SerdeJson(_)
+    )
+}
+
+/// Parse a hex quantity such as `0x5208`
+fn parse_quantity(s: &str) -> Result<u128, RpcError> {
+    let hex = s.strip_prefix("0x").ok_or(RpcError::InvalidParams)?;
+    u128::from_str_radix(hex, 16).map_err(|_| RpcError::InvalidParams)
+}
+
+/// Parse the call object of `eth_call` and `eth_estimateGas`
+fn parse_call_request(call_object: &Value) -> Result<CallRequest, RpcError> {
+    if !call_object.is_object() {
         return Err(RpcError::InvalidParams);
     }
+    let from = call_object["from"].as_str().map(parse_eth_address).transpose()?;
+    let to = call_object["to"].as_str().map(parse_eth_address).transpose()?;
+    // Newer clients send the calldata as `input`
+    let data = call_object["input"]
+        .as_str()
+        .or_else(|| call_object["data"].as_str())
+        .map(parse_hex)
+        .transpose()?;
+    let value = call_object["value"].as_str().map(parse_quantity).transpose()?;
+    let gas = call_object["gas"]
+        .as_str()
+        .map(parse_quantity)
+        .transpose()?
+        .map(|gas| u64::try_from(gas).map_err(|_| RpcError::InvalidParams))
+        .transpose()?;
+
+    Ok(CallRequest {
+        from: from.unwrap_or_default(),
+        to,
+        data: data.unwrap_or_default(),
+        value: value.unwrap_or_default(),
+        gas,
+    })
+}
+
+/// Reject `tx` unless a sender holding `balance` can pay its most gas and its value
+///
+/// The fee cap must also be at most the maximum gas price the program accepts.
+fn check_affordable(tx: &SignedTransaction, balance: u128) -> Result<(), RpcError> {
+    if tx.max_fee_per_gas > GasConfig::default().max_gas_price {
+        return Err(RpcError::TransactionRejected("gas price above the maximum".to_string()));
+    }
+    let cost = (tx.gas_limit as u128)
+        .checked_mul(tx.max_fee_per_gas)
+        .and_then(|fee| fee.checked_add(tx.value));
+    match cost {
+        Some(cost) if balance >= cost => Ok(()),
+        _ => Err(RpcError::TransactionRejected(
+            "insufficient funds for gas * price + value".to_string(),
+        )),
+    }
+}
 
-    Ok(EthTransaction {
-        nonce: 0,
-        gas_price: 20000000000, // 20 gwei
-        gas_limit: 21000,
-        to: None,
-        value: 0,
-        data: vec![],
-        v: 0,
-        r: [0u8; 32],
-        s: [0u8; 32],
+/// Compute units to request for executing `gas_limit`, at most [`MAX_COMPUTE_UNITS`]
+fn compute_unit_limit(gas_limit: u64) -> u32 {
+    gas_to_compute_units(gas_limit).min(MAX_COMPUTE_UNITS) as u32
+}
+
+/// Gas used before refunds, from the bsc-evm program logs
+fn gas_used_from_logs(logs: &[String]) -> Option<u64> {
+    logs.iter().find_map(|log| {
+        let used = log.strip_prefix("Program log: Transaction gas: used=")?;
+        used.split(',').next()?.parse().ok()
     })
 }
 
+/// State accounts the bsc-evm program reported missing
+fn missing_accounts_from_logs(logs: &[String]) -> Vec<Pubkey> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program log: Missing EVM state account: "))
+        .filter_map(|pubkey| Pubkey::from_str(pubkey).ok())
+        .collect()
+}
+
+/// Decode the message of an `Error(string)` revert
+fn revert_reason(output: &[u8]) -> Option<String> {
+    let encoded = output.strip_prefix(&ERROR_SELECTOR)?;
+    let offset = usize::try_from(u64::from_be_bytes(encoded.get(24..32)?.try_into().ok()?)).ok()?;
+    let length_word = encoded.get(offset..offset.checked_add(32)?)?;
+    let length = usize::try_from(u64::from_be_bytes(length_word[24..].try_into().ok()?)).ok()?;
+    let start = offset + 32;
+    let message = encoded.get(start..start.checked_add(length)?)?;
+    String::from_utf8(message.to_vec()).ok()
+}
+
+/// Lowest gas limit in `(lo, hi]` that `succeeds`, given that `hi` does
+///
+/// Success must be monotonic in the gas limit.
+fn search_gas_limit<F>(mut lo: u64, mut hi: u64, succeeds: &mut F) -> Result<u64, RpcError>
+where
+    F: FnMut(u64) -> Result<bool, RpcError>,
+{
+    // Most calls need only a little more than they use, because of the
+    // 63/64 rule, so try that before bisecting the whole range
+    let optimistic = lo.saturating_add(1).saturating_mul(64) / 63;
+    if optimistic < hi {
+        if succeeds(optimistic)? {
+            hi = optimistic;
+        } else {
+            lo = optimistic;
+        }
+    }
+
+    while lo + 1 < hi {
+        let mid = lo + (hi - lo) / 2;
+        if succeeds(mid)? {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Ok(hi)
+}
+
+/// Parse a legacy, access list or dynamic fee transaction from raw bytes
+fn parse_eth_transaction(data: &[u8]) -> Result<SignedTransaction, RpcError> {
+    SignedTransaction::decode(data).map_err(|_| RpcError::InvalidParams)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -311,4 +1507,235 @@ mod tests {
         let parsed = parse_hex(hex);
         assert_eq!(parsed.unwrap(), vec![0x12, 0x34]);
     }
+
+    #[test]
+    fn test_parse_storage_key() {
+        let mut key = [0u8; 32];
+        assert_eq!(parse_storage_key("0x0").unwrap(), key);
+        key[31] = 0x12;
+        assert_eq!(parse_storage_key("0x12").unwrap(), key);
+        key[0] = 0xab;
+        let full = format!("0xab{}12", "0".repeat(60));
+        assert_eq!(parse_storage_key(&full).unwrap(), key);
+        assert!(parse_storage_key(&format!("{}0", full)).is_err());
+        assert!(parse_storage_key("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_tag_commitment() {
+        assert_eq!(tag_commitment("pending"), Some(CommitmentConfig::processed()));
+        assert_eq!(tag_commitment("latest"), Some(CommitmentConfig::confirmed()));
+        assert_eq!(tag_commitment("safe"), Some(CommitmentConfig::confirmed()));
+        assert_eq!(tag_commitment("finalized"), Some(CommitmentConfig::finalized()));
+        assert_eq!(tag_commitment("earliest"), None);
+        assert_eq!(tag_commitment("0x10"), None);
+    }
+
+    #[test]
+    fn test_decode_state_account() {
+        let program_id = Pubkey::new_unique();
+        let record = EvmStateAccount::Account {
+            address: [7u8; 20],
+            nonce: 3,
+            balance: 1_000,
+        };
+        let mut data = borsh::to_vec(&record).unwrap();
+        // Stale bytes of a longer record written before
+        data.extend_from_slice(&[0xff; 16]);
+        let mut account = Account::new(1, 0, &program_id);
+        account.data = data;
+        assert_eq!(decode_state_account(&account, &program_id), Some(record));
+
+        account.owner = Pubkey::new_unique();
+        assert_eq!(decode_state_account(&account, &program_id), None);
+    }
+
+    /// The example transaction from EIP-155
+    const EIP155_EXAMPLE: &str = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    #[test]
+    fn test_parse_eth_transaction() {
+        let raw = parse_hex(EIP155_EXAMPLE).unwrap();
+        let tx = parse_eth_transaction(&raw).unwrap();
+        assert_eq!(tx.nonce, 9);
+        assert_eq!(tx.to, Some([0x35; 20]));
+        assert_eq!(tx.chain_id, Some(1));
+
+        assert!(parse_eth_transaction(&[0u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_parse_call_request() {
+        let call = parse_call_request(&json!({
+            "from": "0x1111111111111111111111111111111111111111",
+            "to": "0x2222222222222222222222222222222222222222",
+            "input": "0x70a08231",
+            "value": "0xde0b6b3a7640000",
+            "gas": "0x5208",
+        }))
+        .unwrap();
+        assert_eq!(
+            call,
+            CallRequest {
+                from: [0x11; 20],
+                to: Some([0x22; 20]),
+                data: vec![0x70, 0xa0, 0x82, 0x31],
+                value: 1_000_000_000_000_000_000,
+                gas: Some(21_000),
+            }
+        );
+
+        let call = parse_call_request(&json!({ "data": "0x" })).unwrap();
+        assert_eq!(call.from, [0u8; 20]);
+        assert_eq!(call.to, None);
+        assert_eq!(call.gas, None);
+
+        assert!(parse_call_request(&json!({ "value": "12" })).is_err());
+        assert!(parse_call_request(&json!({ "gas": "0x1ffffffffffffffff" })).is_err());
+        assert!(parse_call_request(&json!("0x")).is_err());
+    }
+
+    #[test]
+    fn test_simulation_logs() {
+        let missing = Pubkey::new_unique();
+        let logs = vec![
+            "Program log: Transaction gas: used=43512, refund=4800".to_string(),
+            format!("Program log: Missing EVM state account: {}", missing),
+        ];
+        assert_eq!(gas_used_from_logs(&logs), Some(43_512));
+        assert_eq!(missing_accounts_from_logs(&logs), vec![missing]);
+        assert_eq!(gas_used_from_logs(&[]), None);
+    }
+
+    #[test]
+    fn test_revert_reason() {
+        // Error("Not enough balance")
+        let mut output = ERROR_SELECTOR.to_vec();
+        output.extend_from_slice(&[0u8; 31]);
+        output.push(0x20);
+        output.extend_from_slice(&[0u8; 31]);
+        output.push(18);
+        output.extend_from_slice(b"Not enough balance");
+        output.extend_from_slice(&[0u8; 14]);
+        assert_eq!(revert_reason(&output).as_deref(), Some("Not enough balance"));
+        assert_eq!(
+            RpcError::ExecutionReverted(output).to_string(),
+            "execution reverted: Not enough balance"
+        );
+
+        assert_eq!(revert_reason(&[0x4e, 0x48, 0x7b, 0x71]), None);
+        assert_eq!(RpcError::ExecutionReverted(Vec::new()).to_string(), "execution reverted");
+    }
+
+    #[test]
+    fn test_search_gas_limit() {
+        for needed in [21_000, 21_001, 45_000, 1_000_000, CALL_GAS_CAP] {
+            let mut runs = 0;
+            let mut succeeds = |gas_limit| {
+                runs += 1;
+                Ok(gas_limit >= needed)
+            };
+            let used = needed - needed / 100;
+            assert_eq!(search_gas_limit(used - 1, CALL_GAS_CAP, &mut succeeds).unwrap(), needed);
+            assert!(runs <= 20);
+        }
+
+        let mut unavailable = |_| Err(RpcError::InternalError);
+        assert!(search_gas_limit(20_999, CALL_GAS_CAP, &mut unavailable).is_err());
+    }
+
+    #[test]
+    fn test_check_affordable() {
+        let mut tx = parse_eth_transaction(&parse_hex(EIP155_EXAMPLE).unwrap()).unwrap();
+        let cost = tx.gas_limit as u128 * tx.max_fee_per_gas + tx.value;
+        assert!(check_affordable(&tx, cost).is_ok());
+        assert!(matches!(
+            check_affordable(&tx, cost - 1),
+            Err(RpcError::TransactionRejected(_))
+        ));
+
+        tx.max_fee_per_gas = GasConfig::default().max_gas_price + 1;
+        assert!(matches!(
+            check_affordable(&tx, u128::MAX),
+            Err(RpcError::TransactionRejected(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_calls_reject_historical_blocks() {
+        let server = BscEvmRpcServer::new(
+            "http://127.0.0.1:1".to_string(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Keypair::new(),
+        );
+        let call = json!({ "to": "0x2222222222222222222222222222222222222222" });
+        for method in ["eth_call", "eth_estimateGas"] {
+            let earliest = json!([call, "earliest"]);
+            let result = server.handle_request(method, earliest).await;
+            assert!(matches!(result, Err(RpcError::HistoricalState)));
+            let malformed = json!([call, { "blockNumber": 1 }]);
+            let result = server.handle_request(method, malformed).await;
+            assert!(matches!(result, Err(RpcError::InvalidParams)));
+        }
+    }
+
+    #[test]
+    fn test_compute_unit_limit() {
+        assert_eq!(compute_unit_limit(21_000), 260_000);
+        assert_eq!(compute_unit_limit(135_000), MAX_COMPUTE_UNITS as u32);
+        assert_eq!(compute_unit_limit(CALL_GAS_CAP), MAX_COMPUTE_UNITS as u32);
+    }
+
+    #[test]
+    fn test_parse_jsonrpc_message() {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_chainId" });
+        assert_eq!(
+            parse_jsonrpc_message(request.to_string().as_bytes()).unwrap(),
+            (vec![request.clone()], false)
+        );
+        assert_eq!(
+            parse_jsonrpc_message(json!([request]).to_string().as_bytes()).unwrap(),
+            (vec![request.clone()], true)
+        );
+
+        assert!(matches!(parse_jsonrpc_message(b"{"), Err(RpcError::ParseError)));
+        assert!(matches!(parse_jsonrpc_message(b"[]"), Err(RpcError::InvalidRequest)));
+        let batch = Value::Array(vec![request; MAX_BATCH_SIZE + 1]);
+        assert!(matches!(
+            parse_jsonrpc_message(batch.to_string().as_bytes()),
+            Err(RpcError::LimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_jsonrpc_envelope() {
+        let request = json!({ "jsonrpc": "2.0", "id": 7, "method": "eth_chainId" });
+        assert_eq!(parse_jsonrpc_request(&request).unwrap(), ("eth_chainId", json!([])));
+        assert!(matches!(
+            parse_jsonrpc_request(&json!({ "id": 7, "method": "eth_chainId" })),
+            Err(RpcError::InvalidRequest)
+        ));
+        assert!(matches!(
+            parse_jsonrpc_request(&json!({ "jsonrpc": "2.0", "method": "eth_call", "params": 1 })),
+            Err(RpcError::InvalidRequest)
+        ));
+
+        assert_eq!(
+            jsonrpc_response(json!(7), Ok(json!("0x1"))),
+            json!({ "jsonrpc": "2.0", "id": 7, "result": "0x1" })
+        );
+        assert_eq!(
+            jsonrpc_response(json!("a"), Err(RpcError::MethodNotFound)),
+            json!({
+                "jsonrpc": "2.0",
+                "id": "a",
+                "error": { "code": -32601, "message": "Method not found" },
+            })
+        );
+
+        let error = RpcError::ExecutionReverted(vec![0xde, 0xad]).to_json();
+        assert_eq!(error["code"], 3);
+        assert_eq!(error["data"], "0xdead");
+    }
 }
@@ -0,0 +1,146 @@
+//! Standalone Ethereum JSON-RPC server for a cluster running the bsc-evm program
+
+use {
+    clap::{crate_description, crate_name, crate_version, value_t_or_exit, App, Arg},
+    rpc_bsc_evm::service::{EvmRpcService, EvmRpcServiceConfig, MAX_REQUEST_BODY_SIZE},
+    solana_clap_utils::{
+        input_parsers::{keypair_of, pubkey_of},
+        input_validators::{
+            is_keypair, is_parsable, is_pubkey, is_url_or_moniker, normalize_to_url_if_moniker,
+        },
+    },
+    solana_cli_config::Config,
+    std::{
+        net::{IpAddr, SocketAddr},
+        path::PathBuf,
+        process::exit,
+    },
+};
+
+/// Port Ethereum clients expect a JSON-RPC server on
+const DEFAULT_PORT: &str = "8545";
+
+fn main() {
+    solana_logger::setup_with_default("solana=info,rpc_bsc_evm=info");
+
+    let default_max_request_body_size = MAX_REQUEST_BODY_SIZE.to_string();
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .short("u")
+                .long("url")
+                .value_name("URL_OR_MONIKER")
+                .takes_value(true)
+                .default_value("http://127.0.0.1:8899")
+                .validator(is_url_or_moniker)
+                .help("Solana JSON RPC URL of the cluster running the bsc-evm program"),
+        )
+        .arg(
+            Arg::with_name("websocket_url")
+                .long("ws")
+                .value_name("URL")
+                .takes_value(true)
+                .help("Solana pubsub URL of the cluster [default: derived from --url]"),
+        )
+        .arg(
+            Arg::with_name("evm_program_id")
+                .long("evm-program-id")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .required(true)
+                .validator(is_pubkey)
+                .help("Address of the bsc-evm program"),
+        )
+        .arg(
+            Arg::with_name("evm_state")
+                .long("evm-state")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .required(true)
+                .validator(is_pubkey)
+                .help("State account of the bsc-evm program"),
+        )
+        .arg(
+            Arg::with_name("eth_bridge_program_id")
+                .long("eth-bridge-program-id")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .validator(is_pubkey)
+                .help("Address of the eth-bridge program whose token balances are served"),
+        )
+        .arg(
+            Arg::with_name("payer")
+                .long("payer")
+                .value_name("KEYPAIR")
+                .takes_value(true)
+                .required(true)
+                .validator(is_keypair)
+                .help("Keypair paying for relayed and simulated transactions"),
+        )
+        .arg(
+            Arg::with_name("bind_address")
+                .long("bind-address")
+                .value_name("HOST")
+                .takes_value(true)
+                .default_value("0.0.0.0")
+                .validator(is_parsable::<IpAddr>)
+                .help("IP address to bind the JSON RPC and websocket ports"),
+        )
+        .arg(
+            Arg::with_name("port")
+                .long("port")
+                .value_name("PORT")
+                .takes_value(true)
+                .default_value(DEFAULT_PORT)
+                .validator(is_parsable::<u16>)
+                .help("Serve JSON RPC on this port, and the next port for the websocket"),
+        )
+        .arg(
+            Arg::with_name("transaction_index")
+                .long("transaction-index")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("File to index relayed transactions in [default: in memory]"),
+        )
+        .arg(
+            Arg::with_name("max_request_body_size")
+                .long("max-request-body-size")
+                .value_name("BYTES")
+                .takes_value(true)
+                .default_value(&default_max_request_body_size)
+                .validator(is_parsable::<usize>)
+                .help("The maximum request body or websocket message size accepted"),
+        )
+        .get_matches();
+
+    let json_rpc_url = normalize_to_url_if_moniker(matches.value_of("json_rpc_url").unwrap());
+    let websocket_url = matches
+        .value_of("websocket_url")
+        .map(str::to_string)
+        .unwrap_or_else(|| Config::compute_websocket_url(&json_rpc_url));
+    let bind_address = value_t_or_exit!(matches, "bind_address", IpAddr);
+    let port = value_t_or_exit!(matches, "port", u16);
+
+    let mut config = EvmRpcServiceConfig::new(
+        SocketAddr::new(bind_address, port),
+        json_rpc_url,
+        websocket_url,
+        pubkey_of(&matches, "evm_program_id").unwrap(),
+        pubkey_of(&matches, "evm_state").unwrap(),
+    );
+    config.transaction_index_path = matches.value_of("transaction_index").map(PathBuf::from);
+    config.max_request_body_size = value_t_or_exit!(matches, "max_request_body_size", usize);
+    config.eth_bridge_program_id = pubkey_of(&matches, "eth_bridge_program_id");
+    let payer = keypair_of(&matches, "payer").unwrap_or_else(|| {
+        eprintln!("Error: unable to read the payer keypair");
+        exit(1);
+    });
+
+    let service = EvmRpcService::new(config, payer).unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        exit(1);
+    });
+    service.join().unwrap();
+}
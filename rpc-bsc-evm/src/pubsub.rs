@@ -0,0 +1,638 @@
+//! # Ethereum Subscriptions
+//!
+//! WebSocket endpoint speaking the `eth_subscribe` protocol, fed by the
+//! cluster's Solana pubsub service:
+//!
+//! - `newHeads`: on every `slotSubscribe` notification the blocks confirmed
+//!   since the last announced one are fetched, and their headers announced in
+//!   order
+//! - `logs`: the logs of those blocks that match the subscription's address
+//!   and topic filter, see [`LogFilter`]
+//! - `newPendingTransactions`: the hashes of Ethereum transactions as the
+//!   bsc-evm program announces them, read from a `logsSubscribe` on the
+//!   program at processed commitment, see [`bsc_evm::events`]
+//!
+//! Blocks are fetched with `getBlock` rather than `blockSubscribe`, which
+//! validators only offer when started with
+//! `--rpc-pubsub-enable-block-subscription`.
+//!
+//! Every other request on a connection is answered by the
+//! [`BscEvmRpcServer`].
+
+use {
+    crate::{
+        blocks::{data, program_data, quantity, Block},
+        filters::LogFilter,
+        jsonrpc_response, parse_jsonrpc_message, parse_jsonrpc_request,
+        service::handle_blocking,
+        BscEvmRpcServer, RpcError, MAX_LOG_BLOCK_RANGE,
+    },
+    bsc_evm::events::decode_transaction_hash,
+    futures_util::{SinkExt, StreamExt},
+    log::warn,
+    serde_json::{json, Value},
+    solana_pubsub_client::nonblocking::pubsub_client::{PubsubClient, PubsubClientError},
+    solana_rpc_client_api::config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    solana_sdk::commitment_config::CommitmentConfig,
+    std::{
+        collections::HashMap,
+        io,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tokio::{
+        io::{AsyncRead, AsyncWrite},
+        net::TcpListener,
+        sync::{broadcast, broadcast::error::RecvError, mpsc},
+        task::JoinHandle,
+    },
+    tokio_tungstenite::{
+        accept_async_with_config,
+        tungstenite::{protocol::WebSocketConfig, Message},
+    },
+};
+
+/// Events buffered for each subscription before a slow one misses some
+const EVENT_CAPACITY: usize = 1_024;
+
+/// Messages queued for each connection
+const OUTGOING_CAPACITY: usize = 1_024;
+
+/// Most subscriptions one connection can hold
+const MAX_SUBSCRIPTIONS: usize = 128;
+
+/// Wait before reconnecting to the Solana pubsub service
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Something subscriptions may be notified of
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A block was confirmed
+    Head(Arc<Block>),
+    /// A transaction was executed and is not confirmed yet
+    PendingTransaction([u8; 32]),
+}
+
+/// What a subscription reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    /// Headers of new blocks
+    NewHeads,
+    /// Logs of new blocks matching the filter
+    Logs(LogFilter),
+    /// Hashes of executed transactions
+    NewPendingTransactions,
+}
+
+impl SubscriptionKind {
+    /// Parse the parameters of `eth_subscribe`
+    ///
+    /// A log subscription follows new blocks, so its filter cannot name blocks.
+    pub fn parse(params: &Value) -> Result<Self, RpcError> {
+        match params[0].as_str() {
+            Some("newHeads") => Ok(Self::NewHeads),
+            Some("logs") => {
+                let filter = match &params[1] {
+                    Value::Null => LogFilter::default(),
+                    filter_object => LogFilter::parse(filter_object)?,
+                };
+                if filter.from_block.is_some()
+                    || filter.to_block.is_some()
+                    || filter.block_hash.is_some()
+                {
+                    return Err(RpcError::InvalidParams);
+                }
+                Ok(Self::Logs(filter))
+            }
+            Some("newPendingTransactions") => Ok(Self::NewPendingTransactions),
+            _ => Err(RpcError::InvalidParams),
+        }
+    }
+
+    /// Results to notify of `event`, in order
+    pub fn results(&self, event: &Event) -> Vec<Value> {
+        match (self, event) {
+            (Self::NewHeads, Event::Head(block)) => vec![block.header_json()],
+            (Self::Logs(filter), Event::Head(block)) => {
+                block.matching_logs(|log| filter.matches(log))
+            }
+            (Self::NewPendingTransactions, Event::PendingTransaction(hash)) => {
+                vec![json!(data(hash))]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// `eth_subscription` notification of `result` to the subscription `id`
+pub fn notification(id: &str, result: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": {
+            "subscription": id,
+            "result": result,
+        },
+    })
+}
+
+/// WebSocket service for Ethereum subscriptions
+pub struct PubsubService {
+    server: Arc<BscEvmRpcServer>,
+    solana_pubsub_url: String,
+    events: broadcast::Sender<Event>,
+    next_subscription_id: AtomicU64,
+    /// Largest message accepted from clients, in bytes
+    max_message_size: Option<usize>,
+}
+
+impl PubsubService {
+    /// Service answering with `server` and following the Solana pubsub
+    /// service at `solana_pubsub_url`
+    pub fn new(server: Arc<BscEvmRpcServer>, solana_pubsub_url: String) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            server,
+            solana_pubsub_url,
+            events,
+            next_subscription_id: AtomicU64::new(1),
+            max_message_size: None,
+        }
+    }
+
+    /// Reject client messages larger than `max_message_size` bytes
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = Some(max_message_size);
+        self
+    }
+
+    /// Follow the cluster and serve the connections accepted on `listener`
+    pub async fn run(self, listener: TcpListener) -> io::Result<()> {
+        let service = Arc::new(self);
+        tokio::spawn(service.clone().follow_cluster());
+        loop {
+            let (stream, _) = listener.accept().await?;
+            tokio::spawn(service.clone().serve_connection(stream));
+        }
+    }
+
+    /// Publish `event` to every subscription
+    pub fn publish(&self, event: Event) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(event);
+    }
+
+    /// Turn Solana pubsub notifications into events, reconnecting whenever
+    /// the connection drops
+    pub async fn follow_cluster(self: Arc<Self>) {
+        let mut next_block = None;
+        loop {
+            match self.follow_connection(&mut next_block).await {
+                Ok(()) => warn!("Solana pubsub connection closed"),
+                Err(err) => warn!("Solana pubsub connection failed: {}", err),
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    /// Publish events until the Solana pubsub connection drops
+    ///
+    /// `next_block` is the first block not announced yet, kept across
+    /// connections so no block is missed while reconnecting.
+    async fn follow_connection(
+        &self,
+        next_block: &mut Option<u64>,
+    ) -> Result<(), PubsubClientError> {
+        let client = PubsubClient::new(&self.solana_pubsub_url).await?;
+        let (mut slots, _slots_unsubscribe) = client.slot_subscribe().await?;
+        let (mut program_logs, _logs_unsubscribe) = client
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![self.server.evm_program_id.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(CommitmentConfig::processed()),
+                },
+            )
+            .await?;
+
+        loop {
+            tokio::select! {
+                slot = slots.next() => {
+                    if slot.is_none() {
+                        return Ok(());
+                    }
+                    self.publish_new_blocks(next_block).await;
+                }
+                response = program_logs.next() => {
+                    let Some(response) = response else {
+                        return Ok(());
+                    };
+                    for hash in pending_transaction_hashes(&response.value.logs) {
+                        self.publish(Event::PendingTransaction(hash));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Publish the blocks confirmed since `next_block`, and advance it past them
+    async fn publish_new_blocks(&self, next_block: &mut Option<u64>) {
+        let server = self.server.clone();
+        let first = *next_block;
+        match tokio::task::spawn_blocking(move || new_blocks(&server, first)).await {
+            Ok(Ok((blocks, next))) => {
+                for block in blocks {
+                    self.publish(Event::Head(Arc::new(block)));
+                }
+                *next_block = Some(next);
+            }
+            // Retried on the next slot
+            Ok(Err(err)) => warn!("Failed to fetch new blocks: {}", err),
+            Err(err) => warn!("Failed to fetch new blocks: {}", err),
+        }
+    }
+
+    /// Serve one WebSocket connection until the client closes it
+    pub async fn serve_connection<S>(self: Arc<Self>, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let config = WebSocketConfig {
+            max_message_size: self.max_message_size,
+            max_frame_size: self.max_message_size,
+            ..WebSocketConfig::default()
+        };
+        let websocket = match accept_async_with_config(stream, Some(config)).await {
+            Ok(websocket) => websocket,
+            Err(err) => {
+                warn!("WebSocket handshake failed: {}", err);
+                return;
+            }
+        };
+        let (mut sink, mut incoming) = websocket.split();
+        let (outgoing, mut outgoing_rx) = mpsc::channel::<String>(OUTGOING_CAPACITY);
+        let writer = tokio::spawn(async move {
+            while let Some(text) = outgoing_rx.recv().await {
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut connection = Connection {
+            service: self,
+            outgoing: outgoing.clone(),
+            subscriptions: HashMap::new(),
+        };
+        while let Some(Ok(message)) = incoming.next().await {
+            let message = match message {
+                Message::Text(text) => text.into_bytes(),
+                Message::Binary(bytes) => bytes,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let response = connection.handle_message(&message).await;
+            if outgoing.send(response.to_string()).await.is_err() {
+                break;
+            }
+        }
+        drop(connection);
+        writer.abort();
+    }
+}
+
+/// Blocks confirmed from `next_block` on, and the first block after them
+///
+/// Without a `next_block` nothing is returned and the next block is the one
+/// after the latest. At most [`MAX_LOG_BLOCK_RANGE`] slots are searched, the
+/// rest is returned by the next calls.
+fn new_blocks(
+    server: &BscEvmRpcServer,
+    next_block: Option<u64>,
+) -> Result<(Vec<Block>, u64), RpcError> {
+    let latest = server.block_slot("latest")?;
+    let first = match next_block {
+        Some(first) if first <= latest => first,
+        Some(first) => return Ok((Vec::new(), first)),
+        None => return Ok((Vec::new(), latest + 1)),
+    };
+    let last = latest.min(first.saturating_add(MAX_LOG_BLOCK_RANGE - 1));
+
+    let mut blocks = Vec::new();
+    for slot in server.confirmed_slots(first, last)? {
+        if let Some(block) = server.get_block(slot)? {
+            blocks.push(block);
+        }
+    }
+    Ok((blocks, last + 1))
+}
+
+/// Hashes of the transactions announced in a transaction's log messages
+pub fn pending_transaction_hashes(logs: &[String]) -> Vec<[u8; 32]> {
+    logs.iter()
+        .filter_map(|log| program_data(log))
+        .filter_map(|fields| decode_transaction_hash(&fields))
+        .collect()
+}
+
+/// Subscriptions of one WebSocket connection, cancelled when it is dropped
+struct Connection {
+    service: Arc<PubsubService>,
+    outgoing: mpsc::Sender<String>,
+    /// Notification task of each subscription id
+    subscriptions: HashMap<String, JoinHandle<()>>,
+}
+
+impl Connection {
+    /// Answer a request or batch of requests
+    async fn handle_message(&mut self, message: &[u8]) -> Value {
+        let (requests, batch) = match parse_jsonrpc_message(message) {
+            Ok(message) => message,
+            Err(err) => return jsonrpc_response(Value::Null, Err(err)),
+        };
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in &requests {
+            responses.push(self.handle_request(request).await);
+        }
+        if batch {
+            Value::Array(responses)
+        } else {
+            responses.pop().unwrap_or_default()
+        }
+    }
+
+    async fn handle_request(&mut self, request: &Value) -> Value {
+        let result = match parse_jsonrpc_request(request) {
+            Ok(("eth_subscribe", params)) => self.subscribe(&params),
+            Ok(("eth_unsubscribe", params)) => self.unsubscribe(&params),
+            Ok(_) => return handle_blocking(self.service.server.clone(), request.clone()).await,
+            Err(err) => Err(err),
+        };
+        jsonrpc_response(request["id"].clone(), result)
+    }
+
+    /// eth_subscribe - Starts notifying of new heads, logs or pending transactions
+    fn subscribe(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let kind = SubscriptionKind::parse(params)?;
+        if self.subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            return Err(RpcError::LimitExceeded(format!(
+                "connections are limited to {} subscriptions",
+                MAX_SUBSCRIPTIONS
+            )));
+        }
+
+        let id = self
+            .service
+            .next_subscription_id
+            .fetch_add(1, Ordering::Relaxed);
+        let id = quantity(id as u128);
+        let task = tokio::spawn(notify(
+            kind,
+            id.clone(),
+            self.service.events.subscribe(),
+            self.outgoing.clone(),
+        ));
+        self.subscriptions.insert(id.clone(), task);
+        Ok(json!(id))
+    }
+
+    /// eth_unsubscribe - Cancels a subscription of this connection
+    fn unsubscribe(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let id = params[0].as_str().ok_or(RpcError::InvalidParams)?;
+        let task = self.subscriptions.remove(id);
+        if let Some(task) = &task {
+            task.abort();
+        }
+        Ok(json!(task.is_some()))
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for task in self.subscriptions.values() {
+            task.abort();
+        }
+    }
+}
+
+/// Send the notifications of subscription `id` until the connection closes
+async fn notify(
+    kind: SubscriptionKind,
+    id: String,
+    mut events: broadcast::Receiver<Event>,
+    outgoing: mpsc::Sender<String>,
+) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            // A subscription that falls behind skips the events it missed
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+        for result in kind.results(&event) {
+            if outgoing
+                .send(notification(&id, result).to_string())
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::blocks::BlockTransaction,
+        base64::{prelude::BASE64_STANDARD, Engine},
+        bsc_evm::{events::TRANSACTION_TAG, evm_interpreter::Log},
+        solana_sdk::{pubkey::Pubkey, signature::Keypair},
+        tokio::net::TcpStream,
+        tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream},
+    };
+
+    fn block(logs: Vec<Log>) -> Block {
+        let transaction = bsc_evm_rlp::SignedTransaction::decode(&hex::decode(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+        ).unwrap())
+        .unwrap();
+        Block {
+            number: 9,
+            hash: [0x99; 32],
+            parent_hash: [0x98; 32],
+            timestamp: 1_700_000_000,
+            base_fee: 0,
+            transactions: vec![BlockTransaction {
+                hash: transaction.hash(),
+                signature: Default::default(),
+                from: [0x55; 20],
+                transaction,
+                success: true,
+                gas_used: 21_000,
+                logs,
+            }],
+        }
+    }
+
+    fn log(address: u8, topic: u8) -> Log {
+        Log {
+            address: [address; 20],
+            topics: vec![[topic; 32]],
+            data: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_subscription() {
+        assert_eq!(
+            SubscriptionKind::parse(&json!(["newHeads"])).unwrap(),
+            SubscriptionKind::NewHeads
+        );
+        assert_eq!(
+            SubscriptionKind::parse(&json!(["newPendingTransactions"])).unwrap(),
+            SubscriptionKind::NewPendingTransactions
+        );
+        assert_eq!(
+            SubscriptionKind::parse(&json!(["logs"])).unwrap(),
+            SubscriptionKind::Logs(LogFilter::default())
+        );
+        assert_eq!(
+            SubscriptionKind::parse(&json!([
+                "logs",
+                { "address": "0x1111111111111111111111111111111111111111" },
+            ]))
+            .unwrap(),
+            SubscriptionKind::Logs(LogFilter {
+                addresses: vec![[0x11; 20]],
+                ..LogFilter::default()
+            })
+        );
+
+        assert!(SubscriptionKind::parse(&json!(["logs", { "fromBlock": "0x1" }])).is_err());
+        assert!(SubscriptionKind::parse(&json!(["syncing"])).is_err());
+        assert!(SubscriptionKind::parse(&json!([])).is_err());
+    }
+
+    #[test]
+    fn test_subscription_results() {
+        let head = Event::Head(Arc::new(block(vec![log(0x11, 0xaa), log(0x22, 0xbb)])));
+        let pending = Event::PendingTransaction([0x33; 32]);
+
+        let heads = SubscriptionKind::NewHeads.results(&head);
+        assert_eq!(heads.len(), 1);
+        assert_eq!(heads[0]["number"], "0x9");
+        assert!(SubscriptionKind::NewHeads.results(&pending).is_empty());
+
+        let logs = SubscriptionKind::Logs(LogFilter {
+            addresses: vec![[0x22; 20]],
+            ..LogFilter::default()
+        });
+        let results = logs.results(&head);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["logIndex"], "0x1");
+        assert!(logs.results(&pending).is_empty());
+
+        let transactions = SubscriptionKind::NewPendingTransactions;
+        assert_eq!(
+            transactions.results(&pending),
+            vec![json!(data(&[0x33; 32]))]
+        );
+        assert!(transactions.results(&head).is_empty());
+    }
+
+    #[test]
+    fn test_pending_transaction_hashes() {
+        let logs = vec![
+            "Program invoke [1]".to_string(),
+            format!(
+                "Program data: {} {}",
+                BASE64_STANDARD.encode(TRANSACTION_TAG),
+                BASE64_STANDARD.encode([0x44; 32])
+            ),
+            format!(
+                "Program data: {} {}",
+                BASE64_STANDARD.encode(bsc_evm::events::LOG_TAG),
+                BASE64_STANDARD.encode([0x44; 32])
+            ),
+            "Program data: not-base64".to_string(),
+        ];
+        assert_eq!(pending_transaction_hashes(&logs), vec![[0x44; 32]]);
+    }
+
+    type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    async fn receive(websocket: &mut Client) -> Value {
+        let Some(Ok(Message::Text(text))) = websocket.next().await else {
+            panic!("connection closed");
+        };
+        serde_json::from_str(&text).unwrap()
+    }
+
+    async fn request(websocket: &mut Client, request: Value) -> Value {
+        websocket
+            .send(Message::Text(request.to_string()))
+            .await
+            .unwrap();
+        receive(websocket).await
+    }
+
+    #[tokio::test]
+    async fn test_websocket_subscriptions() {
+        let server = BscEvmRpcServer::new(
+            "http://127.0.0.1:1".to_string(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Keypair::new(),
+        );
+        let service = Arc::new(PubsubService::new(
+            Arc::new(server),
+            "ws://127.0.0.1:1".to_string(),
+        ));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let accepting = service.clone();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            accepting.serve_connection(stream).await;
+        });
+
+        let (mut websocket, _) = connect_async(format!("ws://{}", address)).await.unwrap();
+        let response = request(
+            &mut websocket,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_subscribe",
+                "params": ["newPendingTransactions"],
+            }),
+        )
+        .await;
+        let id = response["result"].as_str().unwrap().to_string();
+
+        service.publish(Event::PendingTransaction([0x77; 32]));
+        assert_eq!(
+            receive(&mut websocket).await,
+            notification(&id, json!(data(&[0x77; 32])))
+        );
+
+        let responses = request(
+            &mut websocket,
+            json!([
+                { "jsonrpc": "2.0", "id": 2, "method": "eth_unsubscribe", "params": [id] },
+                { "jsonrpc": "2.0", "id": 3, "method": "eth_unsubscribe", "params": [id] },
+                { "jsonrpc": "2.0", "id": 4, "method": "eth_mining" },
+            ]),
+        )
+        .await;
+        assert_eq!(responses[0]["result"], true);
+        assert_eq!(responses[1]["result"], false);
+        assert_eq!(responses[2]["error"]["code"], -32601);
+
+        let response = request(&mut websocket, json!("not a request")).await;
+        assert_eq!(response["error"]["code"], -32600);
+    }
+}
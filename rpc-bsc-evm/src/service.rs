@@ -0,0 +1,448 @@
+//! # Ethereum JSON-RPC Service
+//!
+//! Serves a [`BscEvmRpcServer`] over HTTP, and `eth_subscribe` over
+//! WebSocket on a second port, see [`pubsub`](crate::pubsub). The HTTP
+//! endpoint accepts single and batch requests POSTed to any path, answers
+//! CORS preflight requests for any origin, and rejects bodies larger than the
+//! configured limit.
+//!
+//! The service runs on its own thread and Tokio runtime, so it can be
+//! started standalone or next to a validator.
+
+use {
+    crate::{
+        jsonrpc_response, parse_jsonrpc_message, pubsub::PubsubService, BscEvmRpcServer, RpcError,
+        SlotState, TransactionIndex,
+    },
+    hyper::{
+        body::HttpBody,
+        header::{self, HeaderValue},
+        server::conn::AddrIncoming,
+        service::{make_service_fn, service_fn},
+        Body, Method, Request, Response, Server, StatusCode,
+    },
+    log::{info, warn},
+    serde_json::Value,
+    solana_sdk::{pubkey::Pubkey, signature::Keypair},
+    std::{
+        convert::Infallible,
+        net::SocketAddr,
+        path::PathBuf,
+        sync::Arc,
+        thread::{self, JoinHandle},
+        time::Duration,
+    },
+    tokio::{net::TcpListener, runtime::Handle, sync::oneshot},
+};
+
+/// Default largest request body, in bytes
+pub const MAX_REQUEST_BODY_SIZE: usize = 5 * 1024 * 1024;
+
+/// How long shutting down waits for requests still reading from the cluster
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Configuration of an [`EvmRpcService`]
+#[derive(Debug, Clone)]
+pub struct EvmRpcServiceConfig {
+    /// Address of the HTTP endpoint
+    pub rpc_addr: SocketAddr,
+    /// Address of the WebSocket endpoint
+    pub pubsub_addr: SocketAddr,
+    /// Solana JSON RPC of the cluster running the bsc-evm program
+    pub solana_rpc_url: String,
+    /// Solana pubsub service of the same cluster
+    pub solana_pubsub_url: String,
+    pub evm_program_id: Pubkey,
+    /// State account of the bsc-evm program
+    pub evm_state: Pubkey,
+    /// File the relayed transactions are indexed in, in memory if `None`
+    pub transaction_index_path: Option<PathBuf>,
+    /// Largest HTTP request body or WebSocket message, in bytes
+    pub max_request_body_size: usize,
+    /// State of recent slots to trace transactions against, see
+    /// [`BscEvmRpcServer::with_slot_state`]
+    pub slot_state: Option<Arc<dyn SlotState>>,
+    /// eth-bridge program whose token balances are served, see
+    /// [`BscEvmRpcServer::with_eth_bridge`]
+    pub eth_bridge_program_id: Option<Pubkey>,
+}
+
+impl EvmRpcServiceConfig {
+    /// Configuration serving on `rpc_addr` and the next port
+    pub fn new(
+        rpc_addr: SocketAddr,
+        solana_rpc_url: String,
+        solana_pubsub_url: String,
+        evm_program_id: Pubkey,
+        evm_state: Pubkey,
+    ) -> Self {
+        let pubsub_addr = SocketAddr::new(rpc_addr.ip(), rpc_addr.port().saturating_add(1));
+        Self {
+            rpc_addr,
+            pubsub_addr,
+            solana_rpc_url,
+            solana_pubsub_url,
+            evm_program_id,
+            evm_state,
+            transaction_index_path: None,
+            max_request_body_size: MAX_REQUEST_BODY_SIZE,
+            slot_state: None,
+            eth_bridge_program_id: None,
+        }
+    }
+}
+
+/// Running Ethereum JSON-RPC service
+pub struct EvmRpcService {
+    thread_hdl: JoinHandle<()>,
+    rpc_addr: SocketAddr,
+    pubsub_addr: SocketAddr,
+    exit_sender: Option<oneshot::Sender<()>>,
+}
+
+impl EvmRpcService {
+    /// Bind the endpoints and start serving, with `payer` paying for relayed transactions
+    pub fn new(config: EvmRpcServiceConfig, payer: Keypair) -> Result<Self, String> {
+        let mut server = BscEvmRpcServer::new(
+            config.solana_rpc_url,
+            config.evm_program_id,
+            config.evm_state,
+            payer,
+        );
+        if let Some(path) = &config.transaction_index_path {
+            let transaction_index = TransactionIndex::open(path).map_err(|err| {
+                format!(
+                    "failed to open transaction index {}: {}",
+                    path.display(),
+                    err
+                )
+            })?;
+            server = server.with_transaction_index(transaction_index);
+        }
+        if let Some(slot_state) = config.slot_state.clone() {
+            server = server.with_slot_state(slot_state);
+        }
+        if let Some(program_id) = config.eth_bridge_program_id {
+            server = server.with_eth_bridge(program_id);
+        }
+        let server = Arc::new(server);
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("solEvmRpcEl")
+            .enable_all()
+            .build()
+            .map_err(|err| err.to_string())?;
+        // Bind before returning so an address in use is reported to the caller
+        let (http, pubsub_listener) = runtime.block_on(async {
+            let http = Server::try_bind(&config.rpc_addr).map_err(|err| {
+                format!("EVM JSON RPC unavailable on {}: {}", config.rpc_addr, err)
+            })?;
+            let pubsub_listener = TcpListener::bind(config.pubsub_addr).await.map_err(|err| {
+                format!(
+                    "EVM RPC websocket unavailable on {}: {}",
+                    config.pubsub_addr, err
+                )
+            })?;
+            Ok::<_, String>((http, pubsub_listener))
+        })?;
+        let rpc_addr = http.local_addr();
+        let pubsub_addr = pubsub_listener
+            .local_addr()
+            .map_err(|err| err.to_string())?;
+        info!(
+            "EVM JSON RPC bound to {} and websocket to {}",
+            rpc_addr, pubsub_addr
+        );
+
+        let (exit_sender, exit_receiver) = oneshot::channel();
+        let pubsub = PubsubService::new(server.clone(), config.solana_pubsub_url)
+            .with_max_message_size(config.max_request_body_size);
+        let max_request_body_size = config.max_request_body_size;
+        let thread_hdl = thread::Builder::new()
+            .name("solEvmRpcSvc".to_string())
+            .spawn(move || {
+                runtime.block_on(serve(
+                    server,
+                    http,
+                    max_request_body_size,
+                    pubsub,
+                    pubsub_listener,
+                    exit_receiver,
+                ));
+                runtime.shutdown_timeout(SHUTDOWN_TIMEOUT);
+            })
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            thread_hdl,
+            rpc_addr,
+            pubsub_addr,
+            exit_sender: Some(exit_sender),
+        })
+    }
+
+    /// Address the HTTP endpoint is bound to
+    pub fn rpc_addr(&self) -> SocketAddr {
+        self.rpc_addr
+    }
+
+    /// Address the WebSocket endpoint is bound to
+    pub fn pubsub_addr(&self) -> SocketAddr {
+        self.pubsub_addr
+    }
+
+    pub fn exit(&mut self) {
+        if let Some(exit_sender) = self.exit_sender.take() {
+            let _ = exit_sender.send(());
+        }
+    }
+
+    /// Wait until the service stops, which only happens after [`Self::exit`]
+    /// or if a listener fails
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+/// Serve both endpoints until `exit` fires or one of them fails
+async fn serve(
+    server: Arc<BscEvmRpcServer>,
+    http: hyper::server::Builder<AddrIncoming>,
+    max_request_body_size: usize,
+    pubsub: PubsubService,
+    pubsub_listener: TcpListener,
+    exit: oneshot::Receiver<()>,
+) {
+    let make_service = make_service_fn(move |_| {
+        let server = server.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let server = server.clone();
+                async move {
+                    Ok::<_, Infallible>(
+                        handle_http_request(&server, request, max_request_body_size).await,
+                    )
+                }
+            }))
+        }
+    });
+    let http = http.serve(make_service).with_graceful_shutdown(async {
+        let _ = exit.await;
+    });
+
+    tokio::select! {
+        result = http => {
+            if let Err(err) = result {
+                warn!("EVM JSON RPC stopped: {}", err);
+            }
+        }
+        result = pubsub.run(pubsub_listener) => {
+            if let Err(err) = result {
+                warn!("EVM RPC websocket stopped: {}", err);
+            }
+        }
+    }
+}
+
+/// Answer one HTTP request
+pub async fn handle_http_request(
+    server: &Arc<BscEvmRpcServer>,
+    request: Request<Body>,
+    max_request_body_size: usize,
+) -> Response<Body> {
+    match *request.method() {
+        Method::POST => {}
+        Method::OPTIONS => {
+            let mut response = empty_response(StatusCode::NO_CONTENT);
+            let headers = response.headers_mut();
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_METHODS,
+                HeaderValue::from_static("POST, OPTIONS"),
+            );
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_HEADERS,
+                HeaderValue::from_static("content-type"),
+            );
+            headers.insert(
+                header::ACCESS_CONTROL_MAX_AGE,
+                HeaderValue::from_static("86400"),
+            );
+            return response;
+        }
+        _ => return empty_response(StatusCode::METHOD_NOT_ALLOWED),
+    }
+
+    let Some(body) = read_body(request.into_body(), max_request_body_size).await else {
+        return empty_response(StatusCode::PAYLOAD_TOO_LARGE);
+    };
+    let mut response = Response::new(Body::from(handle_json_rpc(server, &body).await.to_string()));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    allow_any_origin(&mut response);
+    response
+}
+
+/// Answer a JSON-RPC message, a request or a batch of requests
+pub async fn handle_json_rpc(server: &Arc<BscEvmRpcServer>, message: &[u8]) -> Value {
+    let (requests, batch) = match parse_jsonrpc_message(message) {
+        Ok(message) => message,
+        Err(err) => return jsonrpc_response(Value::Null, Err(err)),
+    };
+    let mut responses = Vec::with_capacity(requests.len());
+    for request in &requests {
+        responses.push(handle_blocking(server.clone(), request.clone()).await);
+    }
+    if batch {
+        Value::Array(responses)
+    } else {
+        responses.pop().unwrap_or_default()
+    }
+}
+
+/// Answer a JSON-RPC request on the blocking pool, as the server's RPC client
+/// blocks until the cluster responds
+pub async fn handle_blocking(server: Arc<BscEvmRpcServer>, request: Value) -> Value {
+    let id = request["id"].clone();
+    let runtime = Handle::current();
+    let response =
+        tokio::task::spawn_blocking(move || runtime.block_on(server.handle_json_request(&request)));
+    match response.await {
+        Ok(response) => response,
+        Err(err) => {
+            warn!("EVM JSON RPC request failed: {}", err);
+            jsonrpc_response(id, Err(RpcError::InternalError))
+        }
+    }
+}
+
+/// Body of at most `limit` bytes, `None` if it is larger or cannot be read
+async fn read_body(mut body: Body, limit: usize) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.ok()?;
+        if bytes.len() + chunk.len() > limit {
+            return None;
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Some(bytes)
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    allow_any_origin(&mut response);
+    response
+}
+
+fn allow_any_origin(response: &mut Response<Body>) {
+    response.headers_mut().insert(
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        HeaderValue::from_static("*"),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        serde_json::json,
+        std::net::{IpAddr, Ipv4Addr},
+    };
+
+    fn test_server() -> BscEvmRpcServer {
+        BscEvmRpcServer::new(
+            "http://127.0.0.1:1".to_string(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Keypair::new(),
+        )
+    }
+
+    fn post(body: impl Into<Body>) -> Request<Body> {
+        Request::post("/").body(body.into()).unwrap()
+    }
+
+    async fn json_body(response: Response<Body>) -> Value {
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_json_rpc_messages() {
+        let server = Arc::new(test_server());
+        let version = json!({ "jsonrpc": "2.0", "id": 1, "method": "web3_clientVersion" });
+        let unknown = json!({ "jsonrpc": "2.0", "id": 2, "method": "eth_mining" });
+
+        let response = handle_json_rpc(&server, version.to_string().as_bytes()).await;
+        assert_eq!(response["id"], 1);
+        assert!(response["result"].is_string());
+
+        let responses =
+            handle_json_rpc(&server, json!([version, unknown]).to_string().as_bytes()).await;
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[1]["error"]["code"], -32601);
+
+        let response = handle_json_rpc(&server, json!([version]).to_string().as_bytes()).await;
+        assert!(response.is_array());
+
+        let response = handle_json_rpc(&server, b"{").await;
+        assert_eq!(response["error"]["code"], -32700);
+        let response = handle_json_rpc(&server, b"[]").await;
+        assert_eq!(response["error"]["code"], -32600);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_http_requests() {
+        let server = Arc::new(test_server());
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "web3_clientVersion" });
+
+        let response = handle_http_request(&server, post(request.to_string()), 1_024).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(json_body(response).await["id"], 1);
+
+        let response = handle_http_request(&server, post(request.to_string()), 16).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let preflight = Request::options("/").body(Body::empty()).unwrap();
+        let response = handle_http_request(&server, preflight, 1_024).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS],
+            "content-type"
+        );
+
+        let get = Request::get("/").body(Body::empty()).unwrap();
+        let response = handle_http_request(&server, get, 1_024).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn test_service_starts_and_exits() {
+        let localhost = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+        let mut config = EvmRpcServiceConfig::new(
+            localhost,
+            "http://127.0.0.1:1".to_string(),
+            "ws://127.0.0.1:1".to_string(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        config.pubsub_addr = localhost;
+
+        let mut service = EvmRpcService::new(config.clone(), Keypair::new()).unwrap();
+        assert_ne!(service.rpc_addr().port(), 0);
+        assert_ne!(service.pubsub_addr().port(), 0);
+
+        // The address is taken while the service runs
+        config.rpc_addr = service.rpc_addr();
+        assert!(EvmRpcService::new(config, Keypair::new()).is_err());
+
+        service.exit();
+        service.join().unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+//! # Bridged Token Balances
+//!
+//! An Ethereum address holds SPL and Token-2022 tokens through its eth-bridge
+//! PDA, see [`eth_bridge::derive_pda`], which signs for them under the
+//! address's signature. `bssc_getTokenBalances` lists them with ERC-20 style
+//! addresses and balances:
+//!
+//! - each mint is identified by [`token_address`], a 20-byte address derived
+//!   from the mint alone, so it is the same for every holder. No contract is
+//!   deployed there: `eth_call` to it returns nothing, so wallets cannot read
+//!   `balanceOf`, `decimals` or `symbol` from it and must list the tokens
+//!   from this method
+//! - balances are quantities in the mint's base units, given with the mint's
+//!   decimals
+//! - every token account the PDA owns is listed, its associated token account
+//!   and any other
+
+use {
+    crate::blocks::{data, quantity},
+    bsc_evm_rlp::keccak256,
+    serde_json::{json, Value},
+    solana_sdk::{pubkey, pubkey::Pubkey},
+    std::str::FromStr,
+};
+
+/// SPL Token program
+pub const SPL_TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// SPL Token-2022 program
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Token programs whose accounts are listed
+pub const TOKEN_PROGRAM_IDS: [Pubkey; 2] = [SPL_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID];
+
+/// Prefix hashed with a mint into its token address
+const TOKEN_ADDRESS_PREFIX: &[u8] = b"bssc-token";
+
+/// ERC-20 style address identifying the token minted by `mint`
+pub fn token_address(mint: &Pubkey) -> [u8; 20] {
+    let hash = keccak256(&[TOKEN_ADDRESS_PREFIX, mint.as_ref()].concat());
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+/// Tokens held in one token account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub token_program: Pubkey,
+    /// Balance in the mint's base units
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TokenBalance {
+    /// Balance held in `token_account`, from its `jsonParsed` encoding
+    ///
+    /// `None` if the account is not a parsed token account.
+    pub fn from_parsed_account(
+        token_account: Pubkey,
+        token_program: Pubkey,
+        account: &Value,
+    ) -> Option<Self> {
+        let info = &account["data"]["parsed"]["info"];
+        let token_amount = &info["tokenAmount"];
+        Some(Self {
+            mint: Pubkey::from_str(info["mint"].as_str()?).ok()?,
+            token_account,
+            token_program,
+            amount: token_amount["amount"].as_str()?.parse().ok()?,
+            decimals: u8::try_from(token_amount["decimals"].as_u64()?).ok()?,
+        })
+    }
+
+    /// Entry of `bssc_getTokenBalances`
+    pub fn to_json(&self) -> Value {
+        json!({
+            "address": data(&token_address(&self.mint)),
+            "mint": self.mint.to_string(),
+            "tokenAccount": self.token_account.to_string(),
+            "tokenProgram": self.token_program.to_string(),
+            "balance": quantity(self.amount.into()),
+            "decimals": self.decimals,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_address() {
+        let mint = Pubkey::new_unique();
+        assert_eq!(token_address(&mint), token_address(&mint));
+        assert_ne!(token_address(&mint), token_address(&Pubkey::new_unique()));
+        assert_eq!(
+            token_address(&mint)[..],
+            keccak256(&[b"bssc-token".as_ref(), mint.as_ref()].concat())[12..]
+        );
+    }
+
+    #[test]
+    fn test_parsed_account() {
+        let mint = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let account = json!({
+            "data": {
+                "program": "spl-token-2022",
+                "parsed": {
+                    "type": "account",
+                    "info": {
+                        "isNative": false,
+                        "mint": mint.to_string(),
+                        "owner": Pubkey::new_unique().to_string(),
+                        "state": "initialized",
+                        "tokenAmount": {
+                            "amount": "1500000",
+                            "decimals": 6,
+                            "uiAmount": 1.5,
+                            "uiAmountString": "1.5",
+                        },
+                    },
+                },
+                "space": 170,
+            },
+            "executable": false,
+            "lamports": 2_074_080,
+            "owner": TOKEN_2022_PROGRAM_ID.to_string(),
+        });
+        let balance =
+            TokenBalance::from_parsed_account(token_account, TOKEN_2022_PROGRAM_ID, &account)
+                .unwrap();
+        assert_eq!(
+            balance,
+            TokenBalance {
+                mint,
+                token_account,
+                token_program: TOKEN_2022_PROGRAM_ID,
+                amount: 1_500_000,
+                decimals: 6,
+            }
+        );
+        assert_eq!(
+            balance.to_json(),
+            json!({
+                "address": data(&token_address(&mint)),
+                "mint": mint.to_string(),
+                "tokenAccount": token_account.to_string(),
+                "tokenProgram": TOKEN_2022_PROGRAM_ID.to_string(),
+                "balance": "0x16e360",
+                "decimals": 6,
+            })
+        );
+
+        // Base64 encoded data is not parsed
+        let raw = json!({ "data": ["", "base64"], "owner": SPL_TOKEN_PROGRAM_ID.to_string() });
+        assert_eq!(
+            TokenBalance::from_parsed_account(token_account, SPL_TOKEN_PROGRAM_ID, &raw),
+            None
+        );
+    }
+}
@@ -0,0 +1,771 @@
+//! # Execution Traces
+//!
+//! `debug_traceTransaction` and `debug_traceCall` replay EVM execution in the
+//! RPC server with the bsc-evm interpreter, observed by a
+//! [`Tracer`](bsc_evm::tracer::Tracer). The trace options pick the output:
+//!
+//! - by default, geth's struct logger: every opcode executed, with the stack
+//!   and the storage slots touched, and with `enableMemory` and
+//!   `enableReturnData` the memory and the last call's output.
+//!   `disableStack` and `disableStorage` leave those out and `limit` caps
+//!   the number of steps
+//! - `{"tracer": "callTracer"}`: the tree of message calls and creations,
+//!   only the transaction's own frame with `onlyTopCall` and with the logs
+//!   each frame emitted with `withLog`
+//!
+//! A replay needs the state accounts the transaction saw, which a
+//! [`SlotState`] provides for recent slots.
+
+use {
+    crate::{
+        blocks::{data, quantity, quantity_bytes},
+        revert_reason, RpcError,
+    },
+    bsc_evm::{
+        evm_interpreter::{Log, Opcode},
+        tracer::{FrameEnd, FrameStart, Step, Tracer},
+    },
+    serde_json::{json, Map, Value},
+    solana_sdk::{
+        account::Account,
+        program_stubs::{set_syscall_stubs, SyscallStubs},
+        pubkey::Pubkey,
+    },
+    std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Once},
+};
+
+/// Most steps the struct logger records unless the request sets a limit
+pub const MAX_STRUCT_LOGS: usize = 100_000;
+
+/// Account state of recent slots, as a validator's banks hold it
+pub trait SlotState: std::fmt::Debug + Send + Sync {
+    /// Accounts as the first transaction of `slot` saw them: sysvars as set
+    /// for `slot`, and every other account as the parent slot left it
+    ///
+    /// `None` once the state of `slot` is no longer kept.
+    fn accounts_at_start(&self, slot: u64, keys: &[Pubkey]) -> Option<Vec<Option<Account>>>;
+}
+
+/// Options of a `debug_trace*` request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceOptions {
+    StructLogs {
+        disable_stack: bool,
+        disable_storage: bool,
+        enable_memory: bool,
+        enable_return_data: bool,
+        /// Most steps recorded, 0 for no limit of the request's own
+        limit: usize,
+    },
+    Calls {
+        only_top_call: bool,
+        with_log: bool,
+    },
+}
+
+impl TraceOptions {
+    /// Parse the trace options object, which may be missing
+    pub fn parse(options: &Value) -> Result<Self, RpcError> {
+        let options = match options {
+            Value::Null => return Ok(Self::parse_struct_logs(&Map::new())),
+            Value::Object(options) => options,
+            _ => return Err(RpcError::InvalidParams),
+        };
+        match options.get("tracer") {
+            None | Some(Value::Null) => Ok(Self::parse_struct_logs(options)),
+            Some(Value::String(tracer)) if tracer == "callTracer" => {
+                let config = match options.get("tracerConfig") {
+                    None | Some(Value::Null) => &Value::Null,
+                    Some(config @ Value::Object(_)) => config,
+                    Some(_) => return Err(RpcError::InvalidParams),
+                };
+                Ok(Self::Calls {
+                    only_top_call: config["onlyTopCall"].as_bool().unwrap_or_default(),
+                    with_log: config["withLog"].as_bool().unwrap_or_default(),
+                })
+            }
+            Some(_) => Err(RpcError::ExecutionFailed("tracer not found".to_string())),
+        }
+    }
+
+    fn parse_struct_logs(options: &Map<String, Value>) -> Self {
+        let flag = |name: &str| {
+            options
+                .get(name)
+                .and_then(Value::as_bool)
+                .unwrap_or_default()
+        };
+        Self::StructLogs {
+            disable_stack: flag("disableStack"),
+            disable_storage: flag("disableStorage"),
+            enable_memory: flag("enableMemory"),
+            enable_return_data: flag("enableReturnData"),
+            limit: options
+                .get("limit")
+                .and_then(Value::as_u64)
+                .unwrap_or_default() as usize,
+        }
+    }
+
+    /// A fresh tracer with these options
+    pub fn start(&self) -> ActiveTracer {
+        match self {
+            Self::StructLogs { .. } => {
+                ActiveTracer::StructLogs(Rc::new(RefCell::new(StructLogger::new(self.clone()))))
+            }
+            Self::Calls {
+                only_top_call,
+                with_log,
+            } => ActiveTracer::Calls(Rc::new(RefCell::new(CallTracer::new(
+                *only_top_call,
+                *with_log,
+            )))),
+        }
+    }
+}
+
+/// Tracer of one replay, shared with the state it observes
+pub enum ActiveTracer {
+    StructLogs(Rc<RefCell<StructLogger>>),
+    Calls(Rc<RefCell<CallTracer>>),
+}
+
+impl ActiveTracer {
+    /// The tracer to install on the replayed state
+    pub fn tracer(&self) -> Rc<RefCell<dyn Tracer>> {
+        match self {
+            Self::StructLogs(tracer) => tracer.clone(),
+            Self::Calls(tracer) => tracer.clone(),
+        }
+    }
+
+    /// The trace, `None` if the transaction never started executing
+    pub fn result(&self) -> Option<Result<Value, RpcError>> {
+        match self {
+            Self::StructLogs(tracer) => tracer.borrow().result(),
+            Self::Calls(tracer) => tracer.borrow().result().map(Ok),
+        }
+    }
+}
+
+/// One opcode executed, as the struct logger records it
+struct StructLog {
+    pc: usize,
+    op: String,
+    gas: u64,
+    gas_cost: u64,
+    /// 1 for the transaction's frame, as geth counts
+    depth: usize,
+    refund: u64,
+    stack: Option<Vec<String>>,
+    memory: Option<Vec<String>>,
+    return_data: Option<String>,
+    storage: Option<BTreeMap<String, String>>,
+    error: Option<String>,
+}
+
+/// geth's default tracer, recording every opcode executed
+pub struct StructLogger {
+    options: TraceOptions,
+    logs: Vec<StructLog>,
+    /// Whether steps were left out for exceeding the limit
+    truncated: bool,
+    /// Storage slots each account touched, as the last step that touched them left them
+    storage: BTreeMap<[u8; 20], BTreeMap<String, String>>,
+    /// Frames entered and not yet exited
+    open_frames: usize,
+    /// Whether the transaction's frame succeeded, its gas used and its output
+    outcome: Option<(bool, u64, Vec<u8>)>,
+}
+
+impl StructLogger {
+    fn new(options: TraceOptions) -> Self {
+        Self {
+            options,
+            logs: Vec::new(),
+            truncated: false,
+            storage: BTreeMap::new(),
+            open_frames: 0,
+            outcome: None,
+        }
+    }
+
+    /// Steps recorded at most
+    fn limit(&self) -> usize {
+        match self.options {
+            TraceOptions::StructLogs { limit, .. } if limit > 0 => limit,
+            _ => MAX_STRUCT_LOGS,
+        }
+    }
+
+    /// `{gas, failed, returnValue, structLogs}`, or an error if the trace
+    /// outgrew the server's limit
+    fn result(&self) -> Option<Result<Value, RpcError>> {
+        let (success, gas_used, output) = self.outcome.as_ref()?;
+        if self.truncated
+            && !matches!(self.options, TraceOptions::StructLogs { limit, .. } if limit > 0)
+        {
+            return Some(Err(RpcError::LimitExceeded(format!(
+                "traces are limited to {} steps, set a limit",
+                MAX_STRUCT_LOGS
+            ))));
+        }
+
+        let logs: Vec<Value> = self
+            .logs
+            .iter()
+            .map(|log| {
+                let mut entry = json!({
+                    "pc": log.pc,
+                    "op": log.op,
+                    "gas": log.gas,
+                    "gasCost": log.gas_cost,
+                    "depth": log.depth,
+                });
+                if let Some(stack) = &log.stack {
+                    entry["stack"] = json!(stack);
+                }
+                if let Some(memory) = &log.memory {
+                    entry["memory"] = json!(memory);
+                }
+                if let Some(return_data) = &log.return_data {
+                    entry["returnData"] = json!(return_data);
+                }
+                if let Some(storage) = &log.storage {
+                    entry["storage"] = json!(storage);
+                }
+                if log.refund > 0 {
+                    entry["refund"] = json!(log.refund);
+                }
+                if let Some(error) = &log.error {
+                    entry["error"] = json!(error);
+                }
+                entry
+            })
+            .collect();
+
+        Some(Ok(json!({
+            "gas": gas_used,
+            "failed": !success,
+            "returnValue": hex::encode(output),
+            "structLogs": logs,
+        })))
+    }
+}
+
+impl Tracer for StructLogger {
+    fn enter(&mut self, _frame: &FrameStart) {
+        self.open_frames += 1;
+    }
+
+    fn step(&mut self, step: &Step) {
+        if self.logs.len() >= self.limit() {
+            self.truncated = true;
+            return;
+        }
+        let TraceOptions::StructLogs {
+            disable_stack,
+            enable_memory,
+            enable_return_data,
+            ..
+        } = self.options
+        else {
+            return;
+        };
+        self.logs.push(StructLog {
+            pc: step.pc,
+            op: opcode_name(step.opcode),
+            gas: step.gas,
+            gas_cost: 0,
+            depth: step.depth + 1,
+            refund: step.refund,
+            stack: (!disable_stack).then(|| {
+                step.stack
+                    .iter()
+                    .map(|word| quantity_bytes(&word.to_be_bytes()))
+                    .collect()
+            }),
+            memory: enable_memory.then(|| step.memory.chunks(32).map(hex::encode).collect()),
+            return_data: enable_return_data.then(|| data(step.return_data)),
+            storage: None,
+            error: None,
+        });
+    }
+
+    fn step_cost(&mut self, gas_cost: u64) {
+        if let Some(log) = self.logs.last_mut() {
+            log.gas_cost = gas_cost;
+        }
+    }
+
+    fn storage(&mut self, address: &[u8; 20], key: &[u8; 32], value: &[u8; 32]) {
+        if matches!(
+            self.options,
+            TraceOptions::StructLogs {
+                disable_storage: true,
+                ..
+            }
+        ) {
+            return;
+        }
+        let storage = self.storage.entry(*address).or_default();
+        storage.insert(hex::encode(key), hex::encode(value));
+        if let Some(log) = self.logs.last_mut() {
+            log.storage = Some(storage.clone());
+        }
+    }
+
+    fn fault(&mut self, error: &str) {
+        if let Some(log) = self.logs.last_mut() {
+            log.error.get_or_insert_with(|| error.to_string());
+        }
+    }
+
+    fn exit(&mut self, frame: &FrameEnd) {
+        self.open_frames = self.open_frames.saturating_sub(1);
+        if self.open_frames == 0 {
+            self.outcome = Some((frame.success, frame.gas_used, frame.output.to_vec()));
+        }
+    }
+}
+
+/// A message call or creation, as the call tracer records it
+struct CallFrame {
+    kind: Opcode,
+    from: [u8; 20],
+    to: [u8; 20],
+    value: u128,
+    gas: u64,
+    gas_used: u64,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    error: Option<String>,
+    calls: Vec<CallFrame>,
+    /// Logs emitted by the frame itself, with the number of calls it had made by then
+    logs: Vec<(Log, usize)>,
+}
+
+impl CallFrame {
+    fn to_json(&self) -> Value {
+        let mut frame = json!({
+            "type": opcode_name(self.kind as u8),
+            "from": data(&self.from),
+            "to": data(&self.to),
+            "gas": quantity(self.gas.into()),
+            "gasUsed": quantity(self.gas_used.into()),
+            "input": data(&self.input),
+        });
+        if self.kind != Opcode::STATICCALL {
+            frame["value"] = json!(quantity(self.value));
+        }
+        if !self.output.is_empty() {
+            frame["output"] = json!(data(&self.output));
+        }
+        if let Some(error) = &self.error {
+            frame["error"] = json!(error);
+            if let Some(reason) = revert_reason(&self.output) {
+                frame["revertReason"] = json!(reason);
+            }
+        }
+        if !self.calls.is_empty() {
+            frame["calls"] = self.calls.iter().map(CallFrame::to_json).collect();
+        }
+        if !self.logs.is_empty() {
+            frame["logs"] = self
+                .logs
+                .iter()
+                .map(|(log, position)| {
+                    json!({
+                        "address": data(&log.address),
+                        "topics": log.topics.iter().map(|topic| data(topic)).collect::<Vec<_>>(),
+                        "data": data(&log.data),
+                        "position": quantity(*position as u128),
+                    })
+                })
+                .collect();
+        }
+        frame
+    }
+
+    /// Drop the logs of a failed frame and of every frame it called
+    fn clear_logs(&mut self) {
+        self.logs.clear();
+        self.calls.iter_mut().for_each(CallFrame::clear_logs);
+    }
+}
+
+/// geth's `callTracer`, recording the tree of calls and creations
+pub struct CallTracer {
+    only_top_call: bool,
+    with_log: bool,
+    /// Frames entered and not yet exited, innermost last
+    stack: Vec<CallFrame>,
+    /// Nested frames entered and not yet exited that are not recorded
+    skipped: usize,
+    /// The transaction's frame, once it exited
+    root: Option<CallFrame>,
+}
+
+impl CallTracer {
+    fn new(only_top_call: bool, with_log: bool) -> Self {
+        Self {
+            only_top_call,
+            with_log,
+            stack: Vec::new(),
+            skipped: 0,
+            root: None,
+        }
+    }
+
+    fn result(&self) -> Option<Value> {
+        self.root.as_ref().map(CallFrame::to_json)
+    }
+}
+
+impl Tracer for CallTracer {
+    fn enter(&mut self, frame: &FrameStart) {
+        if self.skipped > 0 || (self.only_top_call && !self.stack.is_empty()) {
+            self.skipped += 1;
+            return;
+        }
+        self.stack.push(CallFrame {
+            kind: frame.kind,
+            from: frame.from,
+            to: frame.to,
+            value: frame.value,
+            gas: frame.gas,
+            gas_used: 0,
+            input: frame.input.to_vec(),
+            output: Vec::new(),
+            error: None,
+            calls: Vec::new(),
+            logs: Vec::new(),
+        });
+    }
+
+    fn log(&mut self, log: &Log) {
+        if self.skipped > 0 || !self.with_log {
+            return;
+        }
+        if let Some(frame) = self.stack.last_mut() {
+            frame.logs.push((log.clone(), frame.calls.len()));
+        }
+    }
+
+    fn fault(&mut self, error: &str) {
+        if self.skipped > 0 {
+            return;
+        }
+        if let Some(frame) = self.stack.last_mut() {
+            frame.error.get_or_insert_with(|| error.to_string());
+        }
+    }
+
+    fn exit(&mut self, end: &FrameEnd) {
+        if self.skipped > 0 {
+            self.skipped -= 1;
+            return;
+        }
+        let Some(mut frame) = self.stack.pop() else {
+            return;
+        };
+        frame.gas_used = end.gas_used;
+        frame.output = end.output.to_vec();
+        if end.success {
+            frame.error = None;
+        } else {
+            frame
+                .error
+                .get_or_insert_with(|| "execution reverted".to_string());
+            frame.clear_logs();
+        }
+        match self.stack.last_mut() {
+            Some(parent) => parent.calls.push(frame),
+            None => self.root = Some(frame),
+        }
+    }
+}
+
+/// Name of an opcode, as geth prints it
+fn opcode_name(opcode: u8) -> String {
+    match Opcode::from_u8(opcode) {
+        Some(Opcode::SHA3) => "KECCAK256".to_string(),
+        Some(opcode) => format!("{:?}", opcode),
+        None => format!("opcode {:#x} not defined", opcode),
+    }
+}
+
+/// Program log messages of replays, which would otherwise go to stdout
+struct SilentSyscalls;
+
+impl SyscallStubs for SilentSyscalls {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+}
+
+/// Keep the program's log messages of replays off stdout
+pub fn silence_program_logs() {
+    static SILENCE: Once = Once::new();
+    SILENCE.call_once(|| {
+        set_syscall_stubs(Box::new(SilentSyscalls));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, bsc_evm::u256::U256};
+
+    fn step<'a>(pc: usize, opcode: u8, gas: u64, depth: usize, stack: &'a [U256]) -> Step<'a> {
+        Step {
+            pc,
+            opcode,
+            gas,
+            refund: 0,
+            depth,
+            address: [0x11; 20],
+            stack,
+            memory: &[0u8; 32],
+            return_data: &[],
+        }
+    }
+
+    fn frame(kind: Opcode, depth: usize, input: &[u8]) -> FrameStart {
+        FrameStart {
+            kind,
+            from: [0xaa; 20],
+            to: [0x11; 20],
+            value: 5,
+            gas: 100_000,
+            input,
+            depth,
+        }
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(
+            TraceOptions::parse(&Value::Null).unwrap(),
+            TraceOptions::StructLogs {
+                disable_stack: false,
+                disable_storage: false,
+                enable_memory: false,
+                enable_return_data: false,
+                limit: 0,
+            }
+        );
+        assert_eq!(
+            TraceOptions::parse(&json!({"disableStack": true, "enableMemory": true, "limit": 10}))
+                .unwrap(),
+            TraceOptions::StructLogs {
+                disable_stack: true,
+                disable_storage: false,
+                enable_memory: true,
+                enable_return_data: false,
+                limit: 10,
+            }
+        );
+        assert_eq!(
+            TraceOptions::parse(
+                &json!({"tracer": "callTracer", "tracerConfig": {"withLog": true}})
+            )
+            .unwrap(),
+            TraceOptions::Calls {
+                only_top_call: false,
+                with_log: true
+            }
+        );
+        assert!(matches!(
+            TraceOptions::parse(&json!({"tracer": "prestateTracer"})),
+            Err(RpcError::ExecutionFailed(_))
+        ));
+        assert!(matches!(
+            TraceOptions::parse(&json!([])),
+            Err(RpcError::InvalidParams)
+        ));
+    }
+
+    #[test]
+    fn test_struct_logger() {
+        let mut logger = StructLogger::new(TraceOptions::parse(&Value::Null).unwrap());
+        assert!(logger.result().is_none());
+
+        logger.enter(&frame(Opcode::CALL, 0, &[]));
+        logger.step(&step(0, 0x60, 100, 0, &[]));
+        logger.step_cost(3);
+        let stack = [U256::from(1u64), U256::from(0u64)];
+        logger.step(&step(4, 0x55, 97, 0, &stack));
+        logger.storage(&[0x11; 20], &[0u8; 32], &U256::from(1u64).to_be_bytes());
+        logger.step_cost(22_100);
+        logger.step(&step(5, 0x0c, 50, 0, &[]));
+        logger.fault("invalid opcode");
+        logger.exit(&FrameEnd {
+            success: false,
+            gas_used: 100,
+            output: &[],
+        });
+
+        let result = logger.result().unwrap().unwrap();
+        assert_eq!(result["gas"], 100);
+        assert_eq!(result["failed"], true);
+        assert_eq!(result["returnValue"], "");
+        let logs = result["structLogs"].as_array().unwrap();
+        assert_eq!(
+            logs[0],
+            json!({"pc": 0, "op": "PUSH1", "gas": 100, "gasCost": 3, "depth": 1, "stack": []})
+        );
+        assert_eq!(logs[1]["op"], "SSTORE");
+        assert_eq!(logs[1]["stack"], json!(["0x1", "0x0"]));
+        assert_eq!(
+            logs[1]["storage"][&"00".repeat(32)],
+            format!("{}01", "00".repeat(31))
+        );
+        assert!(logs[1].get("memory").is_none());
+        assert_eq!(logs[2]["op"], "opcode 0xc not defined");
+        assert_eq!(logs[2]["error"], "invalid opcode");
+    }
+
+    #[test]
+    fn test_struct_logger_limit() {
+        let options = TraceOptions::parse(&json!({"limit": 1, "disableStack": true})).unwrap();
+        let mut logger = StructLogger::new(options);
+        logger.enter(&frame(Opcode::CALL, 0, &[]));
+        logger.step(&step(0, 0x5f, 100, 0, &[]));
+        logger.step(&step(1, 0x00, 98, 0, &[]));
+        logger.exit(&FrameEnd {
+            success: true,
+            gas_used: 21_002,
+            output: &[],
+        });
+        let result = logger.result().unwrap().unwrap();
+        assert_eq!(
+            result["structLogs"],
+            json!([{"pc": 0, "op": "PUSH0", "gas": 100, "gasCost": 0, "depth": 1}])
+        );
+
+        let mut logger = StructLogger::new(TraceOptions::parse(&Value::Null).unwrap());
+        logger.enter(&frame(Opcode::CALL, 0, &[]));
+        for pc in 0..=MAX_STRUCT_LOGS {
+            logger.step(&step(pc, 0x5b, 100, 0, &[]));
+        }
+        logger.exit(&FrameEnd {
+            success: true,
+            gas_used: 21_000,
+            output: &[],
+        });
+        assert!(matches!(
+            logger.result(),
+            Some(Err(RpcError::LimitExceeded(_)))
+        ));
+    }
+
+    #[test]
+    fn test_call_tracer() {
+        let mut tracer = CallTracer::new(false, true);
+        tracer.enter(&frame(Opcode::CALL, 0, &[1, 2]));
+        tracer.log(&Log {
+            address: [0x11; 20],
+            topics: vec![[7u8; 32]],
+            data: vec![],
+        });
+
+        // A successful static call
+        tracer.enter(&frame(Opcode::STATICCALL, 1, &[]));
+        tracer.exit(&FrameEnd {
+            success: true,
+            gas_used: 10,
+            output: &[9],
+        });
+
+        // A call that halted, and whose logs are dropped
+        tracer.enter(&frame(Opcode::DELEGATECALL, 1, &[]));
+        tracer.log(&Log {
+            address: [0x11; 20],
+            topics: vec![],
+            data: vec![],
+        });
+        tracer.fault("out of gas");
+        tracer.exit(&FrameEnd {
+            success: false,
+            gas_used: 100_000,
+            output: &[],
+        });
+
+        tracer.exit(&FrameEnd {
+            success: false,
+            gas_used: 50_000,
+            output: &[0x08, 0xc3, 0x79, 0xa0],
+        });
+
+        let result = tracer.result().unwrap();
+        assert_eq!(result["type"], "CALL");
+        assert_eq!(result["from"], data(&[0xaa; 20]));
+        assert_eq!(result["value"], "0x5");
+        assert_eq!(result["gas"], "0x186a0");
+        assert_eq!(result["gasUsed"], "0xc350");
+        assert_eq!(result["input"], "0x0102");
+        assert_eq!(result["error"], "execution reverted");
+        assert!(result.get("revertReason").is_none());
+        // A reverted frame keeps no logs
+        assert!(result.get("logs").is_none());
+
+        let calls = result["calls"].as_array().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0]["type"], "STATICCALL");
+        assert!(calls[0].get("value").is_none());
+        assert_eq!(calls[0]["output"], "0x09");
+        assert!(calls[0].get("error").is_none());
+        assert_eq!(calls[1]["type"], "DELEGATECALL");
+        assert_eq!(calls[1]["error"], "out of gas");
+    }
+
+    #[test]
+    fn test_call_tracer_logs_and_top_call() {
+        let mut tracer = CallTracer::new(false, true);
+        tracer.enter(&frame(Opcode::CALL, 0, &[]));
+        tracer.enter(&frame(Opcode::CALL, 1, &[]));
+        tracer.exit(&FrameEnd {
+            success: true,
+            gas_used: 10,
+            output: &[],
+        });
+        tracer.log(&Log {
+            address: [0x11; 20],
+            topics: vec![[7u8; 32]],
+            data: vec![1],
+        });
+        tracer.exit(&FrameEnd {
+            success: true,
+            gas_used: 21_010,
+            output: &[],
+        });
+        let result = tracer.result().unwrap();
+        assert_eq!(
+            result["logs"],
+            json!([{
+                "address": data(&[0x11; 20]),
+                "topics": [data(&[7u8; 32])],
+                "data": "0x01",
+                "position": "0x1",
+            }])
+        );
+
+        let mut tracer = CallTracer::new(true, false);
+        tracer.enter(&frame(Opcode::CALL, 0, &[]));
+        tracer.enter(&frame(Opcode::CALL, 1, &[]));
+        tracer.fault("out of gas");
+        tracer.exit(&FrameEnd {
+            success: false,
+            gas_used: 10,
+            output: &[],
+        });
+        tracer.exit(&FrameEnd {
+            success: true,
+            gas_used: 21_010,
+            output: &[],
+        });
+        let result = tracer.result().unwrap();
+        assert!(result.get("calls").is_none());
+        assert!(result.get("error").is_none());
+    }
+}
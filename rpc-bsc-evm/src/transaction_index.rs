@@ -0,0 +1,177 @@
+//! # Transaction Index
+//!
+//! Maps the hash of every Ethereum transaction relayed by this server to the
+//! signature of the Solana transaction that carried it, and back. The index
+//! is kept in memory and, when opened from a file, appended to that file one
+//! `0x<ethereum hash> <solana signature>` line per transaction so it
+//! survives restarts.
+
+use {
+    solana_sdk::signature::Signature,
+    std::{
+        collections::HashMap,
+        fs::{File, OpenOptions},
+        io::{self, Read, Write},
+        path::Path,
+        str::FromStr,
+    },
+};
+
+/// Ethereum transaction hash to Solana signature mapping
+#[derive(Debug, Default)]
+pub struct TransactionIndex {
+    by_hash: HashMap<[u8; 32], Signature>,
+    by_signature: HashMap<Signature, [u8; 32]>,
+    /// File new entries are appended to, if persistent
+    file: Option<File>,
+}
+
+impl TransactionIndex {
+    /// Index that only lives as long as the server
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open the index stored at `path`, creating it if needed
+    ///
+    /// A last line without a newline is the remains of an interrupted write
+    /// and is discarded.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        let mut index = Self::default();
+        for line in contents.split_inclusive('\n') {
+            let Some(line) = line.strip_suffix('\n') else {
+                break;
+            };
+            let (hash, signature) = parse_entry(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid transaction index entry: {}", line),
+                )
+            })?;
+            index.add(hash, signature);
+        }
+        // Drop the interrupted write so the next entry starts on a line of its own
+        let complete = contents.rfind('\n').map_or(0, |newline| newline + 1);
+        if complete < contents.len() {
+            file.set_len(complete as u64)?;
+        }
+
+        index.file = Some(file);
+        Ok(index)
+    }
+
+    /// Record that the transaction with `hash` was sent as `signature`
+    pub fn insert(&mut self, hash: [u8; 32], signature: Signature) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            writeln!(file, "0x{} {}", hex::encode(hash), signature)?;
+            file.sync_data()?;
+        }
+        self.add(hash, signature);
+        Ok(())
+    }
+
+    /// Signature of the Solana transaction carrying the transaction `hash`
+    pub fn signature(&self, hash: &[u8; 32]) -> Option<Signature> {
+        self.by_hash.get(hash).copied()
+    }
+
+    /// Hash of the Ethereum transaction carried by `signature`
+    pub fn hash(&self, signature: &Signature) -> Option<[u8; 32]> {
+        self.by_signature.get(signature).copied()
+    }
+
+    fn add(&mut self, hash: [u8; 32], signature: Signature) {
+        self.by_hash.insert(hash, signature);
+        self.by_signature.insert(signature, hash);
+    }
+}
+
+/// Parse a `0x<hash> <signature>` line
+fn parse_entry(line: &str) -> Option<([u8; 32], Signature)> {
+    let (hash, signature) = line.split_once(' ')?;
+    let hash = hex::decode(hash.strip_prefix("0x")?)
+        .ok()?
+        .try_into()
+        .ok()?;
+    let signature = Signature::from_str(signature).ok()?;
+    Some((hash, signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::fs};
+
+    fn index_path(name: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rpc-bsc-evm-{}-{}.index", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_lookups_in_both_directions() {
+        let mut index = TransactionIndex::in_memory();
+        let signature = Signature::new_unique();
+        index.insert([0x11; 32], signature).unwrap();
+
+        assert_eq!(index.signature(&[0x11; 32]), Some(signature));
+        assert_eq!(index.hash(&signature), Some([0x11; 32]));
+        assert_eq!(index.signature(&[0x22; 32]), None);
+        assert_eq!(index.hash(&Signature::new_unique()), None);
+    }
+
+    #[test]
+    fn test_index_survives_reopening() {
+        let path = index_path("reopen");
+        let first = Signature::new_unique();
+        let second = Signature::new_unique();
+        {
+            let mut index = TransactionIndex::open(&path).unwrap();
+            index.insert([0x11; 32], first).unwrap();
+            index.insert([0x22; 32], second).unwrap();
+        }
+
+        let index = TransactionIndex::open(&path).unwrap();
+        assert_eq!(index.signature(&[0x11; 32]), Some(first));
+        assert_eq!(index.hash(&second), Some([0x22; 32]));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_interrupted_write_is_ignored() {
+        let path = index_path("torn");
+        let signature = Signature::new_unique();
+        fs::write(
+            &path,
+            format!("0x{} {}\n0x1234", hex::encode([0x11; 32]), signature),
+        )
+        .unwrap();
+
+        let mut index = TransactionIndex::open(&path).unwrap();
+        assert_eq!(index.signature(&[0x11; 32]), Some(signature));
+        index.insert([0x22; 32], signature).unwrap();
+
+        let index = TransactionIndex::open(&path).unwrap();
+        assert_eq!(index.signature(&[0x22; 32]), Some(signature));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_entry_is_rejected() {
+        let path = index_path("corrupt");
+        fs::write(&path, "not an entry\n").unwrap();
+        assert_eq!(
+            TransactionIndex::open(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
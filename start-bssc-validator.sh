@@ -4,8 +4,8 @@
 cd "$(dirname "$0")"
 
 echo "Starting BSSC Validator with EVM Support..."
-echo "Chain ID: 16979"
-echo "Native Token: BNB"
+echo "Chain ID: 16979 (unless the EVM state account configures another)"
+echo "Native Token: BSSC"
 echo ""
 
 ./target/release/solana-test-validator \
@@ -30,7 +30,7 @@ use {
     solana_test_validator::*,
     solana_validator::{
         admin_rpc_service, cli, dashboard::Dashboard, ledger_lockfile, lock_ledger,
-        println_name_value, redirect_stderr_to_file,
+        println_name_value, redirect_stderr_to_file, start_evm_rpc_service,
     },
     std::{
         collections::HashSet,
@@ -565,10 +565,33 @@ fn main() {
         rpc_to_plugin_manager_receiver,
     ) {
         Ok(test_validator) => {
+            let validator_identity = read_keypair_file(ledger_path.join("validator-keypair.json"))
+                .unwrap_or_else(|err| {
+                    println!("Error: failed to read the validator identity: {err}");
+                    exit(1);
+                });
+            let evm_rpc_service = start_evm_rpc_service(
+                &matches,
+                bind_address.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+                test_validator.rpc_url(),
+                test_validator.rpc_pubsub_url(),
+                &ledger_path,
+                &validator_identity,
+                test_validator.bank_forks(),
+            )
+            .unwrap_or_else(|err| {
+                println!("Error: failed to start EVM JSON RPC service: {err}");
+                exit(1);
+            });
+
             if let Some(dashboard) = dashboard {
                 dashboard.run(Duration::from_millis(250));
             }
             test_validator.join();
+            if let Some(mut evm_rpc_service) = evm_rpc_service {
+                evm_rpc_service.exit();
+                let _ = evm_rpc_service.join();
+            }
         }
         Err(err) => {
             drop(dashboard);
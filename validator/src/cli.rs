@@ -1555,6 +1555,7 @@ pub fn app<'a>(version: &'a str, default_args: &'a DefaultArgs) -> App<'a, 'a> {
                 ",
                 ),
         )
+        .args(&get_evm_rpc_arguments())
         .args(&get_deprecated_arguments())
         .after_help("The default subcommand is run")
         .subcommand(
@@ -2094,6 +2095,49 @@ fn deprecated_arguments() -> Vec<DeprecatedArg> {
     res
 }
 
+// Arguments of the Ethereum JSON RPC service, see `start_evm_rpc_service`
+fn get_evm_rpc_arguments() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("evm_rpc_port")
+            .long("evm-rpc-port")
+            .value_name("PORT")
+            .takes_value(true)
+            .validator(port_validator)
+            .requires_all(&["rpc_port", "evm_program_id", "evm_state"])
+            .help("Enable the Ethereum JSON RPC on this port, and the next port for its websocket"),
+        Arg::with_name("evm_program_id")
+            .long("evm-program-id")
+            .value_name("ADDRESS")
+            .takes_value(true)
+            .validator(is_pubkey)
+            .help("Address of the bsc-evm program served by the Ethereum JSON RPC"),
+        Arg::with_name("evm_state")
+            .long("evm-state")
+            .value_name("ADDRESS")
+            .takes_value(true)
+            .validator(is_pubkey)
+            .help("State account of the bsc-evm program served by the Ethereum JSON RPC"),
+        Arg::with_name("eth_bridge_program_id")
+            .long("eth-bridge-program-id")
+            .value_name("ADDRESS")
+            .takes_value(true)
+            .validator(is_pubkey)
+            .help(
+                "Address of the eth-bridge program whose token balances the Ethereum JSON RPC \
+                 lists",
+            ),
+        Arg::with_name("evm_rpc_payer")
+            .long("evm-rpc-payer")
+            .value_name("KEYPAIR")
+            .takes_value(true)
+            .validator(is_keypair)
+            .help(
+                "Keypair paying for the transactions relayed by the Ethereum JSON RPC \
+                 [default: validator identity]",
+            ),
+    ]
+}
+
 // Helper to add arguments that are no longer used but are being kept around to avoid breaking
 // validator startup commands.
 fn get_deprecated_arguments() -> Vec<Arg<'static, 'static>> {
@@ -2753,7 +2797,8 @@ pub fn test_app<'a>(version: &'a str, default_args: &'a DefaultTestArgs) -> App<
                 .validator(is_parsable::<u64>)
                 .takes_value(true)
                 .help("Override the runtime's account lock limit per transaction"),
-        );
+        )
+        .args(&get_evm_rpc_arguments());
 }
 
 pub struct DefaultTestArgs {
@@ -1,16 +1,26 @@
 #![allow(clippy::arithmetic_side_effects)]
 pub use solana_test_validator as test_validator;
 use {
+    clap::{value_t, ArgMatches},
     console::style,
     fd_lock::{RwLock, RwLockWriteGuard},
     indicatif::{ProgressDrawTarget, ProgressStyle},
+    rpc_bsc_evm::{
+        service::{EvmRpcService, EvmRpcServiceConfig},
+        SlotState,
+    },
+    solana_clap_utils::input_parsers::{keypair_of, pubkey_of},
+    solana_runtime::bank_forks::BankForks,
+    solana_sdk::{account::Account, pubkey::Pubkey, signature::Keypair, sysvar},
     std::{
         borrow::Cow,
         env,
         fmt::Display,
         fs::{File, OpenOptions},
+        net::{IpAddr, SocketAddr},
         path::Path,
         process::exit,
+        sync::Arc,
         thread::JoinHandle,
         time::Duration,
     },
@@ -160,3 +170,72 @@ pub fn lock_ledger<'lock>(
         exit(1);
     })
 }
+
+/// Account state of the slots the validator still keeps a bank for
+struct BankSlotState {
+    bank_forks: Arc<std::sync::RwLock<BankForks>>,
+}
+
+impl std::fmt::Debug for BankSlotState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BankSlotState").finish_non_exhaustive()
+    }
+}
+
+impl SlotState for BankSlotState {
+    fn accounts_at_start(&self, slot: u64, keys: &[Pubkey]) -> Option<Vec<Option<Account>>> {
+        let (bank, parent) = {
+            let bank_forks = self.bank_forks.read().unwrap();
+            let bank = bank_forks.get(slot)?;
+            let parent = bank_forks.get(bank.parent_slot())?;
+            (bank, parent)
+        };
+        // Sysvars are updated when the bank is created, before any transaction
+        let accounts = keys
+            .iter()
+            .map(|key| {
+                if sysvar::is_sysvar_id(key) {
+                    bank.get_account(key)
+                } else {
+                    parent.get_account(key)
+                }
+            })
+            .map(|account| account.map(Account::from))
+            .collect();
+        Some(accounts)
+    }
+}
+
+/// Start the Ethereum JSON RPC service if `--evm-rpc-port` was given
+///
+/// The service serves the bsc-evm program through the validator's own JSON
+/// RPC at `rpc_url` and `rpc_pubsub_url`, and indexes the transactions it
+/// relays in the ledger directory. Transactions are traced against the banks
+/// of `bank_forks`, so only those of slots that are not yet rooted can be.
+pub fn start_evm_rpc_service(
+    matches: &ArgMatches,
+    bind_address: IpAddr,
+    rpc_url: String,
+    rpc_pubsub_url: String,
+    ledger_path: &Path,
+    identity_keypair: &Keypair,
+    bank_forks: Arc<std::sync::RwLock<BankForks>>,
+) -> Result<Option<EvmRpcService>, String> {
+    let Ok(evm_rpc_port) = value_t!(matches, "evm_rpc_port", u16) else {
+        return Ok(None);
+    };
+    // --evm-rpc-port requires both addresses
+    let mut config = EvmRpcServiceConfig::new(
+        SocketAddr::new(bind_address, evm_rpc_port),
+        rpc_url,
+        rpc_pubsub_url,
+        pubkey_of(matches, "evm_program_id").unwrap(),
+        pubkey_of(matches, "evm_state").unwrap(),
+    );
+    config.transaction_index_path = Some(ledger_path.join("evm-transaction-index"));
+    config.slot_state = Some(Arc::new(BankSlotState { bank_forks }));
+    config.eth_bridge_program_id = pubkey_of(matches, "eth_bridge_program_id");
+    let payer =
+        keypair_of(matches, "evm_rpc_payer").unwrap_or_else(|| identity_keypair.insecure_clone());
+    EvmRpcService::new(config, payer).map(Some)
+}
@@ -67,7 +67,7 @@ use {
         cli::{app, warn_for_deprecated_arguments, DefaultArgs},
         dashboard::Dashboard,
         ledger_lockfile, lock_ledger, new_spinner_progress_bar, println_name_value,
-        redirect_stderr_to_file,
+        redirect_stderr_to_file, start_evm_rpc_service,
     },
     std::{
         collections::{HashSet, VecDeque},
@@ -1953,7 +1953,38 @@ pub fn main() {
         });
     }
     info!("Validator initialized");
+
+    let evm_rpc_service = validator_config
+        .rpc_addrs
+        .and_then(|(rpc_addr, rpc_pubsub_addr)| {
+            // Reach the RPC over loopback when it listens on every interface
+            let local_addr = |addr: SocketAddr| {
+                if addr.ip().is_unspecified() {
+                    SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), addr.port())
+                } else {
+                    addr
+                }
+            };
+            start_evm_rpc_service(
+                &matches,
+                rpc_bind_address,
+                format!("http://{}", local_addr(rpc_addr)),
+                format!("ws://{}", local_addr(rpc_pubsub_addr)),
+                &ledger_path,
+                &validator.cluster_info.keypair(),
+                validator.bank_forks.clone(),
+            )
+            .unwrap_or_else(|err| {
+                error!("Failed to start EVM JSON RPC service: {}", err);
+                exit(1);
+            })
+        });
+
     validator.join();
+    if let Some(mut evm_rpc_service) = evm_rpc_service {
+        evm_rpc_service.exit();
+        let _ = evm_rpc_service.join();
+    }
     info!("Validator exiting..");
 }
 